@@ -0,0 +1,290 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    alloc::{self, Layout},
+    fmt::Debug,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::bits;
+
+/// Size of the transparent hugepages used by [`AlignedBuffer::new_hugepage`].
+pub const HUGEPAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// A growable byte buffer that owns memory aligned to `align`, for direct IO.
+pub struct AlignedBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    capacity: usize,
+    align: usize,
+    hugepage: bool,
+}
+
+unsafe impl Send for AlignedBuffer {}
+unsafe impl Sync for AlignedBuffer {}
+
+impl Debug for AlignedBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AlignedBuffer")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity)
+            .field("align", &self.align)
+            .field("hugepage", &self.hugepage)
+            .finish()
+    }
+}
+
+impl AlignedBuffer {
+    /// Create an empty buffer without allocation.
+    pub fn new(align: usize) -> Self {
+        assert!(align.is_power_of_two());
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            capacity: 0,
+            align,
+            hugepage: false,
+        }
+    }
+
+    /// Create an empty buffer whose memory is backed by transparent hugepages.
+    ///
+    /// The memory is aligned to and allocated in multiples of [`HUGEPAGE_SIZE`], and advised with `MADV_HUGEPAGE`
+    /// where available. Hugepages reduce TLB pressure when large buffers are swept.
+    pub fn new_hugepage(align: usize) -> Self {
+        assert!(align.is_power_of_two());
+        Self {
+            ptr: NonNull::dangling(),
+            len: 0,
+            capacity: 0,
+            align: std::cmp::max(align, HUGEPAGE_SIZE),
+            hugepage: true,
+        }
+    }
+
+    pub fn with_capacity_hugepage(align: usize, capacity: usize) -> Self {
+        let mut buf = Self::new_hugepage(align);
+        buf.reserve(capacity);
+        buf
+    }
+
+    pub fn with_capacity(align: usize, capacity: usize) -> Self {
+        let mut buf = Self::new(align);
+        buf.reserve(capacity);
+        buf
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn align(&self) -> usize {
+        self.align
+    }
+
+    pub fn is_hugepage(&self) -> bool {
+        self.hugepage
+    }
+
+    /// Set the length of the buffer.
+    ///
+    /// # Safety
+    ///
+    /// `len` must not exceed the capacity. Bytes between the old and the new length are uninitialized.
+    pub unsafe fn set_len(&mut self, len: usize) {
+        debug_assert!(len <= self.capacity);
+        self.len = len;
+    }
+
+    /// Reserve capacity for at least `additional` more bytes.
+    ///
+    /// The address of the buffer stays aligned after reallocation.
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self.len.checked_add(additional).expect("capacity overflow");
+        if required <= self.capacity {
+            return;
+        }
+        let mut capacity = std::cmp::max(required, self.capacity * 2);
+        if self.hugepage {
+            capacity = bits::align_up(HUGEPAGE_SIZE, capacity);
+        }
+        let layout = Layout::from_size_align(capacity, self.align).unwrap();
+
+        let ptr = unsafe {
+            if self.capacity == 0 {
+                alloc::alloc(layout)
+            } else {
+                alloc::realloc(self.ptr.as_ptr(), self.layout(), capacity)
+            }
+        };
+        self.ptr = NonNull::new(ptr).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        self.capacity = capacity;
+        bits::debug_assert_aligned(self.align, self.ptr.as_ptr() as usize);
+
+        if self.hugepage {
+            self.advise_hugepage();
+        }
+    }
+
+    pub fn extend_from_slice(&mut self, other: &[u8]) {
+        self.reserve(other.len());
+        unsafe {
+            std::ptr::copy_nonoverlapping(other.as_ptr(), self.ptr.as_ptr().add(self.len), other.len());
+        }
+        self.len += other.len();
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.len = std::cmp::min(self.len, len);
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    #[cfg(target_os = "linux")]
+    fn advise_hugepage(&self) {
+        // The advice is only a hint, the buffer works without hugepages if it is rejected.
+        let res = unsafe {
+            libc::madvise(
+                self.ptr.as_ptr() as *mut libc::c_void,
+                self.capacity,
+                libc::MADV_HUGEPAGE,
+            )
+        };
+        if res != 0 {
+            tracing::debug!("[buffer] madvise hugepage error: {}", std::io::Error::last_os_error());
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn advise_hugepage(&self) {}
+
+    fn layout(&self) -> Layout {
+        Layout::from_size_align(self.capacity, self.align).unwrap()
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        if self.capacity > 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr(), self.layout()) }
+        }
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl AsMut<[u8]> for AlignedBuffer {
+    fn as_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl std::io::Write for AlignedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_aligned_buffer() {
+        const ALIGN: usize = 512;
+
+        let mut buf = AlignedBuffer::with_capacity(ALIGN, ALIGN * 8);
+        assert_eq!(buf.capacity(), ALIGN * 8);
+        bits::assert_aligned(ALIGN, buf.as_ptr() as usize);
+
+        buf.extend_from_slice(&[b'x'; ALIGN * 8]);
+        bits::assert_aligned(ALIGN, buf.as_ptr() as usize);
+        assert_eq!(&buf[..], &[b'x'; ALIGN * 8]);
+
+        buf.write_all(&[b'x'; ALIGN * 8]).unwrap();
+        bits::assert_aligned(ALIGN, buf.as_ptr() as usize);
+        assert_eq!(&buf[..], &[b'x'; ALIGN * 16]);
+
+        buf.truncate(ALIGN);
+        buf[0] = b'y';
+        assert_eq!(buf.len(), ALIGN);
+        assert_eq!(buf[0], b'y');
+        assert!(buf.capacity() >= ALIGN * 16);
+
+        buf.clear();
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_aligned_buffer_hugepage() {
+        let mut buf = AlignedBuffer::with_capacity_hugepage(4096, 4096);
+        assert!(buf.is_hugepage());
+        assert_eq!(buf.align(), HUGEPAGE_SIZE);
+        assert_eq!(buf.capacity(), HUGEPAGE_SIZE);
+        bits::assert_aligned(HUGEPAGE_SIZE, buf.as_ptr() as usize);
+
+        buf.extend_from_slice(&vec![b'x'; HUGEPAGE_SIZE + 1]);
+        assert_eq!(buf.capacity(), 2 * HUGEPAGE_SIZE);
+        bits::assert_aligned(HUGEPAGE_SIZE, buf.as_ptr() as usize);
+        assert!(buf.iter().all(|b| *b == b'x'));
+    }
+
+    #[test]
+    fn test_aligned_buffer_empty() {
+        let mut buf = AlignedBuffer::new(4096);
+        assert!(buf.is_empty());
+        assert_eq!(buf.capacity(), 0);
+        assert_eq!(&buf[..], &[] as &[u8]);
+
+        buf.reserve(1);
+        bits::assert_aligned(4096, buf.as_ptr() as usize);
+    }
+}
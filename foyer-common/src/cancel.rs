@@ -0,0 +1,135 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Weak,
+};
+
+use parking_lot::Mutex;
+use tokio::sync::Notify;
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+    children: Mutex<Vec<Weak<Inner>>>,
+}
+
+impl Inner {
+    fn cancel(&self) {
+        if self.cancelled.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        self.notify.notify_waiters();
+        let children = std::mem::take(&mut *self.children.lock());
+        for child in children.iter().filter_map(Weak::upgrade) {
+            child.cancel();
+        }
+    }
+}
+
+/// A token to signal background tasks to stop.
+///
+/// Tokens form a tree: cancelling a token cancels all its descendants, but not its ancestors. A store cancels the
+/// token of a worker group to stop all workers in it, each worker holding a child token of the group.
+///
+/// Cloned tokens share the same state.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a child token, which is cancelled when this token is cancelled.
+    ///
+    /// The child of a cancelled token is cancelled at creation.
+    pub fn child(&self) -> Self {
+        let child = Self::new();
+        {
+            let mut children = self.inner.children.lock();
+            if !self.is_cancelled() {
+                // Drop the records of dropped children on the way.
+                children.retain(|child| child.strong_count() > 0);
+                children.push(Arc::downgrade(&child.inner));
+                return child;
+            }
+        }
+        child.cancel();
+        child
+    }
+
+    /// Cancel this token and all its descendants.
+    pub fn cancel(&self) {
+        self.inner.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Resolves when the token is cancelled, immediately if it is already cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_cancellation_token() {
+        let root = CancellationToken::new();
+        let group = root.child();
+        let workers = (0..4).map(|_| group.child()).collect::<Vec<_>>();
+        let other = root.child();
+
+        let handles = workers
+            .iter()
+            .cloned()
+            .map(|worker| tokio::spawn(async move { worker.cancelled().await }))
+            .collect::<Vec<_>>();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(handles.iter().all(|handle| !handle.is_finished()));
+
+        // Cancelling a group stops its workers only.
+        group.cancel();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert!(workers.iter().all(|worker| worker.is_cancelled()));
+        assert!(!root.is_cancelled());
+        assert!(!other.is_cancelled());
+
+        // Children of a cancelled token are cancelled at creation.
+        assert!(group.child().is_cancelled());
+
+        root.cancel();
+        assert!(other.is_cancelled());
+        other.cancelled().await;
+    }
+}
@@ -429,6 +429,100 @@ impl Value for () {
     }
 }
 
+/// A key composed of several [`Key`] parts, e.g. `CompositeKey((tenant, id))`, of 2 to 4 parts.
+///
+/// Each part is encoded with its length as a big-endian `u32` prefix, so parts of any length are decoded back as they
+/// are and `serialized_len` always equals the length of the encoded key. Keys are ordered part by part, the same as
+/// their tuples.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CompositeKey<T>(pub T);
+
+#[derive(Debug)]
+pub struct CompositeKeyCursor<T> {
+    inner: CompositeKey<T>,
+    encoded: std::io::Cursor<Vec<u8>>,
+}
+
+impl<T: Send + Sync + 'static + std::fmt::Debug> std::io::Read for CompositeKeyCursor<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.encoded.read(buf)
+    }
+}
+
+impl<T: Send + Sync + 'static + std::fmt::Debug> Cursor for CompositeKeyCursor<T> {
+    type T = CompositeKey<T>;
+
+    fn into_inner(self) -> Self::T {
+        self.inner
+    }
+}
+
+const COMPOSITE_KEY_PREFIX_LEN: usize = std::mem::size_of::<u32>();
+
+fn encode_part<P: Key>(part: &P, buf: &mut Vec<u8>) {
+    let len = part.serialized_len();
+    buf.put_u32(len as u32);
+    let start = buf.len();
+    std::io::copy(&mut part.clone().into_cursor(), buf).unwrap();
+    assert_eq!(
+        buf.len() - start,
+        len,
+        "encoded length of composite key part {:?} differs from its `serialized_len`",
+        part
+    );
+}
+
+fn decode_part<P: Key>(buf: &mut &[u8]) -> CodingResult<P> {
+    anyhow::ensure!(buf.len() >= COMPOSITE_KEY_PREFIX_LEN, "composite key is truncated");
+    let len = buf.get_u32() as usize;
+    anyhow::ensure!(buf.len() >= len, "composite key part of {} bytes is truncated", len);
+    let part = P::read(&buf[..len])?;
+    buf.advance(len);
+    Ok(part)
+}
+
+macro_rules! impl_composite_key {
+    ($( ($($part:ident),+) ),*) => {
+        paste! {
+            $(
+                impl<$($part: Key),+> Key for CompositeKey<($($part,)+)> {
+                    type Cursor = CompositeKeyCursor<($($part,)+)>;
+
+                    fn weight(&self) -> usize {
+                        let ($([<$part:lower>],)+) = &self.0;
+                        0 $(+ [<$part:lower>].weight())+
+                    }
+
+                    fn serialized_len(&self) -> usize {
+                        let ($([<$part:lower>],)+) = &self.0;
+                        0 $(+ COMPOSITE_KEY_PREFIX_LEN + [<$part:lower>].serialized_len())+
+                    }
+
+                    fn read(mut buf: &[u8]) -> CodingResult<Self> {
+                        let key = CompositeKey(($(decode_part::<$part>(&mut buf)?,)+));
+                        anyhow::ensure!(buf.is_empty(), "composite key has {} trailing bytes", buf.len());
+                        Ok(key)
+                    }
+
+                    fn into_cursor(self) -> Self::Cursor {
+                        let mut encoded = Vec::with_capacity(self.serialized_len());
+                        {
+                            let ($([<$part:lower>],)+) = &self.0;
+                            $(encode_part([<$part:lower>], &mut encoded);)+
+                        }
+                        CompositeKeyCursor {
+                            inner: self,
+                            encoded: std::io::Cursor::new(encoded),
+                        }
+                    }
+                }
+            )*
+        }
+    };
+}
+
+impl_composite_key! { (A, B), (A, B, C), (A, B, C, D) }
+
 #[derive(Debug)]
 pub struct UnimplementedCursor<T: Send + Sync + 'static + std::fmt::Debug>(PhantomData<T>);
 
@@ -445,3 +539,58 @@ impl<T: Send + Sync + 'static + std::fmt::Debug> Cursor for UnimplementedCursor<
         unimplemented!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    fn encode<K: Key>(key: K) -> Vec<u8> {
+        let mut buf = vec![];
+        key.into_cursor().read_to_end(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_composite_key_round_trip() {
+        let key = CompositeKey((42u64, b"tenant".to_vec(), vec![], 7u8));
+        let buf = encode(key.clone());
+        assert_eq!(buf.len(), key.serialized_len());
+        assert_eq!(buf.len(), 4 * 4 + 8 + 6 + 1);
+        assert_eq!(CompositeKey::<(u64, Vec<u8>, Vec<u8>, u8)>::read(&buf).unwrap(), key);
+        assert_eq!(key.weight(), 8 + 6 + 1);
+
+        // Parts are not confused by the lengths of their neighbours, unlike a plain concatenation.
+        let a = CompositeKey((b"ab".to_vec(), b"c".to_vec()));
+        let b = CompositeKey((b"a".to_vec(), b"bc".to_vec()));
+        assert_ne!(encode(a.clone()), encode(b.clone()));
+        assert_eq!(CompositeKey::<(Vec<u8>, Vec<u8>)>::read(&encode(b.clone())).unwrap(), b);
+        assert!(b < a);
+
+        // Truncated or trailing bytes are rejected.
+        let buf = encode(a);
+        assert!(CompositeKey::<(Vec<u8>, Vec<u8>)>::read(&buf[..buf.len() - 1]).is_err());
+        assert!(CompositeKey::<(Vec<u8>, Vec<u8>)>::read(&buf[..3]).is_err());
+        let mut trailing = buf.clone();
+        trailing.push(0);
+        assert!(CompositeKey::<(Vec<u8>, Vec<u8>)>::read(&trailing).is_err());
+    }
+
+    #[test]
+    fn test_composite_key_order() {
+        let mut keys = vec![
+            CompositeKey((2u32, 1u64)),
+            CompositeKey((1u32, u64::MAX)),
+            CompositeKey((1u32, 0u64)),
+            CompositeKey((2u32, 0u64)),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys.iter().map(|key| key.0).collect::<Vec<_>>(),
+            vec![(1, 0), (1, u64::MAX), (2, 0), (2, 1)]
+        );
+        // Fixed-size parts are encoded big-endian, so the encoded keys are ordered the same.
+        assert!(keys.windows(2).all(|w| encode(w[0].clone()) < encode(w[1].clone())));
+    }
+}
@@ -0,0 +1,34 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::future::Future;
+
+/// A minimal async key-value abstraction.
+///
+/// It lets foyer slot into frameworks that program against a generic async key-value store, without bespoke glue for
+/// each of them.
+pub trait AsyncKv: Send + Sync + 'static {
+    type Key: Send + Sync + 'static;
+    type Value: Send + Sync + 'static;
+    type Error: std::error::Error + Send + Sync + 'static;
+
+    /// Get the value of `key`, returns `None` if not found.
+    fn get(&self, key: &Self::Key) -> impl Future<Output = Result<Option<Self::Value>, Self::Error>> + Send;
+
+    /// Put the entry, returns `false` if the entry is rejected by the store.
+    fn put(&self, key: Self::Key, value: Self::Value) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Delete the entry of `key`, returns `true` if it exists.
+    fn delete(&self, key: &Self::Key) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+}
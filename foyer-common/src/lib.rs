@@ -23,9 +23,13 @@
 pub mod async_queue;
 pub mod batch;
 pub mod bits;
+pub mod buf;
+pub mod cancel;
+pub mod clock;
 pub mod code;
 pub mod continuum;
 pub mod erwlock;
+pub mod kv;
 pub mod range;
 pub mod rate;
 pub mod rated_ticket;
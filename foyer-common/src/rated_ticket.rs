@@ -19,11 +19,12 @@ use parking_lot::Mutex;
 #[derive(Debug)]
 pub struct RatedTicket {
     inner: Mutex<Inner>,
-    rate: f64,
 }
 
 #[derive(Debug)]
 struct Inner {
+    rate: f64,
+
     quota: f64,
 
     last: Instant,
@@ -32,37 +33,46 @@ struct Inner {
 impl RatedTicket {
     pub fn new(rate: f64) -> Self {
         let inner = Inner {
+            rate,
             quota: 0.0,
             last: Instant::now(),
         };
         Self {
-            rate,
             inner: Mutex::new(inner),
         }
     }
 
-    pub fn probe(&self) -> bool {
-        let mut inner = self.inner.lock();
+    pub fn rate(&self) -> f64 {
+        self.inner.lock().rate
+    }
 
-        let now = Instant::now();
-        let refill = now.duration_since(inner.last).as_secs_f64() * self.rate;
-        inner.last = now;
-        inner.quota = f64::min(inner.quota + refill, self.rate);
+    /// Change the rate, the quota refilled so far is kept.
+    pub fn set_rate(&self, rate: f64) {
+        let mut inner = self.inner.lock();
+        inner.refill();
+        inner.rate = rate;
+    }
 
+    pub fn probe(&self) -> bool {
+        let mut inner = self.inner.lock();
+        inner.refill();
         inner.quota > 0.0
     }
 
+    /// Returns the refilled quota without consuming it.
+    pub fn quota(&self) -> f64 {
+        let mut inner = self.inner.lock();
+        inner.refill();
+        inner.quota
+    }
+
     pub fn reduce(&self, weight: f64) {
         self.inner.lock().quota -= weight;
     }
 
     pub fn consume(&self, weight: f64) -> bool {
         let mut inner = self.inner.lock();
-
-        let now = Instant::now();
-        let refill = now.duration_since(inner.last).as_secs_f64() * self.rate;
-        inner.last = now;
-        inner.quota = f64::min(inner.quota + refill, self.rate);
+        inner.refill();
 
         if inner.quota <= 0.0 {
             return false;
@@ -74,6 +84,15 @@ impl RatedTicket {
     }
 }
 
+impl Inner {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let refill = now.duration_since(self.last).as_secs_f64() * self.rate;
+        self.last = now;
+        self.quota = f64::min(self.quota + refill, self.rate);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -67,11 +67,7 @@ where
     A: Adapter<Link = DlistLink>,
 {
     fn drop(&mut self) {
-        let mut iter = self.iter_mut();
-        iter.front();
-        while iter.is_valid() {
-            iter.remove();
-        }
+        self.drain();
         assert!(self.is_empty());
     }
 }
@@ -164,6 +160,31 @@ where
         self.len() == 0
     }
 
+    /// Retains only the items for which `f` returns `true`, visiting from front to back.
+    ///
+    /// Returns the removed pointers in list order.
+    pub fn retain<F>(&mut self, mut f: F) -> Vec<A::Pointer>
+    where
+        F: FnMut(&<A::Pointer as Pointer>::Item) -> bool,
+    {
+        let mut removed = vec![];
+        let mut iter = self.iter_mut();
+        iter.front();
+        while let Some(item) = iter.get() {
+            if f(item) {
+                iter.next();
+            } else {
+                removed.push(unsafe { iter.remove().unwrap_unchecked() });
+            }
+        }
+        removed
+    }
+
+    /// Removes all items, returns the removed pointers from front to back.
+    pub fn drain(&mut self) -> Vec<A::Pointer> {
+        self.retain(|_| false)
+    }
+
     /// Get the prev element of the given `link`.
     ///
     /// # Safety
@@ -541,8 +562,6 @@ where
     }
 }
 
-// TODO(MrCroxx): Need more tests.
-
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -621,6 +640,66 @@ mod tests {
         assert_eq!(l.len(), 0);
     }
 
+    #[test]
+    fn test_dlist_iter() {
+        let mut l = Dlist::<DlistAdapter>::new();
+        assert_eq!(l.iter().count(), 0);
+
+        for i in 0..5 {
+            l.push_back(Box::new(DlistItem::new(i)));
+        }
+        assert_eq!(l.iter().map(|item| item.val).collect_vec(), vec![0, 1, 2, 3, 4]);
+
+        for item in l.iter_mut() {
+            item.val *= 10;
+        }
+        assert_eq!(l.iter().map(|item| item.val).collect_vec(), vec![0, 10, 20, 30, 40]);
+
+        let mut iter = l.iter();
+        iter.back();
+        let mut v = vec![];
+        while let Some(item) = iter.get() {
+            v.push(item.val);
+            iter.prev();
+        }
+        assert_eq!(v, vec![40, 30, 20, 10, 0]);
+    }
+
+    #[test]
+    fn test_dlist_retain() {
+        let mut l = Dlist::<DlistAdapter>::new();
+        for i in 0..10 {
+            l.push_back(Box::new(DlistItem::new(i)));
+        }
+
+        let removed = l.retain(|item| item.val % 3 != 0);
+        assert_eq!(removed.iter().map(|item| item.val).collect_vec(), vec![0, 3, 6, 9]);
+        assert!(removed.iter().all(|item| !item.link.is_linked()));
+        assert_eq!(l.iter().map(|item| item.val).collect_vec(), vec![1, 2, 4, 5, 7, 8]);
+        assert_eq!(l.len(), 6);
+        assert_eq!(l.front().unwrap().val, 1);
+        assert_eq!(l.back().unwrap().val, 8);
+
+        let removed = l.retain(|_| true);
+        assert!(removed.is_empty());
+        assert_eq!(l.len(), 6);
+
+        // Removed items can be linked again.
+        for item in removed {
+            l.push_front(item);
+        }
+
+        let drained = l.drain();
+        assert_eq!(
+            drained.iter().map(|item| item.val).collect_vec(),
+            vec![1, 2, 4, 5, 7, 8]
+        );
+        assert!(l.is_empty());
+        assert!(l.front().is_none());
+        assert!(l.back().is_none());
+        assert!(l.retain(|_| false).is_empty());
+    }
+
     #[test]
     fn test_arc_drop() {
         let mut l = Dlist::<DlistArcAdapter>::new();
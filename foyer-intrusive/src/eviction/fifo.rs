@@ -28,7 +28,7 @@
 
 use std::{mem::ManuallyDrop, ptr::NonNull};
 
-use super::EvictionPolicy;
+use super::{EvictionPolicy, Head};
 use crate::{
     collections::dlist::{Dlist, DlistIter, DlistLink},
     core::{
@@ -74,6 +74,8 @@ where
     len: usize,
 
     adapter: A,
+
+    head: Head<<A as Adapter>::Pointer>,
 }
 
 impl<A> Drop for Fifo<A>
@@ -106,6 +108,8 @@ where
             len: 0,
 
             adapter: A::new(),
+
+            head: Head::new(None),
         }
     }
 
@@ -119,6 +123,8 @@ where
             self.queue.push_back(link);
 
             self.len += 1;
+
+            self.update_head();
         }
     }
 
@@ -133,6 +139,8 @@ where
 
             self.len -= 1;
 
+            self.update_head();
+
             A::Pointer::from_ptr(item.as_ptr())
         }
     }
@@ -154,6 +162,10 @@ where
             ptr: ManuallyDrop::new(None),
         }
     }
+
+    fn update_head(&mut self) {
+        self.head = Head::new(self.iter().next());
+    }
 }
 
 pub struct FifoIter<'a, A>
@@ -275,6 +287,10 @@ where
         self.len()
     }
 
+    fn peek(&self) -> Option<&A::Pointer> {
+        self.head.get()
+    }
+
     fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> {
         self.iter()
     }
@@ -327,6 +343,13 @@ mod tests {
         let v = fifo.iter().map(|item| item.key).collect_vec();
         assert_eq!(v, (5..10).collect_vec());
 
+        assert_eq!(fifo.peek().unwrap().key, 5);
+        fifo.remove(&items[5]);
+        assert_eq!(fifo.peek().unwrap().key, 6);
+        fifo.remove(&items[8]);
+        assert_eq!(fifo.iter().map(|item| item.key).collect_vec(), vec![6, 7, 9]);
+        assert_eq!(fifo.len(), 3);
+
         drop(fifo);
 
         for item in items {
@@ -35,7 +35,7 @@ use std::{
 use cmsketch::CMSketchUsize;
 use twox_hash::XxHash64;
 
-use super::EvictionPolicy;
+use super::{EvictionPolicy, Head};
 use crate::{
     collections::dlist::{Dlist, DlistIter, DlistLink},
     core::{
@@ -155,6 +155,8 @@ where
     config: LfuConfig,
 
     adapter: A,
+
+    head: Head<<A as Adapter>::Pointer>,
 }
 
 impl<A> Drop for Lfu<A>
@@ -195,6 +197,8 @@ where
             config,
 
             adapter: A::new(),
+
+            head: Head::new(None),
         };
         res.maybe_grow_access_counters();
         res
@@ -226,6 +230,8 @@ where
             self.maybe_grow_access_counters();
 
             self.len += 1;
+
+            self.update_head();
         }
     }
 
@@ -240,6 +246,8 @@ where
 
             self.len -= 1;
 
+            self.update_head();
+
             A::Pointer::from_ptr(item.as_ptr())
         }
     }
@@ -255,6 +263,8 @@ where
 
             self.update_frequencies(link);
         }
+
+        self.update_head();
     }
 
     fn len(&self) -> usize {
@@ -277,6 +287,10 @@ where
         }
     }
 
+    fn update_head(&mut self) {
+        self.head = Head::new(self.iter().next());
+    }
+
     fn maybe_grow_access_counters(&mut self) {
         let capacity = self.lru_tiny.len() + self.lru_main.len();
 
@@ -548,6 +562,10 @@ where
         self.len()
     }
 
+    fn peek(&self) -> Option<&A::Pointer> {
+        self.head.get()
+    }
+
     fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> {
         self.iter()
     }
@@ -617,6 +635,11 @@ mod tests {
             lfu.iter().map(|item| item.key).collect_vec()
         );
 
+        assert_eq!(lfu.peek().unwrap().key, 100);
+        lfu.remove(&items[100]);
+        assert_eq!(lfu.peek().unwrap().key, 1);
+        assert_eq!(lfu.len(), 100);
+
         drop(lfu);
 
         for item in items {
@@ -28,7 +28,7 @@
 
 use std::{mem::ManuallyDrop, ptr::NonNull};
 
-use super::EvictionPolicy;
+use super::{EvictionPolicy, Head};
 use crate::{
     collections::dlist::{Dlist, DlistIter, DlistLink},
     core::{
@@ -85,6 +85,8 @@ where
     config: LruConfig,
 
     adapter: A,
+
+    head: Head<<A as Adapter>::Pointer>,
 }
 
 impl<A> Drop for Lru<A>
@@ -121,6 +123,8 @@ where
             config,
 
             adapter: A::new(),
+
+            head: Head::new(None),
         }
     }
 
@@ -136,6 +140,8 @@ where
             self.update_lru_insertion_point();
 
             self.len += 1;
+
+            self.update_head();
         }
     }
 
@@ -158,6 +164,8 @@ where
 
             self.len -= 1;
 
+            self.update_head();
+
             A::Pointer::from_ptr(item.as_ptr())
         }
     }
@@ -179,6 +187,8 @@ where
                 self.update_lru_insertion_point();
             }
         }
+
+        self.update_head();
     }
 
     fn len(&self) -> usize {
@@ -195,6 +205,10 @@ where
         }
     }
 
+    fn update_head(&mut self) {
+        self.head = Head::new(self.iter().next());
+    }
+
     fn update_lru_insertion_point(&mut self) {
         unsafe {
             if self.config.lru_insertion_point_fraction == 0.0 {
@@ -398,6 +412,10 @@ where
         self.len()
     }
 
+    fn peek(&self) -> Option<&A::Pointer> {
+        self.head.get()
+    }
+
     fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> {
         self.iter()
     }
@@ -453,10 +471,18 @@ mod tests {
 
         assert_eq!(vec![0, 2, 1], lru.iter().map(|item| item.key).collect_vec());
 
+        assert_eq!(lru.peek().unwrap().key, 0);
+        assert_eq!(lru.len(), 3);
+
         lru.remove(&handles[2]);
 
         assert_eq!(vec![0, 1], lru.iter().map(|item| item.key).collect_vec());
 
+        lru.remove(&handles[0]);
+
+        assert_eq!(lru.peek().unwrap().key, 1);
+        assert_eq!(lru.len(), 1);
+
         drop(lru);
 
         for handle in handles {
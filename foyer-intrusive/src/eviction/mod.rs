@@ -12,9 +12,9 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, mem::ManuallyDrop};
 
-use crate::core::adapter::Adapter;
+use crate::core::{adapter::Adapter, pointer::Pointer};
 
 pub trait Config = Send + Sync + 'static + Debug + Clone;
 
@@ -26,16 +26,35 @@ pub trait EvictionPolicy: Send + Sync + Debug + 'static {
 
     fn insert(&mut self, ptr: <Self::Adapter as Adapter>::Pointer);
 
+    /// Remove the given `ptr` from the policy regardless of its eviction order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ptr` is not in the policy.
     fn remove(&mut self, ptr: &<Self::Adapter as Adapter>::Pointer) -> <Self::Adapter as Adapter>::Pointer;
 
     fn access(&mut self, ptr: &<Self::Adapter as Adapter>::Pointer);
 
+    /// Record access without exclusive access to the policy.
+    ///
+    /// Returns `false` if the policy does not support it, then [`EvictionPolicy::access`] must be used instead.
+    fn access_shared(&self, _ptr: &<Self::Adapter as Adapter>::Pointer) -> bool {
+        false
+    }
+
+    /// Count of the pointers in the policy.
     fn len(&self) -> usize;
 
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    /// Returns the pointer of the next victim without removing it from the policy.
+    fn peek(&self) -> Option<&<Self::Adapter as Adapter>::Pointer> {
+        self.iter().next()
+    }
+
+    /// Iterate the pointers in eviction order, the first one is the next victim.
     fn iter(&self) -> impl Iterator<Item = &'_ <Self::Adapter as Adapter>::Pointer> + '_;
 }
 
@@ -43,8 +62,6 @@ pub trait EvictionPolicyExt: EvictionPolicy {
     fn push(&mut self, ptr: <Self::Adapter as Adapter>::Pointer);
 
     fn pop(&mut self) -> Option<<Self::Adapter as Adapter>::Pointer>;
-
-    fn peek(&self) -> Option<&<Self::Adapter as Adapter>::Pointer>;
 }
 
 impl<E: EvictionPolicy> EvictionPolicyExt for E
@@ -63,13 +80,32 @@ where
         };
         ptr.map(|ptr| self.remove(&ptr))
     }
+}
+
+/// Next victim of a policy whose iterator yields pointers that only live as long as the iterator, so that `peek` can
+/// return a pointer that lives as long as the policy.
+///
+/// Must be refreshed whenever the order of the policy changes.
+pub(crate) struct Head<P: Pointer>(ManuallyDrop<Option<P>>);
+
+impl<P: Pointer> Head<P> {
+    pub(crate) fn new(ptr: Option<&P>) -> Self {
+        Self(ManuallyDrop::new(ptr.map(|ptr| unsafe { P::from_ptr(P::as_ptr(ptr)) })))
+    }
 
-    fn peek(&self) -> Option<&<Self::Adapter as Adapter>::Pointer> {
-        self.iter().next()
+    pub(crate) fn get(&self) -> Option<&P> {
+        self.0.as_ref()
+    }
+}
+
+impl<P: Pointer> Debug for Head<P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Head").field(&self.0.as_ref().map(P::as_ptr)).finish()
     }
 }
 
 pub mod fifo;
 pub mod lfu;
 pub mod lru;
+pub mod sampling;
 pub mod sfifo;
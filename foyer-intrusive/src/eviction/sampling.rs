@@ -0,0 +1,383 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Debug,
+    ptr::NonNull,
+    sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+};
+
+use super::EvictionPolicy;
+use crate::core::{
+    adapter::{Adapter, Link},
+    pointer::Pointer,
+};
+
+/// Marks that no victim is sampled since the last change of the policy.
+const NO_VICTIM: usize = usize::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SamplingScore {
+    /// Evict the least recently accessed sample.
+    Age,
+    /// Evict the least frequently accessed sample, break ties by age.
+    AccessCount,
+}
+
+#[derive(Debug, Clone)]
+pub struct SamplingConfig {
+    /// Count of random samples to pick the victim from.
+    pub samples: usize,
+
+    /// Score to compare samples with.
+    pub score: SamplingScore,
+}
+
+#[derive(Debug, Default)]
+pub struct SamplingLink {
+    linked: AtomicBool,
+
+    /// Position in the item vector.
+    index: AtomicUsize,
+
+    /// Logical time of the last access or insertion.
+    last_access: AtomicU64,
+
+    accesses: AtomicU64,
+}
+
+impl Link for SamplingLink {
+    fn is_linked(&self) -> bool {
+        self.linked.load(Ordering::Relaxed)
+    }
+}
+
+/// Sampling policy
+///
+/// Picks the victim with the worst score among a few random samples instead of maintaining an ordered list, so
+/// recording an access only updates atomics of the link and does not require exclusive access to the policy.
+pub struct Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+    items: Vec<A::Pointer>,
+
+    config: SamplingConfig,
+
+    /// Logical clock, advanced by insertions and accesses.
+    clock: AtomicU64,
+
+    /// State of the xorshift random generator.
+    rng: AtomicU64,
+
+    /// Index of the sampled victim, kept until the policy changes so that `peek` and `pop` pick the same one.
+    victim: AtomicUsize,
+
+    adapter: A,
+}
+
+impl<A> Debug for Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sampling")
+            .field("len", &self.items.len())
+            .field("config", &self.config)
+            .field("clock", &self.clock)
+            .finish()
+    }
+}
+
+impl<A> Drop for Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+    fn drop(&mut self) {
+        for ptr in std::mem::take(&mut self.items) {
+            self.link(&ptr).linked.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<A> Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+    pub fn new(config: SamplingConfig) -> Self {
+        Self {
+            items: vec![],
+            config,
+            clock: AtomicU64::new(0),
+            rng: AtomicU64::new(0x9E37_79B9_7F4A_7C15),
+            victim: AtomicUsize::new(NO_VICTIM),
+            adapter: A::new(),
+        }
+    }
+
+    fn insert(&mut self, ptr: A::Pointer) {
+        let index = self.items.len();
+        let tick = self.tick();
+
+        let link = self.link(&ptr);
+        assert!(!link.is_linked());
+        link.index.store(index, Ordering::Relaxed);
+        link.last_access.store(tick, Ordering::Relaxed);
+        link.accesses.store(0, Ordering::Relaxed);
+        link.linked.store(true, Ordering::Relaxed);
+
+        self.items.push(ptr);
+        self.victim.store(NO_VICTIM, Ordering::Relaxed);
+    }
+
+    fn remove(&mut self, ptr: &A::Pointer) -> A::Pointer {
+        let link = self.link(ptr);
+        assert!(link.is_linked());
+        let index = link.index.load(Ordering::Relaxed);
+        link.linked.store(false, Ordering::Relaxed);
+
+        let removed = self.items.swap_remove(index);
+        debug_assert_eq!(A::Pointer::as_ptr(&removed), A::Pointer::as_ptr(ptr));
+        if let Some(moved) = self.items.get(index) {
+            self.link(moved).index.store(index, Ordering::Relaxed);
+        }
+        self.victim.store(NO_VICTIM, Ordering::Relaxed);
+
+        removed
+    }
+
+    fn access(&self, ptr: &A::Pointer) {
+        let link = self.link(ptr);
+        if link.is_linked() {
+            link.last_access.store(self.tick(), Ordering::Relaxed);
+            link.accesses.fetch_add(1, Ordering::Relaxed);
+            self.victim.store(NO_VICTIM, Ordering::Relaxed);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Iterate items with the sampled victim first, others in no particular order.
+    fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> + '_ {
+        let victim = self.victim();
+        victim.map(|index| &self.items[index]).into_iter().chain(
+            self.items
+                .iter()
+                .enumerate()
+                .filter(move |(index, _)| Some(*index) != victim)
+                .map(|(_, ptr)| ptr),
+        )
+    }
+
+    fn victim(&self) -> Option<usize> {
+        if self.items.is_empty() {
+            return None;
+        }
+        let victim = self.victim.load(Ordering::Relaxed);
+        if victim != NO_VICTIM {
+            return Some(victim);
+        }
+        let samples = self.config.samples.clamp(1, self.items.len());
+        let victim = (0..samples)
+            .map(|_| self.random() as usize % self.items.len())
+            .min_by_key(|index| self.score(&self.items[*index]))
+            .unwrap();
+        self.victim.store(victim, Ordering::Relaxed);
+        Some(victim)
+    }
+
+    /// The smaller, the more likely to be evicted.
+    fn score(&self, ptr: &A::Pointer) -> (u64, u64) {
+        let link = self.link(ptr);
+        let last_access = link.last_access.load(Ordering::Relaxed);
+        match self.config.score {
+            SamplingScore::Age => (last_access, 0),
+            SamplingScore::AccessCount => (link.accesses.load(Ordering::Relaxed), last_access),
+        }
+    }
+
+    fn link(&self, ptr: &A::Pointer) -> &SamplingLink {
+        unsafe {
+            let item = NonNull::new_unchecked(A::Pointer::as_ptr(ptr) as *mut _);
+            self.adapter.item2link(item).as_ref()
+        }
+    }
+
+    fn tick(&self) -> u64 {
+        self.clock.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// xorshift64*, racing updates only affect randomness.
+    fn random(&self) -> u64 {
+        let mut x = self.rng.load(Ordering::Relaxed);
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.rng.store(x, Ordering::Relaxed);
+        x.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+// unsafe impl `Send + Sync` for structs with `NonNull` usage
+
+unsafe impl<A> Send for Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+}
+
+unsafe impl<A> Sync for Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+}
+
+impl<A> EvictionPolicy for Sampling<A>
+where
+    A: Adapter<Link = SamplingLink>,
+    <A as Adapter>::Pointer: Clone,
+{
+    type Adapter = A;
+    type Config = SamplingConfig;
+
+    fn new(config: Self::Config) -> Self {
+        Self::new(config)
+    }
+
+    fn insert(&mut self, ptr: A::Pointer) {
+        self.insert(ptr)
+    }
+
+    fn remove(&mut self, ptr: &A::Pointer) -> A::Pointer {
+        self.remove(ptr)
+    }
+
+    fn access(&mut self, ptr: &A::Pointer) {
+        Sampling::access(self, ptr)
+    }
+
+    fn access_shared(&self, ptr: &A::Pointer) -> bool {
+        Sampling::access(self, ptr);
+        true
+    }
+
+    fn len(&self) -> usize {
+        self.len()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{eviction::EvictionPolicyExt, intrusive_adapter};
+
+    #[derive(Debug)]
+    struct SamplingItem {
+        link: SamplingLink,
+        key: u64,
+    }
+
+    impl SamplingItem {
+        fn new(key: u64) -> Self {
+            Self {
+                link: SamplingLink::default(),
+                key,
+            }
+        }
+    }
+
+    intrusive_adapter! { SamplingItemAdapter = Arc<SamplingItem>: SamplingItem { link: SamplingLink } }
+
+    #[test]
+    fn test_sampling_simple() {
+        let config = SamplingConfig {
+            samples: 64,
+            score: SamplingScore::AccessCount,
+        };
+        let mut sampling = Sampling::<SamplingItemAdapter>::new(config);
+
+        let items = (0..4).map(|key| Arc::new(SamplingItem::new(key))).collect_vec();
+        for item in items.iter() {
+            sampling.push(item.clone());
+        }
+        for (key, item) in items.iter().enumerate() {
+            for _ in 0..key {
+                assert!(sampling.access_shared(item));
+            }
+        }
+        assert_eq!(sampling.len(), 4);
+        assert_eq!(
+            sampling.iter().map(|item| item.key).sorted().collect_vec(),
+            vec![0, 1, 2, 3]
+        );
+
+        // With enough samples, the least accessed items are evicted first.
+        let v = (0..2)
+            .map(|_| sampling.pop().unwrap())
+            .map(|item| item.key)
+            .collect_vec();
+        assert_eq!(v, vec![0, 1]);
+        assert!(!items[0].link.is_linked());
+
+        sampling.remove(&items[3]);
+        assert_eq!(sampling.pop().unwrap().key, 2);
+        assert!(sampling.is_empty());
+
+        sampling.push(items[0].clone());
+        drop(sampling);
+
+        for item in items {
+            assert_eq!(Arc::strong_count(&item), 1);
+            assert!(!item.link.is_linked());
+        }
+    }
+
+    #[test]
+    fn test_sampling_peek_pop() {
+        let config = SamplingConfig {
+            samples: 2,
+            score: SamplingScore::Age,
+        };
+        let mut sampling = Sampling::<SamplingItemAdapter>::new(config);
+
+        let items = (0..16).map(|key| Arc::new(SamplingItem::new(key))).collect_vec();
+        for item in items.iter() {
+            sampling.push(item.clone());
+        }
+
+        // Only a few samples are taken, the victim must not be re-sampled between `peek` and `pop`.
+        while !sampling.is_empty() {
+            let key = sampling.peek().unwrap().key;
+            assert_eq!(sampling.peek().unwrap().key, key);
+            assert_eq!(sampling.iter().next().unwrap().key, key);
+            assert_eq!(sampling.pop().unwrap().key, key);
+        }
+    }
+}
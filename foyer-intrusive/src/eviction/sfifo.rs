@@ -30,7 +30,7 @@ use std::{mem::ManuallyDrop, ptr::NonNull};
 
 use itertools::Itertools;
 
-use super::EvictionPolicy;
+use super::{EvictionPolicy, Head};
 use crate::{
     collections::dlist::{Dlist, DlistIter, DlistLink},
     core::{
@@ -117,6 +117,8 @@ where
     len: usize,
 
     adapter: A,
+
+    head: Head<<A as Adapter>::Pointer>,
 }
 
 impl<A> Drop for SegmentedFifo<A>
@@ -153,6 +155,8 @@ where
             len: 0,
 
             adapter: A::new(),
+
+            head: Head::new(None),
         }
     }
 
@@ -171,6 +175,8 @@ where
             self.rebalance();
 
             self.len += 1;
+
+            self.update_head();
         }
     }
 
@@ -191,6 +197,8 @@ where
 
             self.len -= 1;
 
+            self.update_head();
+
             A::Pointer::from_ptr(item.as_ptr())
         }
     }
@@ -236,6 +244,10 @@ where
             ptr: ManuallyDrop::new(None),
         }
     }
+
+    fn update_head(&mut self) {
+        self.head = Head::new(self.iter().next());
+    }
 }
 
 pub struct SegmentedFifoIter<'a, A>
@@ -366,6 +378,10 @@ where
         self.len()
     }
 
+    fn peek(&self) -> Option<&A::Pointer> {
+        self.head.get()
+    }
+
     fn iter(&self) -> impl Iterator<Item = &'_ A::Pointer> {
         self.iter()
     }
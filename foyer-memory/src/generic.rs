@@ -36,6 +36,16 @@ use crate::{
     Key, Value,
 };
 
+/// An entry released by a cache shard.
+struct ReleasedEntry<K, V, C> {
+    key: K,
+    value: V,
+    context: C,
+    charges: usize,
+    /// The count of accesses if the entry is released because of eviction.
+    evicted: Option<usize>,
+}
+
 struct CacheSharedState<T, L> {
     metrics: Metrics,
     /// The object pool to avoid frequent handle allocating, shared by all shards.
@@ -102,7 +112,7 @@ where
         value: V,
         charge: usize,
         context: H::Context,
-        last_reference_entries: &mut Vec<(K, V, H::Context, usize)>,
+        last_reference_entries: &mut Vec<ReleasedEntry<K, V, H::Context>>,
     ) -> NonNull<H> {
         let mut handle = self.state.object_pool.pop().unwrap_or_else(|| Box::new(H::new()));
         handle.init(hash, key, value, charge, context);
@@ -152,6 +162,7 @@ where
         debug_assert!(base.is_in_indexer());
 
         base.inc_refs();
+        base.inc_accesses();
         self.eviction.access(ptr);
 
         Some(ptr)
@@ -160,7 +171,7 @@ where
     /// Remove a key from the cache.
     ///
     /// Return `Some(..)` if the handle is released, or `None` if the handle is still in use.
-    unsafe fn remove(&mut self, hash: u64, key: &K) -> Option<(K, V, H::Context, usize)> {
+    unsafe fn remove(&mut self, hash: u64, key: &K) -> Option<ReleasedEntry<K, V, H::Context>> {
         let ptr = self.indexer.remove(hash, key)?;
         self.state.metrics.remove.fetch_add(1, Ordering::Relaxed);
         if ptr.as_ref().base().is_in_eviction() {
@@ -172,7 +183,7 @@ where
     }
 
     /// Clear all cache entries.
-    unsafe fn clear(&mut self, last_reference_entries: &mut Vec<(K, V, H::Context, usize)>) {
+    unsafe fn clear(&mut self, last_reference_entries: &mut Vec<ReleasedEntry<K, V, H::Context>>) {
         // TODO(MrCroxx): Avoid collecting here?
         let ptrs = self.indexer.drain().collect_vec();
         let eptrs = self.eviction.clear();
@@ -197,7 +208,7 @@ where
         }
     }
 
-    unsafe fn evict(&mut self, charge: usize, last_reference_entries: &mut Vec<(K, V, H::Context, usize)>) {
+    unsafe fn evict(&mut self, charge: usize, last_reference_entries: &mut Vec<ReleasedEntry<K, V, H::Context>>) {
         while self.usage.load(Ordering::Relaxed) + charge > self.capacity
             && let Some(evicted) = self.eviction.pop()
         {
@@ -214,7 +225,7 @@ where
     /// Release a handle used by an external user.
     ///
     /// Return `Some(..)` if the handle is released, or `None` if the handle is still in use.
-    unsafe fn try_release_external_handle(&mut self, mut ptr: NonNull<H>) -> Option<(K, V, H::Context, usize)> {
+    unsafe fn try_release_external_handle(&mut self, mut ptr: NonNull<H>) -> Option<ReleasedEntry<K, V, H::Context>> {
         ptr.as_mut().base_mut().dec_refs();
         self.try_release_handle(ptr, true)
     }
@@ -224,7 +235,11 @@ where
     /// Return the entry if the handle is released.
     ///
     /// Recycle it if possible.
-    unsafe fn try_release_handle(&mut self, mut ptr: NonNull<H>, reinsert: bool) -> Option<(K, V, H::Context, usize)> {
+    unsafe fn try_release_handle(
+        &mut self,
+        mut ptr: NonNull<H>,
+        reinsert: bool,
+    ) -> Option<ReleasedEntry<K, V, H::Context>> {
         let base = ptr.as_mut().base_mut();
 
         if base.has_refs() {
@@ -234,9 +249,12 @@ where
         debug_assert!(base.is_inited());
         debug_assert!(!base.has_refs());
 
+        // The entry is released because of eviction only if it is not updated, removed or cleared from the cache.
+        let evicted = base.is_in_indexer();
+
         // If the entry is not updated or removed from the cache, try to reinsert it or remove it from the indexer and
         // the eviction container.
-        if base.is_in_indexer() {
+        if evicted {
             // The usage is higher than the capacity means most handles are held externally,
             // the cache shard cannot release enough charges for the new inserted entries.
             // In this case, the reinsertion should be given up.
@@ -266,12 +284,19 @@ where
         self.state.metrics.release.fetch_add(1, Ordering::Relaxed);
 
         self.usage.fetch_sub(base.charge(), Ordering::Relaxed);
-        let entry = base.take();
+        let accesses = base.accesses();
+        let (key, value, context, charges) = base.take();
 
         let handle = Box::from_raw(ptr.as_ptr());
         let _ = self.state.object_pool.push(handle);
 
-        Some(entry)
+        Some(ReleasedEntry {
+            key,
+            value,
+            context,
+            charges,
+            evicted: evicted.then_some(accesses),
+        })
     }
 }
 
@@ -459,8 +484,8 @@ where
         }
 
         // Do not deallocate data within the lock section.
-        for (key, value, context, charges) in to_deallocate {
-            self.context.listener.on_release(key, value, context.into(), charges)
+        for entry in to_deallocate {
+            self.release(entry);
         }
 
         entry
@@ -475,8 +500,8 @@ where
         };
 
         // Do not deallocate data within the lock section.
-        if let Some((key, value, context, charges)) = entry {
-            self.context.listener.on_release(key, value, context.into(), charges);
+        if let Some(entry) = entry {
+            self.release(entry);
         }
     }
 
@@ -512,6 +537,23 @@ where
         &self.context.metrics
     }
 
+    fn release(&self, entry: ReleasedEntry<K, V, H::Context>) {
+        let ReleasedEntry {
+            key,
+            value,
+            context,
+            charges,
+            evicted,
+        } = entry;
+        match evicted {
+            Some(accesses) => self
+                .context
+                .listener
+                .on_evict(key, value, context.into(), charges, accesses),
+            None => self.context.listener.on_release(key, value, context.into(), charges),
+        }
+    }
+
     unsafe fn try_release_external_handle(&self, ptr: NonNull<H>) {
         let entry = {
             let base = ptr.as_ref().base();
@@ -520,8 +562,8 @@ where
         };
 
         // Do not deallocate data within the lock section.
-        if let Some((key, value, context, charges)) = entry {
-            self.context.listener.on_release(key, value, context.into(), charges);
+        if let Some(entry) = entry {
+            self.release(entry);
         }
     }
 }
@@ -946,4 +988,49 @@ mod tests {
         // For cache policy like FIFO, the entries will not be reinserted while all handles are referenced.
         // It's okay for this is not a common situation and is not supposed to happen in real workload.
     }
+
+    #[derive(Default)]
+    struct RecordingListener {
+        released: Mutex<Vec<u64>>,
+        evicted: Mutex<Vec<(u64, usize)>>,
+    }
+
+    impl CacheEventListener<u64, String> for Arc<RecordingListener> {
+        fn on_release(&self, key: u64, _value: String, _context: CacheContext, _charges: usize) {
+            self.released.lock().push(key);
+        }
+
+        fn on_evict(&self, key: u64, _value: String, _context: CacheContext, _charges: usize, accesses: usize) {
+            self.evicted.lock().push((key, accesses));
+        }
+    }
+
+    #[test]
+    fn test_evict_listener() {
+        let listener = Arc::new(RecordingListener::default());
+        let config = FifoCacheConfig {
+            capacity: 2,
+            shards: 1,
+            eviction_config: FifoConfig {},
+            object_pool_capacity: 1,
+            hash_builder: RandomState::default(),
+            event_listener: listener.clone(),
+        };
+        let cache = Arc::new(FifoCache::<u64, String, Arc<RecordingListener>>::new(config));
+
+        cache.insert(1, "a".to_string(), 1);
+        cache.insert(2, "b".to_string(), 1);
+        for _ in 0..3 {
+            drop(cache.get(&1).unwrap());
+        }
+
+        // replaced and removed entries are released but not evicted
+        cache.insert(2, "c".to_string(), 1);
+        cache.remove(&2);
+        assert_eq!(*listener.released.lock(), vec![2, 2]);
+
+        cache.insert(3, "d".to_string(), 1);
+        cache.insert(4, "e".to_string(), 1);
+        assert_eq!(*listener.evicted.lock(), vec![(1, 3)]);
+    }
 }
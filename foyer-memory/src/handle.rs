@@ -50,6 +50,8 @@ where
     charge: usize,
     /// external reference count
     refs: usize,
+    /// count of hits since inited
+    accesses: usize,
     /// flags that used by the general cache abstraction
     flags: BaseHandleFlags,
 }
@@ -77,6 +79,7 @@ where
             hash: 0,
             charge: 0,
             refs: 0,
+            accesses: 0,
             flags: BaseHandleFlags::empty(),
         }
     }
@@ -89,6 +92,7 @@ where
         self.entry = Some((key, value, context));
         self.charge = charge;
         self.refs = 0;
+        self.accesses = 0;
         self.flags = BaseHandleFlags::empty();
     }
 
@@ -185,6 +189,18 @@ where
         self.refs
     }
 
+    /// Increase the count of hits on the handle.
+    #[inline(always)]
+    pub fn inc_accesses(&mut self) {
+        self.accesses += 1;
+    }
+
+    /// Get the count of hits on the handle since inited.
+    #[inline(always)]
+    pub fn accesses(&self) -> usize {
+        self.accesses
+    }
+
     /// Return `true` if there are external references.
     #[inline(always)]
     pub fn has_refs(&self) -> bool {
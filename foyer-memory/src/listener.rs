@@ -25,6 +25,15 @@ where
     ///
     /// The arguments includes the key and value with ownership.
     fn on_release(&self, key: K, value: V, context: CacheContext, charges: usize);
+
+    /// The function is called instead of [`CacheEventListener::on_release`] if the entry is released because it is
+    /// evicted by the eviction algorithm, rather than removed, replaced or cleared.
+    ///
+    /// `accesses` is the count of hits on the entry while it was cached.
+    fn on_evict(&self, key: K, value: V, context: CacheContext, charges: usize, accesses: usize) {
+        let _ = accesses;
+        self.on_release(key, value, context, charges)
+    }
 }
 
 pub struct DefaultCacheEventListener<K, V>(PhantomData<(K, V)>)
@@ -0,0 +1,198 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! Runs a fixed battery of workloads against a directory on the device to qualify, and prints whether the device
+//! meets the thresholds.
+//!
+//! The battery runs in order on a single store:
+//!
+//! 1. sequential fill: write half of the capacity.
+//! 2. random read: look up random entries of the fill.
+//! 3. mixed: look up entries of the fill and write new entries at the same rate.
+//! 4. crash recovery: kill a writer process, recover the store and verify every recovered entry.
+
+mod report;
+mod workload;
+
+use std::{fs::create_dir_all, path::PathBuf, process::exit, time::Duration};
+
+use clap::Parser;
+use foyer_intrusive::eviction::lfu::LfuConfig;
+use foyer_storage::{
+    device::{
+        fs::{FsDeviceConfig, FsReadMode},
+        ReadIoPolicy,
+    },
+    generic::RecoveryMode,
+    store::LfuFsStoreConfig,
+};
+use report::Report;
+use tracing_subscriber::EnvFilter;
+
+#[derive(Parser, Debug, Clone)]
+#[command(author, version, about)]
+pub struct Args {
+    /// dir for cache data on the device to qualify, must be empty
+    #[arg(short, long)]
+    dir: String,
+
+    /// (MiB)
+    #[arg(long, default_value_t = 1024)]
+    capacity: usize,
+
+    /// (MiB)
+    #[arg(long, default_value_t = 64)]
+    region_size: usize,
+
+    #[arg(long, default_value_t = 4096)]
+    align: usize,
+
+    #[arg(long, default_value_t = 16 * 1024)]
+    io_size: usize,
+
+    /// (KiB)
+    #[arg(long, default_value_t = 64)]
+    entry_size: usize,
+
+    /// concurrent tasks of each workload
+    #[arg(long, default_value_t = 16)]
+    concurrency: usize,
+
+    /// duration of the random read and mixed workloads, and of the writes before the crash (s)
+    #[arg(short, long, default_value_t = 30)]
+    time: u64,
+
+    #[arg(long, default_value_t = 4)]
+    flushers: usize,
+
+    #[arg(long, default_value_t = 4)]
+    reclaimers: usize,
+
+    /// verify entries on recovery
+    #[arg(long, default_value_t = false)]
+    recovery_verify: bool,
+
+    /// min write throughput of the sequential fill, 0 means unchecked (MiB/s)
+    #[arg(long, default_value_t = 0.0)]
+    min_fill_mbps: f64,
+
+    /// min lookups per second of the random read, 0 means unchecked
+    #[arg(long, default_value_t = 0.0)]
+    min_read_iops: f64,
+
+    /// max p99 lookup latency of the random read, 0 means unchecked (us)
+    #[arg(long, default_value_t = 0)]
+    max_read_p99_us: u64,
+
+    /// min operations per second of the mixed workload, 0 means unchecked
+    #[arg(long, default_value_t = 0.0)]
+    min_mixed_iops: f64,
+
+    /// max time to recover the store after the crash, 0 means unchecked (ms)
+    #[arg(long, default_value_t = 0)]
+    max_recovery_ms: u64,
+
+    /// run as the writer process killed by the crash recovery workload
+    #[arg(long, hide = true, default_value_t = false)]
+    crash_writer: bool,
+}
+
+impl Args {
+    /// Count of entries written by the sequential fill, half of the capacity so that none is evicted.
+    pub fn entries(&self) -> u64 {
+        (self.capacity * 1024 / self.entry_size / 2) as u64
+    }
+
+    pub fn entry_bytes(&self) -> usize {
+        self.entry_size * 1024
+    }
+}
+
+pub fn store_config(args: &Args) -> LfuFsStoreConfig<u64, Vec<u8>> {
+    LfuFsStoreConfig {
+        flushers: args.flushers,
+        reclaimers: args.reclaimers,
+        recover_concurrency: 16,
+        clean_region_threshold: args.reclaimers,
+        // Writers back off while the flushers fall behind, so writes run at the speed of the device.
+        write_stall_threshold: Some(Duration::from_millis(100)),
+        recovery_mode: if args.recovery_verify {
+            RecoveryMode::Verify
+        } else {
+            RecoveryMode::Trust
+        },
+        ..LfuFsStoreConfig::new(
+            "foyer-qualify",
+            LfuConfig {
+                window_to_cache_size_ratio: 1,
+                tiny_lru_capacity_ratio: 0.01,
+            },
+            FsDeviceConfig {
+                dir: PathBuf::from(&args.dir),
+                capacity: args.capacity * 1024 * 1024,
+                file_capacity: args.region_size * 1024 * 1024,
+                align: args.align,
+                io_size: args.io_size,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
+    }
+}
+
+fn init_logger() {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+}
+
+#[tokio::main]
+async fn main() {
+    init_logger();
+
+    let args = Args::parse();
+
+    if args.crash_writer {
+        workload::crash_writer(&args).await;
+        return;
+    }
+
+    println!("{:#?}", args);
+
+    assert!(args.entries() > 0, "\"--capacity\" must hold at least 2 entries");
+    create_dir_all(&args.dir).unwrap();
+    assert!(
+        std::fs::read_dir(&args.dir).unwrap().next().is_none(),
+        "\"--dir\" must be empty, the battery starts from an empty cache"
+    );
+
+    let reports = vec![
+        workload::sequential_fill(&args).await,
+        workload::random_read(&args).await,
+        workload::mixed(&args).await,
+        workload::crash_recovery(&args).await,
+    ];
+
+    println!("\n{}", Report::header());
+    for report in reports.iter() {
+        println!("{report}");
+    }
+
+    let passed = reports.iter().all(Report::passed);
+    println!("\n{}", if passed { "PASS" } else { "FAIL" });
+    if !passed {
+        exit(1);
+    }
+}
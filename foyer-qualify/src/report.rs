@@ -0,0 +1,164 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{fmt::Display, time::Duration};
+
+use hdrhistogram::Histogram;
+
+/// Errors reported per workload, the rest are counted.
+const MAX_REPORTED_ERRORS: usize = 8;
+
+/// Latency histogram (us).
+pub fn histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 60 * 1000 * 1000, 3).unwrap()
+}
+
+/// Measurements of a task of a workload.
+#[derive(Debug)]
+pub struct Stats {
+    /// Completed operations.
+    pub ops: u64,
+    /// Value bytes written or read.
+    pub bytes: u64,
+    /// Lookups that found an entry.
+    pub hits: u64,
+    /// Lookups that found no entry.
+    pub misses: u64,
+    /// Inserts rejected by the store.
+    pub shed: u64,
+    /// Operation latency (us).
+    pub latency: Histogram<u64>,
+    pub errors: Vec<String>,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            ops: 0,
+            bytes: 0,
+            hits: 0,
+            misses: 0,
+            shed: 0,
+            latency: histogram(),
+            errors: vec![],
+        }
+    }
+}
+
+impl Stats {
+    pub fn record(&mut self, latency: Duration, bytes: usize) {
+        self.ops += 1;
+        self.bytes += bytes as u64;
+        self.latency.saturating_record(latency.as_micros() as u64);
+    }
+
+    pub fn merge(&mut self, other: Stats) {
+        self.ops += other.ops;
+        self.bytes += other.bytes;
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.shed += other.shed;
+        self.latency.add(other.latency).unwrap();
+        self.errors.extend(other.errors);
+    }
+
+    pub fn hit_ratio(&self) -> f64 {
+        self.hits as f64 / (self.hits + self.misses).max(1) as f64
+    }
+}
+
+/// Outcome of a workload of the battery.
+#[derive(Debug)]
+pub struct Report {
+    pub name: &'static str,
+    pub elapsed: Duration,
+    pub stats: Stats,
+    /// Measurements besides throughput and latency, e.g. hit ratio.
+    pub notes: Vec<String>,
+    /// Unmet thresholds and errors.
+    pub failures: Vec<String>,
+}
+
+impl Report {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            elapsed: Duration::ZERO,
+            stats: Stats::default(),
+            notes: vec![],
+            failures: vec![],
+        }
+    }
+
+    /// Take the measurements of the workload, its errors fail it.
+    pub fn finish(&mut self, elapsed: Duration, stats: Stats) {
+        self.elapsed = elapsed;
+        let errors = stats.errors.len();
+        self.failures
+            .extend(stats.errors.iter().take(MAX_REPORTED_ERRORS).cloned());
+        if errors > MAX_REPORTED_ERRORS {
+            self.failures
+                .push(format!("... and {} more errors", errors - MAX_REPORTED_ERRORS));
+        }
+        self.stats = stats;
+    }
+
+    pub fn iops(&self) -> f64 {
+        self.stats.ops as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Throughput (MiB/s).
+    pub fn mbps(&self) -> f64 {
+        self.stats.bytes as f64 / 1024.0 / 1024.0 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    /// Fail the workload with `failure` unless `ok`.
+    pub fn check(&mut self, ok: bool, failure: impl Into<String>) {
+        if !ok {
+            self.failures.push(failure.into());
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    pub fn header() -> String {
+        format!(
+            "{:<16} {:<6} {:>12} {:>10} {:>10} {:>10} {:>10}  notes",
+            "workload", "result", "ops/s", "MiB/s", "p50(us)", "p99(us)", "max(us)"
+        )
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<16} {:<6} {:>12.1} {:>10.1} {:>10} {:>10} {:>10}  {}",
+            self.name,
+            if self.passed() { "PASS" } else { "FAIL" },
+            self.iops(),
+            self.mbps(),
+            self.stats.latency.value_at_quantile(0.5),
+            self.stats.latency.value_at_quantile(0.99),
+            self.stats.latency.max(),
+            self.notes.join(", "),
+        )?;
+        for failure in self.failures.iter() {
+            write!(f, "\n    - {failure}")?;
+        }
+        Ok(())
+    }
+}
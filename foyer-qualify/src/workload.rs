@@ -0,0 +1,326 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    process::{Command, Stdio},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use foyer_storage::{error::Result, storage::Storage, store::LfuFsStore};
+use futures::{future::join_all, Future};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    report::{Report, Stats},
+    store_config, Args,
+};
+
+type QualifyStore = LfuFsStore<u64, Vec<u8>>;
+
+/// Keys written by the crash writer start here, apart from the keys of the other workloads.
+const CRASH_KEY_BASE: u64 = 1 << 48;
+
+/// Backoff of a writer while the store sheds inserts.
+const SHED_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Content of the value of `key`, lookups verify values against it.
+fn value(key: u64, len: usize) -> Vec<u8> {
+    let mut value = vec![0; len];
+    for (i, chunk) in value.chunks_mut(8).enumerate() {
+        let word = key.wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ i as u64;
+        chunk.copy_from_slice(&word.to_le_bytes()[..chunk.len()]);
+    }
+    value
+}
+
+async fn open(args: &Args) -> QualifyStore {
+    QualifyStore::open(store_config(args)).await.unwrap()
+}
+
+/// Run `task` with the index of each of the concurrent tasks, and merge their measurements.
+async fn run_tasks<F, FU>(concurrency: usize, task: F) -> Stats
+where
+    F: Fn(usize) -> FU,
+    FU: Future<Output = Stats> + Send + 'static,
+{
+    let handles = (0..concurrency).map(|i| tokio::spawn(task(i))).collect::<Vec<_>>();
+    let mut stats = Stats::default();
+    for res in join_all(handles).await {
+        stats.merge(res.unwrap());
+    }
+    stats
+}
+
+/// Insert the entry of `key`, retrying while the store sheds inserts. Returns the latency of the accepted insert.
+async fn insert(store: &QualifyStore, key: u64, len: usize) -> Result<Duration> {
+    loop {
+        let now = Instant::now();
+        if store.insert(key, value(key, len)).await? {
+            let latency = now.elapsed();
+            // An insert only enqueues the entry, give way to the flushers.
+            tokio::task::yield_now().await;
+            return Ok(latency);
+        }
+        tokio::time::sleep(SHED_BACKOFF).await;
+    }
+}
+
+/// Look up the entry of `key` and verify its value.
+async fn lookup(store: &QualifyStore, key: u64, len: usize, stats: &mut Stats) {
+    let now = Instant::now();
+    match store.lookup(&key).await {
+        Ok(Some(v)) => {
+            stats.record(now.elapsed(), v.len());
+            stats.hits += 1;
+            if v != value(key, len) {
+                stats.errors.push(format!("corrupted value of key {key}"));
+            }
+        }
+        Ok(None) => {
+            stats.record(now.elapsed(), 0);
+            stats.misses += 1;
+        }
+        Err(e) => stats.errors.push(format!("lookup error of key {key}: {e}")),
+    }
+}
+
+/// Write half of the capacity. Entries are written to the device when the store is closed at the latest, so the
+/// close is measured as well.
+pub async fn sequential_fill(args: &Args) -> Report {
+    let mut report = Report::new("sequential-fill");
+    let store = open(args).await;
+
+    let now = Instant::now();
+    let mut stats = run_tasks(args.concurrency, |i| {
+        let store = store.clone();
+        let (entries, concurrency, len) = (args.entries(), args.concurrency, args.entry_bytes());
+        async move {
+            let mut stats = Stats::default();
+            for key in (i as u64..entries).step_by(concurrency) {
+                match insert(&store, key, len).await {
+                    Ok(latency) => stats.record(latency, len),
+                    Err(e) => stats.errors.push(format!("insert error of key {key}: {e}")),
+                }
+            }
+            stats
+        }
+    })
+    .await;
+    if let Err(e) = store.close().await {
+        stats.errors.push(format!("close error: {e}"));
+    }
+    report.finish(now.elapsed(), stats);
+
+    report.check(
+        args.min_fill_mbps == 0.0 || report.mbps() >= args.min_fill_mbps,
+        format!(
+            "write throughput {:.1} MiB/s is below {:.1} MiB/s",
+            report.mbps(),
+            args.min_fill_mbps
+        ),
+    );
+    report
+}
+
+/// Look up random entries of the fill.
+pub async fn random_read(args: &Args) -> Report {
+    let mut report = Report::new("random-read");
+    let store = open(args).await;
+
+    let now = Instant::now();
+    let deadline = now + Duration::from_secs(args.time);
+    let mut stats = run_tasks(args.concurrency, |_| {
+        let store = store.clone();
+        let (entries, len) = (args.entries(), args.entry_bytes());
+        async move {
+            let mut stats = Stats::default();
+            let mut rng = StdRng::from_entropy();
+            while Instant::now() < deadline {
+                lookup(&store, rng.gen_range(0..entries), len, &mut stats).await;
+            }
+            stats
+        }
+    })
+    .await;
+    let elapsed = now.elapsed();
+    if let Err(e) = store.close().await {
+        stats.errors.push(format!("close error: {e}"));
+    }
+    report.notes.push(format!("hit ratio: {:.4}", stats.hit_ratio()));
+    report.finish(elapsed, stats);
+
+    // The fill takes half of the capacity, none of it is evicted.
+    report.check(
+        report.stats.misses == 0,
+        format!("{} lookups missed the entries of the fill", report.stats.misses),
+    );
+    report.check(
+        args.min_read_iops == 0.0 || report.iops() >= args.min_read_iops,
+        format!(
+            "{:.1} lookups per second is below {:.1}",
+            report.iops(),
+            args.min_read_iops
+        ),
+    );
+    let p99 = report.stats.latency.value_at_quantile(0.99);
+    report.check(
+        args.max_read_p99_us == 0 || p99 <= args.max_read_p99_us,
+        format!("p99 lookup latency {}us is above {}us", p99, args.max_read_p99_us),
+    );
+    report
+}
+
+/// Look up random entries of the fill and write new entries at the same rate. Shed inserts are not retried.
+pub async fn mixed(args: &Args) -> Report {
+    let mut report = Report::new("mixed");
+    let store = open(args).await;
+
+    let now = Instant::now();
+    let deadline = now + Duration::from_secs(args.time);
+    let mut stats = run_tasks(args.concurrency, |i| {
+        let store = store.clone();
+        let (entries, concurrency, len) = (args.entries(), args.concurrency as u64, args.entry_bytes());
+        async move {
+            let mut stats = Stats::default();
+            let mut rng = StdRng::from_entropy();
+            let mut key = entries + i as u64;
+            while Instant::now() < deadline {
+                if rng.gen_bool(0.5) {
+                    lookup(&store, rng.gen_range(0..entries), len, &mut stats).await;
+                    continue;
+                }
+                let now = Instant::now();
+                match store.insert(key, value(key, len)).await {
+                    Ok(true) => {
+                        stats.record(now.elapsed(), len);
+                        tokio::task::yield_now().await;
+                    }
+                    Ok(false) => {
+                        stats.shed += 1;
+                        tokio::time::sleep(SHED_BACKOFF).await;
+                    }
+                    Err(e) => stats.errors.push(format!("insert error of key {key}: {e}")),
+                }
+                key += concurrency;
+            }
+            stats
+        }
+    })
+    .await;
+    let elapsed = now.elapsed();
+    if let Err(e) = store.close().await {
+        stats.errors.push(format!("close error: {e}"));
+    }
+    report.notes.push(format!("hit ratio: {:.4}", stats.hit_ratio()));
+    report.notes.push(format!("shed inserts: {}", stats.shed));
+    report.finish(elapsed, stats);
+
+    report.check(
+        args.min_mixed_iops == 0.0 || report.iops() >= args.min_mixed_iops,
+        format!(
+            "{:.1} operations per second is below {:.1}",
+            report.iops(),
+            args.min_mixed_iops
+        ),
+    );
+    report
+}
+
+/// Write entries until the process is killed, run in a child process by [`crash_recovery`].
+pub async fn crash_writer(args: &Args) {
+    let store = open(args).await;
+    run_tasks(args.concurrency, |i| {
+        let store = store.clone();
+        let (concurrency, len) = (args.concurrency as u64, args.entry_bytes());
+        async move {
+            let mut key = CRASH_KEY_BASE + i as u64;
+            loop {
+                insert(&store, key, len).await.unwrap();
+                key += concurrency;
+            }
+        }
+    })
+    .await;
+}
+
+/// Kill a process writing to the store, recover the store and verify every recovered entry.
+pub async fn crash_recovery(args: &Args) -> Report {
+    let mut report = Report::new("crash-recovery");
+
+    let mut writer = Command::new(std::env::current_exe().unwrap())
+        .args(std::env::args_os().skip(1))
+        .arg("--crash-writer")
+        .stdout(Stdio::null())
+        .spawn()
+        .unwrap();
+    tokio::time::sleep(Duration::from_secs(args.time)).await;
+    if let Some(status) = writer.try_wait().unwrap() {
+        report
+            .failures
+            .push(format!("crash writer exited before the crash: {status}"));
+        return report;
+    }
+    // SIGKILL, the writer has no chance to flush or close the store.
+    writer.kill().unwrap();
+    writer.wait().unwrap();
+
+    let now = Instant::now();
+    let store = match QualifyStore::open(store_config(args)).await {
+        Ok(store) => store,
+        Err(e) => {
+            report.failures.push(format!("recovery error: {e}"));
+            return report;
+        }
+    };
+    let recovery = now.elapsed();
+
+    let keys = Arc::new(store.range_keys(..));
+    let written = keys.iter().filter(|key| **key >= CRASH_KEY_BASE).count();
+    report.notes.push(format!("recovery: {}ms", recovery.as_millis()));
+    report.notes.push(format!("recovered entries: {}", keys.len()));
+    report.notes.push(format!("recovered entries of the writer: {written}"));
+
+    // Every recovered entry must be intact.
+    let now = Instant::now();
+    let mut stats = run_tasks(args.concurrency, |i| {
+        let store = store.clone();
+        let keys = keys.clone();
+        let (concurrency, len) = (args.concurrency, args.entry_bytes());
+        async move {
+            let mut stats = Stats::default();
+            for key in keys.iter().skip(i).step_by(concurrency) {
+                lookup(&store, *key, len, &mut stats).await;
+            }
+            stats
+        }
+    })
+    .await;
+    let elapsed = now.elapsed();
+    if let Err(e) = store.close().await {
+        stats.errors.push(format!("close error: {e}"));
+    }
+    report.finish(elapsed, stats);
+
+    report.check(written > 0, "no entry of the writer is recovered");
+    report.check(
+        args.max_recovery_ms == 0 || recovery <= Duration::from_millis(args.max_recovery_ms),
+        format!(
+            "recovery {}ms is above {}ms",
+            recovery.as_millis(),
+            args.max_recovery_ms
+        ),
+    );
+    report
+}
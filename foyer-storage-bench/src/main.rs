@@ -41,8 +41,13 @@ use foyer_intrusive::eviction::lfu::LfuConfig;
 use foyer_storage::{
     admission::{rated_ticket::RatedTicketAdmissionPolicy, AdmissionPolicy},
     compress::Compression,
-    device::fs::FsDeviceConfig,
+    device::{
+        fs::{FsDeviceConfig, FsReadMode},
+        mmap::MmapAdvice,
+        ReadIoPolicy,
+    },
     error::Result,
+    generic::{EntryPadding, RecoveryMode},
     reinsertion::{rated_ticket::RatedTicketReinsertionPolicy, ReinsertionPolicy},
     runtime::{RuntimeConfig, RuntimeStore, RuntimeStoreConfig, RuntimeStoreWriter},
     storage::{AsyncStorageExt, Storage, StorageExt, StorageWriter},
@@ -117,6 +122,18 @@ pub struct Args {
     #[arg(long, default_value_t = 16 * 1024)]
     io_size: usize,
 
+    /// Back io buffers not smaller than a hugepage with transparent hugepages.
+    #[arg(long, default_value_t = false)]
+    hugepage_buffers: bool,
+
+    /// Max size of a single physical read, reads are split into `io_size` chunks if 0.
+    #[arg(long, default_value_t = 0)]
+    read_io_max_size: usize,
+
+    /// Read sealed regions from read-only mappings of the device files.
+    #[arg(long, default_value_t = false)]
+    mmap: bool,
+
     #[arg(long, default_value_t = 16)]
     writers: usize,
 
@@ -144,6 +161,30 @@ pub struct Args {
     #[arg(long, default_value_t = 6)]
     catalog_bits: usize,
 
+    /// Count of region accesses buffered per shard before applied to the eviction policy, `0` means no buffering.
+    #[arg(long, default_value_t = 0)]
+    access_record_batch: usize,
+
+    /// Device read timeout (ms) of lookups, `0` means no timeout.
+    #[arg(long, default_value_t = 0)]
+    read_timeout_ms: u64,
+
+    /// Device write timeout (ms) of flushes, `0` means no timeout.
+    #[arg(long, default_value_t = 0)]
+    write_timeout_ms: u64,
+
+    /// Verify entry checksums when recovering.
+    #[arg(long, default_value_t = false)]
+    recovery_verify: bool,
+
+    /// Seed of the key hasher, a random seed is used if not set.
+    #[arg(long)]
+    hash_seed: Option<u64>,
+
+    /// Pack small entries into shared aligned blocks.
+    #[arg(long, default_value_t = false)]
+    pack_entries: bool,
+
     /// weigher to enable metrics exporter
     #[arg(long, default_value_t = false)]
     metrics: bool,
@@ -282,6 +323,13 @@ where
             BenchStoreWriter::RuntimeStoreWriter { writer } => writer.set_compression(compression),
         }
     }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        match self {
+            BenchStoreWriter::StoreWriter { writer } => writer.set_trace_id(trace_id),
+            BenchStoreWriter::RuntimeStoreWriter { writer } => writer.set_trace_id(trace_id),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -551,6 +599,21 @@ async fn main() {
         file_capacity: args.region_size * 1024 * 1024,
         align: args.align,
         io_size: args.io_size,
+        hugepage_buffers: args.hugepage_buffers,
+        read_io_policy: if args.read_io_max_size == 0 {
+            ReadIoPolicy::Fixed
+        } else {
+            ReadIoPolicy::Adaptive {
+                max: args.read_io_max_size,
+            }
+        },
+        read_mode: if args.mmap {
+            FsReadMode::Mmap {
+                advice: MmapAdvice::Random,
+            }
+        } else {
+            FsReadMode::Pread
+        },
     };
 
     let mut admissions: Vec<Arc<dyn AdmissionPolicy<Key = u64, Value = Arc<Vec<u8>>>>> = vec![];
@@ -577,9 +640,6 @@ async fn main() {
         .expect("unsupported compression algorithm");
 
     let config = LfuFsStoreConfig {
-        name: "".to_string(),
-        eviction_config,
-        device_config,
         catalog_bits: args.catalog_bits,
         admissions,
         reinsertions,
@@ -588,6 +648,21 @@ async fn main() {
         recover_concurrency: args.recover_concurrency,
         clean_region_threshold,
         compression,
+        access_record_batch: args.access_record_batch,
+        read_timeout: (args.read_timeout_ms > 0).then(|| Duration::from_millis(args.read_timeout_ms)),
+        write_timeout: (args.write_timeout_ms > 0).then(|| Duration::from_millis(args.write_timeout_ms)),
+        recovery_mode: if args.recovery_verify {
+            RecoveryMode::Verify
+        } else {
+            RecoveryMode::Trust
+        },
+        hash_seed: args.hash_seed,
+        entry_padding: if args.pack_entries {
+            EntryPadding::Packed
+        } else {
+            EntryPadding::Aligned
+        },
+        ..LfuFsStoreConfig::new("", eviction_config, device_config)
     };
 
     let config = if args.runtime {
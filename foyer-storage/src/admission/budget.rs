@@ -0,0 +1,128 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{fmt::Debug, marker::PhantomData, sync::OnceLock};
+
+use foyer_common::code::{Key, Value};
+
+use super::{AdmissionContext, AdmissionPolicy};
+
+/// Built-in admission gate that rejects inserts while the device bytes in use plus the estimated catalog memory
+/// exceed the budget.
+///
+/// Forced inserts bypass the gate.
+#[derive(Debug)]
+pub struct BudgetAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    budget: usize,
+
+    /// Estimated catalog memory per entry besides its key.
+    entry_overhead: usize,
+
+    context: OnceLock<AdmissionContext<K, V>>,
+
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> BudgetAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(budget: usize, entry_overhead: usize) -> Self {
+        Self {
+            budget,
+            entry_overhead,
+            context: OnceLock::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> AdmissionPolicy for BudgetAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    type Key = K;
+
+    type Value = V;
+
+    fn init(&self, context: AdmissionContext<Self::Key, Self::Value>) {
+        self.context.set(context).unwrap();
+    }
+
+    fn judge(&self, _key: &Self::Key, _weight: usize) -> bool {
+        let context = self.context.get().unwrap();
+        let used = context.metrics.total_bytes.get() as usize + context.catalog.memory_usage(self.entry_overhead);
+        used <= self.budget
+    }
+
+    fn peek(&self, key: &Self::Key, weight: usize) -> Option<bool> {
+        Some(self.judge(key, weight))
+    }
+
+    fn on_insert(&self, _key: &Self::Key, _weight: usize, _judge: bool) {}
+
+    fn on_drop(&self, _key: &Self::Key, weight: usize, judge: bool) {
+        // Forced inserts are not dropped, so only shed inserts are counted here.
+        if !judge {
+            let metrics = self.context.get().unwrap().metrics.as_ref();
+            metrics.op_count_insert_shed.inc();
+            metrics.op_bytes_insert_shed.inc_by(weight as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        catalog::{Catalog, Index, Item},
+        metrics::METRICS,
+    };
+
+    #[test]
+    fn test_budget_admission() {
+        let metrics = Arc::new(METRICS.foyer("test_budget_admission"));
+        let catalog = Arc::new(Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics.clone()));
+        let context = AdmissionContext {
+            catalog: catalog.clone(),
+            metrics: metrics.clone(),
+        };
+
+        let policy = BudgetAdmissionPolicy::new(1024 + 100, 84);
+        policy.init(context);
+
+        metrics.total_bytes.set(1024);
+        assert!(policy.judge(&1, 16));
+
+        // 84 bytes overhead + 2 * 8 bytes key
+        let item = Item::new(0, Index::Inflight { key: 1, value: vec![] }, 0);
+        catalog.insert(1, item.clone());
+        assert!(policy.judge(&2, 16));
+        catalog.insert(2, item);
+        assert!(!policy.judge(&3, 16));
+        policy.on_drop(&3, 16, false);
+        assert_eq!(metrics.op_count_insert_shed.get(), 1);
+
+        metrics.total_bytes.set(0);
+        assert!(policy.judge(&3, 16));
+    }
+}
@@ -0,0 +1,187 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use foyer_common::code::{Key, Value};
+
+use super::rated_ticket::RatedTicketAdmissionPolicy;
+use crate::{
+    error::Result,
+    metrics::Metrics,
+    scheduler::{Job, JobSchedule},
+};
+
+/// Config of [`AdmissionRateController`].
+#[derive(Debug, Clone)]
+pub struct AdmissionRateControllerConfig<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    /// The policy to adjust, which must also be one of the admission policies of the store.
+    pub policy: Arc<RatedTicketAdmissionPolicy<K, V>>,
+    /// Target ratio of lookups that hit.
+    pub target_hit_ratio: f64,
+    /// Lower bound of the admission rate, in bytes per second.
+    pub min_rate: usize,
+    /// Upper bound of the admission rate, in bytes per second, e.g. the device write budget.
+    pub max_rate: usize,
+    /// Ratio of the rate to increase or decrease by in an adjustment.
+    pub step: f64,
+    /// Interval between adjustments.
+    pub interval: Duration,
+    /// Adjustments are skipped if there are fewer lookups in an interval, for the hit ratio is not meaningful.
+    pub min_lookups: u64,
+}
+
+/// Adjusts the rate of a rated ticket admission policy to hold a target hit ratio, with the feedback of the lookup
+/// metrics.
+///
+/// The rate increases while the hit ratio is below the target, and decreases to save device writes while above.
+#[derive(Debug)]
+pub struct AdmissionRateController<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    config: AdmissionRateControllerConfig<K, V>,
+    metrics: Arc<Metrics>,
+    /// Lookup hits and misses at the last adjustment.
+    last: (u64, u64),
+}
+
+impl<K, V> AdmissionRateController<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(config: AdmissionRateControllerConfig<K, V>, metrics: Arc<Metrics>) -> Self {
+        let mut controller = Self {
+            config,
+            metrics,
+            last: (0, 0),
+        };
+        controller.last = controller.lookups();
+        controller
+    }
+
+    /// Adjust the rate by the hit ratio of the lookups since the last adjustment, returns the new rate.
+    pub fn adjust(&mut self) -> usize {
+        let policy = &self.config.policy;
+        let (hits, misses) = self.lookups();
+        let (delta_hits, delta_misses) = (hits - self.last.0, misses - self.last.1);
+        if delta_hits + delta_misses < self.config.min_lookups.max(1) {
+            return policy.rate();
+        }
+        self.last = (hits, misses);
+
+        let ratio = delta_hits as f64 / (delta_hits + delta_misses) as f64;
+        let rate = policy.rate() as f64;
+        let rate = if ratio < self.config.target_hit_ratio {
+            rate * (1.0 + self.config.step)
+        } else if ratio > self.config.target_hit_ratio {
+            rate * (1.0 - self.config.step)
+        } else {
+            rate
+        };
+        let rate = (rate as usize).clamp(self.config.min_rate, self.config.max_rate);
+        if rate != policy.rate() {
+            tracing::debug!(
+                "[admission rate controller] hit ratio: {:.4}, set rate: {}",
+                ratio,
+                rate
+            );
+            policy.set_rate(rate);
+        }
+        rate
+    }
+
+    pub fn schedule(&self) -> JobSchedule {
+        JobSchedule::new(self.config.interval)
+    }
+
+    fn lookups(&self) -> (u64, u64) {
+        let hits = self.metrics.op_duration_lookup_hit.get_sample_count();
+        let misses = self.metrics.op_duration_lookup_miss.get_sample_count()
+            + self.metrics.op_duration_lookup_miss_cached.get_sample_count();
+        (hits, misses)
+    }
+}
+
+impl<K, V> Job for AdmissionRateController<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    async fn run(&mut self) -> Result<()> {
+        self.adjust();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::METRICS;
+
+    #[test]
+    fn test_admission_rate_controller() {
+        let metrics = Arc::new(METRICS.foyer("test_admission_rate_controller"));
+        let policy = Arc::new(RatedTicketAdmissionPolicy::<u64, Vec<u8>>::new(1000));
+        let mut controller = AdmissionRateController::new(
+            AdmissionRateControllerConfig {
+                policy: policy.clone(),
+                target_hit_ratio: 0.5,
+                min_rate: 500,
+                max_rate: 1500,
+                step: 0.25,
+                interval: Duration::from_secs(1),
+                min_lookups: 4,
+            },
+            metrics.clone(),
+        );
+
+        let lookups = |hits: usize, misses: usize| {
+            (0..hits).for_each(|_| metrics.op_duration_lookup_hit.observe(0.0));
+            (0..misses).for_each(|_| metrics.op_duration_lookup_miss.observe(0.0));
+        };
+
+        // Too few lookups to adjust.
+        lookups(0, 3);
+        assert_eq!(controller.adjust(), 1000);
+
+        // Admit more while the hit ratio is below the target, up to the max rate.
+        lookups(1, 0);
+        assert_eq!(controller.adjust(), 1250);
+        lookups(1, 3);
+        assert_eq!(controller.adjust(), 1500);
+        lookups(1, 3);
+        assert_eq!(controller.adjust(), 1500);
+
+        // Admit less while the hit ratio is above the target, down to the min rate.
+        lookups(3, 1);
+        assert_eq!(controller.adjust(), 1125);
+        lookups(4, 0);
+        assert_eq!(controller.adjust(), 843);
+        lookups(4, 0);
+        assert_eq!(controller.adjust(), 632);
+        lookups(4, 0);
+        assert_eq!(controller.adjust(), 500);
+
+        lookups(2, 2);
+        assert_eq!(controller.adjust(), 500);
+        assert_eq!(policy.rate(), 500);
+    }
+}
@@ -0,0 +1,126 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{Arc, OnceLock},
+};
+
+use foyer_common::code::{Key, Value};
+
+use super::{AdmissionContext, AdmissionPolicy};
+use crate::tunables::Tunables;
+
+/// Built-in admission gate that rejects inserts while the serialized bytes of entries waiting for flushers exceed
+/// the inflight budget of the tunables.
+///
+/// Forced inserts bypass the gate.
+#[derive(Debug)]
+pub struct InflightAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    tunables: Arc<Tunables>,
+
+    context: OnceLock<AdmissionContext<K, V>>,
+
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> InflightAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(tunables: Arc<Tunables>) -> Self {
+        Self {
+            tunables,
+            context: OnceLock::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> AdmissionPolicy for InflightAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    type Key = K;
+
+    type Value = V;
+
+    fn init(&self, context: AdmissionContext<Self::Key, Self::Value>) {
+        self.context.set(context).unwrap();
+    }
+
+    fn judge(&self, _key: &Self::Key, _weight: usize) -> bool {
+        let Some(budget) = self.tunables.inflight_budget() else {
+            return true;
+        };
+        let context = self.context.get().unwrap();
+        context.metrics.inflight_bytes.get() as usize <= budget
+    }
+
+    fn peek(&self, key: &Self::Key, weight: usize) -> Option<bool> {
+        Some(self.judge(key, weight))
+    }
+
+    fn on_insert(&self, _key: &Self::Key, _weight: usize, _judge: bool) {}
+
+    fn on_drop(&self, _key: &Self::Key, weight: usize, judge: bool) {
+        // Forced inserts are not dropped, so only shed inserts are counted here.
+        if !judge {
+            let metrics = self.context.get().unwrap().metrics.as_ref();
+            metrics.op_count_insert_shed.inc();
+            metrics.op_bytes_insert_shed.inc_by(weight as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{catalog::Catalog, compress::Compression, metrics::METRICS};
+
+    #[test]
+    fn test_inflight_admission() {
+        let metrics = Arc::new(METRICS.foyer("test_inflight_admission"));
+        let context = AdmissionContext {
+            catalog: Arc::new(Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics.clone())),
+            metrics: metrics.clone(),
+        };
+
+        let tunables = Arc::new(Tunables::new(1, Compression::None));
+        let policy = InflightAdmissionPolicy::new(tunables.clone());
+        policy.init(context);
+
+        metrics.inflight_bytes.set(4096);
+        assert!(policy.judge(&1, 16));
+
+        tunables.set_inflight_budget(Some(1024));
+        assert!(!policy.judge(&1, 16));
+        policy.on_drop(&1, 16, false);
+        assert_eq!(metrics.op_count_insert_shed.get(), 1);
+
+        metrics.inflight_bytes.set(1024);
+        assert!(policy.judge(&1, 16));
+
+        tunables.set_inflight_budget(None);
+        metrics.inflight_bytes.set(usize::MAX as u64);
+        assert!(policy.judge(&1, 16));
+    }
+}
@@ -41,6 +41,31 @@ where
     }
 }
 
+/// Verdict of a dry run of the admission of an entry, see [`GenericStore::would_admit`].
+///
+/// Policies are identified by their indices in the config, followed by the write stall and the combined budget
+/// policies of the store if enabled.
+///
+/// [`GenericStore::would_admit`]: crate::generic::GenericStore::would_admit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdmissionVerdict {
+    /// The entry would be admitted by all admission policies.
+    Admit,
+    /// The entry would be rejected by the admission policies.
+    Reject { policies: Vec<usize> },
+    /// The entry is invalid, e.g. its key is too long, and would be rejected by any writer.
+    Invalid(String),
+    /// The entry would be admitted by the policies that can judge ahead of time, but the listed policies can not.
+    Unknown { policies: Vec<usize> },
+}
+
+impl AdmissionVerdict {
+    /// Returns `true` if the entry would be admitted, or may be.
+    pub fn is_admit(&self) -> bool {
+        matches!(self, AdmissionVerdict::Admit | AdmissionVerdict::Unknown { .. })
+    }
+}
+
 #[expect(unused_variables)]
 pub trait AdmissionPolicy: Send + Sync + 'static + Debug {
     type Key: Key;
@@ -50,9 +75,20 @@ pub trait AdmissionPolicy: Send + Sync + 'static + Debug {
 
     fn judge(&self, key: &Self::Key, weight: usize) -> bool;
 
+    /// Judge like [`AdmissionPolicy::judge`] without changing the state of the policy, for dry runs.
+    ///
+    /// Returns `None` if the policy can not judge without changing its state.
+    fn peek(&self, key: &Self::Key, weight: usize) -> Option<bool> {
+        None
+    }
+
     fn on_insert(&self, key: &Self::Key, weight: usize, judge: bool);
 
     fn on_drop(&self, key: &Self::Key, weight: usize, judge: bool);
 }
 
+pub mod budget;
+pub mod controller;
+pub mod inflight;
 pub mod rated_ticket;
+pub mod write_stall;
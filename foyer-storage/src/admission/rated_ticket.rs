@@ -52,6 +52,15 @@ where
             context: OnceLock::new(),
         }
     }
+
+    /// Admitted bytes per second.
+    pub fn rate(&self) -> usize {
+        self.inner.rate() as usize
+    }
+
+    pub fn set_rate(&self, rate: usize) {
+        self.inner.set_rate(rate as f64);
+    }
 }
 
 impl<K, V> AdmissionPolicy for RatedTicketAdmissionPolicy<K, V>
@@ -83,6 +92,13 @@ where
         res
     }
 
+    fn peek(&self, _key: &Self::Key, _weight: usize) -> Option<bool> {
+        // Bytes flushed since the last judge are not taken from the quota yet.
+        let metrics = self.context.get().unwrap().metrics.as_ref();
+        let pending = (metrics.op_bytes_flush.get() as usize).saturating_sub(self.last.load(Ordering::Relaxed));
+        Some(self.inner.quota() - pending as f64 > 0.0)
+    }
+
     fn on_insert(&self, _key: &Self::Key, _weight: usize, _judge: bool) {}
 
     fn on_drop(&self, _key: &Self::Key, _weight: usize, _judge: bool) {}
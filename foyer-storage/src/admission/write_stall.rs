@@ -0,0 +1,160 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Debug,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, OnceLock,
+    },
+    time::Duration,
+};
+
+use foyer_common::code::{Key, Value};
+
+use super::{AdmissionContext, AdmissionPolicy};
+
+/// Write stall state shared by flushers and [`WriteStallAdmissionPolicy`].
+///
+/// A flusher is stalled when the entry it handles has waited in its queue longer than the threshold, and recovers
+/// when its queue drains or the wait falls back below the threshold.
+#[derive(Debug)]
+pub struct WriteStall {
+    threshold: Duration,
+
+    /// Count of stalled flushers.
+    stalled: AtomicUsize,
+}
+
+impl WriteStall {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            stalled: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.stalled.load(Ordering::Relaxed) > 0
+    }
+
+    pub(crate) fn stall(&self) {
+        self.stalled.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn recover(&self) {
+        self.stalled.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Built-in admission gate that rejects inserts while any flusher is stalled.
+///
+/// Forced inserts bypass the gate.
+#[derive(Debug)]
+pub struct WriteStallAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    write_stall: Arc<WriteStall>,
+
+    context: OnceLock<AdmissionContext<K, V>>,
+
+    _marker: PhantomData<(K, V)>,
+}
+
+impl<K, V> WriteStallAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(write_stall: Arc<WriteStall>) -> Self {
+        Self {
+            write_stall,
+            context: OnceLock::new(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<K, V> AdmissionPolicy for WriteStallAdmissionPolicy<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    type Key = K;
+
+    type Value = V;
+
+    fn init(&self, context: AdmissionContext<Self::Key, Self::Value>) {
+        self.context.set(context).unwrap();
+    }
+
+    fn judge(&self, _key: &Self::Key, _weight: usize) -> bool {
+        !self.write_stall.is_stalled()
+    }
+
+    fn peek(&self, key: &Self::Key, weight: usize) -> Option<bool> {
+        Some(self.judge(key, weight))
+    }
+
+    fn on_insert(&self, _key: &Self::Key, _weight: usize, _judge: bool) {}
+
+    fn on_drop(&self, _key: &Self::Key, weight: usize, judge: bool) {
+        // Forced inserts are not dropped, so only shed inserts are counted here.
+        if !judge {
+            let metrics = self.context.get().unwrap().metrics.as_ref();
+            metrics.op_count_insert_shed.inc();
+            metrics.op_bytes_insert_shed.inc_by(weight as u64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{catalog::Catalog, metrics::METRICS};
+
+    #[test]
+    fn test_write_stall_admission() {
+        let metrics = Arc::new(METRICS.foyer("test_write_stall_admission"));
+        let context = AdmissionContext {
+            catalog: Arc::new(Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics.clone())),
+            metrics: metrics.clone(),
+        };
+
+        let write_stall = Arc::new(WriteStall::new(Duration::from_millis(10)));
+        let policy = WriteStallAdmissionPolicy::new(write_stall.clone());
+        policy.init(context);
+
+        assert!(policy.judge(&1, 16));
+
+        write_stall.stall();
+        write_stall.stall();
+        assert!(!policy.judge(&1, 16));
+        policy.on_drop(&1, 16, false);
+        assert_eq!(metrics.op_count_insert_shed.get(), 1);
+        assert_eq!(metrics.op_bytes_insert_shed.get(), 16);
+
+        write_stall.recover();
+        assert!(!policy.judge(&1, 16));
+        write_stall.recover();
+        assert!(policy.judge(&1, 16));
+    }
+}
@@ -12,19 +12,23 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-use std::fmt::Debug;
+use std::{fmt::Debug, hash::Hasher, sync::Arc, time::Duration};
 
 use foyer_common::{
     bits::{align_up, is_aligned},
+    buf::AlignedBuffer,
     code::{Cursor, Key, Value},
 };
+use twox_hash::XxHash64;
 
 use crate::{
-    compress::Compression,
+    codec::CodecPipeline,
     device::{error::DeviceError, Device},
-    flusher::Entry,
-    generic::{checksum, EntryHeader},
-    region::{RegionHeader, RegionId, Version, REGION_MAGIC},
+    flusher::{Entry, Payload},
+    generic::{checksum, encode_value, EntryHeader, EntryPadding},
+    metrics::Metrics,
+    profile,
+    region::{DirectoryRecord, RegionFooter, RegionHeader, RegionId, Version, REGION_MAGIC},
 };
 
 #[derive(thiserror::Error, Debug)]
@@ -63,7 +67,7 @@ where
 {
     // TODO(MrCroxx): optimize buffer allocation
     /// io buffer
-    buffer: Vec<u8, D::IoBufferAllocator>,
+    buffer: AlignedBuffer,
 
     /// current writing region
     region: Option<RegionId>,
@@ -78,6 +82,31 @@ where
     device: D,
 
     default_buffer_capacity: usize,
+
+    /// Timeout of writing io buffer to device.
+    write_timeout: Option<Duration>,
+
+    /// How entries are padded in the regions written.
+    padding: EntryPadding,
+
+    /// Codecs applied to the values written.
+    codecs: CodecPipeline,
+
+    /// Directory of the entries written to the current region, written as the footer when the region is sealed.
+    ///
+    /// `None` if the region must not be sealed, e.g. a write to it is abandoned.
+    footer: Option<RegionFooter>,
+
+    /// Serialized length of the directory records, reserved at the end of the current region.
+    footer_records_len: usize,
+
+    /// Hasher of the data flushed to the current region, its checksum is recorded in the footer.
+    hasher: XxHash64,
+
+    /// Sync the data of a region before its footer is written.
+    write_barrier: bool,
+
+    metrics: Arc<Metrics>,
 }
 
 impl<K, V, D> FlushBuffer<K, V, D>
@@ -86,7 +115,14 @@ where
     V: Value,
     D: Device,
 {
-    pub fn new(device: D) -> Self {
+    pub fn new(
+        device: D,
+        write_timeout: Option<Duration>,
+        padding: EntryPadding,
+        codecs: CodecPipeline,
+        write_barrier: bool,
+        metrics: Arc<Metrics>,
+    ) -> Self {
         let default_buffer_capacity = align_up(device.align(), device.io_size() + device.io_size() / 2);
         let buffer = device.io_buffer(0, default_buffer_capacity);
         Self {
@@ -96,6 +132,14 @@ where
             entries: vec![],
             device,
             default_buffer_capacity,
+            write_timeout,
+            padding,
+            codecs,
+            footer: None,
+            footer_records_len: 0,
+            hasher: XxHash64::with_seed(0),
+            write_barrier,
+            metrics,
         }
     }
 
@@ -103,6 +147,15 @@ where
         self.region
     }
 
+    pub fn padding(&self) -> EntryPadding {
+        self.padding
+    }
+
+    /// Returns `true` if there are entries in io buffer waiting for flush.
+    pub fn has_pending_entries(&self) -> bool {
+        !self.entries.is_empty()
+    }
+
     pub fn remaining(&self) -> usize {
         if self.region.is_none() {
             0
@@ -113,20 +166,33 @@ where
         }
     }
 
-    /// Flush io buffer if necessary, and reset io buffer to a new region.
+    /// Flush io buffer if necessary, seal the current region, and reset io buffer to a new region.
     ///
     /// Returns fully flushed entries.
-    pub async fn rotate(&mut self, region: RegionId) -> BufferResult<Vec<PositionedEntry<K, V>>, Entry<K, V>> {
+    pub async fn rotate(
+        &mut self,
+        region: RegionId,
+        created_at: u64,
+    ) -> BufferResult<Vec<PositionedEntry<K, V>>, Entry<K, V>> {
         let entries = self.flush().await?;
         debug_assert!(self.buffer.is_empty());
+        self.seal().await?;
         self.region = Some(region);
         self.offset = 0;
+        self.footer = Some(RegionFooter {
+            created_at,
+            ..Default::default()
+        });
+        self.footer_records_len = 0;
+        self.hasher = XxHash64::with_seed(0);
 
         // write region header
         unsafe { self.buffer.set_len(self.device.align()) };
         let header = RegionHeader {
             magic: REGION_MAGIC,
             version: Version::latest(),
+            created_at,
+            padding: self.padding,
         };
         header.write(&mut self.buffer[..]);
         debug_assert_eq!(self.buffer.len(), self.device.align());
@@ -134,6 +200,43 @@ where
         Ok(entries)
     }
 
+    /// Flush io buffer and seal the current region, the next write rotates to a new region.
+    ///
+    /// Returns fully flushed entries and the retired region, if any.
+    pub async fn retire(&mut self) -> BufferResult<(Vec<PositionedEntry<K, V>>, Option<RegionId>), Entry<K, V>> {
+        let region = self.region;
+        let entries = self.flush().await?;
+        self.seal().await?;
+        self.region = None;
+        Ok((entries, region))
+    }
+
+    /// Write the entry directory to the end of the current region.
+    ///
+    /// With the write barrier, the data of the region is synced first, so a footer on the device never references
+    /// data lost by a crash.
+    async fn seal(&mut self) -> BufferResult<(), Entry<K, V>> {
+        let (Some(region), Some(mut footer)) = (self.region, self.footer.take()) else {
+            return Ok(());
+        };
+        if self.write_barrier {
+            self.device.sync(region).await?;
+        }
+        footer.data_len = self.offset as u64;
+        footer.data_checksum = self.hasher.finish();
+
+        let len = RegionFooter::len(self.footer_records_len, self.device.align());
+        let mut buf = self.device.io_buffer(len, len);
+        footer.write(&mut buf[..]);
+        let (res, _buf) = self
+            .device
+            .write(buf, .., region, self.device.region_size() - len)
+            .await;
+        res?;
+
+        Ok(())
+    }
+
     /// Flush io buffer and move the io buffer to the next position.
     ///
     /// The io buffer will be cleared after flush.
@@ -145,18 +248,35 @@ where
             return Ok(vec![]);
         };
 
-        // align io buffer
+        // align io buffer, zero the padding so it is never parsed as an entry
         let len = align_up(self.device.align(), self.buffer.len());
-        debug_assert!(len <= self.buffer.capacity());
-        unsafe { self.buffer.set_len(len) };
+        let padding = len - self.buffer.len();
+        self.buffer.extend_from_slice(&vec![0; padding]);
         debug_assert!(self.offset + self.buffer.len() <= self.device.region_size());
+        if self.footer.is_some() {
+            self.hasher.write(&self.buffer);
+        }
 
         // flush and clear buffer
         let mut buf = self.device.io_buffer(0, self.default_buffer_capacity);
         std::mem::swap(&mut self.buffer, &mut buf);
 
-        let (res, _buf) = self.device.write(buf, .., region, self.offset).await;
-        res?;
+        let token = profile::flush_write_start(region, len);
+        let write = self.device.write(buf, .., region, self.offset);
+        let timeout = match self.write_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, write).await {
+                Ok((res, _buf)) => res.map(|_| false)?,
+                Err(_) => {
+                    tracing::warn!("[buffer] write region {} timeout after {:?}", region, timeout);
+                    self.metrics.op_count_flush_timeout.inc();
+                    true
+                }
+            },
+            None => write.await.0.map(|_| false)?,
+        };
+        if !timeout {
+            profile::flush_write_end(token, region, len);
+        }
 
         // advance io buffer
         self.offset += len;
@@ -166,6 +286,13 @@ where
 
         let mut entries = vec![];
         std::mem::swap(&mut self.entries, &mut entries);
+
+        // The abandoned write may never land, entries in it must not be indexed.
+        if timeout {
+            entries.clear();
+            self.footer = None;
+        }
+
         Ok(entries)
     }
 
@@ -179,7 +306,6 @@ where
     /// # Format
     ///
     /// | header | value (compressed) | key | <padding> |
-    #[expect(clippy::uninit_vec)]
     pub async fn write(
         &mut self,
         Entry {
@@ -187,6 +313,9 @@ where
             value,
             sequence,
             compression,
+            enqueued_at,
+            inserted_at,
+            trace_id,
         }: Entry<K, V>,
     ) -> BufferResult<Vec<PositionedEntry<K, V>>, Entry<K, V>> {
         // Notify caller to rotate buffer if there is not enough space for the entry.
@@ -204,16 +333,23 @@ where
                 value,
                 sequence,
                 compression,
+                enqueued_at,
+                inserted_at,
+                trace_id,
             })));
         }
 
+        let granularity = self.padding.granularity(self.device.align());
+
         let old = self.buffer.len();
-        debug_assert!(is_aligned(self.device.align(), old));
+        debug_assert!(is_aligned(granularity, old));
+
+        let uncompressed_value_len = value.serialized_len();
 
         // reserve underlying buffer to reduce reallocation
         let uncompressed = align_up(
-            self.device.align(),
-            EntryHeader::serialized_len() + key.serialized_len() + value.serialized_len(),
+            granularity,
+            EntryHeader::serialized_len() + key.serialized_len() + uncompressed_value_len,
         );
         self.buffer.reserve(old + uncompressed);
 
@@ -222,25 +358,26 @@ where
         cursor += EntryHeader::serialized_len();
         unsafe { self.buffer.set_len(cursor) };
 
-        // write value
-        let mut vcursor = value.into_cursor();
-        match compression {
-            Compression::None => {
-                std::io::copy(&mut vcursor, &mut self.buffer).map_err(DeviceError::from)?;
+        // write value, a raw payload is written as is, both are encoded with the codecs if any
+        let (value, value_compression) = match value {
+            Payload::Value(value) if self.codecs.is_empty() => {
+                let value = encode_value(value, compression, &mut self.buffer).map_err(DeviceError::from)?;
+                (Payload::Value(value), compression)
             }
-            Compression::Zstd => {
-                zstd::stream::copy_encode(&mut vcursor, &mut self.buffer, 0).map_err(DeviceError::from)?;
+            Payload::Value(value) => {
+                let mut compressed = Vec::with_capacity(uncompressed_value_len);
+                let value = encode_value(value, compression, &mut compressed).map_err(DeviceError::from)?;
+                let encoded = self.codecs.encode(&compressed).map_err(DeviceError::from)?;
+                self.buffer.extend_from_slice(&encoded);
+                (Payload::Value(value), compression)
             }
-            Compression::Lz4 => {
-                let mut encoder = lz4::EncoderBuilder::new()
-                    .checksum(lz4::ContentChecksum::NoChecksum)
-                    .build(&mut self.buffer)
-                    .map_err(DeviceError::from)?;
-                std::io::copy(&mut vcursor, &mut encoder).map_err(DeviceError::from)?;
-                let (_w, res) = encoder.finish();
-                res.map_err(DeviceError::from)?;
+            Payload::Raw(raw) => {
+                let encoded = self.codecs.encode(&raw.value).map_err(DeviceError::from)?;
+                self.buffer.extend_from_slice(&encoded);
+                let compression = raw.compression;
+                (Payload::Raw(raw), compression)
             }
-        }
+        };
         let compressed_value_len = self.buffer.len() - cursor;
         cursor = self.buffer.len();
 
@@ -259,32 +396,54 @@ where
         let header = EntryHeader {
             key_len: encoded_key_len as u32,
             value_len: compressed_value_len as u32,
+            uncompressed_value_len: uncompressed_value_len as u32,
             sequence,
-            compression,
+            inserted_at,
+            compression: value_compression,
             checksum,
+            codecs: self.codecs.ids(),
         };
         header.write(&mut self.buffer[cursor..cursor + EntryHeader::serialized_len()]);
 
-        // (*) if size exceeds region limit, rollback write and return
-        if self.offset + self.buffer.len() > self.device.region_size() {
+        let record = DirectoryRecord {
+            offset: (self.offset + old) as u32,
+            len: (align_up(granularity, self.buffer.len()) - old) as u32,
+            sequence,
+            inserted_at,
+            logical: (encoded_key_len + uncompressed_value_len) as u32,
+            key: self.buffer[cursor + EntryHeader::serialized_len() + compressed_value_len..][..encoded_key_len]
+                .to_vec(),
+        };
+        let footer_records_len = self.footer_records_len + record.serialized_len();
+
+        // (*) if size exceeds region limit with the footer reserved, rollback write and return
+        if align_up(self.device.align(), self.offset + self.buffer.len())
+            + RegionFooter::len(footer_records_len, self.device.align())
+            > self.device.region_size()
+        {
             unsafe { self.buffer.set_len(old) };
             let key = kcursor.into_inner();
-            let value = vcursor.into_inner();
             return Err(BufferError::NeedRotate(Box::new(Entry {
                 key,
                 value,
                 sequence,
                 compression,
+                enqueued_at,
+                inserted_at,
+                trace_id,
             })));
         }
 
-        // 3. align buffer size
-        let target = align_up(self.device.align(), self.buffer.len());
-        self.buffer.reserve(target - self.buffer.len());
-        unsafe { self.buffer.set_len(target) }
+        // 3. pad buffer size
+        let target = align_up(granularity, self.buffer.len());
+        self.buffer.extend_from_slice(&vec![0; target - self.buffer.len()]);
+
+        self.footer_records_len = footer_records_len;
+        if let Some(footer) = &mut self.footer {
+            footer.records.push(record);
+        }
 
         let key = kcursor.into_inner();
-        let value = vcursor.into_inner();
 
         self.entries.push(PositionedEntry {
             entry: Entry {
@@ -292,6 +451,9 @@ where
                 value,
                 sequence,
                 compression,
+                enqueued_at,
+                inserted_at,
+                trace_id,
             },
             region: self.region.unwrap(),
             offset: self.offset + old,
@@ -311,17 +473,30 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::time::Instant;
+
     use tempfile::tempdir;
 
     use super::*;
-    use crate::device::fs::{FsDevice, FsDeviceConfig};
+    use crate::{
+        compress::Compression,
+        device::{
+            fs::{FsDevice, FsDeviceConfig, FsReadMode},
+            tests::NullDevice,
+            ReadIoPolicy,
+        },
+        metrics::METRICS,
+    };
 
     fn ent(size: usize) -> Entry<(), Vec<u8>> {
         Entry {
             key: (),
-            value: vec![b'x'; size],
+            value: Payload::Value(vec![b'x'; size]),
             compression: Compression::None,
             sequence: 0,
+            enqueued_at: Instant::now(),
+            inserted_at: 0,
+            trace_id: None,
         }
     }
 
@@ -335,11 +510,21 @@ mod tests {
             file_capacity: 64 * 1024, // 64 KiB
             align: 4 * 1024,          // 4 KiB
             io_size: 16 * 1024,       // 16 KiB
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
         })
         .await
         .unwrap();
 
-        let mut buffer = FlushBuffer::new(device.clone());
+        let mut buffer = FlushBuffer::new(
+            device.clone(),
+            None,
+            EntryPadding::Aligned,
+            CodecPipeline::default(),
+            false,
+            Arc::new(METRICS.foyer("test_flush_buffer")),
+        );
         assert_eq!(buffer.region(), None);
 
         const HEADER: usize = EntryHeader::serialized_len();
@@ -353,7 +538,7 @@ mod tests {
                 _ => panic!("should be not enough error"),
             };
 
-            let entries = buffer.rotate(0).await.unwrap();
+            let entries = buffer.rotate(0, 0).await.unwrap();
             assert!(entries.is_empty());
 
             // 4 ~ 12 KiB
@@ -400,7 +585,7 @@ mod tests {
                 _ => panic!("should be not enough error"),
             };
 
-            let entries = buffer.rotate(1).await.unwrap();
+            let entries = buffer.rotate(1, 0).await.unwrap();
             assert!(entries.is_empty());
 
             // 4 ~ 60 KiB
@@ -410,10 +595,13 @@ mod tests {
 
             let entry = ent(3 * 1024 - 128); // ~ 3 KiB
 
-            // 60 ~ 64 KiB
-            let entries = buffer.write(entry).await.unwrap();
-            assert_eq!(entries.len(), 1);
-            assert_eq!(entries[0].offset, 60 * 1024);
+            // 60 ~ 64 KiB is reserved for the footer
+            let res = buffer.write(entry).await;
+            assert!(matches!(res, Err(BufferError::NeedRotate(_))));
+
+            // seal region 1
+            let entries = buffer.rotate(2, 0).await.unwrap();
+            assert!(entries.is_empty());
 
             let buf = device.io_buffer(64 * 1024, 64 * 1024);
             let (res, buf) = device.read(buf, .., 1, 0).await;
@@ -422,12 +610,36 @@ mod tests {
                 &buf[HEADER + 4 * 1024..HEADER + 58 * 1024 - 128],
                 &[b'x'; 54 * 1024 - 128]
             );
-            assert_eq!(
-                &buf[HEADER + 60 * 1024..HEADER + 63 * 1024 - 128],
-                &[b'x'; 3 * 1024 - 128]
-            );
+            let footer = RegionFooter::read(&buf[60 * 1024..]).unwrap();
+            assert_eq!(footer.records.len(), 1);
+            assert_eq!(footer.records[0].offset, 4 * 1024);
+            assert_eq!(footer.records[0].len, 56 * 1024);
+            assert_eq!(footer.data_len, 60 * 1024);
+            assert_eq!(footer.data_checksum, checksum(&buf[..60 * 1024]));
 
             assert!(buffer.entries.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn test_flush_buffer_write_timeout() {
+        let device = NullDevice::new(4 * 1024).with_write_delay(Duration::from_millis(100));
+        let metrics = Arc::new(METRICS.foyer("test_flush_buffer_write_timeout"));
+        let mut buffer = FlushBuffer::new(
+            device,
+            Some(Duration::from_millis(10)),
+            EntryPadding::Aligned,
+            CodecPipeline::default(),
+            false,
+            metrics.clone(),
+        );
+
+        buffer.rotate(0, 0).await.unwrap();
+
+        // The region header fills io size, the entry is flushed with it and abandoned, it must not be indexed.
+        let entries = buffer.write(ent(1024)).await.unwrap();
+        assert!(entries.is_empty());
+        assert!(!buffer.has_pending_entries());
+        assert_eq!(metrics.op_count_flush_timeout.get(), 1);
+    }
 }
@@ -15,33 +15,56 @@
 use std::{
     collections::btree_map::{BTreeMap, Entry},
     hash::Hasher,
-    sync::Arc,
-    time::Instant,
+    ops::RangeBounds,
+    sync::{
+        atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
 };
 
-use foyer_common::code::{Key, Value};
+use foyer_common::{
+    clock::Clock,
+    code::{Key, Value},
+};
 use itertools::Itertools;
 use parking_lot::{Mutex, RwLock};
+use tokio::sync::Notify;
 use twox_hash::XxHash64;
 
 use crate::{
+    error::Result,
+    generic::RawEntry,
     metrics::Metrics,
     region::{RegionId, RegionView},
+    scheduler::Job,
 };
 
 pub type Sequence = u64;
 
+/// Soft removed items by key, with when they are removed.
+type SoftRemovedItems<K, V> = BTreeMap<K, (Item<K, V>, u64)>;
+
 #[derive(Debug, Clone)]
 pub enum Index<K, V>
 where
     K: Key,
     V: Value,
 {
-    Inflight { key: K, value: V },
-    Region { view: RegionView },
+    Inflight {
+        key: K,
+        value: V,
+    },
+    /// Inflight entry inserted already serialized and compressed.
+    InflightRaw {
+        raw: RawEntry,
+    },
+    Region {
+        view: RegionView,
+    },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Item<K, V>
 where
     K: Key,
@@ -49,6 +72,14 @@ where
 {
     sequence: Sequence,
     index: Index<K, V>,
+    /// When the entry is inserted, in milliseconds since unix epoch.
+    inserted_at: u64,
+    /// Whether the inflight entry is claimed by a flusher.
+    claimed: bool,
+    /// Count of lookups of the entry.
+    accesses: AtomicU32,
+    /// Opaque trace id attached by the writer of the entry.
+    trace_id: Option<u64>,
 
     inserted: Option<Instant>,
 }
@@ -58,14 +89,31 @@ where
     K: Key,
     V: Value,
 {
-    pub fn new(sequence: Sequence, index: Index<K, V>) -> Self {
+    pub fn new(sequence: Sequence, index: Index<K, V>, inserted_at: u64) -> Self {
         Self {
             sequence,
             index,
+            inserted_at,
+            claimed: false,
+            accesses: AtomicU32::new(0),
+            trace_id: None,
             inserted: None,
         }
     }
 
+    pub fn with_trace_id(mut self, trace_id: Option<u64>) -> Self {
+        self.trace_id = trace_id;
+        self
+    }
+
+    pub fn trace_id(&self) -> Option<u64> {
+        self.trace_id
+    }
+
+    pub fn accesses(&self) -> u32 {
+        self.accesses.load(Ordering::Relaxed)
+    }
+
     pub fn sequence(&self) -> &Sequence {
         &self.sequence
     }
@@ -74,11 +122,38 @@ where
         &self.index
     }
 
+    pub fn inserted_at(&self) -> u64 {
+        self.inserted_at
+    }
+
+    /// Time elapsed since the entry is inserted until `now`, in milliseconds since unix epoch.
+    pub fn age(&self, now: u64) -> Duration {
+        Duration::from_millis(now.saturating_sub(self.inserted_at))
+    }
+
     pub fn consume(self) -> (Sequence, Index<K, V>) {
         (self.sequence, self.index)
     }
 }
 
+impl<K, V> Clone for Item<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sequence: self.sequence,
+            index: self.index.clone(),
+            inserted_at: self.inserted_at,
+            claimed: self.claimed,
+            accesses: AtomicU32::new(self.accesses()),
+            trace_id: self.trace_id,
+            inserted: self.inserted,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Catalog<K, V>
 where
@@ -88,12 +163,33 @@ where
     /// `items` sharding bits.
     bits: usize,
 
+    /// Seed of the key hasher for sharding.
+    seed: u64,
+
     /// Sharded by key hash.
     items: Vec<RwLock<BTreeMap<K, Item<K, V>>>>,
 
     /// Sharded by region id.
     regions: Vec<Mutex<BTreeMap<K, u64>>>,
 
+    /// Items hidden from lookups by [`Catalog::remove_soft`], with when they are removed in milliseconds since unix
+    /// epoch. A key is indexed by either `items` or `soft_removed`, and stays in the region index of its item.
+    ///
+    /// Locked after an `items` shard and before a `regions` shard.
+    soft_removed: Mutex<SoftRemovedItems<K, V>>,
+
+    /// Count of indexed keys.
+    entries: AtomicUsize,
+
+    /// Serialized bytes of indexed keys.
+    key_bytes: AtomicUsize,
+
+    /// Items with a sequence below are discarded, the entries inserted before the last [`Catalog::clear`].
+    cutoff: AtomicU64,
+
+    /// Notified on [`Catalog::clear`].
+    cleared: Notify,
+
     metrics: Arc<Metrics>,
 }
 
@@ -102,41 +198,262 @@ where
     K: Key,
     V: Value,
 {
-    pub fn new(regions: usize, bits: usize, metrics: Arc<Metrics>) -> Self {
+    pub fn new(regions: usize, bits: usize, seed: u64, metrics: Arc<Metrics>) -> Self {
         let infos = (0..1 << bits).map(|_| RwLock::new(BTreeMap::new())).collect_vec();
         let regions = (0..regions).map(|_| Mutex::new(BTreeMap::new())).collect_vec();
         Self {
             bits,
+            seed,
             items: infos,
             regions,
+            soft_removed: Mutex::new(BTreeMap::new()),
+            entries: AtomicUsize::new(0),
+            key_bytes: AtomicUsize::new(0),
+            cutoff: AtomicU64::new(0),
+            cleared: Notify::new(),
 
             metrics,
         }
     }
 
-    pub fn insert(&self, key: K, mut item: Item<K, V>) {
-        // TODO(MrCroxx): compare sequence.
+    /// Index `item` for `key`.
+    ///
+    /// The item is discarded if the key is already indexed by a newer item, e.g. an older entry is flushed after the
+    /// key is inserted again, or if the item is inserted before the last [`Catalog::clear`].
+    pub fn insert(&self, key: K, item: Item<K, V>) {
+        self.insert_with(key, || item)
+    }
 
-        if let Index::Region { view } = &item.index {
-            self.regions[*view.id() as usize]
-                .lock()
-                .insert(key.clone(), item.sequence);
-        };
+    /// Index the item made by `item` for `key`, like [`Catalog::insert`].
+    ///
+    /// `item` is called with the shard locked, so a new entry can take its sequence in the order the entries of the key
+    /// are indexed, see [`Catalog::reserve_overwrite`].
+    pub fn insert_with(&self, key: K, item: impl FnOnce() -> Item<K, V>) {
+        let _ = self.insert_inner(key, false, item);
+    }
+
+    /// Index the item made by `item` for `key` only if the key is not indexed, like [`Catalog::insert_with`].
+    ///
+    /// Returns the existing item otherwise, and `item` is not called.
+    pub fn insert_absent_with(&self, key: K, item: impl FnOnce() -> Item<K, V>) -> std::result::Result<(), Item<K, V>> {
+        self.insert_inner(key, true, item)
+    }
 
+    fn insert_inner(
+        &self,
+        key: K,
+        absent: bool,
+        item: impl FnOnce() -> Item<K, V>,
+    ) -> std::result::Result<(), Item<K, V>> {
         let shard = self.shard(&key);
-        // TODO(MrCroxx): handle old key?
         let old = {
             let mut guard = self.items[shard].write();
+            if absent && let Some(existing) = guard.get(&key) {
+                return Err(existing.clone());
+            }
+            let mut item = item();
+            // Checked with the shard locked, `clear` raises the cutoff before it clears the shards.
+            if item.sequence < self.cutoff() {
+                return Ok(());
+            }
+            if let Some(old) = guard.get(&key)
+                && old.sequence > item.sequence
+            {
+                return Ok(());
+            }
+            let mut soft_removed = self.soft_removed.lock();
+            if let Some((hidden, _)) = soft_removed.get(&key)
+                && hidden.sequence > item.sequence
+            {
+                return Ok(());
+            }
+            if let Index::Region { view } = &item.index {
+                self.regions[*view.id() as usize]
+                    .lock()
+                    .insert(key.clone(), item.sequence);
+            };
             item.inserted = Some(Instant::now());
-            guard.insert(key.clone(), item)
+            match soft_removed.remove(&key) {
+                // A soft removed entry stays hidden when it is flushed.
+                Some((hidden, removed_at)) if hidden.sequence == item.sequence => {
+                    soft_removed.insert(key, (item, removed_at));
+                    Some(hidden)
+                }
+                // A soft removed entry is dropped when the key is inserted again.
+                hidden => {
+                    let key_len = key.serialized_len();
+                    let old = guard.insert(key, item).or(hidden.map(|(hidden, _)| hidden));
+                    if old.is_none() {
+                        self.entries.fetch_add(1, Ordering::Relaxed);
+                        self.key_bytes.fetch_add(key_len, Ordering::Relaxed);
+                    }
+                    old
+                }
+            }
         };
         if let Some(old) = old
-            && let Index::Inflight { .. } = old.index()
+            && let Index::Inflight { .. } | Index::InflightRaw { .. } = old.index()
         {
             self.metrics
                 .inner_op_duration_entry_flush
                 .observe(old.inserted.unwrap().elapsed().as_secs_f64());
         }
+        Ok(())
+    }
+
+    /// Replace the value of the inflight entry of `key` if no flusher has claimed it yet, with the sequence taken from
+    /// `sequence` with the shard locked.
+    ///
+    /// Returns the new sequence, or `None` if there is no such entry, and nothing is changed.
+    pub fn coalesce(
+        &self,
+        key: &K,
+        sequence: impl FnOnce() -> Sequence,
+        value: V,
+        inserted_at: u64,
+        trace_id: Option<u64>,
+    ) -> Option<Sequence> {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        match guard.get_mut(key) {
+            Some(item) if !item.claimed && matches!(item.index, Index::Inflight { .. }) => {
+                item.sequence = sequence();
+                item.index = Index::Inflight {
+                    key: key.clone(),
+                    value,
+                };
+                item.inserted_at = inserted_at;
+                item.trace_id = trace_id;
+                Some(item.sequence)
+            }
+            _ => None,
+        }
+    }
+
+    /// Take the sequence of the entry of `key` to overwrite in place from `next`, if it is still the flushed entry of
+    /// `sequence`.
+    ///
+    /// New entries take their sequences with the shard locked as well, see [`Catalog::insert_with`]. So an entry of
+    /// the key indexed before the reservation makes it fail, and one indexed after it has a greater sequence than the
+    /// overwrite, both in memory and on the device.
+    pub fn reserve_overwrite(&self, key: &K, sequence: Sequence, next: impl FnOnce() -> Sequence) -> Option<Sequence> {
+        let shard = self.shard(key);
+        let guard = self.items[shard].read();
+        match guard.get(key) {
+            Some(item) if item.sequence == sequence && matches!(item.index, Index::Region { .. }) => Some(next()),
+            _ => None,
+        }
+    }
+
+    /// Bump the sequence of the entry of `key` overwritten in place, if it is still the entry of `sequence` and its
+    /// region is not being reclaimed.
+    ///
+    /// Returns `false` if the entry is replaced, removed or taken by the reclaimer, and nothing is changed.
+    pub fn overwrite(
+        &self,
+        key: &K,
+        sequence: Sequence,
+        new_sequence: Sequence,
+        inserted_at: u64,
+        trace_id: Option<u64>,
+    ) -> bool {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        let Some(item) = guard.get_mut(key) else {
+            return false;
+        };
+        let Index::Region { view } = &item.index else {
+            return false;
+        };
+        if item.sequence != sequence {
+            return false;
+        }
+        let mut keys = self.regions[*view.id() as usize].lock();
+        // The key is missing from the region map once the reclaimer has taken the region.
+        match keys.get_mut(key) {
+            Some(seq) if *seq == sequence => *seq = new_sequence,
+            _ => return false,
+        }
+        item.sequence = new_sequence;
+        item.inserted_at = inserted_at;
+        item.trace_id = trace_id;
+        true
+    }
+
+    /// Claim the inflight entry of `key` for flushing, it can no longer be coalesced.
+    ///
+    /// Returns the latest sequence, value, insertion time and trace id of the entry if it is not older than `sequence`.
+    pub fn claim(&self, key: &K, sequence: Sequence) -> Option<(Sequence, V, u64, Option<u64>)> {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        let item = guard.get_mut(key)?;
+        if item.claimed || item.sequence < sequence {
+            return None;
+        }
+        let Index::Inflight { value, .. } = &item.index else {
+            return None;
+        };
+        let value = value.clone();
+        item.claimed = true;
+        Some((item.sequence, value, item.inserted_at, item.trace_id))
+    }
+
+    /// Hide the item of `key` from lookups until it is resurrected, removed or purged. `removed_at` is in milliseconds
+    /// since unix epoch.
+    ///
+    /// Returns `false` if there is no visible item of `key`.
+    pub fn remove_soft(&self, key: &K, removed_at: u64) -> bool {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        let Some(item) = guard.remove(key) else {
+            return false;
+        };
+        self.soft_removed.lock().insert(key.clone(), (item, removed_at));
+        true
+    }
+
+    /// Make the item of `key` hidden by [`Catalog::remove_soft`] visible again, if it is removed at or after
+    /// `removed_after`.
+    pub fn resurrect(&self, key: &K, removed_after: u64) -> bool {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        let mut soft_removed = self.soft_removed.lock();
+        match soft_removed.get(key) {
+            Some((_, removed_at)) if *removed_at >= removed_after => {}
+            _ => return false,
+        }
+        let (item, _) = soft_removed.remove(key).unwrap();
+        guard.insert(key.clone(), item);
+        true
+    }
+
+    /// Drop the items hidden by [`Catalog::remove_soft`] before `removed_before`.
+    ///
+    /// Returns the number of dropped items.
+    pub fn purge_soft_removed(&self, removed_before: u64) -> usize {
+        let mut soft_removed = self.soft_removed.lock();
+        let keys = soft_removed
+            .iter()
+            .filter(|(_, (_, removed_at))| *removed_at < removed_before)
+            .map(|(key, _)| key.clone())
+            .collect_vec();
+        for key in keys.iter() {
+            let (item, _) = soft_removed.remove(key).unwrap();
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+            self.key_bytes.fetch_sub(key.serialized_len(), Ordering::Relaxed);
+            if let Index::Region { view } = &item.index {
+                let mut regions = self.regions[*view.id() as usize].lock();
+                if regions.get(key) == Some(&item.sequence) {
+                    regions.remove(key);
+                }
+            }
+        }
+        keys.len()
+    }
+
+    /// Count of items hidden by [`Catalog::remove_soft`].
+    pub fn soft_removed(&self) -> usize {
+        self.soft_removed.lock().len()
     }
 
     pub fn lookup(&self, key: &K) -> Option<Item<K, V>> {
@@ -144,17 +461,92 @@ where
         self.items[shard].read().get(key).cloned()
     }
 
+    /// Lookup the item of `key` and count it as an access.
+    pub fn access(&self, key: &K) -> Option<Item<K, V>> {
+        let shard = self.shard(key);
+        self.items[shard].read().get(key).map(|item| {
+            item.accesses.fetch_add(1, Ordering::Relaxed);
+            item.clone()
+        })
+    }
+
     pub fn remove(&self, key: &K) -> Option<Item<K, V>> {
         let shard = self.shard(key);
-        let info: Option<Item<K, V>> = self.items[shard].write().remove(key);
-        if let Some(info) = &info
-            && let Index::Region { view } = &info.index
-        {
-            self.regions[*view.id() as usize].lock().remove(key);
+        let info: Option<Item<K, V>> = self.items[shard]
+            .write()
+            .remove(key)
+            .or_else(|| self.soft_removed.lock().remove(key).map(|(item, _)| item));
+        if let Some(info) = &info {
+            self.entries.fetch_sub(1, Ordering::Relaxed);
+            self.key_bytes.fetch_sub(key.serialized_len(), Ordering::Relaxed);
+            if let Index::Region { view } = &info.index {
+                self.regions[*view.id() as usize].lock().remove(key);
+            }
         }
         info
     }
 
+    /// Remove the inflight item of `key` if it is of `sequence`, e.g. when the entry is dropped by the flusher.
+    pub fn remove_inflight(&self, key: &K, sequence: Sequence) -> bool {
+        let shard = self.shard(key);
+        let mut guard = self.items[shard].write();
+        match guard.get(key) {
+            Some(item)
+                if item.sequence == sequence
+                    && matches!(item.index, Index::Inflight { .. } | Index::InflightRaw { .. }) => {}
+            _ => return false,
+        }
+        guard.remove(key);
+        drop(guard);
+        self.entries.fetch_sub(1, Ordering::Relaxed);
+        self.key_bytes.fetch_sub(key.serialized_len(), Ordering::Relaxed);
+        true
+    }
+
+    /// Remove `keys`, locking each shard and each region index only once.
+    ///
+    /// Returns the number of removed items.
+    pub fn remove_batch(&self, keys: &[K]) -> usize {
+        let mut shards: BTreeMap<usize, Vec<&K>> = BTreeMap::new();
+        for key in keys {
+            shards.entry(self.shard(key)).or_default().push(key);
+        }
+
+        let mut removed = 0;
+        let mut key_bytes = 0;
+        let mut regions: BTreeMap<RegionId, Vec<(&K, Sequence)>> = BTreeMap::new();
+        for (shard, keys) in shards {
+            let mut guard = self.items[shard].write();
+            for key in keys {
+                let Some(item) = guard
+                    .remove(key)
+                    .or_else(|| self.soft_removed.lock().remove(key).map(|(item, _)| item))
+                else {
+                    continue;
+                };
+                removed += 1;
+                key_bytes += key.serialized_len();
+                if let Index::Region { view } = &item.index {
+                    regions.entry(*view.id()).or_default().push((key, item.sequence));
+                }
+            }
+        }
+        self.entries.fetch_sub(removed, Ordering::Relaxed);
+        self.key_bytes.fetch_sub(key_bytes, Ordering::Relaxed);
+
+        for (region, keys) in regions {
+            let mut guard = self.regions[region as usize].lock();
+            for (key, sequence) in keys {
+                // The key may be indexed to the region again since the shard lock is released.
+                if guard.get(key) == Some(&sequence) {
+                    guard.remove(key);
+                }
+            }
+        }
+
+        removed
+    }
+
     pub fn take_region(&self, region: &RegionId) -> Vec<(K, Item<K, V>)> {
         let mut keys = BTreeMap::new();
         std::mem::swap(&mut *self.regions[*region as usize].lock(), &mut keys);
@@ -163,10 +555,23 @@ where
         for (key, sequence) in keys {
             let shard = self.shard(&key);
             match self.items[shard].write().entry(key.clone()) {
-                Entry::Vacant(_) => continue,
+                Entry::Vacant(_) => {
+                    // The hidden item is dropped with its region.
+                    let mut soft_removed = self.soft_removed.lock();
+                    if let Some((hidden, _)) = soft_removed.get(&key)
+                        && hidden.sequence == sequence
+                    {
+                        let (item, _) = soft_removed.remove(&key).unwrap();
+                        self.entries.fetch_sub(1, Ordering::Relaxed);
+                        self.key_bytes.fetch_sub(key.serialized_len(), Ordering::Relaxed);
+                        items.push((key.clone(), item));
+                    }
+                }
                 Entry::Occupied(o) => {
                     if o.get().sequence == sequence {
                         let item = o.remove();
+                        self.entries.fetch_sub(1, Ordering::Relaxed);
+                        self.key_bytes.fetch_sub(key.serialized_len(), Ordering::Relaxed);
                         items.push((key.clone(), item));
                     }
                 }
@@ -175,22 +580,526 @@ where
         items
     }
 
-    pub fn clear(&self) {
+    /// Returns items indexed to `region`, without removing them.
+    pub fn region_items(&self, region: &RegionId) -> Vec<(K, Item<K, V>)> {
+        let keys = self.regions[*region as usize].lock().clone();
+
+        let mut items = Vec::with_capacity(keys.len());
+        for (key, sequence) in keys {
+            let shard = self.shard(&key);
+            if let Some(item) = self.items[shard].read().get(&key)
+                && item.sequence == sequence
+            {
+                items.push((key, item.clone()));
+            }
+        }
+        items
+    }
+
+    /// Returns items of keys in `range`, ordered by key.
+    ///
+    /// Shards are ordered by key, so each shard is searched by range instead of scanned.
+    pub fn range<R>(&self, range: R) -> Vec<(K, Item<K, V>)>
+    where
+        R: RangeBounds<K> + Clone,
+    {
+        let mut items = vec![];
+        for shard in self.items.iter() {
+            items.extend(
+                shard
+                    .read()
+                    .range(range.clone())
+                    .map(|(key, item)| (key.clone(), item.clone())),
+            );
+        }
+        items.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        items
+    }
+
+    /// Returns keys of items with a sequence greater than `sequence`, with their sequences, ordered by sequence.
+    pub fn keys_since(&self, sequence: Sequence) -> Vec<(K, Sequence)> {
+        let mut keys = vec![];
+        for shard in self.items.iter() {
+            keys.extend(
+                shard
+                    .read()
+                    .iter()
+                    .filter(|(_, item)| item.sequence > sequence)
+                    .map(|(key, item)| (key.clone(), item.sequence)),
+            );
+        }
+        keys.sort_by_key(|(_, sequence)| *sequence);
+        keys
+    }
+
+    /// Drop all items, and discard the items with a sequence below `cutoff` inserted later, e.g. the entries still
+    /// queued for flush.
+    pub fn clear(&self, cutoff: Sequence) {
+        self.cutoff.fetch_max(cutoff, Ordering::AcqRel);
         for shard in self.items.iter() {
             shard.write().clear();
         }
+        self.soft_removed.lock().clear();
         for region in self.regions.iter() {
             region.lock().clear();
         }
+        self.entries.store(0, Ordering::Relaxed);
+        self.key_bytes.store(0, Ordering::Relaxed);
+        self.cleared.notify_waiters();
+    }
+
+    /// Items with a sequence below are discarded, see [`Catalog::clear`].
+    pub fn cutoff(&self) -> Sequence {
+        self.cutoff.load(Ordering::Acquire)
+    }
+
+    /// Wait for the next [`Catalog::clear`].
+    pub async fn cleared(&self) {
+        self.cleared.notified().await
+    }
+
+    /// Count of indexed keys.
+    pub fn entries(&self) -> usize {
+        self.entries.load(Ordering::Relaxed)
+    }
+
+    /// Estimated memory of the catalog, with `entry_overhead` bytes per entry besides its key.
+    ///
+    /// A key is held twice, by the item index and by the region index.
+    pub fn memory_usage(&self, entry_overhead: usize) -> usize {
+        self.entries() * entry_overhead + 2 * self.key_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Default estimated memory per entry besides its key: the item and the sequence in the region index.
+    pub fn default_entry_overhead() -> usize {
+        std::mem::size_of::<Item<K, V>>() + std::mem::size_of::<Sequence>()
+    }
+
+    /// Count of items of each shard.
+    pub fn shard_entries(&self) -> Vec<usize> {
+        self.items.iter().map(|shard| shard.read().len()).collect()
+    }
+
+    /// Rebuild the map of `shard` from its sorted items, returns the count of items of the shard.
+    ///
+    /// A node of the map is only released when all its items are removed, after churn the nodes are left sparse.
+    /// Rebuilding packs the items into full nodes. The shard is locked while rebuilt.
+    pub fn compact_shard(&self, shard: usize) -> usize {
+        let mut items = self.items[shard].write();
+        let len = items.len();
+        *items = std::mem::take(&mut *items).into_iter().collect();
+        len
     }
 
     fn shard(&self, key: &K) -> usize {
         self.hash(key) as usize & ((1 << self.bits) - 1)
     }
 
-    fn hash(&self, key: &K) -> u64 {
-        let mut hasher = XxHash64::default();
+    /// Seeded hash of the key, as used for sharding.
+    pub fn hash(&self, key: &K) -> u64 {
+        let mut hasher = XxHash64::with_seed(self.seed);
         key.hash(&mut hasher);
         hasher.finish()
     }
 }
+
+/// Drops the items soft removed longer than the grace period ago, see [`Catalog::remove_soft`].
+#[derive(Debug)]
+pub struct SoftRemovePurger<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    catalog: Arc<Catalog<K, V>>,
+    clock: Arc<dyn Clock>,
+    grace: Duration,
+}
+
+impl<K, V> SoftRemovePurger<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(catalog: Arc<Catalog<K, V>>, clock: Arc<dyn Clock>, grace: Duration) -> Self {
+        Self { catalog, clock, grace }
+    }
+}
+
+impl<K, V> Job for SoftRemovePurger<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    async fn run(&mut self) -> Result<()> {
+        let removed_before = self.clock.now_millis().saturating_sub(self.grace.as_millis() as u64);
+        let purged = self.catalog.purge_soft_removed(removed_before);
+        if purged > 0 {
+            tracing::debug!("[soft remove purger] purged {} entries", purged);
+        }
+        Ok(())
+    }
+}
+
+/// Config of [`CatalogCompactor`].
+#[derive(Debug, Clone)]
+pub struct CatalogCompactionConfig {
+    /// Interval between runs.
+    pub interval: Duration,
+    /// Max count of items to rebuild in a run, which bounds the allocation of a run. A shard with more items is
+    /// still rebuilt, alone in its run.
+    pub budget: usize,
+}
+
+/// Rebuilds the catalog shards in turn to keep the memory of the catalog proportional to the live items, see
+/// [`Catalog::compact_shard`], and reports the shard sizes.
+#[derive(Debug)]
+pub struct CatalogCompactor<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    catalog: Arc<Catalog<K, V>>,
+    budget: usize,
+    metrics: Arc<Metrics>,
+    /// The shard to rebuild first in the next run.
+    next: usize,
+}
+
+impl<K, V> CatalogCompactor<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn new(catalog: Arc<Catalog<K, V>>, budget: usize, metrics: Arc<Metrics>) -> Self {
+        Self {
+            catalog,
+            budget,
+            metrics,
+            next: 0,
+        }
+    }
+}
+
+impl<K, V> Job for CatalogCompactor<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    async fn run(&mut self) -> Result<()> {
+        let shards = self.catalog.items.len();
+        let mut rebuilt = 0;
+        for i in 0..shards {
+            let len = self.catalog.items[self.next].read().len();
+            if i > 0 && rebuilt + len > self.budget {
+                break;
+            }
+            rebuilt += self.catalog.compact_shard(self.next);
+            self.next = (self.next + 1) % shards;
+        }
+        self.metrics.op_count_catalog_compact.inc_by(rebuilt as u64);
+
+        let entries = self.catalog.shard_entries();
+        let (min, max) = entries.iter().minmax().into_option().unwrap();
+        self.metrics.catalog_shard_entries_min.set(*min as u64);
+        self.metrics.catalog_shard_entries_max.set(*max as u64);
+        tracing::debug!(
+            "[catalog compactor] rebuilt {} items, shard entries: [{}, {}]",
+            rebuilt,
+            min,
+            max
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::METRICS;
+
+    #[test]
+    fn test_catalog_hash_seed() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_hash_seed"));
+        let shards = |seed| {
+            let catalog = Catalog::<u64, Vec<u8>>::new(1, 4, seed, metrics.clone());
+            (0..64u64).map(|key| catalog.shard(&key)).collect_vec()
+        };
+
+        assert_eq!(shards(0), shards(0));
+        assert_ne!(shards(0), shards(42));
+    }
+
+    #[test]
+    fn test_catalog_soft_remove() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_soft_remove"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics);
+        let inflight = |sequence, value: u8| {
+            Item::new(
+                sequence,
+                Index::Inflight {
+                    key: 1,
+                    value: vec![value],
+                },
+                0,
+            )
+        };
+
+        catalog.insert(1, inflight(1, 1));
+        assert!(catalog.remove_soft(&1, 100));
+        assert!(!catalog.remove_soft(&1, 100));
+        assert!(catalog.lookup(&1).is_none());
+        assert_eq!((catalog.entries(), catalog.soft_removed()), (1, 1));
+
+        // The entry stays hidden when it is flushed, and is discarded if it is older.
+        catalog.insert(1, inflight(1, 1));
+        catalog.insert(1, inflight(0, 0));
+        assert!(catalog.lookup(&1).is_none());
+        assert!(!catalog.resurrect(&1, 101));
+        assert!(catalog.resurrect(&1, 100));
+        assert_eq!(*catalog.lookup(&1).unwrap().sequence(), 1);
+        assert!(!catalog.resurrect(&1, 100));
+
+        // A newer entry drops the hidden one.
+        assert!(catalog.remove_soft(&1, 200));
+        catalog.insert(1, inflight(2, 2));
+        assert_eq!(*catalog.lookup(&1).unwrap().sequence(), 2);
+        assert_eq!((catalog.entries(), catalog.soft_removed()), (1, 0));
+        assert!(!catalog.resurrect(&1, 0));
+
+        assert!(catalog.remove_soft(&1, 300));
+        assert_eq!(catalog.purge_soft_removed(300), 0);
+        assert_eq!(catalog.purge_soft_removed(301), 1);
+        assert_eq!((catalog.entries(), catalog.soft_removed()), (0, 0));
+        assert!(!catalog.resurrect(&1, 0));
+
+        catalog.insert(1, inflight(3, 3));
+        assert!(catalog.remove_soft(&1, 400));
+        assert!(catalog.remove(&1).is_some());
+        assert_eq!((catalog.entries(), catalog.soft_removed()), (0, 0));
+    }
+
+    #[test]
+    fn test_catalog_clear_cutoff() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_clear_cutoff"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics);
+        let inflight = |key, sequence| {
+            Item::new(
+                sequence,
+                Index::Inflight {
+                    key,
+                    value: vec![sequence as u8],
+                },
+                0,
+            )
+        };
+
+        catalog.insert(1, inflight(1, 1));
+        catalog.clear(2);
+        assert_eq!(catalog.cutoff(), 2);
+        assert_eq!(catalog.entries(), 0);
+
+        // Entries inserted before the clear are discarded when they are flushed.
+        catalog.insert(1, inflight(1, 1));
+        catalog.insert(2, inflight(2, 0));
+        assert!(catalog.lookup(&1).is_none());
+        assert!(catalog.lookup(&2).is_none());
+        catalog.insert(1, inflight(1, 2));
+        assert_eq!(*catalog.lookup(&1).unwrap().sequence(), 2);
+
+        // The cutoff never goes back.
+        catalog.clear(1);
+        assert_eq!(catalog.cutoff(), 2);
+    }
+
+    #[test]
+    fn test_catalog_coalesce() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_coalesce"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics);
+        let inflight = |sequence, value: u8| {
+            Item::new(
+                sequence,
+                Index::Inflight {
+                    key: 1,
+                    value: vec![value],
+                },
+                0,
+            )
+        };
+
+        assert_eq!(catalog.coalesce(&1, || 1, vec![1], 0, None), None);
+
+        catalog.insert(1, inflight(1, 1));
+        assert_eq!(catalog.coalesce(&1, || 2, vec![2], 0, None), Some(2));
+        assert_eq!(catalog.claim(&1, 1), Some((2, vec![2], 0, None)));
+
+        // A claimed entry is neither coalesced nor claimed again.
+        assert_eq!(catalog.coalesce(&1, || 3, vec![3], 0, None), None);
+        assert_eq!(catalog.claim(&1, 1), None);
+
+        // An older item never replaces a newer one.
+        catalog.insert(1, inflight(4, 4));
+        catalog.insert(1, inflight(3, 3));
+        assert_eq!(*catalog.lookup(&1).unwrap().sequence(), 4);
+        assert_eq!(catalog.claim(&1, 3), Some((4, vec![4], 0, None)));
+    }
+
+    #[test]
+    fn test_catalog_insert_absent() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_insert_absent"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 0, 0, metrics);
+        let inflight = |sequence, value: u8| {
+            Item::new(
+                sequence,
+                Index::Inflight {
+                    key: 1,
+                    value: vec![value],
+                },
+                0,
+            )
+        };
+
+        assert!(catalog.insert_absent_with(1, || inflight(1, 1)).is_ok());
+        let existing = catalog
+            .insert_absent_with(1, || unreachable!("the key is indexed"))
+            .unwrap_err();
+        assert_eq!(*existing.sequence(), 1);
+
+        // A removed key is absent, soft removed or not.
+        assert!(catalog.remove_soft(&1, 100));
+        assert!(catalog.insert_absent_with(1, || inflight(2, 2)).is_ok());
+        catalog.remove(&1);
+        assert!(catalog.insert_absent_with(1, || inflight(3, 3)).is_ok());
+        assert_eq!(*catalog.lookup(&1).unwrap().sequence(), 3);
+    }
+
+    #[test]
+    fn test_catalog_remove_batch() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_remove_batch"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 2, 0, metrics);
+        for key in 0..16u64 {
+            catalog.insert(
+                key,
+                Item::new(
+                    key,
+                    Index::Inflight {
+                        key,
+                        value: vec![key as u8],
+                    },
+                    0,
+                ),
+            );
+        }
+
+        // Missing and duplicated keys are skipped.
+        let keys = (0..8u64).chain([3, 100]).collect_vec();
+        assert_eq!(catalog.remove_batch(&keys), 8);
+        assert_eq!(catalog.entries(), 8);
+        assert!((0..8u64).all(|key| catalog.lookup(&key).is_none()));
+        assert!((8..16u64).all(|key| catalog.lookup(&key).is_some()));
+        assert_eq!(catalog.memory_usage(0), 2 * 8 * Key::serialized_len(&0u64));
+    }
+
+    #[test]
+    fn test_catalog_range() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_range"));
+        let catalog = Catalog::<u64, Vec<u8>>::new(1, 2, 0, metrics);
+        for key in (0..32u64).rev() {
+            catalog.insert(
+                key,
+                Item::new(
+                    key,
+                    Index::Inflight {
+                        key,
+                        value: vec![key as u8],
+                    },
+                    0,
+                ),
+            );
+        }
+        catalog.remove(&10);
+
+        let keys = |items: Vec<(u64, Item<u64, Vec<u8>>)>| items.into_iter().map(|(key, _)| key).collect_vec();
+        assert_eq!(keys(catalog.range(8..12)), vec![8, 9, 11]);
+        assert_eq!(keys(catalog.range(30..)), vec![30, 31]);
+        assert_eq!(keys(catalog.range(..=2)), vec![0, 1, 2]);
+        assert_eq!(keys(catalog.range(..)), (0..32).filter(|key| *key != 10).collect_vec());
+        assert!(catalog.range(32..).is_empty());
+    }
+
+    #[test]
+    fn test_catalog_memory_usage() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_memory_usage"));
+        let catalog = Catalog::<Vec<u8>, Vec<u8>>::new(1, 2, 0, metrics);
+        let inflight = |key: &Vec<u8>| {
+            Item::new(
+                0,
+                Index::Inflight {
+                    key: key.clone(),
+                    value: vec![],
+                },
+                0,
+            )
+        };
+
+        for len in 1..=4 {
+            let key = vec![0; len];
+            catalog.insert(key.clone(), inflight(&key));
+        }
+        // Replacing an item does not count the key again.
+        catalog.insert(vec![0; 4], inflight(&vec![0; 4]));
+        let key_bytes: usize = (1..=4).map(|len| Key::serialized_len(&vec![0u8; len])).sum();
+        assert_eq!(catalog.entries(), 4);
+        assert_eq!(catalog.memory_usage(100), 4 * 100 + 2 * key_bytes);
+
+        catalog.remove(&vec![0; 4]);
+        assert_eq!(catalog.entries(), 3);
+        assert_eq!(
+            catalog.memory_usage(100),
+            3 * 100 + 2 * (key_bytes - Key::serialized_len(&vec![0u8; 4]))
+        );
+
+        catalog.clear(0);
+        assert_eq!(catalog.memory_usage(100), 0);
+    }
+
+    #[tokio::test]
+    async fn test_catalog_compactor() {
+        let metrics = Arc::new(METRICS.foyer("test_catalog_compactor"));
+        let catalog = Arc::new(Catalog::<u64, Vec<u8>>::new(1, 2, 0, metrics.clone()));
+        let inflight = |key| Item::new(key, Index::Inflight { key, value: vec![] }, 0);
+
+        for key in 0..400 {
+            catalog.insert(key, inflight(key));
+        }
+        for key in (0..400).filter(|key| key % 10 != 0) {
+            catalog.remove(&key);
+        }
+        let entries = catalog.shard_entries();
+        assert_eq!(entries.iter().sum::<usize>(), 40);
+
+        // A shard is rebuilt in a run even if it exceeds the budget.
+        let mut compactor = CatalogCompactor::new(catalog.clone(), 0, metrics.clone());
+        compactor.run().await.unwrap();
+        assert_eq!(compactor.next, 1);
+        assert_eq!(metrics.op_count_catalog_compact.get(), entries[0] as u64);
+        assert_eq!(
+            metrics.catalog_shard_entries_min.get(),
+            *entries.iter().min().unwrap() as u64
+        );
+        assert_eq!(
+            metrics.catalog_shard_entries_max.get(),
+            *entries.iter().max().unwrap() as u64
+        );
+
+        // Shards are rebuilt in turn within the budget.
+        let mut compactor = CatalogCompactor::new(catalog.clone(), 40, metrics.clone());
+        compactor.run().await.unwrap();
+        assert_eq!(compactor.next, 0);
+        assert_eq!(metrics.op_count_catalog_compact.get(), 40 + entries[0] as u64);
+
+        assert_eq!(catalog.shard_entries(), entries);
+        for key in 0..400 {
+            assert_eq!(catalog.lookup(&key).is_some(), key % 10 == 0);
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{borrow::Cow, collections::HashMap, fmt::Debug, sync::Arc};
+
+use anyhow::anyhow;
+
+use crate::error::Result;
+
+/// Max count of codecs in a pipeline.
+pub const MAX_CODECS: usize = 4;
+
+/// A stage of the value codec pipeline, e.g. encryption, dictionary compression or custom framing.
+pub trait Codec: Send + Sync + Debug + 'static {
+    /// Identifies the codec in entry headers.
+    ///
+    /// Must not be 0, and must not change as long as entries encoded with the codec are on the device.
+    fn id(&self) -> u8;
+
+    /// Append the encoding of `input` to `output`.
+    fn encode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()>;
+
+    /// Append the decoding of `input` to `output`.
+    fn decode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()>;
+}
+
+/// Ids of the codecs an entry value is encoded with, in encoding order. Unused slots are 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CodecIds([u8; MAX_CODECS]);
+
+impl CodecIds {
+    pub fn to_u32(self) -> u32 {
+        u32::from_be_bytes(self.0)
+    }
+
+    pub fn from_u32(v: u32) -> Self {
+        Self(v.to_be_bytes())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0[0] == 0
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = u8> + '_ {
+        let len = self.0.iter().position(|id| *id == 0).unwrap_or(MAX_CODECS);
+        self.0[..len].iter().copied()
+    }
+}
+
+/// Codecs applied in order to the (compressed) values of entries, and undone in reverse order on read.
+///
+/// Entries are decoded by the codec ids recorded in their headers.
+#[derive(Debug, Clone, Default)]
+pub struct CodecPipeline {
+    codecs: Vec<Arc<dyn Codec>>,
+    by_id: HashMap<u8, Arc<dyn Codec>>,
+    ids: CodecIds,
+}
+
+impl CodecPipeline {
+    pub fn new(codecs: Vec<Arc<dyn Codec>>) -> Self {
+        assert!(
+            codecs.len() <= MAX_CODECS,
+            "at most {MAX_CODECS} codecs are supported, given: {}",
+            codecs.len()
+        );
+        let mut ids = [0; MAX_CODECS];
+        let mut by_id = HashMap::new();
+        for (i, codec) in codecs.iter().enumerate() {
+            assert_ne!(codec.id(), 0, "codec id 0 is reserved: {:?}", codec);
+            assert!(
+                by_id.insert(codec.id(), codec.clone()).is_none(),
+                "duplicated codec id: {}",
+                codec.id()
+            );
+            ids[i] = codec.id();
+        }
+        Self {
+            codecs,
+            by_id,
+            ids: CodecIds(ids),
+        }
+    }
+
+    /// Ids of the codecs, recorded in the header of the entries encoded by the pipeline.
+    pub fn ids(&self) -> CodecIds {
+        self.ids
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.codecs.is_empty()
+    }
+
+    /// Encode `data` with all codecs in order.
+    pub fn encode<'a>(&self, data: &'a [u8]) -> std::io::Result<Cow<'a, [u8]>> {
+        let mut data = Cow::Borrowed(data);
+        for codec in self.codecs.iter() {
+            let mut output = Vec::with_capacity(data.len());
+            codec.encode(&data, &mut output)?;
+            data = Cow::Owned(output);
+        }
+        Ok(data)
+    }
+
+    /// Decode `data` encoded with the codecs of `ids`, in reverse order.
+    pub fn decode<'a>(&self, ids: CodecIds, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let mut data = Cow::Borrowed(data);
+        for id in ids.iter().rev() {
+            let codec = self
+                .by_id
+                .get(&id)
+                .ok_or_else(|| anyhow!("entry is encoded with unknown codec {}", id))?;
+            let mut output = Vec::with_capacity(data.len());
+            codec.decode(&data, &mut output).map_err(anyhow::Error::from)?;
+            data = Cow::Owned(output);
+        }
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use bytes::{Buf, BufMut};
+
+    use super::*;
+
+    /// Xor every byte with a key, a stand-in for encryption.
+    #[derive(Debug)]
+    pub struct XorCodec(pub u8);
+
+    impl Codec for XorCodec {
+        fn id(&self) -> u8 {
+            1
+        }
+
+        fn encode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            output.extend(input.iter().map(|b| b ^ self.0));
+            Ok(())
+        }
+
+        fn decode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            self.encode(input, output)
+        }
+    }
+
+    /// Prefix the data with its length.
+    #[derive(Debug)]
+    pub struct FramingCodec;
+
+    impl Codec for FramingCodec {
+        fn id(&self) -> u8 {
+            2
+        }
+
+        fn encode(&self, input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            output.put_u32(input.len() as u32);
+            output.extend_from_slice(input);
+            Ok(())
+        }
+
+        fn decode(&self, mut input: &[u8], output: &mut Vec<u8>) -> std::io::Result<()> {
+            if input.remaining() < 4 || input.get_u32() as usize != input.len() {
+                return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "invalid frame"));
+            }
+            output.extend_from_slice(input);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_codec_pipeline() {
+        let data = b"hello world".to_vec();
+
+        let empty = CodecPipeline::default();
+        assert!(empty.ids().is_empty());
+        assert!(matches!(empty.encode(&data).unwrap(), Cow::Borrowed(_)));
+
+        let pipeline = CodecPipeline::new(vec![Arc::new(XorCodec(0x5a)), Arc::new(FramingCodec)]);
+        let ids = pipeline.ids();
+        assert_eq!(ids.iter().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(CodecIds::from_u32(ids.to_u32()), ids);
+
+        let encoded = pipeline.encode(&data).unwrap().into_owned();
+        assert_eq!(encoded.len(), data.len() + 4);
+        assert_eq!(&encoded[..4], &(data.len() as u32).to_be_bytes());
+        assert_ne!(&encoded[4..], &data[..]);
+        assert_eq!(pipeline.decode(ids, &encoded).unwrap(), &data[..]);
+
+        // Data is decoded with the codecs recorded with it, regardless of the pipeline order.
+        let reversed = CodecPipeline::new(vec![Arc::new(FramingCodec), Arc::new(XorCodec(0x5a))]);
+        assert_eq!(reversed.decode(ids, &encoded).unwrap(), &data[..]);
+        assert_eq!(reversed.decode(CodecIds::default(), &data).unwrap(), &data[..]);
+
+        // Data encoded with an unknown codec can not be decoded.
+        let xor = CodecPipeline::new(vec![Arc::new(XorCodec(0x5a))]);
+        assert!(xor.decode(ids, &encoded).is_err());
+    }
+}
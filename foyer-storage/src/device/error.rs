@@ -12,43 +12,145 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
+use std::{fmt::Display, ops::Range, path::PathBuf};
+
+use crate::region::RegionId;
+
 #[derive(thiserror::Error, Debug)]
 #[error("{0}")]
 pub struct DeviceError(Box<DeviceErrorInner>);
 
 #[derive(thiserror::Error, Debug)]
-#[error("{source}")]
 struct DeviceErrorInner {
+    #[source]
     source: DeviceErrorKind,
+    context: Option<IoContext>,
     // https://github.com/dtolnay/thiserror/issues/204
     // backtrace: Backtrace,
 }
 
+impl Display for DeviceErrorInner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{} ({})", self.source, context),
+            None => write!(f, "{}", self.source),
+        }
+    }
+}
+
+/// Operation of a failed io.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOp {
+    Open,
+    Read,
+    Write,
+    Flush,
+}
+
+/// Where a failed io happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoContext {
+    pub op: IoOp,
+    /// Path of the file or directory, if the device is backed by files.
+    pub path: Option<PathBuf>,
+    pub region: Option<RegionId>,
+    /// Byte range in the region.
+    pub range: Option<Range<usize>>,
+}
+
+impl IoContext {
+    pub fn new(op: IoOp) -> Self {
+        Self {
+            op,
+            path: None,
+            region: None,
+            range: None,
+        }
+    }
+
+    pub fn with_path(mut self, path: PathBuf) -> Self {
+        self.path = Some(path);
+        self
+    }
+
+    pub fn with_region(mut self, region: RegionId, range: Range<usize>) -> Self {
+        self.region = Some(region);
+        self.range = Some(range);
+        self
+    }
+}
+
+impl Display for IoContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "op: {:?}", self.op)?;
+        if let Some(path) = &self.path {
+            write!(f, ", path: {}", path.display())?;
+        }
+        if let Some(region) = self.region {
+            write!(f, ", region: {}", region)?;
+        }
+        if let Some(range) = &self.range {
+            write!(f, ", range: {:?}", range)?;
+        }
+        Ok(())
+    }
+}
+
+impl DeviceError {
+    pub fn kind(&self) -> &DeviceErrorKind {
+        &self.0.source
+    }
+
+    /// Where the io failed, if known.
+    pub fn context(&self) -> Option<&IoContext> {
+        self.0.context.as_ref()
+    }
+
+    /// Attach where the io failed, the innermost context is kept.
+    pub fn with_context(mut self, context: impl FnOnce() -> IoContext) -> Self {
+        if self.0.context.is_none() {
+            self.0.context = Some(context());
+        }
+        self
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum DeviceErrorKind {
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
     #[error("nix error: {0}")]
     Nix(#[from] nix::errno::Errno),
+    #[error("incompatible config: {0}")]
+    Incompatible(String),
     #[error("other error: {0}")]
     Other(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
 }
 
+impl From<DeviceErrorKind> for DeviceError {
+    fn from(value: DeviceErrorKind) -> Self {
+        Self(Box::new(DeviceErrorInner {
+            source: value,
+            context: None,
+        }))
+    }
+}
+
 impl From<std::io::Error> for DeviceError {
     fn from(value: std::io::Error) -> Self {
-        value.into()
+        DeviceErrorKind::from(value).into()
     }
 }
 
 impl From<nix::errno::Errno> for DeviceError {
     fn from(value: nix::errno::Errno) -> Self {
-        value.into()
+        DeviceErrorKind::from(value).into()
     }
 }
 
 impl From<String> for DeviceError {
     fn from(value: String) -> Self {
-        value.into()
+        DeviceErrorKind::from(Box::<dyn std::error::Error + Send + Sync + 'static>::from(value)).into()
     }
 }
 
@@ -62,4 +164,19 @@ mod tests {
     fn test_error_size() {
         assert_eq!(std::mem::size_of::<DeviceError>(), std::mem::size_of::<usize>());
     }
+
+    #[test]
+    fn test_error_context() {
+        let e = DeviceError::from(std::io::Error::from(std::io::ErrorKind::UnexpectedEof));
+        assert!(e.context().is_none());
+
+        let e = e
+            .with_context(|| IoContext::new(IoOp::Read).with_region(3, 4096..8192))
+            .with_context(|| IoContext::new(IoOp::Write));
+        assert_eq!(e.context().unwrap().op, IoOp::Read);
+        assert_eq!(
+            e.to_string(),
+            "io error: unexpected end of file (op: Read, region: 3, range: 4096..8192)"
+        );
+    }
 }
@@ -14,20 +14,25 @@
 
 use std::{
     fs::{create_dir_all, File, OpenOptions},
+    ops::Range,
     os::fd::{AsRawFd, BorrowedFd, RawFd},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::Arc,
 };
 
-use foyer_common::range::RangeBoundsExt;
+use bytes::{Buf, BufMut};
+use foyer_common::{
+    buf::{AlignedBuffer, HUGEPAGE_SIZE},
+    range::RangeBoundsExt,
+};
 use futures::future::try_join_all;
 use itertools::Itertools;
 
 use super::{
-    allocator::AlignedAllocator,
     asyncify,
-    error::{DeviceError, DeviceResult},
-    Device, IoBuf, IoBufMut, IoRange,
+    error::{DeviceError, DeviceErrorKind, DeviceResult, IoContext, IoOp},
+    mmap::{MappedSlice, Mapping, MmapAdvice},
+    Device, IoBuf, IoBufMut, IoRange, ReadIoPolicy,
 };
 use crate::region::RegionId;
 
@@ -47,6 +52,29 @@ pub struct FsDeviceConfig {
 
     /// recommended optimized io block size
     pub io_size: usize,
+
+    /// back io buffers not smaller than a hugepage with transparent hugepages
+    pub hugepage_buffers: bool,
+
+    /// how reads are split into physical reads
+    pub read_io_policy: ReadIoPolicy,
+
+    /// how sealed regions are read
+    pub read_mode: FsReadMode,
+}
+
+/// How [`FsDevice`] reads the data of sealed regions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FsReadMode {
+    /// Read into io buffers with `pread(2)`.
+    #[default]
+    Pread,
+    /// Map device files read-only and read sealed regions from the mapping, with no syscall nor copy per read.
+    ///
+    /// Fits hot workloads whose data mostly stays in the page cache. Reads that miss the page cache block the thread
+    /// on page faults, and the page cache is used regardless of direct io. Device files are extended to
+    /// `file_capacity`.
+    Mmap { advice: MmapAdvice },
 }
 
 impl FsDeviceConfig {
@@ -54,6 +82,68 @@ impl FsDeviceConfig {
         assert!(self.align.is_power_of_two());
         assert_eq!(self.file_capacity % self.align, 0);
         assert_eq!(self.capacity % self.file_capacity, 0);
+        if let ReadIoPolicy::Adaptive { max } = self.read_io_policy {
+            assert!(max > 0);
+            assert_eq!(max % self.align, 0);
+        }
+    }
+}
+
+const MANIFEST_MAGIC: u32 = 0x20_24_04_01;
+const MANIFEST_FILENAME: &str = "foyer-manifest";
+
+/// Region geometry of the data in a device dir, pinned on first open.
+///
+/// Data written with a different region size or alignment can not be read, while the other fields of
+/// [`FsDeviceConfig`] may change across restarts. Changing `capacity` adds empty regions or drops the regions beyond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FsDeviceManifest {
+    file_capacity: usize,
+    align: usize,
+}
+
+impl FsDeviceManifest {
+    /// Read the manifest in `dir`, or pin `self` if there is none.
+    ///
+    /// Returns an error if the pinned manifest differs from `self`.
+    fn pin(&self, dir: &Path) -> DeviceResult<()> {
+        let path = dir.join(MANIFEST_FILENAME);
+        if path.exists() {
+            let pinned = Self::decode(&std::fs::read(&path)?)?;
+            if pinned != *self {
+                return Err(DeviceErrorKind::Incompatible(format!(
+                    "device dir {:?} is pinned to {:?}, but opened with {:?}",
+                    dir, pinned, self
+                ))
+                .into());
+            }
+            return Ok(());
+        }
+
+        // Write to a temporary file and rename it to make the update atomic.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, self.encode())?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// | magic (4B) | file capacity (8B) | align (8B) |
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(20);
+        buf.put_u32(MANIFEST_MAGIC);
+        buf.put_u64(self.file_capacity as u64);
+        buf.put_u64(self.align as u64);
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> DeviceResult<Self> {
+        if buf.remaining() != 20 || buf.get_u32() != MANIFEST_MAGIC {
+            return Err(DeviceErrorKind::Incompatible("invalid manifest".to_string()).into());
+        }
+        Ok(Self {
+            file_capacity: buf.get_u64() as usize,
+            align: buf.get_u64() as usize,
+        })
     }
 }
 
@@ -66,7 +156,8 @@ struct FsDeviceInner {
 
     files: Vec<File>,
 
-    io_buffer_allocator: AlignedAllocator,
+    /// Mappings of `files` in [`FsReadMode::Mmap`], empty otherwise.
+    mappings: Vec<Arc<Mapping>>,
 }
 
 #[derive(Debug, Clone)]
@@ -76,7 +167,6 @@ pub struct FsDevice {
 
 impl Device for FsDevice {
     type Config = FsDeviceConfig;
-    type IoBufferAllocator = AlignedAllocator;
 
     async fn open(config: FsDeviceConfig) -> DeviceResult<Self> {
         Self::open(config).await
@@ -98,12 +188,14 @@ impl Device for FsDevice {
 
         let fd = self.fd(region);
 
-        asyncify(move || {
+        let (res, buf) = asyncify(move || {
             let fd = unsafe { BorrowedFd::borrow_raw(fd) };
             let res = nix::sys::uio::pwrite(fd, &buf.as_ref()[range], offset as i64).map_err(DeviceError::from);
             (res, buf)
         })
-        .await
+        .await;
+        let res = res.map_err(|e| e.with_context(|| self.io_context(IoOp::Write, region, offset..offset + len)));
+        (res, buf)
     }
 
     async fn read<B>(
@@ -128,12 +220,14 @@ impl Device for FsDevice {
 
         let fd = self.fd(region);
 
-        asyncify(move || {
+        let (res, buf) = asyncify(move || {
             let fd = unsafe { BorrowedFd::borrow_raw(fd) };
             let res = nix::sys::uio::pread(fd, &mut buf.as_mut()[range], offset as i64).map_err(DeviceError::from);
             (res, buf)
         })
-        .await
+        .await;
+        let res = res.map_err(|e| e.with_context(|| self.io_context(IoOp::Read, region, offset..offset + len)));
+        (res, buf)
     }
 
     #[cfg(target_os = "linux")]
@@ -142,7 +236,9 @@ impl Device for FsDevice {
         // Commit fs cache to disk. Linux waits for I/O completions.
         //
         // See also [syncfs(2)](https://man7.org/linux/man-pages/man2/sync.2.html)
-        asyncify(move || nix::unistd::syncfs(fd).map_err(DeviceError::from)).await?;
+        asyncify(move || nix::unistd::syncfs(fd).map_err(DeviceError::from))
+            .await
+            .map_err(|e| e.with_context(|| IoContext::new(IoOp::Flush).with_path(self.inner.config.dir.clone())))?;
         Ok(())
     }
 
@@ -152,6 +248,22 @@ impl Device for FsDevice {
         Ok(())
     }
 
+    async fn sync(&self, region: RegionId) -> DeviceResult<()> {
+        let fd = self.fd(region);
+        // Only the data and the metadata needed to read it back, e.g. the grown file size, are synced.
+        //
+        // See also [fdatasync(2)](https://man7.org/linux/man-pages/man2/fdatasync.2.html)
+        asyncify(move || {
+            #[cfg(target_os = "linux")]
+            let res = nix::unistd::fdatasync(fd);
+            #[cfg(not(target_os = "linux"))]
+            let res = nix::unistd::fsync(fd);
+            res.map_err(DeviceError::from)
+        })
+        .await
+        .map_err(|e| e.with_context(|| self.io_context(IoOp::Flush, region, 0..self.inner.config.file_capacity)))
+    }
+
     fn capacity(&self) -> usize {
         self.inner.config.capacity
     }
@@ -168,13 +280,37 @@ impl Device for FsDevice {
         self.inner.config.io_size
     }
 
-    fn io_buffer_allocator(&self) -> &Self::IoBufferAllocator {
-        &self.inner.io_buffer_allocator
+    fn read_io_policy(&self) -> ReadIoPolicy {
+        self.inner.config.read_io_policy
     }
 
-    fn io_buffer(&self, len: usize, capacity: usize) -> Vec<u8, Self::IoBufferAllocator> {
+    fn map(&self, region: RegionId, range: Range<usize>) -> Option<MappedSlice> {
+        let mapping = self.inner.mappings.get(region as usize)?;
+        Some(MappedSlice::new(mapping.clone(), range))
+    }
+
+    fn release_cache(&self) -> usize {
+        let mut released = 0;
+        for (file, mapping) in self.inner.files.iter().zip(self.inner.mappings.iter()) {
+            released += mapping.release();
+            // Unmapped clean pages are still cached by the kernel, drop them too.
+            #[cfg(target_os = "linux")]
+            unsafe {
+                libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED)
+            };
+            #[cfg(not(target_os = "linux"))]
+            let _ = file;
+        }
+        released
+    }
+
+    fn io_buffer(&self, len: usize, capacity: usize) -> AlignedBuffer {
         assert!(len <= capacity);
-        let mut buf = Vec::with_capacity_in(capacity, self.inner.io_buffer_allocator);
+        let mut buf = if self.inner.config.hugepage_buffers && capacity >= HUGEPAGE_SIZE {
+            AlignedBuffer::with_capacity_hugepage(self.align(), capacity)
+        } else {
+            AlignedBuffer::with_capacity(self.align(), capacity)
+        };
         unsafe { buf.set_len(len) };
         buf
     }
@@ -184,8 +320,6 @@ impl FsDevice {
     pub async fn open(config: FsDeviceConfig) -> DeviceResult<Self> {
         config.verify();
 
-        // TODO(MrCroxx): write and read config to a manifest file for pinning
-
         let regions = config.capacity / config.file_capacity;
 
         let path = config.dir.clone();
@@ -193,7 +327,17 @@ impl FsDevice {
             create_dir_all(&path)?;
             File::open(&path).map_err(DeviceError::from)
         })
-        .await?;
+        .await
+        .map_err(|e| e.with_context(|| IoContext::new(IoOp::Open).with_path(config.dir.clone())))?;
+
+        let manifest = FsDeviceManifest {
+            file_capacity: config.file_capacity,
+            align: config.align,
+        };
+        let path = config.dir.clone();
+        asyncify(move || manifest.pin(&path))
+            .await
+            .map_err(|e| e.with_context(|| IoContext::new(IoOp::Open).with_path(config.dir.join(MANIFEST_FILENAME))))?;
 
         let futures = (0..regions)
             .map(|i| {
@@ -209,7 +353,9 @@ impl FsDevice {
                     #[cfg(target_os = "linux")]
                     opts.custom_flags(libc::O_DIRECT);
 
-                    let file = opts.open(path)?;
+                    let file = opts.open(&path).map_err(|e| {
+                        DeviceError::from(e).with_context(|| IoContext::new(IoOp::Open).with_path(path))
+                    })?;
 
                     Ok::<_, DeviceError>(file)
                 }
@@ -217,22 +363,59 @@ impl FsDevice {
             .collect_vec();
         let files = try_join_all(futures).await?;
 
-        let io_buffer_allocator = AlignedAllocator::new(config.align);
+        let mappings = match config.read_mode {
+            FsReadMode::Pread => vec![],
+            FsReadMode::Mmap { advice } => {
+                let file_capacity = config.file_capacity;
+                let futures = files
+                    .iter()
+                    .enumerate()
+                    .map(|(i, file)| {
+                        let path = config.dir.clone().join(Self::filename(i as RegionId));
+                        async move {
+                            // Accessing a mapping beyond the end of file faults, extend the file to the full region.
+                            let res = (|| {
+                                if file.metadata()?.len() < file_capacity as u64 {
+                                    file.set_len(file_capacity as u64)?;
+                                }
+                                Mapping::new(file, file_capacity)
+                            })();
+                            let mapping = res.map_err(|e| {
+                                DeviceError::from(e).with_context(|| IoContext::new(IoOp::Open).with_path(path))
+                            })?;
+                            mapping.advise(advice);
+                            Ok::<_, DeviceError>(Arc::new(mapping))
+                        }
+                    })
+                    .collect_vec();
+                try_join_all(futures).await?
+            }
+        };
 
         let inner = FsDeviceInner {
             config,
             dir,
             files,
-            io_buffer_allocator,
+            mappings,
         };
 
         Ok(Self { inner: Arc::new(inner) })
     }
 
+    pub fn config(&self) -> &FsDeviceConfig {
+        &self.inner.config
+    }
+
     fn fd(&self, region: RegionId) -> RawFd {
         self.inner.files[region as usize].as_raw_fd()
     }
 
+    fn io_context(&self, op: IoOp, region: RegionId, range: Range<usize>) -> IoContext {
+        IoContext::new(op)
+            .with_path(self.inner.config.dir.join(Self::filename(region)))
+            .with_region(region, range)
+    }
+
     fn filename(region: RegionId) -> String {
         format!("foyer-cache-{:08}", region)
     }
@@ -244,6 +427,7 @@ mod tests {
     use bytes::BufMut;
 
     use super::*;
+    use crate::device::DeviceExt;
 
     const FILES: usize = 8;
     const FILE_CAPACITY: usize = 8 * 1024; // 8 KiB
@@ -259,6 +443,9 @@ mod tests {
             file_capacity: FILE_CAPACITY,
             align: ALIGN,
             io_size: ALIGN,
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
         };
         let dev = FsDevice::open(config).await.unwrap();
 
@@ -269,12 +456,111 @@ mod tests {
 
         let (res, wbuffer) = dev.write(wbuffer, .., 0, 0).await;
         res.unwrap();
+        dev.sync(0).await.unwrap();
         let (res, rbuffer) = dev.read(rbuffer, .., 0, 0).await;
         res.unwrap();
 
-        assert_eq!(&wbuffer, &rbuffer);
+        assert_eq!(&wbuffer[..], &rbuffer[..]);
 
         drop(wbuffer);
         drop(rbuffer);
     }
+
+    #[tokio::test]
+    async fn test_fs_device_adaptive_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FsDeviceConfig {
+            dir: PathBuf::from(dir.path()),
+            capacity: CAPACITY,
+            file_capacity: FILE_CAPACITY,
+            align: ALIGN,
+            io_size: ALIGN,
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Adaptive { max: FILE_CAPACITY },
+            read_mode: FsReadMode::Pread,
+        };
+        let dev = FsDevice::open(config).await.unwrap();
+
+        let mut wbuffer = dev.io_buffer(FILE_CAPACITY, FILE_CAPACITY);
+        (&mut wbuffer[..ALIGN]).put_slice(&[b'x'; ALIGN]);
+        (&mut wbuffer[ALIGN..]).put_slice(&[b'y'; ALIGN]);
+        let (res, _) = dev.write(wbuffer, .., 0, 0).await;
+        res.unwrap();
+
+        let buf = dev.load(0, ..).await.unwrap();
+        assert_eq!(&buf[..ALIGN], &[b'x'; ALIGN]);
+        assert_eq!(&buf[ALIGN..], &[b'y'; ALIGN]);
+
+        let buf = dev.load(0, ALIGN..).await.unwrap();
+        assert_eq!(&buf[..], &[b'y'; ALIGN]);
+    }
+
+    #[tokio::test]
+    async fn test_fs_device_error_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        File::create(&path).unwrap();
+
+        // A file is not a valid device dir.
+        let config = FsDeviceConfig {
+            dir: path.clone(),
+            capacity: CAPACITY,
+            file_capacity: FILE_CAPACITY,
+            align: ALIGN,
+            io_size: ALIGN,
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        };
+        let e = FsDevice::open(config).await.unwrap_err();
+        assert_eq!(e.context(), Some(&IoContext::new(IoOp::Open).with_path(path)));
+    }
+
+    #[tokio::test]
+    async fn test_fs_device_manifest() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = FsDeviceConfig {
+            dir: PathBuf::from(dir.path()),
+            capacity: CAPACITY,
+            file_capacity: FILE_CAPACITY,
+            align: ALIGN,
+            io_size: ALIGN,
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        };
+        drop(FsDevice::open(config.clone()).await.unwrap());
+
+        // Region geometry is pinned, the others may change.
+        let dev = FsDevice::open(FsDeviceConfig {
+            capacity: 2 * CAPACITY,
+            io_size: 2 * ALIGN,
+            read_io_policy: ReadIoPolicy::Adaptive { max: FILE_CAPACITY },
+            read_mode: FsReadMode::Pread,
+            ..config.clone()
+        })
+        .await
+        .unwrap();
+        assert_eq!(dev.regions(), 2 * FILES);
+        drop(dev);
+
+        for config in [
+            FsDeviceConfig {
+                file_capacity: 2 * FILE_CAPACITY,
+                ..config.clone()
+            },
+            FsDeviceConfig {
+                align: 2 * ALIGN,
+                io_size: 2 * ALIGN,
+                ..config.clone()
+            },
+        ] {
+            let e = FsDevice::open(config).await.unwrap_err();
+            assert!(matches!(e.kind(), DeviceErrorKind::Incompatible(_)));
+            assert_eq!(
+                e.context(),
+                Some(&IoContext::new(IoOp::Open).with_path(dir.path().join(MANIFEST_FILENAME)))
+            );
+        }
+    }
 }
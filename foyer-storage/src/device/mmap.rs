@@ -0,0 +1,202 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Debug,
+    fs::File,
+    ops::{Deref, Range},
+    os::fd::AsRawFd,
+    ptr::NonNull,
+    sync::Arc,
+};
+
+/// Access pattern hint of a mapping, passed to `madvise(2)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MmapAdvice {
+    /// No special treatment.
+    Normal,
+    /// Pages are accessed in random order, read-ahead is disabled. Fits point lookups.
+    #[default]
+    Random,
+    /// Pages are accessed sequentially, read-ahead is aggressive.
+    Sequential,
+}
+
+/// A read-only shared mapping of a whole file.
+#[derive(Debug)]
+pub struct Mapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl Mapping {
+    /// Map the first `len` bytes of `file`.
+    ///
+    /// The file must not be shorter than `len`, or accessing the tail of the mapping faults.
+    pub fn new(file: &File, len: usize) -> std::io::Result<Self> {
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: NonNull::new(ptr as *mut u8).unwrap(),
+            len,
+        })
+    }
+
+    pub fn advise(&self, advice: MmapAdvice) {
+        let advice = match advice {
+            MmapAdvice::Normal => libc::MADV_NORMAL,
+            MmapAdvice::Random => libc::MADV_RANDOM,
+            MmapAdvice::Sequential => libc::MADV_SEQUENTIAL,
+        };
+        // The advice is only a hint, the mapping works without it if it is rejected.
+        let res = unsafe { libc::madvise(self.ptr.as_ptr() as *mut libc::c_void, self.len, advice) };
+        if res != 0 {
+            tracing::debug!("[mmap] madvise error: {}", std::io::Error::last_os_error());
+        }
+    }
+
+    /// Stop mapping the pages of the file cached in memory, which are faulted in again on access. Returns the bytes
+    /// of the cached pages.
+    ///
+    /// The pages stay in the page cache until the kernel evicts them, or they are dropped with `posix_fadvise(2)`.
+    pub fn release(&self) -> usize {
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let mut residency = vec![0u8; self.len.div_ceil(page)];
+        let res = unsafe {
+            libc::mincore(
+                self.ptr.as_ptr() as *mut libc::c_void,
+                self.len,
+                residency.as_mut_ptr() as *mut _,
+            )
+        };
+        if res != 0 {
+            tracing::debug!("[mmap] mincore error: {}", std::io::Error::last_os_error());
+            return 0;
+        }
+        let resident = residency.iter().filter(|&&r| r & 1 != 0).count() * page;
+
+        let res = unsafe { libc::madvise(self.ptr.as_ptr() as *mut libc::c_void, self.len, libc::MADV_DONTNEED) };
+        if res != 0 {
+            tracing::debug!("[mmap] madvise error: {}", std::io::Error::last_os_error());
+            return 0;
+        }
+        resident
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len) };
+    }
+}
+
+/// A slice of a mapping, keeps the mapping alive.
+pub struct MappedSlice {
+    mapping: Arc<Mapping>,
+    range: Range<usize>,
+}
+
+impl Debug for MappedSlice {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedSlice").field("range", &self.range).finish()
+    }
+}
+
+impl MappedSlice {
+    pub fn new(mapping: Arc<Mapping>, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= mapping.len());
+        Self { mapping, range }
+    }
+}
+
+impl Deref for MappedSlice {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.mapping.ptr.as_ptr().add(self.range.start),
+                self.range.end - self.range.start,
+            )
+        }
+    }
+}
+
+impl AsRef<[u8]> for MappedSlice {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_mapped_slice() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("file");
+
+        let mut file = File::create(&path).unwrap();
+        file.write_all(&[1; 4096]).unwrap();
+        file.write_all(&[2; 4096]).unwrap();
+        drop(file);
+
+        let file = File::open(&path).unwrap();
+        let mapping = Arc::new(Mapping::new(&file, 8192).unwrap());
+        mapping.advise(MmapAdvice::Random);
+        let slice = MappedSlice::new(mapping.clone(), 4000..4200);
+        drop(mapping);
+
+        assert_eq!(&slice[..96], &[1; 96]);
+        assert_eq!(&slice[96..], &[2; 104]);
+
+        // Writes to the file are visible through the mapping.
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[3; 4096])
+            .unwrap();
+        assert_eq!(&slice[..96], &[3; 96]);
+
+        // The pages are faulted in again after released.
+        assert!(slice.mapping.release() > 0);
+        assert_eq!(&slice[..96], &[3; 96]);
+    }
+}
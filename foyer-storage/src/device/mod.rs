@@ -12,25 +12,51 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-pub mod allocator;
 pub mod error;
 pub mod fs;
+pub mod mmap;
 
-use std::{alloc::Allocator, fmt::Debug};
+use std::{fmt::Debug, ops::Range};
 
 use error::DeviceResult;
-use foyer_common::range::RangeBoundsExt;
+use foyer_common::{bits, buf::AlignedBuffer, range::RangeBoundsExt};
 use futures::Future;
+use mmap::MappedSlice;
 
 use crate::region::RegionId;
 
-pub trait BufferAllocator = Allocator + Clone + Send + Sync + 'static + Debug;
 pub trait IoBuf = AsRef<[u8]> + Send + Sync + 'static + Debug;
 pub trait IoBufMut = AsRef<[u8]> + AsMut<[u8]> + Send + Sync + 'static + Debug;
 pub trait IoRange = RangeBoundsExt<usize> + Sized + Send + Sync + 'static + Debug;
 
+/// How a read request is split into physical reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadIoPolicy {
+    /// Read in chunks of the device `io_size`.
+    Fixed,
+    /// Read the remaining bytes rounded up to `align` at once, capped by `max`.
+    ///
+    /// `max` must be a multiplier of `align`.
+    Adaptive { max: usize },
+}
+
+impl ReadIoPolicy {
+    /// Size of the next physical read with `remaining` bytes to read.
+    pub fn next_io_size(&self, remaining: usize, align: usize, io_size: usize) -> usize {
+        match self {
+            ReadIoPolicy::Fixed => std::cmp::min(io_size, remaining),
+            ReadIoPolicy::Adaptive { max } => std::cmp::min(*max, bits::align_up(align, remaining)),
+        }
+    }
+}
+
+/// A device the regions of a store are written to.
+///
+/// A completed write is only visible to reads, it is neither durable nor ordered with the other writes until it is
+/// synced by [`Device::sync`] or [`Device::flush`]. Devices with volatile write caches may persist writes in any
+/// order, so data referenced by other data, e.g. the entries of a region referenced by its footer, must be synced
+/// before the reference is written.
 pub trait Device: Sized + Clone + Send + Sync + 'static + Debug {
-    type IoBufferAllocator: BufferAllocator;
     type Config: Send + Debug + Clone;
 
     #[must_use]
@@ -58,9 +84,16 @@ pub trait Device: Sized + Clone + Send + Sync + 'static + Debug {
     where
         B: IoBufMut;
 
+    /// Persist all completed writes of the device to stable storage.
     #[must_use]
     fn flush(&self) -> impl Future<Output = DeviceResult<()>> + Send;
 
+    /// Persist the completed writes of `region` to stable storage, a write barrier of the region.
+    ///
+    /// Writes to `region` issued after the returned future completes are persisted after the synced writes.
+    #[must_use]
+    fn sync(&self, region: RegionId) -> impl Future<Output = DeviceResult<()>> + Send;
+
     fn capacity(&self) -> usize;
 
     fn regions(&self) -> usize;
@@ -71,9 +104,30 @@ pub trait Device: Sized + Clone + Send + Sync + 'static + Debug {
     /// optimized io size
     fn io_size(&self) -> usize;
 
-    fn io_buffer_allocator(&self) -> &Self::IoBufferAllocator;
+    fn read_io_policy(&self) -> ReadIoPolicy {
+        ReadIoPolicy::Fixed
+    }
+
+    /// Returns `range` of `region` backed by a memory mapping, or `None` if the device does not map its data.
+    ///
+    /// The mapped data changes when the region is written, the caller must keep the region from being rewritten while
+    /// the slice is in use.
+    fn map(&self, _region: RegionId, _range: Range<usize>) -> Option<MappedSlice> {
+        None
+    }
 
-    fn io_buffer(&self, len: usize, capacity: usize) -> Vec<u8, Self::IoBufferAllocator>;
+    /// Drop the pages of memory mappings resident in the process, returns the released bytes.
+    fn release_cache(&self) -> usize {
+        0
+    }
+
+    /// Allocate a buffer aligned for the device io with uninitialized `len` bytes.
+    fn io_buffer(&self, len: usize, capacity: usize) -> AlignedBuffer {
+        assert!(len <= capacity);
+        let mut buf = AlignedBuffer::with_capacity(self.align(), capacity);
+        unsafe { buf.set_len(len) };
+        buf
+    }
 
     fn region_size(&self) -> usize {
         debug_assert!(self.capacity() % self.regions() == 0);
@@ -83,21 +137,18 @@ pub trait Device: Sized + Clone + Send + Sync + 'static + Debug {
 
 pub trait DeviceExt: Device {
     #[must_use]
-    fn load(
-        &self,
-        region: RegionId,
-        range: impl IoRange,
-    ) -> impl Future<Output = DeviceResult<Vec<u8, Self::IoBufferAllocator>>> + Send {
+    fn load(&self, region: RegionId, range: impl IoRange) -> impl Future<Output = DeviceResult<AlignedBuffer>> + Send {
         async move {
             let range = range.bounds(0..self.region_size());
             let size = range.size().unwrap();
             debug_assert_eq!(size & (self.align() - 1), 0);
 
             let mut buf = self.io_buffer(size, size);
+            let policy = self.read_io_policy();
             let mut offset = 0;
 
             while range.start + offset < range.end {
-                let len = std::cmp::min(self.io_size(), size - offset);
+                let len = policy.next_io_size(size - offset, self.align(), self.io_size());
                 let (res, b) = self.read(buf, offset..offset + len, region, range.start + offset).await;
                 let bytes = res?;
                 offset += bytes;
@@ -138,20 +189,27 @@ where
 
 #[cfg(test)]
 pub mod tests {
-    use super::{allocator::AlignedAllocator, *};
+    use std::time::Duration;
+
+    use super::*;
 
     #[derive(Debug, Clone)]
-    pub struct NullDevice(AlignedAllocator);
+    pub struct NullDevice(usize, Duration);
 
     impl NullDevice {
         pub fn new(align: usize) -> Self {
-            Self(AlignedAllocator::new(align))
+            Self(align, Duration::ZERO)
+        }
+
+        /// Delay every write by `delay` to simulate a hung device.
+        pub fn with_write_delay(mut self, delay: Duration) -> Self {
+            self.1 = delay;
+            self
         }
     }
 
     impl Device for NullDevice {
         type Config = usize;
-        type IoBufferAllocator = AlignedAllocator;
 
         async fn open(config: usize) -> DeviceResult<Self> {
             Ok(Self::new(config))
@@ -167,6 +225,9 @@ pub mod tests {
         where
             B: IoBuf,
         {
+            if !self.1.is_zero() {
+                tokio::time::sleep(self.1).await;
+            }
             (Ok(0), buf)
         }
 
@@ -187,8 +248,12 @@ pub mod tests {
             Ok(())
         }
 
+        async fn sync(&self, _region: RegionId) -> DeviceResult<()> {
+            Ok(())
+        }
+
         fn capacity(&self) -> usize {
-            usize::MAX
+            self.regions() * 64 * 1024 * 1024
         }
 
         fn regions(&self) -> usize {
@@ -196,21 +261,25 @@ pub mod tests {
         }
 
         fn align(&self) -> usize {
-            4096
+            self.0
         }
 
         fn io_size(&self) -> usize {
             4096
         }
+    }
 
-        fn io_buffer_allocator(&self) -> &Self::IoBufferAllocator {
-            &self.0
-        }
+    #[test]
+    fn test_read_io_policy() {
+        const ALIGN: usize = 4096;
 
-        fn io_buffer(&self, len: usize, capacity: usize) -> Vec<u8, Self::IoBufferAllocator> {
-            let mut buf = Vec::with_capacity_in(capacity, self.0);
-            unsafe { buf.set_len(len) };
-            buf
-        }
+        let policy = ReadIoPolicy::Fixed;
+        assert_eq!(policy.next_io_size(64 * ALIGN, ALIGN, 4 * ALIGN), 4 * ALIGN);
+        assert_eq!(policy.next_io_size(ALIGN, ALIGN, 4 * ALIGN), ALIGN);
+
+        let policy = ReadIoPolicy::Adaptive { max: 16 * ALIGN };
+        assert_eq!(policy.next_io_size(64 * ALIGN, ALIGN, 4 * ALIGN), 16 * ALIGN);
+        assert_eq!(policy.next_io_size(8 * ALIGN, ALIGN, 4 * ALIGN), 8 * ALIGN);
+        assert_eq!(policy.next_io_size(100, ALIGN, 4 * ALIGN), ALIGN);
     }
 }
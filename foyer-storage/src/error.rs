@@ -32,19 +32,28 @@ pub enum ErrorKind {
     Device(#[from] DeviceError),
     #[error("buffer error: {0}")]
     Buffer(anyhow::Error),
+    #[error("validation error: {0}")]
+    Validation(String),
+    /// The key is already written in write-once mode. `conflict` if the existing value differs from the written one,
+    /// e.g. a collision of content hash keys.
+    #[error("key exists, conflict: {conflict}")]
+    Exists { conflict: bool },
+    /// The operation is not allowed before the interleaved recovery of the store finishes.
+    #[error("store is recovering")]
+    Recovering,
     #[error("other error: {0}")]
     Other(#[from] anyhow::Error),
 }
 
 impl From<ErrorKind> for Error {
     fn from(value: ErrorKind) -> Self {
-        value.into()
+        Self(Box::new(ErrorInner { source: value }))
     }
 }
 
 impl From<DeviceError> for Error {
     fn from(value: DeviceError) -> Self {
-        value.into()
+        ErrorKind::from(value).into()
     }
 }
 
@@ -63,7 +72,13 @@ where
 
 impl From<anyhow::Error> for Error {
     fn from(value: anyhow::Error) -> Self {
-        value.into()
+        ErrorKind::from(value).into()
+    }
+}
+
+impl Error {
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0.source
     }
 }
 
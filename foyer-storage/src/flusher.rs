@@ -12,32 +12,83 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-use std::{fmt::Debug, sync::Arc};
+use std::{fmt::Debug, sync::Arc, time::Duration};
 
-use foyer_common::code::{Key, Value};
+use foyer_common::{
+    cancel::CancellationToken,
+    clock::Clock,
+    code::{Key, Value},
+};
 use foyer_intrusive::{core::adapter::Link, eviction::EvictionPolicy};
-use tokio::sync::{broadcast, mpsc};
+use tokio::{sync::mpsc, time::Instant};
 use tracing::Instrument;
 
 use crate::{
+    admission::write_stall::WriteStall,
     buffer::{BufferError, FlushBuffer, PositionedEntry},
     catalog::{Catalog, Index, Item, Sequence},
+    codec::CodecPipeline,
     compress::Compression,
     device::Device,
     error::Result,
+    generic::{EntryPadding, RawEntry},
+    health::HealthReporter,
+    invariant::Invariants,
     metrics::Metrics,
-    region_manager::{RegionEpItemAdapter, RegionManager},
+    region::{RegionHeader, RegionId, Version, REGION_MAGIC},
+    region_manager::{RegionEpItemAdapter, RegionManager, DEFAULT_DOMAIN},
+    tunables::Tunables,
 };
 
+/// Value of an entry to flush.
+#[derive(Debug, Clone)]
+pub enum Payload<V> {
+    /// Serialized and compressed by the flusher.
+    Value(V),
+    /// Already serialized and compressed, written as is.
+    Raw(RawEntry),
+}
+
+impl<V> Payload<V>
+where
+    V: Value,
+{
+    /// Length of the serialized value before compression.
+    pub fn serialized_len(&self) -> usize {
+        match self {
+            Payload::Value(value) => value.serialized_len(),
+            Payload::Raw(raw) => raw.uncompressed_len,
+        }
+    }
+}
+
 pub struct Entry<K, V>
 where
     K: Key,
     V: Value,
 {
     pub key: K,
-    pub value: V,
+    pub value: Payload<V>,
     pub sequence: Sequence,
+    /// The value is compressed by the flusher when written to the flush buffer, off the caller path.
     pub compression: Compression,
+    /// When the entry is sent to the flusher.
+    pub enqueued_at: std::time::Instant,
+    /// When the entry is inserted, in milliseconds since unix epoch. Persisted in the entry header.
+    pub inserted_at: u64,
+    /// Opaque trace id attached by the writer, logged when the entry is flushed.
+    pub trace_id: Option<u64>,
+}
+
+impl<K, V> Entry<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    /// Serialized bytes of the entry accounted in the inflight bytes until a flusher handles it.
+    pub fn inflight_bytes(&self) -> usize {
+        self.key.serialized_len() + self.value.serialized_len()
+    }
 }
 
 impl<K, V> Debug for Entry<K, V>
@@ -49,6 +100,9 @@ where
         f.debug_struct("Entry")
             .field("sequence", &self.sequence)
             .field("compression", &self.compression)
+            .field("enqueued_at", &self.enqueued_at)
+            .field("inserted_at", &self.inserted_at)
+            .field("trace_id", &self.trace_id)
             .finish()
     }
 }
@@ -64,6 +118,9 @@ where
             value: self.value.clone(),
             sequence: self.sequence,
             compression: self.compression,
+            enqueued_at: self.enqueued_at,
+            inserted_at: self.inserted_at,
+            trace_id: self.trace_id,
         }
     }
 }
@@ -85,9 +142,32 @@ where
 
     entry_rx: mpsc::UnboundedReceiver<Entry<K, V>>,
 
+    tunables: Arc<Tunables>,
+
+    /// Since when the oldest entry in the flush buffer waits.
+    pending_since: Option<Instant>,
+
+    write_stall: Option<Arc<WriteStall>>,
+    is_stalled: bool,
+
+    /// Write the latest value of a coalesced inflight entry.
+    coalesce_inflight: bool,
+
+    /// Catalog cutoff the flusher has handled, see [`Catalog::clear`].
+    cutoff: Sequence,
+
+    /// Retire the active region when the catalog is cleared.
+    clear_active_region: bool,
+
+    clock: Arc<dyn Clock>,
+
     metrics: Arc<Metrics>,
 
-    stop_rx: broadcast::Receiver<()>,
+    invariants: Arc<Invariants>,
+
+    health: Arc<HealthReporter>,
+
+    cancel: CancellationToken,
 }
 
 impl<K, V, D, EP, EL> Flusher<K, V, D, EP, EL>
@@ -98,27 +178,66 @@ where
     EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
     EL: Link,
 {
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
         region_manager: Arc<RegionManager<D, EP, EL>>,
         catalog: Arc<Catalog<K, V>>,
         device: D,
         entry_rx: mpsc::UnboundedReceiver<Entry<K, V>>,
+        tunables: Arc<Tunables>,
+        write_stall: Option<Arc<WriteStall>>,
+        write_timeout: Option<Duration>,
+        coalesce_inflight: bool,
+        entry_padding: EntryPadding,
+        codecs: CodecPipeline,
+        write_barrier: bool,
+        clear_active_region: bool,
+        clock: Arc<dyn Clock>,
         metrics: Arc<Metrics>,
-        stop_rx: broadcast::Receiver<()>,
+        invariants: Arc<Invariants>,
+        health: Arc<HealthReporter>,
+        cancel: CancellationToken,
     ) -> Self {
-        let buffer = FlushBuffer::new(device.clone());
+        let buffer = FlushBuffer::new(
+            device.clone(),
+            write_timeout,
+            entry_padding,
+            codecs,
+            write_barrier,
+            metrics.clone(),
+        );
+        let cutoff = catalog.cutoff();
         Self {
             region_manager,
             catalog,
             buffer,
             entry_rx,
+            tunables,
+            pending_since: None,
+            write_stall,
+            is_stalled: false,
+            coalesce_inflight,
+            cutoff,
+            clear_active_region,
+            clock,
             metrics,
-            stop_rx,
+            invariants,
+            health,
+            cancel,
         }
     }
 
     pub async fn run(mut self) -> Result<()> {
         loop {
+            if self.catalog.cutoff() > self.cutoff {
+                self.handle_clear().await?;
+            }
+
+            let deadline = match (self.tunables.flush_interval(), self.pending_since) {
+                (Some(interval), Some(since)) => Some(since + interval),
+                _ => None,
+            };
+
             tokio::select! {
                 biased;
                 entry = self.entry_rx.recv() => {
@@ -127,20 +246,56 @@ where
                         tracing::info!("[flusher] exit");
                         return Ok(());
                     };
+                    let wait = entry.enqueued_at.elapsed();
                     self.handle(entry).await?;
+                    self.update_write_stall(wait);
+                }
+                _ = tokio::time::sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                    let entries = self.buffer.flush().await?;
+                    self.update_catalog(entries).await?;
                 }
-                _ = self.stop_rx.recv() => {
+                _ = self.tunables.changed() => {}
+                _ = self.catalog.cleared() => {}
+                _ = self.cancel.cancelled() => {
+                    // The receive above may yield to the cooperative budget of the task, entries enqueued before
+                    // the cancellation must still be written.
+                    while let Ok(entry) = self.entry_rx.try_recv() {
+                        self.handle(entry).await?;
+                    }
                     self.buffer.flush().await?;
                     tracing::info!("[flusher] exit");
                     return Ok(())
                 }
             }
+
+            self.pending_since = if self.buffer.has_pending_entries() {
+                Some(self.pending_since.unwrap_or_else(Instant::now))
+            } else {
+                None
+            };
+            self.health.success();
         }
     }
 
-    async fn handle(&mut self, entry: Entry<K, V>) -> Result<()> {
+    async fn handle(&mut self, mut entry: Entry<K, V>) -> Result<()> {
         let timer = self.metrics.inner_op_duration_flusher_handle.start_timer();
 
+        self.metrics.inflight_bytes.sub(entry.inflight_bytes() as u64);
+
+        // The entry is inserted before the catalog is cleared, it must not be written.
+        if entry.sequence < self.catalog.cutoff() {
+            return Ok(());
+        }
+
+        if self.coalesce_inflight
+            && let Some((sequence, value, inserted_at, trace_id)) = self.catalog.claim(&entry.key, entry.sequence)
+        {
+            entry.sequence = sequence;
+            entry.value = Payload::Value(value);
+            entry.inserted_at = inserted_at;
+            entry.trace_id = trace_id;
+        }
+
         let old_region = self.buffer.region();
 
         let entry = match self.buffer.write(entry).await {
@@ -150,31 +305,28 @@ where
         };
 
         // current region is full, rotate flush buffer region and retry
+        rotate(
+            &mut self.buffer,
+            old_region,
+            &self.region_manager,
+            &self.catalog,
+            self.clock.as_ref(),
+            &self.metrics,
+        )
+        .await?;
 
-        // 1. get a clean region
-        let acquire_clean_region_timer = self.metrics.inner_op_duration_acquire_clean_region.start_timer();
-        let new_region = self
-            .region_manager
-            .clean_regions()
-            .acquire()
-            .instrument(tracing::debug_span!("acquire_clean_region"))
-            .await;
-        drop(acquire_clean_region_timer);
-
-        // 2. rotate flush buffer
-        let entries = self.buffer.rotate(new_region).await?;
-        self.update_catalog(entries).await?;
-        if let Some(old_region) = old_region {
-            self.region_manager.eviction_push(old_region);
-        }
-
-        self.metrics
-            .total_bytes
-            .add(self.region_manager.region(&new_region).device().region_size() as u64);
-
-        // 3. retry write
+        // retry write
         let entries = match self.buffer.write(entry).await {
-            Err(BufferError::NeedRotate(_)) => unreachable!(),
+            Err(BufferError::NeedRotate(entry)) => {
+                // The entry is dropped, and must not be served from memory either.
+                self.catalog.remove_inflight(&entry.key, entry.sequence);
+                let region = self.buffer.region();
+                let reason = format!("entry of sequence {} exceeds a clean region", entry.sequence);
+                if self.invariants.violated("flusher", region, reason) {
+                    self.drop_region().await?;
+                }
+                return Ok(());
+            }
             result => result?,
         };
 
@@ -184,34 +336,186 @@ where
         Ok(())
     }
 
-    #[tracing::instrument(skip(self))]
-    async fn update_catalog(&self, entries: Vec<PositionedEntry<K, V>>) -> Result<()> {
-        if entries.is_empty() {
+    /// Retire the active region and drop its entries, see [`crate::invariant::InvariantPolicy::DropRegion`].
+    ///
+    /// The region header is wiped so the entries are not recovered either, the region is reclaimed as usual.
+    async fn drop_region(&mut self) -> Result<()> {
+        let (_, Some(region_id)) = self.buffer.retire().await? else {
             return Ok(());
+        };
+        let dropped = self.catalog.take_region(&region_id);
+        tracing::warn!("[flusher] drop region {} with {} entries", region_id, dropped.len());
+
+        let region = self.region_manager.region(&region_id);
+        let align = region.device().align();
+        let mut buf = region.device().io_buffer(align, align);
+        buf.fill(0);
+        let (res, _buf) = region.device().write(buf, .., region_id, 0).await;
+        // The header is wiped again when the region is reclaimed.
+        if let Err(e) = res {
+            self.invariants.violated("flusher", Some(region_id), e);
         }
+        region.invalidate_header();
 
-        // record fully flushed bytes by the way
-        let mut bytes = 0;
+        self.region_manager.eviction_push(region_id);
+        Ok(())
+    }
 
-        let timer = self.metrics.inner_op_duration_update_catalog.start_timer();
-        for PositionedEntry {
-            entry: Entry { key, sequence, .. },
-            region,
-            offset,
-            len,
-        } in entries
-        {
-            bytes += len;
-            let index = Index::Region {
-                view: self.region_manager.region(&region).view(offset as u32, len as u32),
-            };
-            let item = Item::new(sequence, index);
-            self.catalog.insert(key, item);
+    /// The catalog is cleared, retire the active region if configured.
+    ///
+    /// Entries inserted before the clear are dropped from the queue by [`Flusher::handle`], and from the flush buffer
+    /// by the catalog.
+    async fn handle_clear(&mut self) -> Result<()> {
+        self.cutoff = self.catalog.cutoff();
+        if !self.clear_active_region {
+            return Ok(());
         }
-        drop(timer);
 
-        self.metrics.op_bytes_flush.inc_by(bytes as u64);
+        let (entries, region) = self.buffer.retire().await?;
+        self.update_catalog(entries).await?;
+        if let Some(region) = region {
+            tracing::info!("[flusher] retire active region {} on clear", region);
+            self.region_manager.eviction_push(region);
+        }
+        Ok(())
+    }
+
+    /// Update write stall state with how long the handled entry waited in queue.
+    fn update_write_stall(&mut self, wait: Duration) {
+        let Some(write_stall) = &self.write_stall else {
+            return;
+        };
+
+        let stalled = wait > write_stall.threshold() && !self.entry_rx.is_empty();
+        match (self.is_stalled, stalled) {
+            (false, true) => {
+                tracing::warn!("[flusher] write stalled, queue wait: {:?}", wait);
+                write_stall.stall();
+            }
+            (true, false) => {
+                tracing::info!("[flusher] write stall recovered");
+                write_stall.recover();
+            }
+            _ => {}
+        }
+        self.is_stalled = stalled;
+    }
 
+    #[tracing::instrument(skip(self))]
+    async fn update_catalog(&self, entries: Vec<PositionedEntry<K, V>>) -> Result<()> {
+        index_flushed(&self.region_manager, &self.catalog, &self.metrics, entries);
         Ok(())
     }
 }
+
+/// Index the entries flushed to the device, and account their bytes.
+pub(crate) fn index_flushed<K, V, D, EP, EL>(
+    region_manager: &RegionManager<D, EP, EL>,
+    catalog: &Catalog<K, V>,
+    metrics: &Metrics,
+    entries: Vec<PositionedEntry<K, V>>,
+) where
+    K: Key,
+    V: Value,
+    D: Device,
+    EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
+    EL: Link,
+{
+    if entries.is_empty() {
+        return;
+    }
+
+    // record fully flushed bytes by the way
+    let mut bytes = 0;
+    let mut logical_bytes = 0;
+
+    let timer = metrics.inner_op_duration_update_catalog.start_timer();
+    for PositionedEntry {
+        entry:
+            Entry {
+                key,
+                value,
+                sequence,
+                inserted_at,
+                trace_id,
+                ..
+            },
+        region,
+        offset,
+        len,
+    } in entries
+    {
+        let logical = key.serialized_len() + value.serialized_len();
+        region_manager.region(&region).stats().add(logical, len);
+        bytes += len;
+        logical_bytes += logical;
+        let index = Index::Region {
+            view: region_manager.region(&region).view(offset as u32, len as u32),
+        };
+        if let Some(trace_id) = trace_id {
+            tracing::info!(
+                "[flusher] entry flushed, trace id: {}, sequence: {}, region: {}, offset: {}",
+                trace_id,
+                sequence,
+                region,
+                offset
+            );
+        }
+        let item = Item::new(sequence, index, inserted_at).with_trace_id(trace_id);
+        catalog.insert(key, item);
+    }
+    drop(timer);
+
+    metrics.op_bytes_flush.inc_by(bytes as u64);
+    metrics.op_bytes_flush_logical.inc_by(logical_bytes as u64);
+}
+
+/// Rotate `buffer` to a clean region of the default domain.
+///
+/// `old_region` is the region of the buffer before the write that needs the rotation, the write may have filled it up.
+/// The entries flushed with the old region are indexed before it is pushed to eviction, so they are never indexed on a
+/// reclaimed region.
+pub(crate) async fn rotate<K, V, D, EP, EL>(
+    buffer: &mut FlushBuffer<K, V, D>,
+    old_region: Option<RegionId>,
+    region_manager: &RegionManager<D, EP, EL>,
+    catalog: &Catalog<K, V>,
+    clock: &dyn Clock,
+    metrics: &Metrics,
+) -> Result<()>
+where
+    K: Key,
+    V: Value,
+    D: Device,
+    EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
+    EL: Link,
+{
+    // 1. get a clean region, all entries are written to the default domain for now
+    let acquire_clean_region_timer = metrics.inner_op_duration_acquire_clean_region.start_timer();
+    let new_region = region_manager
+        .clean_regions(DEFAULT_DOMAIN)
+        .acquire()
+        .instrument(tracing::debug_span!("acquire_clean_region"))
+        .await;
+    drop(acquire_clean_region_timer);
+
+    // 2. rotate flush buffer
+    let created_at = clock.now_millis();
+    let entries = buffer.rotate(new_region, created_at).await?;
+    let region = region_manager.region(&new_region);
+    region.set_created_at(created_at);
+    region.set_header(RegionHeader {
+        magic: REGION_MAGIC,
+        version: Version::latest(),
+        created_at,
+        padding: buffer.padding(),
+    });
+    index_flushed(region_manager, catalog, metrics, entries);
+    if let Some(old_region) = old_region {
+        region_manager.eviction_push(old_region);
+    }
+
+    metrics.total_bytes.add(region.device().region_size() as u64);
+
+    Ok(())
+}
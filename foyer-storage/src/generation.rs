@@ -0,0 +1,157 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use bytes::{Buf, BufMut};
+use parking_lot::Mutex;
+
+use crate::{catalog::Sequence, error::Result};
+
+const GENERATION_MAGIC: u32 = 0x20_24_05_14;
+
+const GENERATION_FILE_LEN: usize = 4 + 8 + 8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct State {
+    generation: u64,
+    cutoff: Sequence,
+}
+
+/// Generation of the entries of a store.
+///
+/// Each bump records the sequence of the next entry as the cutoff of the new generation. Entries of sequences below
+/// the cutoff belong to previous generations and are discarded by the catalog, both on lookup and on recovery, while
+/// their regions are left as they are until reclaimed. The generation is persisted to `path` (if given) on every bump.
+#[derive(Debug)]
+pub struct Generation {
+    path: Option<PathBuf>,
+    state: Mutex<State>,
+}
+
+impl Generation {
+    /// Open generation, load the persisted generation from `path` if exists.
+    pub fn open(path: Option<PathBuf>) -> Result<Self> {
+        let state = match &path {
+            Some(path) if path.exists() => {
+                let buf = std::fs::read(path).map_err(anyhow::Error::from)?;
+                Self::decode(&buf)?
+            }
+            _ => State::default(),
+        };
+        Ok(Self {
+            path,
+            state: Mutex::new(state),
+        })
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.state.lock().generation
+    }
+
+    /// Entries of sequences below the cutoff belong to previous generations.
+    pub fn cutoff(&self) -> Sequence {
+        self.state.lock().cutoff
+    }
+
+    /// Start a new generation with `cutoff`, returns the new generation.
+    ///
+    /// The cutoff never goes backwards. The in-memory generation is not changed if it fails to persist.
+    pub fn bump(&self, cutoff: Sequence) -> Result<u64> {
+        let mut state = self.state.lock();
+        let next = State {
+            generation: state.generation + 1,
+            cutoff: std::cmp::max(state.cutoff, cutoff),
+        };
+        self.persist(&next)?;
+        *state = next;
+        Ok(next.generation)
+    }
+
+    fn persist(&self, state: &State) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write to a temporary file and rename it to make the update atomic, sync both the file before the rename and
+        // the directory after it to make the update durable.
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp).map_err(anyhow::Error::from)?;
+        file.write_all(&Self::encode(state)).map_err(anyhow::Error::from)?;
+        file.sync_all().map_err(anyhow::Error::from)?;
+        drop(file);
+        std::fs::rename(&tmp, path).map_err(anyhow::Error::from)?;
+        if let Some(dir) = path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(anyhow::Error::from)?;
+        }
+        Ok(())
+    }
+
+    /// | magic (4B) | generation (8B) | cutoff (8B) |
+    fn encode(state: &State) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(GENERATION_FILE_LEN);
+        buf.put_u32(GENERATION_MAGIC);
+        buf.put_u64(state.generation);
+        buf.put_u64(state.cutoff);
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> Result<State> {
+        if buf.remaining() != GENERATION_FILE_LEN || buf.get_u32() != GENERATION_MAGIC {
+            return Err(anyhow!("invalid generation file").into());
+        }
+        Ok(State {
+            generation: buf.get_u64(),
+            cutoff: buf.get_u64(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generation_persist() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("generation");
+
+        let generation = Generation::open(Some(path.clone())).unwrap();
+        assert_eq!((generation.generation(), generation.cutoff()), (0, 0));
+        assert_eq!(generation.bump(10).unwrap(), 1);
+        // The cutoff never goes backwards.
+        assert_eq!(generation.bump(5).unwrap(), 2);
+        assert_eq!((generation.generation(), generation.cutoff()), (2, 10));
+        drop(generation);
+
+        let generation = Generation::open(Some(path.clone())).unwrap();
+        assert_eq!((generation.generation(), generation.cutoff()), (2, 10));
+        drop(generation);
+
+        std::fs::write(&path, [0; GENERATION_FILE_LEN]).unwrap();
+        assert!(Generation::open(Some(path)).is_err());
+    }
+}
@@ -13,11 +13,15 @@
 //  limitations under the License.
 
 use std::{
+    collections::{hash_map, HashMap, VecDeque},
     fmt::Debug,
     hash::Hasher,
     marker::PhantomData,
+    ops::RangeBounds,
+    path::{Path, PathBuf},
+    pin::Pin,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
         Arc,
     },
     time::{Duration, Instant},
@@ -28,36 +32,267 @@ use bitmaps::Bitmap;
 use bytes::{Buf, BufMut};
 use foyer_common::{
     bits,
-    code::{CodingError, Key, Value},
+    cancel::CancellationToken,
+    clock::{Clock, SystemClock},
+    code::{CodingError, Cursor, Key, Value},
 };
 use foyer_intrusive::{core::adapter::Link, eviction::EvictionPolicy};
-use futures::future::try_join_all;
+use futures::{future::try_join_all, Stream, StreamExt};
 use itertools::Itertools;
 use parking_lot::Mutex;
 use tokio::{
-    sync::{broadcast, mpsc, Semaphore},
+    sync::{mpsc, oneshot, Notify, Semaphore},
     task::JoinHandle,
 };
 use twox_hash::XxHash64;
 
 use crate::{
-    admission::{AdmissionContext, AdmissionPolicy},
-    catalog::{Catalog, Index, Item, Sequence},
+    admission::{
+        budget::BudgetAdmissionPolicy,
+        controller::{AdmissionRateController, AdmissionRateControllerConfig},
+        inflight::InflightAdmissionPolicy,
+        write_stall::{WriteStall, WriteStallAdmissionPolicy},
+        AdmissionContext, AdmissionPolicy, AdmissionVerdict,
+    },
+    buffer::{BufferError, FlushBuffer},
+    catalog::{Catalog, CatalogCompactionConfig, CatalogCompactor, Index, Item, Sequence, SoftRemovePurger},
+    codec::{Codec, CodecIds, CodecPipeline},
     compress::Compression,
-    device::Device,
-    error::Result,
-    flusher::{Entry, Flusher},
+    device::{
+        fs::{FsDevice, FsDeviceConfig},
+        Device, DeviceExt,
+    },
+    error::{Error, ErrorKind, Result},
+    flusher::{index_flushed, rotate, Entry, Flusher, Payload},
+    generation::Generation,
+    health::{run_reported, HealthReporter, StoreHealth, TaskKind},
+    invariant::{InvariantPolicy, Invariants},
     judge::Judges,
     metrics::{Metrics, METRICS},
+    miss_cache::{MissCache, MissCacheConfig},
+    partitioner::FlushPartitioner,
+    profile,
+    quarantine::Quarantine,
+    quota::{SoftQuota, SoftQuotaConfig},
     reclaimer::Reclaimer,
-    region::{Region, RegionHeader, RegionId},
-    region_manager::{RegionEpItemAdapter, RegionManager},
+    region::{DirectoryRecord, Region, RegionFooter, RegionId, Version},
+    region_manager::{DomainId, RegionEpItemAdapter, RegionManager},
     reinsertion::{ReinsertionContext, ReinsertionPolicy},
+    scheduler::{JobHandle, JobSchedule, Scheduler},
+    snapshot::{CatalogSnapshot, CatalogSnapshotEntry},
+    stats::{OpCounters, StatsHistory, StatsRollup, StoreStats},
     storage::{Storage, StorageWriter},
+    tunables::Tunables,
+    validator::KeyValidator,
 };
 
-const DEFAULT_BROADCAST_CAPACITY: usize = 4096;
+/// Entries processed by a recovery or reinsertion loop before yielding to other tasks on the runtime.
+pub(crate) const YIELD_BUDGET: usize = 64;
+
+/// How entries are checked when recovering the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryMode {
+    /// Index entries with valid headers. Corrupted payloads are discovered at lookup.
+    Trust,
+    /// Verify checksums and drop corrupted entries before indexing. Recovery takes longer.
+    ///
+    /// A sealed region is verified with one read of its data, entries of a region not sealed or corrupted are read and
+    /// verified one by one.
+    Verify,
+}
+
+/// Progress of the recovery of a store.
+#[derive(Debug)]
+struct RecoveryProgress {
+    /// Whether each region is fully recovered.
+    regions: Vec<AtomicBool>,
+    /// Upper bound of the sequences of the entries of each region that are not recovered yet, `Sequence::MAX` if
+    /// unknown.
+    pending: Vec<AtomicU64>,
+    finished: AtomicBool,
+    /// Error of the failed recovery.
+    error: Mutex<Option<String>>,
+    notify: Notify,
+}
+
+impl RecoveryProgress {
+    fn new(regions: usize) -> Self {
+        Self {
+            regions: (0..regions).map(|_| AtomicBool::new(false)).collect(),
+            pending: (0..regions).map(|_| AtomicU64::new(Sequence::MAX)).collect(),
+            finished: AtomicBool::new(false),
+            error: Mutex::new(None),
+            notify: Notify::new(),
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::Acquire)
+    }
+
+    fn is_recovered(&self, region: RegionId) -> bool {
+        self.is_finished() || self.regions[region as usize].load(Ordering::Acquire)
+    }
+
+    /// Bound the sequences of the entries of `region` once known, e.g. from the footer of a sealed region.
+    fn bound(&self, region: RegionId, sequence: Sequence) {
+        self.pending[region as usize].store(sequence, Ordering::Release);
+    }
+
+    fn recovered(&self, region: RegionId) {
+        self.pending[region as usize].store(0, Ordering::Release);
+        self.regions[region as usize].store(true, Ordering::Release);
+    }
+
+    /// Whether a region not recovered yet may hold an entry newer than the entry of `sequence`.
+    fn may_be_outdated(&self, sequence: Sequence) -> bool {
+        !self.is_finished()
+            && self
+                .pending
+                .iter()
+                .any(|pending| pending.load(Ordering::Acquire) > sequence)
+    }
+
+    fn finish(&self, res: std::result::Result<(), String>) {
+        *self.error.lock() = res.err();
+        self.finished.store(true, Ordering::Release);
+        self.notify.notify_waiters();
+    }
+
+    async fn wait(&self) -> Result<()> {
+        loop {
+            let notified = self.notify.notified();
+            if self.is_finished() {
+                return match self.error.lock().clone() {
+                    Some(e) => Err(anyhow::anyhow!("recovery failed: {}", e).into()),
+                    None => Ok(()),
+                };
+            }
+            notified.await;
+        }
+    }
+}
+
+/// How entries are padded in a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntryPadding {
+    /// Pad every entry up to the device alignment, an entry is read with no more io than its own blocks.
+    #[default]
+    Aligned,
+    /// Pack entries next to each other with a small padding, small entries share aligned blocks.
+    ///
+    /// Saves up to an aligned block per entry, at the cost of reading the blocks shared with the neighbour entries.
+    Packed,
+}
+
+impl EntryPadding {
+    /// Padding granularity of packed entries.
+    pub const PACKED_ALIGN: usize = 8;
+
+    /// Returns the padding granularity of entries on a device with `align`.
+    pub fn granularity(&self, align: usize) -> usize {
+        match self {
+            EntryPadding::Aligned => align,
+            EntryPadding::Packed => Self::PACKED_ALIGN,
+        }
+    }
+
+    pub fn to_u64(&self) -> u64 {
+        match self {
+            EntryPadding::Aligned => 0,
+            EntryPadding::Packed => 1,
+        }
+    }
+}
+
+impl TryFrom<u64> for EntryPadding {
+    type Error = anyhow::Error;
+
+    fn try_from(value: u64) -> std::result::Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Aligned),
+            1 => Ok(Self::Packed),
+            v => Err(anyhow!("invalid entry padding: {}", v)),
+        }
+    }
+}
+
+/// How inserted entries are routed to flushers.
+pub enum FlushRouting<K>
+where
+    K: Key,
+{
+    /// Route by insertion sequence, entries are spread evenly across flushers.
+    Sequence,
+    /// Route by key hash, entries of the same key are written to the same regions.
+    KeyHash,
+    /// Route by the partition of the key, related entries are written to the same regions.
+    Partitioner(Arc<dyn FlushPartitioner<Key = K>>),
+}
+
+impl<K> Default for FlushRouting<K>
+where
+    K: Key,
+{
+    fn default() -> Self {
+        Self::Sequence
+    }
+}
+
+impl<K> Debug for FlushRouting<K>
+where
+    K: Key,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sequence => write!(f, "Sequence"),
+            Self::KeyHash => write!(f, "KeyHash"),
+            Self::Partitioner(partitioner) => f.debug_tuple("Partitioner").field(partitioner).finish(),
+        }
+    }
+}
+
+impl<K> Clone for FlushRouting<K>
+where
+    K: Key,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Sequence => Self::Sequence,
+            Self::KeyHash => Self::KeyHash,
+            Self::Partitioner(partitioner) => Self::Partitioner(partitioner.clone()),
+        }
+    }
+}
+
+/// Clean region target as ratios of the region count of an eviction domain, with hysteresis.
+///
+/// Reclamation starts when clean regions drop below the low watermark, and continues until they reach the high
+/// watermark, instead of reclaiming one region whenever a clean region is taken.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CleanRegionWatermarks {
+    /// Ratio of clean regions to start reclamation.
+    pub low: f64,
+    /// Ratio of clean regions to stop reclamation.
+    pub high: f64,
+}
+
+impl CleanRegionWatermarks {
+    /// Watermarks as clean region counts of a domain with `regions` regions.
+    ///
+    /// The low watermark keeps at least 1 clean region, and the high watermark is never below the low watermark.
+    pub fn counts(&self, regions: usize) -> (usize, usize) {
+        let count = |ratio: f64| (ratio.clamp(0.0, 1.0) * regions as f64).ceil() as usize;
+        let low = count(self.low).max(1);
+        let high = count(self.high).max(low);
+        (low, high)
+    }
+}
 
+/// Config of [`GenericStore`].
+///
+/// A store can be closed and reopened on the same device with any fields changed except the region geometry pinned by
+/// the device, e.g. `file_capacity` and `align` of [`FsDeviceConfig`], and all recoverable data is kept.
 pub struct GenericStoreConfig<K, V, D, EP>
 where
     K: Key,
@@ -93,7 +328,8 @@ where
 
     /// Clean region count threshold to trigger reclamation.
     ///
-    /// `clean_region_threshold` is recommended to be equal or larger than `reclaimers`.
+    /// `clean_region_threshold` is recommended to be equal or larger than `reclaimers`. Ignored if
+    /// `clean_region_watermarks` is set.
     pub clean_region_threshold: usize,
 
     /// Concurrency of recovery.
@@ -101,6 +337,178 @@ where
 
     /// Compression algorithm.
     pub compression: Compression,
+
+    /// Collapse an insert into a no-op if an entry with the same key and byte-identical value is still waiting to
+    /// be flushed.
+    ///
+    /// Comparing values requires serializing both of them, so it is recommended only for small values.
+    pub deduplicate_inflight: bool,
+
+    /// Replace the value of an entry with the same key that is still waiting to be flushed, instead of enqueueing
+    /// another write for it.
+    ///
+    /// Only the latest value is written to the device. An entry stops being coalesced once a flusher picks it up.
+    pub coalesce_inflight: bool,
+
+    /// Max serialized key length, `None` means unlimited.
+    ///
+    /// Writers of larger keys are rejected.
+    pub max_key_len: Option<usize>,
+
+    /// Max serialized value length, `None` means unlimited.
+    ///
+    /// Larger values are rejected by [`GenericStore::insert`], or when the writer is finished.
+    pub max_value_len: Option<usize>,
+
+    /// Validators to reject malformed keys.
+    pub key_validators: Vec<Arc<dyn KeyValidator<Key = K>>>,
+
+    /// File to persist the regions quarantined because of corrupted data, `None` means not persisted.
+    ///
+    /// Quarantined regions are skipped by recovery and zeroed by reclamation.
+    pub quarantine_path: Option<PathBuf>,
+
+    /// Remember recently missed keys for a short window to absorb bursts of lookups on absent keys, `None` means
+    /// disabled.
+    pub miss_cache_config: Option<MissCacheConfig>,
+
+    /// Reject new non-forced inserts while entries wait in a flusher queue longer than the threshold, until the
+    /// backlog drains. `None` means disabled.
+    pub write_stall_threshold: Option<Duration>,
+
+    /// Max age of data on device, `None` means unlimited.
+    ///
+    /// Entries older than it are never served, and regions older than it are reclaimed before others. Reinserted
+    /// entries keep their original insert time, so they still expire once they outlive the limit.
+    pub max_data_age: Option<Duration>,
+
+    /// Count of region accesses buffered per shard before applied to the eviction policy.
+    ///
+    /// `0` applies every access immediately. Buffered accesses are always applied before picking a region to evict.
+    pub access_record_batch: usize,
+
+    /// Timeout of reading an entry from the device, `None` means no timeout.
+    ///
+    /// A timed out read is abandoned and the lookup is treated as a miss.
+    pub read_timeout: Option<Duration>,
+
+    /// Timeout of flushing a buffer to the device, `None` means no timeout.
+    ///
+    /// Entries of a timed out flush are abandoned and never indexed.
+    pub write_timeout: Option<Duration>,
+
+    /// How entries are checked when recovering.
+    pub recovery_mode: RecoveryMode,
+
+    /// Seed of the key hasher for catalog sharding and the miss cache.
+    ///
+    /// A random seed is chosen for each store instance if `None`, so keys crafted to collide on one catalog shard
+    /// can not be derived from the source.
+    pub hash_seed: Option<u64>,
+
+    /// How entries are padded in a region, applies to regions written after the store is opened.
+    pub entry_padding: EntryPadding,
+
+    /// Estimated catalog memory per entry besides its key, used by stats and the combined budget.
+    ///
+    /// `None` means the size of the in-memory index of an entry.
+    pub catalog_entry_overhead: Option<usize>,
+
+    /// Budget of device bytes in use plus estimated catalog memory. Non-forced inserts are rejected while the budget
+    /// is exceeded. `None` means disabled.
+    pub combined_budget: Option<usize>,
+
+    /// Clean region target relative to the region count, overrides `clean_region_threshold` if set.
+    pub clean_region_watermarks: Option<CleanRegionWatermarks>,
+
+    /// How inserted entries are routed to flushers.
+    ///
+    /// Entries routed to the same flusher are written to the same regions, routing related entries together improves
+    /// reclaim efficiency and read locality, at the risk of unbalanced flushers.
+    pub flush_routing: FlushRouting<K>,
+
+    /// Occupancy levels to notify a listener when crossed, `None` means disabled.
+    ///
+    /// Occupancy is checked by the reclaimers whenever clean regions change, so it is not tracked without reclaimers.
+    pub soft_quota: Option<SoftQuotaConfig>,
+
+    /// Adjusts the rate of a rated ticket admission policy in the background to hold a target hit ratio, `None` means
+    /// disabled.
+    pub admission_rate_controller: Option<AdmissionRateControllerConfig<K, V>>,
+
+    /// Source of the time of insert timestamps, entry and region ages, and data expiration. `None` means the system
+    /// clock.
+    pub clock: Option<Arc<dyn Clock>>,
+
+    /// Overwrite the entry of a re-inserted key in place if the new entry has the same aligned size, instead of
+    /// appending a new copy.
+    ///
+    /// Reduces space amplification of frequently updated fixed-size entries. Each overwrite reads the old entry header
+    /// and blocks reads of its region until written. A sealed region with overwritten entries no longer matches its
+    /// data checksum and is verified entry by entry in [`RecoveryMode::Verify`]. Entries recovered from the footer of
+    /// a sealed region keep the insert time of their first write.
+    pub overwrite_in_place: bool,
+
+    /// Codecs applied in order to values after compression, e.g. encryption or custom framing. At most
+    /// [`MAX_CODECS`](crate::codec::MAX_CODECS) codecs with distinct ids are supported.
+    ///
+    /// The codec ids are recorded in the entry header and entries are decoded in reverse order on read, so the
+    /// pipeline can change between restarts as long as the codecs of the entries on the device stay registered.
+    /// Entries with unregistered codecs fail to read.
+    pub codecs: Vec<Arc<dyn Codec>>,
+
+    /// How long an entry removed by [`GenericStore::remove_soft`] can be resurrected by [`GenericStore::resurrect`].
+    /// `None` means soft removal is disabled, and entries are removed for good.
+    ///
+    /// Soft removed entries are hidden from lookups but still indexed until the grace period ends or their region is
+    /// reclaimed. Like removal, soft removal is not persisted, removed entries are recovered after a restart.
+    pub soft_remove_grace: Option<Duration>,
+
+    /// Sync the data of a region to stable storage before its footer is written.
+    ///
+    /// Without the barrier, a device with a volatile write cache may persist the footer of a region but not all of its
+    /// data before a crash, and recovery from the footer indexes entries that are lost. Costs a sync per sealed
+    /// region.
+    pub write_barrier: bool,
+
+    /// Retire the active regions of the flushers on [`GenericStore::clear`], so the entries written to them before the
+    /// clear are reclaimed with the regions. Otherwise the regions are filled up first.
+    ///
+    /// Entries inserted before the clear are never indexed either way, including the entries still queued for flush.
+    pub clear_active_regions: bool,
+
+    /// Reject writing a key that is already indexed with [`ErrorKind::Exists`], unless the writer allows it with
+    /// [`GenericStoreWriter::set_overwrite`]. Avoids duplicate writes of immutable entries, e.g. of content-addressed
+    /// caches.
+    ///
+    /// The key is checked and indexed atomically, so of concurrent writers of a new key only one succeeds. The
+    /// existing value is read to tell whether the written value is the same, a different value is reported as a
+    /// conflict and logged. Neither the check nor the read is counted as a lookup.
+    pub write_once: bool,
+
+    /// File to persist the generation of the entries, `None` means not persisted.
+    ///
+    /// See [`GenericStore::bump_generation`].
+    pub generation_path: Option<PathBuf>,
+
+    /// Return from `open` once the recovery is started, and serve lookups while regions are recovered one by one.
+    ///
+    /// Until the recovery finishes, a lookup or read misses if the entry is not recovered yet or a region not fully
+    /// recovered may hold a newer entry of the key, writes are not admitted, and removing entries or clearing the
+    /// store is rejected with [`ErrorKind::Recovering`]. See [`GenericStore::recovered_regions`] and
+    /// [`GenericStore::wait_recovered`].
+    pub interleaved_recovery: bool,
+
+    /// Periodically rebuild the catalog shards to release the memory left by removed entries, and report the shard
+    /// sizes. `None` means disabled.
+    ///
+    /// Worth it for long running stores that churn many keys.
+    pub catalog_compaction: Option<CatalogCompactionConfig>,
+
+    /// How the background workers handle a violated invariant, see [`InvariantPolicy`].
+    ///
+    /// The store is degraded by [`InvariantPolicy::Degrade`], see [`GenericStore::is_degraded`].
+    pub invariant_policy: InvariantPolicy,
 }
 
 impl<K, V, D, EP> Debug for GenericStoreConfig<K, V, D, EP>
@@ -122,6 +530,38 @@ where
             .field("clean_region_threshold", &self.clean_region_threshold)
             .field("recover_concurrency", &self.recover_concurrency)
             .field("compression", &self.compression)
+            .field("deduplicate_inflight", &self.deduplicate_inflight)
+            .field("coalesce_inflight", &self.coalesce_inflight)
+            .field("max_key_len", &self.max_key_len)
+            .field("max_value_len", &self.max_value_len)
+            .field("key_validators", &self.key_validators)
+            .field("quarantine_path", &self.quarantine_path)
+            .field("miss_cache_config", &self.miss_cache_config)
+            .field("write_stall_threshold", &self.write_stall_threshold)
+            .field("max_data_age", &self.max_data_age)
+            .field("access_record_batch", &self.access_record_batch)
+            .field("read_timeout", &self.read_timeout)
+            .field("write_timeout", &self.write_timeout)
+            .field("recovery_mode", &self.recovery_mode)
+            .field("hash_seed", &self.hash_seed)
+            .field("entry_padding", &self.entry_padding)
+            .field("catalog_entry_overhead", &self.catalog_entry_overhead)
+            .field("combined_budget", &self.combined_budget)
+            .field("clean_region_watermarks", &self.clean_region_watermarks)
+            .field("flush_routing", &self.flush_routing)
+            .field("soft_quota", &self.soft_quota)
+            .field("admission_rate_controller", &self.admission_rate_controller)
+            .field("clock", &self.clock)
+            .field("overwrite_in_place", &self.overwrite_in_place)
+            .field("codecs", &self.codecs)
+            .field("soft_remove_grace", &self.soft_remove_grace)
+            .field("write_barrier", &self.write_barrier)
+            .field("clear_active_regions", &self.clear_active_regions)
+            .field("write_once", &self.write_once)
+            .field("generation_path", &self.generation_path)
+            .field("interleaved_recovery", &self.interleaved_recovery)
+            .field("catalog_compaction", &self.catalog_compaction)
+            .field("invariant_policy", &self.invariant_policy)
             .finish()
     }
 }
@@ -146,6 +586,155 @@ where
             clean_region_threshold: self.clean_region_threshold,
             recover_concurrency: self.recover_concurrency,
             compression: self.compression,
+            deduplicate_inflight: self.deduplicate_inflight,
+            coalesce_inflight: self.coalesce_inflight,
+            max_key_len: self.max_key_len,
+            max_value_len: self.max_value_len,
+            key_validators: self.key_validators.clone(),
+            quarantine_path: self.quarantine_path.clone(),
+            miss_cache_config: self.miss_cache_config.clone(),
+            write_stall_threshold: self.write_stall_threshold,
+            max_data_age: self.max_data_age,
+            access_record_batch: self.access_record_batch,
+            read_timeout: self.read_timeout,
+            write_timeout: self.write_timeout,
+            recovery_mode: self.recovery_mode,
+            hash_seed: self.hash_seed,
+            entry_padding: self.entry_padding,
+            catalog_entry_overhead: self.catalog_entry_overhead,
+            combined_budget: self.combined_budget,
+            clean_region_watermarks: self.clean_region_watermarks,
+            flush_routing: self.flush_routing.clone(),
+            soft_quota: self.soft_quota.clone(),
+            admission_rate_controller: self.admission_rate_controller.clone(),
+            clock: self.clock.clone(),
+            overwrite_in_place: self.overwrite_in_place,
+            codecs: self.codecs.clone(),
+            soft_remove_grace: self.soft_remove_grace,
+            write_barrier: self.write_barrier,
+            clear_active_regions: self.clear_active_regions,
+            write_once: self.write_once,
+            generation_path: self.generation_path.clone(),
+            interleaved_recovery: self.interleaved_recovery,
+            catalog_compaction: self.catalog_compaction.clone(),
+            invariant_policy: self.invariant_policy,
+        }
+    }
+}
+
+impl<K, V, D, EP> GenericStoreConfig<K, V, D, EP>
+where
+    K: Key,
+    V: Value,
+    D: Device,
+    EP: EvictionPolicy,
+{
+    /// Config of a store named `name` on the device of `device_config`, with `catalog_bits` of 6, a flusher, a
+    /// reclaimer and a clean region threshold of 1, no compression, [`RecoveryMode::Trust`], the default entry padding,
+    /// flush routing and invariant policy, and no admission nor reinsertion policies. The optional features are
+    /// disabled and the limits are unlimited.
+    ///
+    /// Override the fields to change with the struct update syntax.
+    pub fn new(name: impl Into<String>, eviction_config: EP::Config, device_config: D::Config) -> Self {
+        Self {
+            name: name.into(),
+            eviction_config,
+            device_config,
+            catalog_bits: 6,
+            admissions: vec![],
+            reinsertions: vec![],
+            flushers: 1,
+            reclaimers: 1,
+            clean_region_threshold: 1,
+            recover_concurrency: 8,
+            compression: Compression::None,
+            deduplicate_inflight: false,
+            coalesce_inflight: false,
+            max_key_len: None,
+            max_value_len: None,
+            key_validators: vec![],
+            quarantine_path: None,
+            miss_cache_config: None,
+            write_stall_threshold: None,
+            max_data_age: None,
+            access_record_batch: 0,
+            read_timeout: None,
+            write_timeout: None,
+            recovery_mode: RecoveryMode::Trust,
+            hash_seed: None,
+            entry_padding: EntryPadding::default(),
+            catalog_entry_overhead: None,
+            combined_budget: None,
+            clean_region_watermarks: None,
+            flush_routing: FlushRouting::default(),
+            soft_quota: None,
+            admission_rate_controller: None,
+            clock: None,
+            overwrite_in_place: false,
+            codecs: vec![],
+            soft_remove_grace: None,
+            write_barrier: false,
+            clear_active_regions: false,
+            write_once: false,
+            generation_path: None,
+            interleaved_recovery: false,
+            catalog_compaction: None,
+            invariant_policy: InvariantPolicy::default(),
+        }
+    }
+
+    /// Convert the config to use another eviction policy, keeping all other fields.
+    ///
+    /// The eviction policy is not persisted on the device. A store opened with the converted config recovers the
+    /// regions written under the previous policy and rebuilds the eviction state from them.
+    pub fn with_eviction_config<EP2>(self, eviction_config: EP2::Config) -> GenericStoreConfig<K, V, D, EP2>
+    where
+        EP2: EvictionPolicy,
+    {
+        GenericStoreConfig {
+            name: self.name,
+            eviction_config,
+            device_config: self.device_config,
+            catalog_bits: self.catalog_bits,
+            admissions: self.admissions,
+            reinsertions: self.reinsertions,
+            flushers: self.flushers,
+            reclaimers: self.reclaimers,
+            clean_region_threshold: self.clean_region_threshold,
+            recover_concurrency: self.recover_concurrency,
+            compression: self.compression,
+            deduplicate_inflight: self.deduplicate_inflight,
+            coalesce_inflight: self.coalesce_inflight,
+            max_key_len: self.max_key_len,
+            max_value_len: self.max_value_len,
+            key_validators: self.key_validators,
+            quarantine_path: self.quarantine_path,
+            miss_cache_config: self.miss_cache_config,
+            write_stall_threshold: self.write_stall_threshold,
+            max_data_age: self.max_data_age,
+            access_record_batch: self.access_record_batch,
+            read_timeout: self.read_timeout,
+            write_timeout: self.write_timeout,
+            recovery_mode: self.recovery_mode,
+            hash_seed: self.hash_seed,
+            entry_padding: self.entry_padding,
+            catalog_entry_overhead: self.catalog_entry_overhead,
+            combined_budget: self.combined_budget,
+            clean_region_watermarks: self.clean_region_watermarks,
+            flush_routing: self.flush_routing,
+            soft_quota: self.soft_quota,
+            admission_rate_controller: self.admission_rate_controller,
+            clock: self.clock,
+            overwrite_in_place: self.overwrite_in_place,
+            codecs: self.codecs,
+            soft_remove_grace: self.soft_remove_grace,
+            write_barrier: self.write_barrier,
+            clear_active_regions: self.clear_active_regions,
+            write_once: self.write_once,
+            generation_path: self.generation_path,
+            interleaved_recovery: self.interleaved_recovery,
+            catalog_compaction: self.catalog_compaction,
+            invariant_policy: self.invariant_policy,
         }
     }
 }
@@ -198,14 +787,82 @@ where
 
     flusher_entry_txs: Vec<mpsc::UnboundedSender<Entry<K, V>>>,
     flusher_handles: Mutex<Vec<JoinHandle<()>>>,
-    flushers_stop_tx: broadcast::Sender<()>,
+    /// Cancelled to stop the flushers.
+    flushers_cancel: CancellationToken,
 
     reclaimer_handles: Mutex<Vec<JoinHandle<()>>>,
-    reclaimers_stop_tx: broadcast::Sender<()>,
+    /// Cancelled to stop the reclaimers.
+    reclaimers_cancel: CancellationToken,
+
+    /// Runs the periodic background jobs.
+    scheduler: Scheduler,
+
+    /// Health reporters of the background tasks.
+    health: Mutex<Vec<Arc<HealthReporter>>>,
+
+    invariants: Arc<Invariants>,
+
+    /// Root of the cancellation tokens of all background tasks.
+    cancel: CancellationToken,
 
     metrics: Arc<Metrics>,
 
-    compression: Compression,
+    tunables: Arc<Tunables>,
+
+    deduplicate_inflight: bool,
+
+    coalesce_inflight: bool,
+
+    max_key_len: Option<usize>,
+    max_value_len: Option<usize>,
+    key_validators: Vec<Arc<dyn KeyValidator<Key = K>>>,
+
+    quarantine: Quarantine,
+
+    generation: Generation,
+
+    recovery: RecoveryProgress,
+    recovery_handle: Mutex<Option<JoinHandle<()>>>,
+
+    miss_cache: Option<MissCache>,
+
+    /// Lookups waiting for the result of another lookup of the same key that is reading from the device.
+    lookup_waiters: LookupWaiters<K, V>,
+
+    max_data_age: Option<Duration>,
+
+    read_timeout: Option<Duration>,
+
+    recovery_mode: RecoveryMode,
+
+    /// Estimated catalog memory per entry besides its key.
+    catalog_entry_overhead: usize,
+
+    ops: OpCounters,
+
+    history: StatsHistory,
+
+    write_once: bool,
+
+    entry_padding: EntryPadding,
+
+    write_barrier: bool,
+
+    clean_region_watermarks: Option<CleanRegionWatermarks>,
+    /// Whether each eviction domain is reclaiming towards the high watermark.
+    reclaiming: Vec<AtomicBool>,
+
+    flush_routing: FlushRouting<K>,
+
+    soft_quota: Option<SoftQuota>,
+
+    clock: Arc<dyn Clock>,
+
+    overwrite_in_place: bool,
+
+    codecs: CodecPipeline,
+
+    soft_remove_grace: Option<Duration>,
 
     _marker: PhantomData<V>,
 }
@@ -229,22 +886,60 @@ where
         let region_manager = Arc::new(RegionManager::new(
             device.regions(),
             config.eviction_config,
+            config.access_record_batch,
             device.clone(),
         ));
 
-        let catalog = Arc::new(Catalog::new(device.regions(), config.catalog_bits, metrics.clone()));
+        let hash_seed = config.hash_seed.unwrap_or_else(rand::random);
+        let catalog = Arc::new(Catalog::new(
+            device.regions(),
+            config.catalog_bits,
+            hash_seed,
+            metrics.clone(),
+        ));
+
+        let quarantine = Quarantine::open(config.quarantine_path)?;
+
+        // Entries of previous generations are discarded by the catalog from the start, including on recovery.
+        let generation = Generation::open(config.generation_path)?;
+        catalog.clear(generation.cutoff());
+
+        let tunables = Arc::new(Tunables::new(config.clean_region_threshold, config.compression));
+        let invariants = Arc::new(Invariants::new(config.invariant_policy, metrics.clone()));
+
+        let write_stall = config
+            .write_stall_threshold
+            .map(|threshold| Arc::new(WriteStall::new(threshold)));
+        let mut admissions = config.admissions;
+        if let Some(write_stall) = &write_stall {
+            admissions.push(Arc::new(WriteStallAdmissionPolicy::new(write_stall.clone())));
+        }
+        let catalog_entry_overhead = config
+            .catalog_entry_overhead
+            .unwrap_or_else(Catalog::<K, V>::default_entry_overhead);
+        if let Some(budget) = config.combined_budget {
+            admissions.push(Arc::new(BudgetAdmissionPolicy::new(budget, catalog_entry_overhead)));
+        }
+        admissions.push(Arc::new(InflightAdmissionPolicy::new(tunables.clone())));
+
+        let clock = config.clock.unwrap_or_else(|| Arc::new(SystemClock));
 
-        let (flushers_stop_tx, _) = broadcast::channel(DEFAULT_BROADCAST_CAPACITY);
-        let flusher_stop_rxs = (0..config.flushers).map(|_| flushers_stop_tx.subscribe()).collect_vec();
+        let codecs = CodecPipeline::new(config.codecs);
+
+        let cancel = CancellationToken::new();
+
+        let flushers_cancel = cancel.child();
+        let flusher_cancels = (0..config.flushers).map(|_| flushers_cancel.child()).collect_vec();
         #[expect(clippy::type_complexity)]
         let (flusher_entry_txs, flusher_entry_rxs): (
             Vec<mpsc::UnboundedSender<Entry<K, V>>>,
             Vec<mpsc::UnboundedReceiver<Entry<K, V>>>,
         ) = (0..config.flushers).map(|_| mpsc::unbounded_channel()).unzip();
 
-        let (reclaimers_stop_tx, _) = broadcast::channel(DEFAULT_BROADCAST_CAPACITY);
-        let reclaimer_stop_rxs = (0..config.reclaimers)
-            .map(|_| reclaimers_stop_tx.subscribe())
+        let reclaimers_cancel = cancel.child();
+        // Each eviction domain is served by its own reclaimers.
+        let reclaimer_cancels = (0..config.reclaimers * region_manager.domains())
+            .map(|_| reclaimers_cancel.child())
             .collect_vec();
 
         let inner = GenericStoreInner {
@@ -252,15 +947,49 @@ where
             catalog: catalog.clone(),
             region_manager: region_manager.clone(),
             device: device.clone(),
-            admissions: config.admissions,
+            admissions,
             reinsertions: config.reinsertions,
             flusher_entry_txs,
             flusher_handles: Mutex::new(vec![]),
             reclaimer_handles: Mutex::new(vec![]),
-            flushers_stop_tx,
-            reclaimers_stop_tx,
+            scheduler: Scheduler::new(clock.clone(), metrics.clone(), invariants.clone(), cancel.child()),
+            health: Mutex::new(vec![]),
+            invariants: invariants.clone(),
+            flushers_cancel,
+            reclaimers_cancel,
+            cancel,
             metrics: metrics.clone(),
-            compression: config.compression,
+            tunables: tunables.clone(),
+            deduplicate_inflight: config.deduplicate_inflight,
+            coalesce_inflight: config.coalesce_inflight,
+            max_key_len: config.max_key_len,
+            max_value_len: config.max_value_len,
+            key_validators: config.key_validators,
+            quarantine,
+            generation,
+            recovery: RecoveryProgress::new(device.regions()),
+            recovery_handle: Mutex::new(None),
+            miss_cache: config
+                .miss_cache_config
+                .map(|config| MissCache::new(config, hash_seed, clock.clone())),
+            lookup_waiters: Mutex::new(HashMap::new()),
+            max_data_age: config.max_data_age,
+            read_timeout: config.read_timeout,
+            recovery_mode: config.recovery_mode,
+            catalog_entry_overhead,
+            ops: OpCounters::default(),
+            history: StatsHistory::default(),
+            write_once: config.write_once,
+            entry_padding: config.entry_padding,
+            write_barrier: config.write_barrier,
+            clean_region_watermarks: config.clean_region_watermarks,
+            reclaiming: (0..region_manager.domains()).map(|_| AtomicBool::new(false)).collect(),
+            flush_routing: config.flush_routing,
+            soft_quota: config.soft_quota.map(SoftQuota::new),
+            clock: clock.clone(),
+            overwrite_in_place: config.overwrite_in_place,
+            codecs: codecs.clone(),
+            soft_remove_grace: config.soft_remove_grace,
             _marker: PhantomData,
         };
         let store = Self { inner: Arc::new(inner) };
@@ -281,104 +1010,341 @@ where
             reinsertion.init(reinsertion_context.clone());
         }
 
-        let flushers = flusher_stop_rxs
+        let flusher_health = (0..flusher_cancels.len())
+            .map(|i| Arc::new(HealthReporter::new(TaskKind::Flusher, i, clock.clone())))
+            .collect_vec();
+        let reclaimer_health = (0..reclaimer_cancels.len())
+            .map(|i| Arc::new(HealthReporter::new(TaskKind::Reclaimer, i, clock.clone())))
+            .collect_vec();
+
+        let flushers = flusher_cancels
             .into_iter()
             .zip_eq(flusher_entry_rxs.into_iter())
-            .map(|(stop_rx, entry_rx)| {
+            .zip_eq(flusher_health.iter().cloned())
+            .map(|((cancel, entry_rx), reporter)| {
                 Flusher::new(
                     region_manager.clone(),
                     catalog.clone(),
                     device.clone(),
                     entry_rx,
+                    tunables.clone(),
+                    write_stall.clone(),
+                    config.write_timeout,
+                    config.coalesce_inflight,
+                    config.entry_padding,
+                    codecs.clone(),
+                    config.write_barrier,
+                    config.clear_active_regions,
+                    clock.clone(),
                     metrics.clone(),
-                    stop_rx,
+                    invariants.clone(),
+                    reporter,
+                    cancel,
                 )
             })
             .collect_vec();
 
-        let reclaimers = reclaimer_stop_rxs
+        let reclaimers = reclaimer_cancels
             .into_iter()
-            .map(|stop_rx| {
+            .zip_eq(reclaimer_health.iter().cloned())
+            .enumerate()
+            .map(|(i, (cancel, reporter))| {
                 Reclaimer::new(
-                    config.clean_region_threshold,
+                    i % region_manager.domains(),
+                    tunables.clone(),
                     store.clone(),
                     region_manager.clone(),
                     metrics.clone(),
-                    stop_rx,
+                    invariants.clone(),
+                    reporter,
+                    cancel,
                 )
             })
             .collect_vec();
 
-        let sequence = store.recover(config.recover_concurrency).await?;
-        store.inner.sequence.store(sequence + 1, Ordering::Relaxed);
+        let mut health = flusher_health.clone();
+        health.extend(reclaimer_health.iter().cloned());
+
+        if config.interleaved_recovery {
+            let handle = tokio::spawn({
+                let store = store.clone();
+                let concurrency = config.recover_concurrency;
+                async move {
+                    let res = store
+                        .start(concurrency, flushers, reclaimers, flusher_health, reclaimer_health)
+                        .await;
+                    if let Err(e) = &res {
+                        tracing::error!("[store] interleaved recovery error: {}", e);
+                    }
+                    store.inner.recovery.finish(res.map_err(|e| e.to_string()));
+                }
+            });
+            *store.inner.recovery_handle.lock() = Some(handle);
+        } else {
+            store
+                .start(
+                    config.recover_concurrency,
+                    flushers,
+                    reclaimers,
+                    flusher_health,
+                    reclaimer_health,
+                )
+                .await?;
+            store.inner.recovery.finish(Ok(()));
+        }
+
+        if let Some(config) = config.admission_rate_controller {
+            let controller = AdmissionRateController::new(config, metrics.clone());
+            let schedule = controller.schedule();
+            health.push(
+                store
+                    .inner
+                    .scheduler
+                    .spawn(TaskKind::AdmissionRateController, schedule, controller),
+            );
+        }
+
+        if let Some(grace) = config.soft_remove_grace {
+            let purger = SoftRemovePurger::new(catalog.clone(), clock.clone(), grace);
+            // Resurrection checks the grace period by itself, the purger only releases the expired entries.
+            let schedule = JobSchedule::new(grace).with_jitter(0.1);
+            health.push(
+                store
+                    .inner
+                    .scheduler
+                    .spawn(TaskKind::SoftRemovePurger, schedule, purger),
+            );
+        }
+
+        if let Some(config) = config.catalog_compaction {
+            let compactor = CatalogCompactor::new(catalog.clone(), config.budget, metrics.clone());
+            let schedule = JobSchedule::new(config.interval).with_jitter(0.1);
+            health.push(
+                store
+                    .inner
+                    .scheduler
+                    .spawn(TaskKind::CatalogCompactor, schedule, compactor),
+            );
+        }
+
+        *store.inner.health.lock() = health;
+
+        Ok(store)
+    }
+
+    /// Recover the store, then start the flushers and the reclaimers.
+    async fn start(
+        &self,
+        recover_concurrency: usize,
+        flushers: Vec<Flusher<K, V, D, EP, EL>>,
+        reclaimers: Vec<Reclaimer<K, V, D, EP, EL>>,
+        flusher_health: Vec<Arc<HealthReporter>>,
+        reclaimer_health: Vec<Arc<HealthReporter>>,
+    ) -> Result<()> {
+        let sequence = self.recover(recover_concurrency).await?;
+        // Entries of the current generation are written with sequences from the cutoff on, even if none is recovered.
+        let sequence = std::cmp::max(sequence + 1, self.inner.generation.cutoff());
+        self.inner.sequence.store(sequence, Ordering::Relaxed);
+        self.update_soft_quota();
 
         let flusher_handles = flushers
             .into_iter()
-            .map(|flusher| tokio::spawn(async move { flusher.run().await.unwrap() }))
+            .zip_eq(flusher_health.iter().cloned())
+            .map(|(flusher, reporter)| {
+                let invariants = self.inner.invariants.clone();
+                tokio::spawn(async move {
+                    // The error is reported to the health of the flusher.
+                    if let Err(e) = run_reported(reporter, flusher.run()).await {
+                        invariants.violated("flusher", None, e);
+                    }
+                })
+            })
             .collect_vec();
         let reclaimer_handles = reclaimers
             .into_iter()
-            .map(|reclaimer| tokio::spawn(async move { reclaimer.run().await.unwrap() }))
+            .zip_eq(reclaimer_health.iter().cloned())
+            .map(|(reclaimer, reporter)| {
+                let invariants = self.inner.invariants.clone();
+                tokio::spawn(async move {
+                    // The error is reported to the health of the reclaimer.
+                    if let Err(e) = run_reported(reporter, reclaimer.run()).await {
+                        invariants.violated("reclaimer", None, e);
+                    }
+                })
+            })
             .collect_vec();
 
-        *store.inner.flusher_handles.lock() = flusher_handles;
-        *store.inner.reclaimer_handles.lock() = reclaimer_handles;
+        *self.inner.flusher_handles.lock() = flusher_handles;
+        *self.inner.reclaimer_handles.lock() = reclaimer_handles;
 
-        Ok(store)
+        Ok(())
     }
 
     async fn close(&self) -> Result<()> {
+        // wait for the interleaved recovery to start the flushers and the reclaimers
+        let handle = self.inner.recovery_handle.lock().take();
+        if let Some(handle) = handle {
+            handle.await.map_err(anyhow::Error::from)?;
+        }
+
         // stop and wait for flushers
         let handles = self.inner.flusher_handles.lock().drain(..).collect_vec();
-        if !handles.is_empty() {
-            self.inner.flushers_stop_tx.send(()).unwrap();
-        }
+        self.inner.flushers_cancel.cancel();
         for handle in handles {
             handle.await.unwrap();
         }
 
         // stop and wait for reclaimers
         let handles = self.inner.reclaimer_handles.lock().drain(..).collect_vec();
-        if !handles.is_empty() {
-            self.inner.reclaimers_stop_tx.send(()).unwrap();
-        }
+        self.inner.reclaimers_cancel.cancel();
         for handle in handles {
             handle.await.unwrap();
         }
 
+        self.inner.cancel.cancel();
+
+        self.inner.scheduler.shutdown().await;
+
         Ok(())
     }
 
     /// `weight` MUST be equal to `key.serialized_len() + value.serialized_len()`
+    ///
+    /// A writer of an invalid key is never admitted, and returns the error when finished, see
+    /// [`GenericStore::try_writer`].
     #[tracing::instrument(skip(self))]
     fn writer(&self, key: K, weight: usize) -> GenericStoreWriter<K, V, D, EP, EL> {
-        GenericStoreWriter::new(self.clone(), key, weight)
+        let error = self.validate_key(&key).err();
+        if let Some(e) = &error {
+            tracing::warn!("reject invalid entry, key: {:?}, weight: {}, error: {}", key, weight, e);
+        }
+        let mut writer = GenericStoreWriter::new(self.clone(), key, weight);
+        writer.error = error;
+        writer
     }
 
-    #[tracing::instrument(skip(self))]
-    fn exists(&self, key: &K) -> Result<bool> {
-        Ok(self.inner.catalog.lookup(key).is_some())
+    /// Create a writer of `key`, or return the error if the key is invalid.
+    ///
+    /// The value is validated when the writer is finished.
+    pub fn try_writer(&self, key: K, weight: usize) -> Result<GenericStoreWriter<K, V, D, EP, EL>> {
+        let mut writer = self.writer(key, weight);
+        match writer.error.take() {
+            Some(e) => Err(e),
+            None => Ok(writer),
+        }
     }
 
-    #[tracing::instrument(skip(self))]
-    async fn lookup(&self, key: &K) -> Result<Option<V>> {
-        let now = Instant::now();
+    /// Insert `value` of `key`, an invalid entry is rejected before a writer is created.
+    pub async fn insert(&self, key: K, value: V) -> Result<bool> {
+        self.validate_value(&value)?;
+        let weight = key.serialized_len() + value.serialized_len();
+        self.try_writer(key, weight)?.finish(value).await
+    }
 
-        let (_sequence, index) = match self.inner.catalog.lookup(key) {
-            Some(item) => item.consume(),
-            None => {
-                self.inner
-                    .metrics
-                    .op_duration_lookup_miss
-                    .observe(now.elapsed().as_secs_f64());
-                return Ok(None);
-            }
+    fn validate_key(&self, key: &K) -> Result<()> {
+        let key_len = key.serialized_len();
+        if let Some(max) = self.inner.max_key_len
+            && key_len > max
+        {
+            return Err(ErrorKind::Validation(format!("key length {} exceeds the limit {}", key_len, max)).into());
+        }
+        for validator in self.inner.key_validators.iter() {
+            validator
+                .validate(key)
+                .map_err(|e| ErrorKind::Validation(format!("key rejected by {:?}: {}", validator, e)))?;
+        }
+        Ok(())
+    }
+
+    fn validate_value(&self, value: &V) -> Result<()> {
+        self.validate_value_len(value.serialized_len())
+    }
+
+    fn validate_value_len(&self, value_len: usize) -> Result<()> {
+        if let Some(max) = self.inner.max_value_len
+            && value_len > max
+        {
+            return Err(ErrorKind::Validation(format!("value length {} exceeds the limit {}", value_len, max)).into());
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    fn exists(&self, key: &K) -> Result<bool> {
+        Ok(self.inner.catalog.lookup(key).is_some())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn lookup(&self, key: &K) -> Result<Option<V>> {
+        let token = profile::lookup_start();
+        let res = self.lookup_inner(key).await;
+        if let Ok(value) = &res {
+            let hit = value.is_some();
+            self.inner.ops.record(|ops| {
+                ops.lookups += 1;
+                ops.hits += hit as u64;
+            });
+            self.inner.history.record(self.inner.clock.now_millis(), |rollup| {
+                rollup.hits += hit as u64;
+                rollup.misses += !hit as u64;
+            });
+            profile::lookup_end(token, hit);
+        }
+        res
+    }
+
+    async fn lookup_inner(&self, key: &K) -> Result<Option<V>> {
+        let now = Instant::now();
+
+        if let Some(miss_cache) = &self.inner.miss_cache
+            && miss_cache.contains(key)
+        {
+            self.inner
+                .metrics
+                .op_duration_lookup_miss_cached
+                .observe(now.elapsed().as_secs_f64());
+            return Ok(None);
+        }
+
+        let item = match self.inner.catalog.access(key) {
+            Some(item) => item,
+            None => {
+                // The entry may not be recovered yet.
+                if let Some(miss_cache) = &self.inner.miss_cache
+                    && self.inner.recovery.is_finished()
+                {
+                    miss_cache.record(key);
+                }
+                self.inner
+                    .metrics
+                    .op_duration_lookup_miss
+                    .observe(now.elapsed().as_secs_f64());
+                return Ok(None);
+            }
         };
 
+        let age = item.age(self.inner.clock.now_millis());
+        let inserted_at = item.inserted_at();
+        let (sequence, index) = item.consume();
+
         match index {
             crate::catalog::Index::Inflight { key: _, value } => {
                 let value = value.clone();
 
+                self.inner.metrics.entry_age_hit.observe(age.as_secs_f64());
+
+                self.inner
+                    .metrics
+                    .op_duration_lookup_hit
+                    .observe(now.elapsed().as_secs_f64());
+
+                Ok(Some(value))
+            }
+            crate::catalog::Index::InflightRaw { raw } => {
+                let value = decode_value(&raw.value, raw.compression, raw.uncompressed_len)?;
+
+                self.inner.metrics.entry_age_hit.observe(age.as_secs_f64());
+
                 self.inner
                     .metrics
                     .op_duration_lookup_hit
@@ -389,15 +1355,94 @@ where
             crate::catalog::Index::Region { view } => {
                 let region = view.id();
 
+                // A newer entry of the key may be in a region not recovered yet, including the region of the entry.
+                if self.inner.recovery.may_be_outdated(sequence) {
+                    self.inner
+                        .metrics
+                        .op_duration_lookup_miss
+                        .observe(now.elapsed().as_secs_f64());
+                    return Ok(None);
+                }
+
                 self.inner.region_manager.record_access(region);
                 let region = self.inner.region_manager.region(region);
 
+                if self.is_expired(region) || self.is_entry_expired(inserted_at) {
+                    self.inner.catalog.remove(key);
+                    self.inner
+                        .metrics
+                        .op_duration_lookup_miss
+                        .observe(now.elapsed().as_secs_f64());
+                    return Ok(None);
+                }
+
+                // Concurrent lookups of the same entry share one device read and decoding. Lookups of a newer entry of
+                // the key never wait for the read of an older one.
+                let rx = match self.inner.lookup_waiters.lock().entry((key.clone(), sequence)) {
+                    hash_map::Entry::Vacant(v) => {
+                        v.insert(vec![]);
+                        None
+                    }
+                    hash_map::Entry::Occupied(mut o) => {
+                        let (tx, rx) = oneshot::channel();
+                        o.get_mut().push(tx);
+                        Some(rx)
+                    }
+                };
+                let guard = match rx {
+                    Some(rx) => match rx.await {
+                        Ok(value) => {
+                            self.inner.metrics.op_count_lookup_shared.inc();
+                            if value.is_some() {
+                                self.inner.metrics.entry_age_hit.observe(age.as_secs_f64());
+                                self.inner
+                                    .metrics
+                                    .op_duration_lookup_hit
+                                    .observe(now.elapsed().as_secs_f64());
+                            } else {
+                                self.inner
+                                    .metrics
+                                    .op_duration_lookup_miss
+                                    .observe(now.elapsed().as_secs_f64());
+                            }
+                            return Ok(value);
+                        }
+                        // The reading lookup failed or is cancelled, read by itself.
+                        Err(_) => None,
+                    },
+                    None => Some(LookupWaitersGuard {
+                        waiters: &self.inner.lookup_waiters,
+                        key: (key.clone(), sequence),
+                    }),
+                };
+
                 // TODO(MrCroxx): read value only
-                let buf = match region.load(view).await? {
+                let load = match self.inner.read_timeout {
+                    Some(timeout) => match tokio::time::timeout(timeout, region.load(view)).await {
+                        Ok(res) => res?,
+                        Err(_) => {
+                            tracing::warn!("[lookup] read region {} timeout after {:?}", region.id(), timeout);
+                            self.inner.metrics.op_count_lookup_timeout.inc();
+                            if let Some(guard) = guard {
+                                guard.notify(None);
+                            }
+                            self.inner
+                                .metrics
+                                .op_duration_lookup_miss
+                                .observe(now.elapsed().as_secs_f64());
+                            return Ok(None);
+                        }
+                    },
+                    None => region.load(view).await?,
+                };
+                let buf = match load {
                     Some(buf) => buf,
                     None => {
                         // Remove index if the storage layer fails to lookup it (because of region version mismatch).
                         self.inner.catalog.remove(key);
+                        if let Some(guard) = guard {
+                            guard.notify(None);
+                        }
                         self.inner
                             .metrics
                             .op_duration_lookup_miss
@@ -406,14 +1451,19 @@ where
                     }
                 };
 
-                let res = match read_entry::<K, V>(buf.as_ref()) {
+                let res = match read_entry::<K, V>(buf.as_ref(), &self.inner.codecs) {
                     Ok((_key, value)) => {
                         self.inner.metrics.op_bytes_lookup.inc_by(value.serialized_len() as u64);
+                        self.inner.metrics.entry_age_hit.observe(age.as_secs_f64());
+                        if let Some(guard) = guard {
+                            guard.notify(Some(value.clone()));
+                        }
                         Ok(Some(value))
                     }
                     Err(e) => {
                         // Remove index if the storage layer fails to lookup it (because of entry magic mismatch).
                         self.inner.catalog.remove(key);
+                        self.quarantine(region.id());
                         Err(e)
                     }
                 };
@@ -428,8 +1478,66 @@ where
         }
     }
 
+    /// Lookup the entry of `key` as it is stored on the device, without decompressing and decoding the value.
+    ///
+    /// An entry that is not flushed yet is returned serialized but uncompressed.
+    #[tracing::instrument(skip(self))]
+    pub async fn lookup_raw(&self, key: &K) -> Result<Option<RawEntry>> {
+        let Some(item) = self.inner.catalog.access(key) else {
+            return Ok(None);
+        };
+        let inserted_at = item.inserted_at();
+
+        let view = match item.consume() {
+            (_, Index::Inflight { key, value }) => {
+                let mut buf = Vec::with_capacity(value.serialized_len() + key.serialized_len());
+                std::io::copy(&mut value.into_cursor(), &mut buf).map_err(CodingError::from)?;
+                let value_len = buf.len();
+                std::io::copy(&mut key.into_cursor(), &mut buf).map_err(CodingError::from)?;
+                let checksum = checksum(&buf);
+                buf.truncate(value_len);
+                return Ok(Some(RawEntry {
+                    value: buf,
+                    compression: Compression::None,
+                    uncompressed_len: value_len,
+                    checksum,
+                }));
+            }
+            (_, Index::InflightRaw { raw }) => return Ok(Some(raw)),
+            // A newer entry of the key may be in a region not recovered yet.
+            (sequence, Index::Region { .. }) if self.inner.recovery.may_be_outdated(sequence) => return Ok(None),
+            (_, Index::Region { view }) => view,
+        };
+
+        self.inner.region_manager.record_access(view.id());
+        let region = self.inner.region_manager.region(view.id());
+        if self.is_expired(region) || self.is_entry_expired(inserted_at) {
+            self.inner.catalog.remove(key);
+            return Ok(None);
+        }
+
+        let Some(buf) = region.load(view).await? else {
+            self.inner.catalog.remove(key);
+            return Ok(None);
+        };
+
+        match read_raw_entry(buf.as_ref(), &self.inner.codecs) {
+            Ok(raw) => Ok(Some(raw)),
+            Err(e) => {
+                self.inner.catalog.remove(key);
+                self.quarantine(region.id());
+                Err(e)
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     fn remove(&self, key: &K) -> Result<bool> {
+        // The entry would come back when a region not recovered yet is recovered.
+        if !self.inner.recovery.is_finished() {
+            return Err(ErrorKind::Recovering.into());
+        }
+
         let _timer = self.inner.metrics.op_duration_remove.start_timer();
 
         let res = self.inner.catalog.remove(key).is_some();
@@ -437,15 +1545,242 @@ where
         Ok(res)
     }
 
+    /// Remove `keys` with each catalog shard locked once, cheaper than removing the keys one by one.
+    ///
+    /// Returns the number of removed keys.
+    #[tracing::instrument(skip(self, keys), fields(keys = keys.len()))]
+    fn remove_batch(&self, keys: &[K]) -> Result<usize> {
+        if !self.inner.recovery.is_finished() {
+            return Err(ErrorKind::Recovering.into());
+        }
+
+        let _timer = self.inner.metrics.op_duration_remove.start_timer();
+
+        let res = self.inner.catalog.remove_batch(keys);
+
+        Ok(res)
+    }
+
+    /// Hide the entry of `key` from lookups, it can be restored by [`Self::resurrect`] within the soft remove grace
+    /// period. Inserting the key again drops the removed entry.
+    ///
+    /// Removes the entry for good if soft removal is disabled. Returns `false` if there is no entry of `key`.
+    #[tracing::instrument(skip(self))]
+    pub fn remove_soft(&self, key: &K) -> Result<bool> {
+        if self.inner.soft_remove_grace.is_none() {
+            return self.remove(key);
+        }
+        if !self.inner.recovery.is_finished() {
+            return Err(ErrorKind::Recovering.into());
+        }
+
+        let _timer = self.inner.metrics.op_duration_remove.start_timer();
+
+        let res = self.inner.catalog.remove_soft(key, self.inner.clock.now_millis());
+
+        Ok(res)
+    }
+
+    /// Restore the entry of `key` removed by [`Self::remove_soft`] within the grace period.
+    ///
+    /// Returns `false` if there is no such entry, e.g. the grace period has ended, the key is inserted again or the
+    /// region of the entry is reclaimed.
+    #[tracing::instrument(skip(self))]
+    pub fn resurrect(&self, key: &K) -> Result<bool> {
+        let Some(grace) = self.inner.soft_remove_grace else {
+            return Ok(false);
+        };
+        let removed_after = self.inner.clock.now_millis().saturating_sub(grace.as_millis() as u64);
+        let res = self.inner.catalog.resurrect(key, removed_after);
+        if res && let Some(miss_cache) = &self.inner.miss_cache {
+            miss_cache.invalidate(key);
+        }
+
+        Ok(res)
+    }
+
     #[tracing::instrument(skip(self))]
     fn clear(&self) -> Result<()> {
-        self.inner.catalog.clear();
+        if !self.inner.recovery.is_finished() {
+            return Err(ErrorKind::Recovering.into());
+        }
+
+        // Entries inserted so far are discarded when flushed later.
+        let cutoff = self.inner.sequence.load(Ordering::Relaxed);
+        self.inner.catalog.clear(cutoff);
 
         // TODO(MrCroxx): set all regions as clean?
 
         Ok(())
     }
 
+    /// Start a new generation, returns the new generation.
+    ///
+    /// All entries written before are invisible at once, and stay invisible after restart if
+    /// [`GenericStoreConfig::generation_path`] is given. Unlike removing the entries one by one, no region is touched,
+    /// the space of the previous generations is reused when their regions are reclaimed.
+    pub fn bump_generation(&self) -> Result<u64> {
+        if !self.inner.recovery.is_finished() {
+            return Err(ErrorKind::Recovering.into());
+        }
+
+        let cutoff = self.inner.sequence.load(Ordering::Relaxed);
+        let generation = self.inner.generation.bump(cutoff)?;
+        self.inner.catalog.clear(self.inner.generation.cutoff());
+        tracing::info!("[store] bump generation to {}, cutoff: {}", generation, cutoff);
+        Ok(generation)
+    }
+
+    /// Generation of the entries, see [`GenericStore::bump_generation`].
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.generation()
+    }
+
+    /// Whether each region is recovered, indexed by region id. All `true` once the recovery finishes.
+    ///
+    /// See [`GenericStoreConfig::interleaved_recovery`].
+    pub fn recovered_regions(&self) -> Vec<bool> {
+        (0..self.inner.device.regions() as RegionId)
+            .map(|region| self.inner.recovery.is_recovered(region))
+            .collect()
+    }
+
+    /// Whether the interleaved recovery is still in progress.
+    pub fn is_recovering(&self) -> bool {
+        !self.inner.recovery.is_finished()
+    }
+
+    /// Whether the store stopped admitting writes after an invariant violation, see [`InvariantPolicy::Degrade`].
+    pub fn is_degraded(&self) -> bool {
+        self.inner.invariants.is_degraded()
+    }
+
+    /// Wait for the recovery to finish, returns the error of a failed recovery.
+    pub async fn wait_recovered(&self) -> Result<()> {
+        self.inner.recovery.wait().await
+    }
+
+    /// Health of the background tasks of the store.
+    ///
+    /// A task that stopped before the store is closed has failed, e.g. on a device error.
+    pub fn health(&self) -> StoreHealth {
+        StoreHealth {
+            tasks: self
+                .inner
+                .health
+                .lock()
+                .iter()
+                .map(|reporter| reporter.health())
+                .collect(),
+        }
+    }
+
+    /// Handle of the periodic background job of `kind`, to pause or resume it. `None` if the job is not enabled.
+    pub fn job(&self, kind: TaskKind) -> Option<Arc<JobHandle>> {
+        self.inner.scheduler.job(kind)
+    }
+
+    /// Handle to adjust parameters of the running store.
+    pub fn tunables(&self) -> &Arc<Tunables> {
+        &self.inner.tunables
+    }
+
+    /// Statistics of entries on device, the estimated memory to index them, and the counters of operations.
+    ///
+    /// Entries are accounted when flushed and released when their region is reclaimed. The operation counters are a
+    /// consistent snapshot, see [`OpStats`](crate::stats::OpStats).
+    pub fn stats(&self) -> StoreStats {
+        let mut stats = StoreStats::default();
+        for region in 0..self.inner.device.regions() as RegionId {
+            let region = self.inner.region_manager.region(&region);
+            stats.logical_bytes += region.stats().logical_bytes();
+            stats.physical_bytes += region.stats().physical_bytes();
+        }
+        stats.catalog_bytes = self.inner.catalog.memory_usage(self.inner.catalog_entry_overhead);
+        for domain in 0..self.inner.region_manager.domains() {
+            let clean_regions = self.inner.region_manager.clean_regions(domain).len();
+            stats.clean_regions += clean_regions;
+            stats.reclaiming |= match self.inner.clean_region_watermarks {
+                Some(_) => self.inner.reclaiming[domain].load(Ordering::Relaxed),
+                None => clean_regions < self.inner.tunables.clean_region_threshold(),
+            };
+        }
+        stats.ops = self.inner.ops.snapshot();
+        stats
+    }
+
+    /// Per-minute rollups of the operations in the last [`STATS_HISTORY_MINUTES`] minutes, oldest first.
+    ///
+    /// The rollups are kept in memory since the store is opened, minutes without operations are omitted.
+    ///
+    /// [`STATS_HISTORY_MINUTES`]: crate::stats::STATS_HISTORY_MINUTES
+    pub fn stats_history(&self) -> Vec<StatsRollup> {
+        self.inner.history.snapshot(self.inner.clock.now_millis())
+    }
+
+    /// Account the bytes of a reclaimed region in the stats history.
+    pub(crate) fn record_reclaimed(&self, bytes: usize) {
+        self.inner.history.record(self.inner.clock.now_millis(), |rollup| {
+            rollup.reclaimed_bytes += bytes as u64
+        });
+    }
+
+    /// Release in-memory caches of the store under memory pressure, until at least `target` bytes are released or
+    /// nothing is left to release. Returns the estimated released bytes.
+    ///
+    /// Caches are released in order: the miss cache, the lookup waiter table, the cached region headers, then the
+    /// resident pages of the device mappings in [`FsReadMode::Mmap`]. If the target is still not reached, the inflight
+    /// budget of the [`Tunables`] is lowered below the bytes waiting for flushers by the remaining target, so inserts
+    /// are rejected until flushers drain that much. Reset the budget with [`Tunables::set_inflight_budget`] once the
+    /// pressure is gone.
+    ///
+    /// Indexed entries are never released, since they are required for correctness. Flush buffers and io buffers are
+    /// not released either: io buffers are allocated per io and freed after it, and a flush buffer falls back to its
+    /// default capacity after every flush, so neither holds idle memory.
+    ///
+    /// [`FsReadMode::Mmap`]: crate::device::fs::FsReadMode::Mmap
+    pub fn reduce_memory(&self, target: usize) -> usize {
+        let mut released = 0;
+
+        if let Some(miss_cache) = &self.inner.miss_cache {
+            released += miss_cache.clear();
+        }
+
+        if released < target {
+            let mut waiters = self.inner.lookup_waiters.lock();
+            let capacity = waiters.capacity();
+            waiters.shrink_to_fit();
+            released += (capacity - waiters.capacity())
+                * std::mem::size_of::<((K, Sequence), Vec<oneshot::Sender<Option<V>>>)>();
+        }
+
+        for region in 0..self.inner.device.regions() as RegionId {
+            if released >= target {
+                break;
+            }
+            released += self.inner.region_manager.region(&region).drop_header();
+        }
+
+        if released < target {
+            released += self.inner.device.release_cache();
+        }
+
+        if released < target {
+            let inflight = self.inner.metrics.inflight_bytes.get() as usize;
+            let budget = inflight.saturating_sub(target - released);
+            let budget = self
+                .inner
+                .tunables
+                .inflight_budget()
+                .map_or(budget, |current| current.min(budget));
+            self.inner.tunables.set_inflight_budget(Some(budget));
+            released += inflight.saturating_sub(budget);
+        }
+
+        tracing::info!("[store] reduce memory, target: {}, released: {}", target, released);
+        released
+    }
+
     pub(crate) fn catalog(&self) -> &Arc<Catalog<K, V>> {
         &self.inner.catalog
     }
@@ -454,6 +1789,208 @@ where
         &self.inner.reinsertions
     }
 
+    pub(crate) fn max_data_age(&self) -> Option<Duration> {
+        self.inner.max_data_age
+    }
+
+    pub(crate) fn clock(&self) -> &Arc<dyn Clock> {
+        &self.inner.clock
+    }
+
+    pub(crate) fn codecs(&self) -> &CodecPipeline {
+        &self.inner.codecs
+    }
+
+    /// Returns up to `limit` keys of the entries on the device, from the hottest.
+    ///
+    /// The hotness of an entry is the recency of its region in the eviction policy, scaled to the region count of its
+    /// eviction domain, multiplied by the count of lookups of the entry since it is indexed.
+    pub fn hot_keys(&self, limit: usize) -> Vec<K> {
+        let mut keys = vec![];
+        for domain in 0..self.inner.region_manager.domains() {
+            let regions = self.inner.region_manager.eviction_order(domain);
+            for (rank, region) in regions.iter().enumerate() {
+                let recency = (rank + 1) as f64 / regions.len() as f64;
+                for (key, item) in self.inner.catalog.region_items(region) {
+                    keys.push((recency * (item.accesses() + 1) as f64, key));
+                }
+            }
+        }
+        keys.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+        keys.into_iter().take(limit).map(|(_, key)| key).collect()
+    }
+
+    /// Returns the keys in `range` of the entries in the store, ordered by key.
+    ///
+    /// Only the catalog is searched, no entry is read.
+    pub fn range_keys<R>(&self, range: R) -> Vec<K>
+    where
+        R: RangeBounds<K> + Clone,
+    {
+        self.inner
+            .catalog
+            .range(range)
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect()
+    }
+
+    /// Look up the entries of the keys in `range`, ordered by key.
+    ///
+    /// Entries removed or expired after they are found in the catalog are skipped.
+    pub async fn lookup_range<R>(&self, range: R) -> Result<Vec<(K, V)>>
+    where
+        R: RangeBounds<K> + Clone,
+    {
+        let mut entries = vec![];
+        for key in self.range_keys(range) {
+            if let Some(value) = self.lookup(&key).await? {
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Locate the entry of `key` without reading it, the first phase of a lookup.
+    ///
+    /// Callers can sort the locations by region and offset to batch reads, and read them with [`Self::read`]. A
+    /// location holds its region from being reclaimed, it should be read or dropped soon.
+    pub fn locate(&self, key: &K) -> Option<EntryLocation<K, V>> {
+        let item = self.inner.catalog.access(key)?;
+        Some(EntryLocation { key: key.clone(), item })
+    }
+
+    /// Read the entry at `location`, the second phase of a lookup.
+    ///
+    /// Returns `None` if the entry has expired or its region is no longer valid.
+    pub async fn read(&self, location: EntryLocation<K, V>) -> Result<Option<V>> {
+        let EntryLocation { key, item } = location;
+        if let Index::Region { view } = item.index() {
+            self.inner.region_manager.record_access(view.id());
+        }
+        let value = self.read_item(&key, item).await?;
+        if let Some(value) = &value {
+            self.inner.metrics.op_bytes_lookup.inc_by(value.serialized_len() as u64);
+        }
+        Ok(value)
+    }
+
+    /// Iterate the entries inserted with a sequence greater than `sequence`, in insertion order.
+    ///
+    /// The entries to yield are fixed when the iterator is created. Consumers can record the sequence of the last
+    /// yielded entry as a checkpoint and pass it to the next call to pull only the entries inserted since. Sequences
+    /// start from 1, so `iter_since(0)` iterates all entries.
+    pub fn iter_since(&self, sequence: Sequence) -> GenericStoreIter<K, V, D, EP, EL> {
+        GenericStoreIter {
+            store: self.clone(),
+            keys: self.inner.catalog.keys_since(sequence).into(),
+        }
+    }
+
+    /// Read the value of an indexed item without recording the access, returns `None` if it is no longer valid.
+    async fn read_item(&self, key: &K, item: Item<K, V>) -> Result<Option<V>> {
+        let inserted_at = item.inserted_at();
+        let view = match item.consume() {
+            (_, Index::Inflight { value, .. }) => return Ok(Some(value)),
+            (_, Index::InflightRaw { raw }) => {
+                return decode_value(&raw.value, raw.compression, raw.uncompressed_len).map(Some)
+            }
+            // A newer entry of the key may be in a region not recovered yet.
+            (sequence, Index::Region { .. }) if self.inner.recovery.may_be_outdated(sequence) => return Ok(None),
+            (_, Index::Region { view }) => view,
+        };
+
+        let region = self.inner.region_manager.region(view.id());
+        if self.is_expired(region) || self.is_entry_expired(inserted_at) {
+            return Ok(None);
+        }
+        let Some(buf) = region.load(view).await? else {
+            return Ok(None);
+        };
+        match read_entry::<K, V>(buf.as_ref(), &self.inner.codecs) {
+            Ok((_key, value)) => Ok(Some(value)),
+            Err(e) => {
+                self.inner.catalog.remove(key);
+                self.quarantine(region.id());
+                Err(e)
+            }
+        }
+    }
+
+    /// Returns `true` if the reclaimers of the domain should reclaim a region.
+    pub(crate) fn should_reclaim(&self, domain: DomainId) -> bool {
+        let clean = self.inner.region_manager.clean_regions(domain).len();
+        let Some(watermarks) = &self.inner.clean_region_watermarks else {
+            return clean < self.inner.tunables.clean_region_threshold();
+        };
+        let (low, high) = watermarks.counts(self.inner.region_manager.domain_regions(domain));
+        let reclaiming = &self.inner.reclaiming[domain];
+        if clean < low {
+            reclaiming.store(true, Ordering::Relaxed);
+        } else if clean >= high {
+            reclaiming.store(false, Ordering::Relaxed);
+        }
+        reclaiming.load(Ordering::Relaxed)
+    }
+
+    /// Notify the soft quota listener if the occupancy crosses any level since the last check.
+    pub(crate) fn update_soft_quota(&self) {
+        let Some(soft_quota) = &self.inner.soft_quota else {
+            return;
+        };
+        let regions = self.inner.device.regions();
+        let clean = (0..self.inner.region_manager.domains())
+            .map(|domain| self.inner.region_manager.clean_regions(domain).len())
+            .sum::<usize>();
+        let occupancy = regions.saturating_sub(clean) as f64 / regions as f64;
+        soft_quota.update(occupancy, || self.stats());
+    }
+
+    /// Returns `true` if the data in the region is older than the max data age.
+    pub(crate) fn is_expired(&self, region: &Region<D>) -> bool {
+        matches!(self.inner.max_data_age, Some(max) if region.age(self.inner.clock.now_millis()) > max)
+    }
+
+    /// Returns `true` if the entry inserted at `inserted_at` is older than the max data age.
+    ///
+    /// A reinserted entry keeps its original insert time, so it can expire before the region it is reinserted into.
+    pub(crate) fn is_entry_expired(&self, inserted_at: u64) -> bool {
+        matches!(self.inner.max_data_age, Some(max) if self.inner.clock.now_millis().saturating_sub(inserted_at) > max.as_millis() as u64)
+    }
+
+    pub(crate) fn is_quarantined(&self, region: RegionId) -> bool {
+        self.inner.quarantine.contains(region)
+    }
+
+    /// Quarantine a region with corrupted data and drop all its indices.
+    fn quarantine(&self, region: RegionId) {
+        match self.inner.quarantine.insert(region) {
+            Ok(true) => {
+                tracing::warn!("[store] quarantine region {} with corrupted data", region);
+                self.inner.catalog.take_region(&region);
+                self.update_quarantined_bytes();
+            }
+            Ok(false) => {}
+            Err(e) => tracing::error!("[store] quarantine region {} error: {}", region, e),
+        }
+    }
+
+    /// Release a zeroed region from quarantine.
+    pub(crate) fn release_quarantine(&self, region: RegionId) -> Result<()> {
+        if self.inner.quarantine.remove(region)? {
+            tracing::info!("[store] release region {} from quarantine", region);
+            self.update_quarantined_bytes();
+        }
+        Ok(())
+    }
+
+    fn update_quarantined_bytes(&self) {
+        self.inner
+            .metrics
+            .quarantined_bytes
+            .set((self.inner.quarantine.len() * self.inner.device.region_size()) as u64);
+    }
+
     #[tracing::instrument(skip(self))]
     async fn recover(&self, concurrency: usize) -> Result<Sequence> {
         tracing::info!("start store recovery");
@@ -465,10 +2002,28 @@ where
             let semaphore = semaphore.clone();
             let region_manager = self.inner.region_manager.clone();
             let indices = self.inner.catalog.clone();
+            let quarantined = self.inner.quarantine.contains(region_id);
+            let max_data_age = self.inner.max_data_age;
+            let recovery_mode = self.inner.recovery_mode;
+            let clock = self.inner.clock.clone();
+            let store = self.clone();
             let handle = tokio::spawn(async move {
                 let permit = semaphore.acquire().await;
-                let res = Self::recover_region(region_id, region_manager, indices).await;
+                let res = Self::recover_region(
+                    region_id,
+                    region_manager,
+                    indices,
+                    quarantined,
+                    max_data_age,
+                    recovery_mode,
+                    clock,
+                    &store.inner.recovery,
+                )
+                .await;
                 drop(permit);
+                if res.is_ok() {
+                    store.inner.recovery.recovered(region_id);
+                }
                 res
             });
             handles.push(handle);
@@ -488,6 +2043,7 @@ where
         }
 
         tracing::info!("finish store recovery, {} region recovered", recovered);
+        self.update_quarantined_bytes();
         self.inner
             .metrics
             .total_bytes
@@ -495,56 +2051,597 @@ where
 
         // Force trigger reclamation.
         if recovered == self.inner.device.regions() {
-            self.inner.region_manager.clean_regions().flash();
+            for domain in 0..self.inner.region_manager.domains() {
+                self.inner.region_manager.clean_regions(domain).flash();
+            }
         }
 
         Ok(sequence)
     }
 
     /// Return `Some(max sequence)` if region is valid, otherwise `None`
+    ///
+    /// A quarantined region is not indexed, but still pushed to eviction to let the reclaimer zero it.
+    #[expect(clippy::too_many_arguments)]
     async fn recover_region(
         region_id: RegionId,
         region_manager: Arc<RegionManager<D, EP, EL>>,
         catalog: Arc<Catalog<K, V>>,
+        quarantined: bool,
+        max_data_age: Option<Duration>,
+        recovery_mode: RecoveryMode,
+        clock: Arc<dyn Clock>,
+        recovery: &RecoveryProgress,
     ) -> Result<Option<Sequence>> {
+        if quarantined {
+            tracing::warn!("skip recovering quarantined region {}", region_id);
+            region_manager.eviction_push(region_id);
+            return Ok(Some(0));
+        }
+
         let region = region_manager.region(&region_id).clone();
         let mut sequence = 0;
-        let res = if let Some(mut iter) = RegionEntryIter::<K, V, D>::open(region).await? {
+        let iter = RegionEntryIter::<K, V, D>::open(region.clone()).await?;
+        if let Some(iter) = &iter {
+            region.set_created_at(iter.created_at());
+        }
+        let iter = match (iter, max_data_age) {
+            (Some(_), Some(max)) if region.age(clock.now_millis()) > max => {
+                tracing::info!("skip recovering expired region {}", region_id);
+                None
+            }
+            (iter, _) => iter,
+        };
+        let res = if let Some(mut iter) = iter {
+            if let Some(sequence) = iter.max_sequence() {
+                recovery.bound(region_id, sequence);
+            }
+            // A sealed region is verified as a whole with its data checksum, entries are verified one by one only if
+            // the region is not sealed or is corrupted.
+            let verify = recovery_mode == RecoveryMode::Verify && iter.verify_data().await? != Some(true);
+            let mut corrupted = 0;
+            let mut processed = 0;
             while let Some((key, item)) = iter.next().await? {
+                processed += 1;
+                if processed % YIELD_BUDGET == 0 {
+                    tokio::task::yield_now().await;
+                }
+                if verify && !Self::verify_item(&region, &item).await? {
+                    corrupted += 1;
+                    continue;
+                }
+                // Reinserted entries keep their original insert time and may be expired in a live region.
+                if let Some(max) = max_data_age
+                    && item.age(clock.now_millis()) > max
+                {
+                    continue;
+                }
                 sequence = std::cmp::max(sequence, *item.sequence());
                 catalog.insert(key, item);
             }
+            if corrupted > 0 {
+                tracing::warn!(
+                    "drop {} corrupted entries when recovering region {}",
+                    corrupted,
+                    region_id
+                );
+            }
+            region.stats().add(iter.logical_bytes(), iter.physical_bytes());
             region_manager.eviction_push(region_id);
             Some(sequence)
         } else {
-            region_manager.clean_regions().release(region_id);
+            region_manager.release_clean_region(region_id);
             None
         };
         Ok(res)
     }
 
-    fn judge_inner(&self, writer: &mut GenericStoreWriter<K, V, D, EP, EL>) {
-        for (index, admission) in self.inner.admissions.iter().enumerate() {
-            let judge = admission.judge(writer.key.as_ref().unwrap(), writer.weight);
-            writer.judges.set(index, judge);
-        }
-        writer.is_judged = true;
+    /// Returns `true` if the checksum of the entry indexed by `item` matches.
+    async fn verify_item(region: &Region<D>, item: &Item<K, V>) -> Result<bool> {
+        let Index::Region { view } = item.index() else {
+            return Ok(true);
+        };
+        let Some(buf) = region.load(view.clone()).await? else {
+            return Ok(false);
+        };
+        Ok(verify_entry(buf.as_ref()))
+    }
+
+    /// Run the admission of an entry of `key` and `weight` without creating a writer or changing the state of the
+    /// admission policies, e.g. to decide whether to serialize a value before inserting it.
+    ///
+    /// The verdict is a hint, the state of the policies can change before the entry is inserted.
+    pub fn would_admit(&self, key: &K, weight: usize) -> AdmissionVerdict {
+        // Only the weight is known, the value length is derived from it.
+        let value_len = weight.saturating_sub(key.serialized_len());
+        if let Err(e) = self.validate_key(key).and_then(|_| self.validate_value_len(value_len)) {
+            return AdmissionVerdict::Invalid(e.to_string());
+        }
+
+        let mut rejected = vec![];
+        let mut unknown = vec![];
+        for (index, admission) in self.inner.admissions.iter().enumerate() {
+            match admission.peek(key, weight) {
+                Some(true) => {}
+                Some(false) => rejected.push(index),
+                None => unknown.push(index),
+            }
+        }
+
+        if !rejected.is_empty() {
+            AdmissionVerdict::Reject { policies: rejected }
+        } else if !unknown.is_empty() {
+            AdmissionVerdict::Unknown { policies: unknown }
+        } else {
+            AdmissionVerdict::Admit
+        }
+    }
+
+    fn judge_inner(&self, writer: &mut GenericStoreWriter<K, V, D, EP, EL>) {
+        for (index, admission) in self.inner.admissions.iter().enumerate() {
+            let judge = admission.judge(writer.key.as_ref().unwrap(), writer.weight);
+            writer.judges.set(index, judge);
+        }
+        writer.is_judged = true;
     }
 
     #[tracing::instrument(skip(self, value))]
     async fn apply_writer(&self, mut writer: GenericStoreWriter<K, V, D, EP, EL>, value: V) -> Result<bool> {
         debug_assert!(!writer.is_inserted);
 
-        if !writer.judge() {
+        if let Some(e) = writer.error.take() {
+            return Err(e);
+        }
+        // The weight of the writer is given by the caller, check the value itself.
+        if let Err(e) = self.validate_value(&value) {
+            tracing::warn!(
+                "reject invalid entry, key: {:?}, error: {}",
+                writer.key.as_ref().unwrap(),
+                e
+            );
+            return Err(e);
+        }
+
+        self.check_write_once(&writer, || Ok(value.clone())).await?;
+
+        if !self.judge_counted(&mut writer) {
             return Ok(false);
         }
 
         let now = Instant::now();
 
-        let sequence = if let Some(sequence) = writer.sequence {
-            sequence
+        if self.inner.deduplicate_inflight && self.is_inflight_duplicate(writer.key.as_ref().unwrap(), &value) {
+            writer.is_inserted = true;
+            let key = writer.key.take().unwrap();
+            for (i, admission) in self.inner.admissions.iter().enumerate() {
+                let judge = writer.judges.get(i);
+                admission.on_drop(&key, writer.weight, judge);
+            }
+
+            let duration = now.elapsed() + writer.duration;
+            self.inner
+                .metrics
+                .op_duration_insert_deduplicated
+                .observe(duration.as_secs_f64());
+
+            // The identical value is already cached and will be flushed, so the insertion is regarded as succeeded.
+            return Ok(true);
+        }
+
+        if self.inner.overwrite_in_place
+            && writer.sequence.is_none()
+            && self
+                .overwrite(
+                    writer.key.as_ref().unwrap(),
+                    &value,
+                    writer.compression,
+                    writer.trace_id,
+                )
+                .await?
+        {
+            writer.is_inserted = true;
+            let key = writer.key.take().unwrap();
+            for (i, admission) in self.inner.admissions.iter().enumerate() {
+                let judge = writer.judges.get(i);
+                admission.on_insert(&key, writer.weight, judge);
+            }
+            if let Some(miss_cache) = &self.inner.miss_cache {
+                miss_cache.invalidate(&key);
+            }
+
+            self.inner.metrics.op_count_insert_overwritten.inc();
+            let duration = now.elapsed() + writer.duration;
+            self.inner
+                .metrics
+                .op_duration_insert_inserted
+                .observe(duration.as_secs_f64());
+            return Ok(true);
+        }
+
+        self.enqueue_write_once(writer, Payload::Value(value), now).await
+    }
+
+    /// Overwrite the entry of `key` on its region in place with `value`, if the new entry has the same aligned size.
+    ///
+    /// The entry header gets a new sequence, while the footer directory of a sealed region keeps the former one. Either
+    /// is greater than the sequences of the older entries of the key and less than those of the newer ones, so the
+    /// entry recovered after a restart is the one served before it.
+    ///
+    /// Returns `false` if the entry can not be overwritten, and nothing is written.
+    async fn overwrite(&self, key: &K, value: &V, compression: Compression, trace_id: Option<u64>) -> Result<bool> {
+        // The looked up item holds its region view, so the region is not reclaimed until the overwrite finishes.
+        let Some(item) = self.inner.catalog.lookup(key) else {
+            return Ok(false);
+        };
+        let Index::Region { view } = item.index() else {
+            return Ok(false);
+        };
+        let align = self.inner.device.align();
+        let (offset, len) = (*view.offset() as usize, *view.len() as usize);
+        if !bits::is_aligned(align, offset) || !bits::is_aligned(align, len) {
+            return Ok(false);
+        }
+        let region = self.inner.region_manager.region(view.id());
+
+        // | header | value (compressed, encoded) | key | <padding> |
+        let header_len = EntryHeader::serialized_len();
+        let mut buf = self.inner.device.io_buffer(header_len, len);
+        if self.inner.codecs.is_empty() {
+            encode_value(value.clone(), compression, &mut buf).map_err(CodingError::from)?;
         } else {
+            let mut compressed = Vec::with_capacity(value.serialized_len());
+            encode_value(value.clone(), compression, &mut compressed).map_err(CodingError::from)?;
+            let encoded = self.inner.codecs.encode(&compressed).map_err(CodingError::from)?;
+            buf.extend_from_slice(&encoded);
+        }
+        let value_len = buf.len() - header_len;
+        std::io::copy(&mut key.clone().into_cursor(), &mut buf).map_err(CodingError::from)?;
+        let key_len = buf.len() - header_len - value_len;
+        if bits::align_up(align, buf.len()) != len {
+            return Ok(false);
+        }
+
+        // Any entry of the key indexed from now on has a greater sequence, so it supersedes the overwritten one either
+        // in memory or after a restart.
+        let Some(sequence) = self.inner.catalog.reserve_overwrite(key, *item.sequence(), || {
             self.inner.sequence.fetch_add(1, Ordering::Relaxed)
+        }) else {
+            return Ok(false);
+        };
+        let inserted_at = self.inner.clock.now_millis();
+        let header = EntryHeader {
+            key_len: key_len as u32,
+            value_len: value_len as u32,
+            uncompressed_value_len: value.serialized_len() as u32,
+            sequence,
+            inserted_at,
+            checksum: checksum(&buf[header_len..]),
+            compression,
+            codecs: self.inner.codecs.ids(),
+        };
+        header.write(&mut buf[..header_len]);
+        let padding = len - buf.len();
+        buf.extend_from_slice(&vec![0; padding]);
+
+        let guard = region.exclusive().await;
+        // The entry on device must still be the indexed one.
+        let block = self.inner.device.load(region.id(), offset..offset + align).await?;
+        let old = match EntryHeader::read(&block) {
+            Ok(old) if old.sequence == *item.sequence() => old,
+            _ => return Ok(false),
+        };
+        let (res, _buf) = self.inner.device.write(buf, .., region.id(), offset).await;
+        res?;
+        drop(guard);
+
+        region
+            .stats()
+            .sub(old.key_len as usize + old.uncompressed_value_len as usize, 0);
+        region.stats().add(key_len + value.serialized_len(), 0);
+        // The entry is superseded or taken by the reclaimer otherwise, either happens after the overwrite.
+        self.inner
+            .catalog
+            .overwrite(key, *item.sequence(), sequence, inserted_at, trace_id);
+        drop(item);
+
+        if let Some(trace_id) = trace_id {
+            tracing::info!(
+                "[store] entry overwritten in place, trace id: {}, sequence: {}, region: {}, offset: {}",
+                trace_id,
+                sequence,
+                region.id(),
+                offset
+            );
+        }
+
+        Ok(true)
+    }
+
+    /// Insert an already serialized and compressed value of `key`, which is written to the device as is.
+    ///
+    /// `raw.checksum` and the value length limit are verified before the entry is admitted.
+    pub async fn insert_raw(&self, key: K, raw: RawEntry) -> Result<bool> {
+        let mut buf = Vec::with_capacity(raw.value.len() + key.serialized_len());
+        buf.extend_from_slice(&raw.value);
+        std::io::copy(&mut key.clone().into_cursor(), &mut buf).map_err(CodingError::from)?;
+        let checksum = checksum(&buf);
+        if checksum != raw.checksum {
+            return Err(ErrorKind::Validation(format!(
+                "checksum mismatch, expected: {}, got: {}",
+                raw.checksum, checksum
+            ))
+            .into());
+        }
+
+        self.validate_value_len(raw.uncompressed_len)?;
+
+        let weight = key.serialized_len() + raw.uncompressed_len;
+        let mut writer = self.writer(key, weight);
+
+        if let Some(e) = writer.error.take() {
+            return Err(e);
+        }
+
+        self.check_write_once(&writer, || {
+            decode_value(&raw.value, raw.compression, raw.uncompressed_len)
+        })
+        .await?;
+
+        if !self.judge_counted(&mut writer) {
+            return Ok(false);
+        }
+
+        self.enqueue_write_once(writer, Payload::Raw(raw), Instant::now()).await
+    }
+
+    /// Returns [`ErrorKind::Exists`] if the key of `writer` is already written in write-once mode.
+    ///
+    /// The check is not counted as a lookup. A writer of the key getting in between the check and the enqueue is
+    /// caught by [`GenericStore::enqueue`].
+    ///
+    /// `value` is only made to compare with the existing value.
+    async fn check_write_once(
+        &self,
+        writer: &GenericStoreWriter<K, V, D, EP, EL>,
+        value: impl FnOnce() -> Result<V>,
+    ) -> Result<()> {
+        let key = writer.key.as_ref().unwrap();
+        if !self.inner.write_once || writer.overwrite {
+            return Ok(());
+        }
+        match self.inner.catalog.lookup(key) {
+            Some(item) => self.write_once_exists(key, item, value).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Returns [`ErrorKind::Exists`] for the indexed `item` of the write-once `key`, or `Ok` if the entry is removed
+    /// or expired meanwhile.
+    async fn write_once_exists(&self, key: &K, item: Item<K, V>, value: impl FnOnce() -> Result<V>) -> Result<()> {
+        let Some(existing) = self.read_item(key, item).await? else {
+            return Ok(());
+        };
+        let conflict = value_bytes(&existing)? != value_bytes(&value()?)?;
+        if conflict {
+            tracing::warn!(
+                "[store] write-once key {:?} is written with a different value, hash: {}",
+                key,
+                self.inner.catalog.hash(key)
+            );
+        }
+        self.inner.metrics.op_count_insert_exists.inc();
+        Err(ErrorKind::Exists { conflict }.into())
+    }
+
+    /// Write `entries` directly to clean regions, bypassing the flushers and the admission policies, e.g. to seed the
+    /// cache from an offline job. Returns the count of entries written.
+    ///
+    /// Keys must be in ascending order. Regions are filled up one by one and sealed with their footers, only the last
+    /// one may be partially filled. Entries are indexed as they are written, so a failure keeps the entries written
+    /// before it.
+    ///
+    /// Returns [`ErrorKind::Validation`] for a key out of order or an entry larger than a whole region.
+    pub async fn bulk_load<S>(&self, entries: S) -> Result<usize>
+    where
+        S: Stream<Item = (K, V)> + Send,
+    {
+        self.wait_recovered().await?;
+
+        let entries = std::pin::pin!(entries);
+        let mut buffer = FlushBuffer::new(
+            self.inner.device.clone(),
+            None,
+            self.inner.entry_padding,
+            self.inner.codecs.clone(),
+            self.inner.write_barrier,
+            self.inner.metrics.clone(),
+        );
+
+        let mut count = 0;
+        let result = self.bulk_load_entries(entries, &mut buffer, &mut count).await;
+
+        // Seal the last region even on failure, so the entries written before it are kept.
+        let (flushed, region) = buffer.retire().await?;
+        index_flushed(
+            &self.inner.region_manager,
+            &self.inner.catalog,
+            &self.inner.metrics,
+            flushed,
+        );
+        if let Some(region) = region {
+            self.inner.region_manager.eviction_push(region);
+        }
+        result?;
+
+        tracing::info!("[store] bulk loaded {} entries", count);
+        Ok(count)
+    }
+
+    async fn bulk_load_entries<S>(
+        &self,
+        mut entries: Pin<&mut S>,
+        buffer: &mut FlushBuffer<K, V, D>,
+        count: &mut usize,
+    ) -> Result<()>
+    where
+        S: Stream<Item = (K, V)> + Send,
+    {
+        let compression = self.inner.tunables.compression();
+        let mut last: Option<K> = None;
+        while let Some((key, value)) = entries.next().await {
+            self.validate_key(&key)?;
+            self.validate_value(&value)?;
+            if let Some(last) = &last
+                && key <= *last
+            {
+                return Err(
+                    ErrorKind::Validation(format!("bulk load key {:?} is not after key {:?}", key, last)).into(),
+                );
+            }
+            last = Some(key.clone());
+            if let Some(miss_cache) = &self.inner.miss_cache {
+                miss_cache.invalidate(&key);
+            }
+
+            let entry = Entry {
+                key,
+                value: Payload::Value(value),
+                sequence: self.inner.sequence.fetch_add(1, Ordering::Relaxed),
+                compression,
+                enqueued_at: Instant::now(),
+                inserted_at: self.inner.clock.now_millis(),
+                trace_id: None,
+            };
+            let region = buffer.region();
+            let flushed = match buffer.write(entry).await {
+                Err(BufferError::NeedRotate(entry)) => {
+                    rotate(
+                        buffer,
+                        region,
+                        &self.inner.region_manager,
+                        &self.inner.catalog,
+                        self.inner.clock.as_ref(),
+                        &self.inner.metrics,
+                    )
+                    .await?;
+                    match buffer.write(Box::into_inner(entry)).await {
+                        Err(BufferError::NeedRotate(entry)) => {
+                            return Err(ErrorKind::Validation(format!(
+                                "bulk load entry of key {:?} exceeds a clean region",
+                                entry.key
+                            ))
+                            .into());
+                        }
+                        result => result?,
+                    }
+                }
+                result => result?,
+            };
+            index_flushed(
+                &self.inner.region_manager,
+                &self.inner.catalog,
+                &self.inner.metrics,
+                flushed,
+            );
+            *count += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Judge the entry to write, and count the write.
+    fn judge_counted(&self, writer: &mut GenericStoreWriter<K, V, D, EP, EL>) -> bool {
+        // Sequences of new entries are only known once the recovery finishes.
+        let admitted = self.inner.recovery.is_finished() && !self.inner.invariants.is_degraded() && writer.judge();
+        self.inner.ops.record(|ops| {
+            ops.inserts += 1;
+            ops.admitted += admitted as u64;
+        });
+        if admitted {
+            let weight = writer.weight as u64;
+            self.inner
+                .history
+                .record(self.inner.clock.now_millis(), |rollup| rollup.inserted_bytes += weight);
+        }
+        admitted
+    }
+
+    /// Like [`GenericStore::enqueue`], returns [`ErrorKind::Exists`] if a write-once writer of the key got in between
+    /// since [`GenericStore::check_write_once`], or `false` if that entry is already gone.
+    async fn enqueue_write_once(
+        &self,
+        writer: GenericStoreWriter<K, V, D, EP, EL>,
+        value: Payload<V>,
+        now: Instant,
+    ) -> Result<bool> {
+        let Err(existing) = self.enqueue(writer, value, now) else {
+            return Ok(true);
+        };
+        let WriteOnceExisting { key, item, value } = *existing;
+        self.write_once_exists(&key, item, || match value {
+            Payload::Value(value) => Ok(value),
+            Payload::Raw(raw) => decode_value(&raw.value, raw.compression, raw.uncompressed_len),
+        })
+        .await?;
+        Ok(false)
+    }
+
+    /// Index the admitted entry as inflight and send it to a flusher.
+    ///
+    /// In write-once mode, the key is indexed only if it is absent with the catalog shard locked, so that no other
+    /// writer gets in between since [`GenericStore::check_write_once`]. Otherwise the writer is dropped, and the
+    /// existing item is returned.
+    fn enqueue(
+        &self,
+        mut writer: GenericStoreWriter<K, V, D, EP, EL>,
+        value: Payload<V>,
+        now: Instant,
+    ) -> std::result::Result<(), Box<WriteOnceExisting<K, V>>> {
+        // Taken with the catalog shard locked, see `Catalog::reserve_overwrite`.
+        let preset = writer.sequence;
+        let next = || preset.unwrap_or_else(|| self.inner.sequence.fetch_add(1, Ordering::Relaxed));
+        let exclusive = self.inner.write_once && !writer.overwrite;
+
+        let inserted_at = writer.inserted_at.unwrap_or_else(|| self.inner.clock.now_millis());
+        let coalesced = match &value {
+            Payload::Value(value) if self.inner.coalesce_inflight && !exclusive => self.inner.catalog.coalesce(
+                writer.key.as_ref().unwrap(),
+                next,
+                value.clone(),
+                inserted_at,
+                writer.trace_id,
+            ),
+            _ => None,
+        };
+        let sequence = match coalesced {
+            Some(sequence) => sequence,
+            None => {
+                let key = writer.key.as_ref().unwrap();
+                let index = match &value {
+                    Payload::Value(value) => Index::Inflight {
+                        key: key.clone(),
+                        value: value.clone(),
+                    },
+                    Payload::Raw(raw) => Index::InflightRaw { raw: raw.clone() },
+                };
+                let mut sequence = 0;
+                let item = || {
+                    sequence = next();
+                    Item::new(sequence, index, inserted_at).with_trace_id(writer.trace_id)
+                };
+                if exclusive {
+                    if let Err(item) = self.inner.catalog.insert_absent_with(key.clone(), item) {
+                        return Err(Box::new(WriteOnceExisting {
+                            key: key.clone(),
+                            item,
+                            value,
+                        }));
+                    }
+                } else {
+                    self.inner.catalog.insert_with(key.clone(), item);
+                }
+                sequence
+            }
         };
 
         writer.is_inserted = true;
@@ -563,34 +2660,211 @@ where
         self.inner.metrics.op_bytes_insert.inc_by(len as u64);
         self.inner.metrics.insert_entry_bytes.observe(len as f64);
 
-        self.inner.catalog.insert(
-            key.clone(),
-            Item::new(
-                sequence,
-                Index::Inflight {
-                    key: key.clone(),
-                    value: value.clone(),
-                },
-            ),
-        );
+        if let Some(miss_cache) = &self.inner.miss_cache {
+            miss_cache.invalidate(&key);
+        }
 
-        let flusher = sequence as usize % self.inner.flusher_entry_txs.len();
-        self.inner.flusher_entry_txs[flusher]
-            .send(Entry {
+        if coalesced.is_some() {
+            // The pending entry will be flushed with the latest value.
+            self.inner.metrics.op_count_insert_coalesced.inc();
+        } else {
+            let route = match &self.inner.flush_routing {
+                FlushRouting::Sequence => sequence,
+                FlushRouting::KeyHash => self.inner.catalog.hash(&key),
+                FlushRouting::Partitioner(partitioner) => partitioner.partition(&key),
+            };
+            let flusher = route as usize % self.inner.flusher_entry_txs.len();
+            let entry = Entry {
                 sequence,
                 key,
                 value,
                 compression: writer.compression,
-            })
-            .unwrap();
+                enqueued_at: Instant::now(),
+                inserted_at,
+                trace_id: writer.trace_id,
+            };
+            self.inner.metrics.inflight_bytes.add(entry.inflight_bytes() as u64);
+            self.inner.flusher_entry_txs[flusher].send(entry).unwrap();
+        }
 
         let duration = now.elapsed() + writer.duration;
         self.inner
             .metrics
             .op_duration_insert_inserted
             .observe(duration.as_secs_f64());
+        Ok(())
+    }
 
-        Ok(true)
+    /// Return `true` if there is an inflight entry of `key` whose value is byte-identical to `value`.
+    fn is_inflight_duplicate(&self, key: &K, value: &V) -> bool {
+        let Some(item) = self.inner.catalog.lookup(key) else {
+            return false;
+        };
+        let Index::Inflight { value: inflight, .. } = item.index() else {
+            return false;
+        };
+        // Values of different lengths are told apart without serializing them.
+        if inflight.serialized_len() != value.serialized_len() {
+            return false;
+        }
+        match (value_bytes(inflight), value_bytes(value)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<K, V, EP, EL> GenericStore<K, V, FsDevice, EP, EL>
+where
+    K: Key,
+    V: Value,
+    EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
+    EL: Link,
+{
+    /// Copy the sealed regions and a catalog snapshot of them to a new directory `path` while online.
+    ///
+    /// Regions being written or reclaimed are skipped. The snapshot can be opened by a store with the same device
+    /// config except for the dir, and its catalog snapshot can be read with [`CatalogSnapshot::read`].
+    pub async fn snapshot_to(&self, path: impl AsRef<Path>) -> Result<()> {
+        // Pinned regions cannot be reclaimed, so their data and indices stay unchanged until the copy is finished.
+        let views = self.inner.region_manager.pin_evictable_regions();
+
+        let mut entries = vec![];
+        for view in views.iter() {
+            for (key, item) in self.inner.catalog.region_items(view.id()) {
+                if let Index::Region { view } = item.index() {
+                    entries.push(CatalogSnapshotEntry {
+                        key,
+                        sequence: *item.sequence(),
+                        region: *view.id(),
+                        offset: *view.offset(),
+                        len: *view.len(),
+                    });
+                }
+            }
+        }
+
+        let target = FsDevice::open(FsDeviceConfig {
+            dir: path.as_ref().to_path_buf(),
+            ..self.inner.device.config().clone()
+        })
+        .await?;
+
+        for view in views.iter() {
+            let buf = self.inner.device.load(*view.id(), ..).await?;
+            let (res, _buf) = target.write(buf, .., *view.id(), 0).await;
+            res?;
+        }
+        // The copied regions must reach stable storage before the catalog snapshot references them.
+        target.flush().await?;
+
+        CatalogSnapshot { entries }.write(&path)?;
+
+        tracing::info!("[store] snapshot {} regions to {:?}", views.len(), path.as_ref());
+
+        Ok(())
+    }
+}
+
+/// Location of an entry, returned by [`GenericStore::locate`].
+#[derive(Debug, Clone)]
+pub struct EntryLocation<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    key: K,
+    item: Item<K, V>,
+}
+
+impl<K, V> EntryLocation<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn sequence(&self) -> Sequence {
+        *self.item.sequence()
+    }
+
+    /// Region of the entry, `None` if the entry is not flushed yet.
+    pub fn region(&self) -> Option<RegionId> {
+        match self.item.index() {
+            Index::Region { view } => Some(*view.id()),
+            _ => None,
+        }
+    }
+
+    /// Offset of the entry in its region, `None` if the entry is not flushed yet.
+    pub fn offset(&self) -> Option<u32> {
+        match self.item.index() {
+            Index::Region { view } => Some(*view.offset()),
+            _ => None,
+        }
+    }
+
+    /// On-disk bytes of the entry, `None` if the entry is not flushed yet.
+    pub fn bytes(&self) -> Option<u32> {
+        match self.item.index() {
+            Index::Region { view } => Some(*view.len()),
+            _ => None,
+        }
+    }
+}
+
+/// Iterator over the entries of a store in insertion order, created by [`GenericStore::iter_since`].
+pub struct GenericStoreIter<K, V, D, EP, EL>
+where
+    K: Key,
+    V: Value,
+    D: Device,
+    EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
+    EL: Link,
+{
+    store: GenericStore<K, V, D, EP, EL>,
+    keys: VecDeque<(K, Sequence)>,
+}
+
+impl<K, V, D, EP, EL> GenericStoreIter<K, V, D, EP, EL>
+where
+    K: Key,
+    V: Value,
+    D: Device,
+    EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
+    EL: Link,
+{
+    /// Returns the next entry with its sequence, or `None` if all entries are yielded.
+    ///
+    /// Entries removed, evicted or replaced after the iterator is created are skipped. A replaced entry has a greater
+    /// sequence, and is yielded by an iterator created later.
+    ///
+    /// Returns [`ErrorKind::Recovering`] without skipping the entry if a region not recovered yet may hold a newer
+    /// entry of the key, the call can be retried once the recovery finishes.
+    pub async fn next(&mut self) -> Result<Option<(K, V, Sequence)>> {
+        while let Some((key, sequence)) = self.keys.pop_front() {
+            let Some(item) = self.store.inner.catalog.lookup(&key) else {
+                continue;
+            };
+            if *item.sequence() != sequence {
+                continue;
+            }
+            if self.store.inner.recovery.may_be_outdated(sequence) {
+                self.keys.push_front((key, sequence));
+                return Err(ErrorKind::Recovering.into());
+            }
+            if let Some(value) = self.store.read_item(&key, item).await? {
+                return Ok(Some((key, value, sequence)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Count of entries left to visit, including those that will be skipped.
+    pub fn remaining(&self) -> usize {
+        self.keys.len()
     }
 }
 
@@ -608,6 +2882,8 @@ where
     weight: usize,
 
     sequence: Option<Sequence>,
+    /// Insertion time in milliseconds since unix epoch, the time the writer finishes if not set.
+    inserted_at: Option<u64>,
 
     judges: Judges,
     is_judged: bool,
@@ -617,7 +2893,13 @@ where
 
     is_inserted: bool,
     is_skippable: bool,
+    /// Write an existing key in write-once mode.
+    overwrite: bool,
     compression: Compression,
+    trace_id: Option<u64>,
+
+    /// Validation error, the writer is always rejected if set.
+    error: Option<Error>,
 }
 
 impl<K, V, D, EP, EL> GenericStoreWriter<K, V, D, EP, EL>
@@ -630,23 +2912,30 @@ where
 {
     fn new(store: GenericStore<K, V, D, EP, EL>, key: K, weight: usize) -> Self {
         let judges = Judges::new(store.inner.admissions.len());
-        let compression = store.inner.compression;
+        let compression = store.inner.tunables.compression();
         Self {
             store,
             key: Some(key),
             weight,
             sequence: None,
+            inserted_at: None,
             judges,
             is_judged: false,
             duration: Duration::from_nanos(0),
             is_inserted: false,
             is_skippable: false,
+            overwrite: false,
             compression,
+            trace_id: None,
+            error: None,
         }
     }
 
     /// Judge if the entry can be admitted by configured admission policies.
     pub fn judge(&mut self) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
         let store = self.store.clone();
         if !self.is_judged {
             let now = Instant::now();
@@ -673,10 +2962,20 @@ where
         self.is_skippable = true
     }
 
+    /// Allow writing the key if it is already written in write-once mode.
+    pub fn set_overwrite(&mut self) {
+        self.overwrite = true;
+    }
+
     pub fn set_sequence(&mut self, sequence: Sequence) {
         self.sequence = Some(sequence);
     }
 
+    /// Keep the original insertion time of the entry, e.g. when the entry is reinserted.
+    pub fn set_inserted_at(&mut self, inserted_at: u64) {
+        self.inserted_at = Some(inserted_at);
+    }
+
     pub fn compression(&self) -> Compression {
         self.compression
     }
@@ -684,6 +2983,11 @@ where
     pub fn set_compression(&mut self, compression: Compression) {
         self.compression = compression
     }
+
+    /// Attach an opaque trace id to the entry, logged when the entry is flushed to the device and when it is evicted.
+    pub fn set_trace_id(&mut self, trace_id: u64) {
+        self.trace_id = Some(trace_id);
+    }
 }
 
 impl<K, V, D, EP, EL> Debug for GenericStoreWriter<K, V, D, EP, EL>
@@ -702,6 +3006,8 @@ where
             .field("is_judged", &self.is_judged)
             .field("duration", &self.duration)
             .field("inserted", &self.is_inserted)
+            .field("trace_id", &self.trace_id)
+            .field("error", &self.error)
             .finish()
     }
 }
@@ -750,31 +3056,43 @@ const ENTRY_MAGIC_MASK: u32 = 0xFF_FF_FF_00;
 #[derive(Debug)]
 pub struct EntryHeader {
     pub key_len: u32,
+    /// Length of the (compressed) value on disk.
     pub value_len: u32,
+    /// Length of the serialized value before compression.
+    pub uncompressed_value_len: u32,
     pub sequence: Sequence,
+    /// When the entry is inserted, in milliseconds since unix epoch.
+    pub inserted_at: u64,
     pub checksum: u64,
     pub compression: Compression,
+    /// Codecs applied to the (compressed) value.
+    pub codecs: CodecIds,
 }
 
 impl EntryHeader {
     pub const fn serialized_len() -> usize {
-        4 + 4 + 8 + 8 + 4 /* magic & compression */
+        4 + 4 + 4 + 8 + 8 + 8 + 4 /* magic & compression */ + 4 /* codecs */
     }
 
     pub fn write(&self, mut buf: &mut [u8]) {
         buf.put_u32(self.key_len);
         buf.put_u32(self.value_len);
+        buf.put_u32(self.uncompressed_value_len);
         buf.put_u64(self.sequence);
+        buf.put_u64(self.inserted_at);
         buf.put_u64(self.checksum);
 
         let v = ENTRY_MAGIC | self.compression.to_u8() as u32;
         buf.put_u32(v);
+        buf.put_u32(self.codecs.to_u32());
     }
 
     pub fn read(mut buf: &[u8]) -> Result<Self> {
         let key_len = buf.get_u32();
         let value_len = buf.get_u32();
+        let uncompressed_value_len = buf.get_u32();
         let sequence = buf.get_u64();
+        let inserted_at = buf.get_u64();
         let checksum = buf.get_u64();
 
         let v = buf.get_u32();
@@ -783,50 +3101,188 @@ impl EntryHeader {
             return Err(anyhow!("magic mismatch, expected: {}, got: {}", ENTRY_MAGIC, magic).into());
         }
         let compression = Compression::try_from(v as u8)?;
+        let codecs = CodecIds::from_u32(buf.get_u32());
 
         Ok(Self {
             key_len,
             value_len,
+            uncompressed_value_len,
             sequence,
+            inserted_at,
             compression,
             checksum,
+            codecs,
         })
     }
 }
 
-/// | header | value (compressed) | key | <padding> |
-///
-/// # Safety
+/// The indexed item found by a write-once writer of `key` when it is enqueued, with the value of the writer.
+struct WriteOnceExisting<K, V>
+where
+    K: Key,
+    V: Value,
+{
+    key: K,
+    item: Item<K, V>,
+    value: Payload<V>,
+}
+
+/// Lookups waiting for the device read of an entry, by the key and sequence of the entry.
+type LookupWaiters<K, V> = Mutex<HashMap<(K, Sequence), Vec<oneshot::Sender<Option<V>>>>>;
+
+/// Wakes up the lookups waiting for the reading lookup of `key`.
 ///
-/// `buf.len()` must exactly fit entry size
-fn read_entry<K, V>(buf: &[u8]) -> Result<(K, V)>
+/// If dropped without notifying, e.g. the reading lookup fails or is cancelled, the waiters read by themselves.
+struct LookupWaitersGuard<'a, K, V>
 where
     K: Key,
     V: Value,
 {
-    // read entry header
-    let header = EntryHeader::read(buf)?;
+    waiters: &'a LookupWaiters<K, V>,
+    key: (K, Sequence),
+}
 
-    // read value
-    let mut offset = EntryHeader::serialized_len();
-    let compressed = &buf[offset..offset + header.value_len as usize];
-    offset += header.value_len as usize;
-    let value = match header.compression {
-        Compression::None => V::read(compressed)?,
-        Compression::Zstd => {
-            let mut decompressed = Vec::with_capacity((header.value_len + header.value_len / 2) as usize);
-            zstd::stream::copy_decode(compressed, &mut decompressed).map_err(CodingError::from)?;
-            V::read(&decompressed[..])?
+impl<'a, K, V> LookupWaitersGuard<'a, K, V>
+where
+    K: Key,
+    V: Value,
+{
+    fn notify(self, value: Option<V>) {
+        let Some(waiters) = self.waiters.lock().remove(&self.key) else {
+            return;
+        };
+        for waiter in waiters {
+            let _ = waiter.send(value.clone());
         }
-        Compression::Lz4 => {
-            let mut decompressed = Vec::with_capacity((header.value_len + header.value_len / 2) as usize);
-            let mut decoder = lz4::Decoder::new(compressed).map_err(CodingError::from)?;
-            std::io::copy(&mut decoder, &mut decompressed).map_err(CodingError::from)?;
-            let (_r, res) = decoder.finish();
+    }
+}
+
+impl<'a, K, V> Drop for LookupWaitersGuard<'a, K, V>
+where
+    K: Key,
+    V: Value,
+{
+    fn drop(&mut self) {
+        self.waiters.lock().remove(&self.key);
+    }
+}
+
+/// An entry as it is stored on the device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawEntry {
+    /// Serialized value, compressed with `compression`.
+    pub value: Vec<u8>,
+    pub compression: Compression,
+    /// Length of the serialized value before compression.
+    pub uncompressed_len: usize,
+    /// Checksum of the (compressed) value followed by the serialized key, see [`checksum`].
+    pub checksum: u64,
+}
+
+/// Read the raw value of the entry in `buf` and verify its checksum.
+///
+/// The raw value is decoded with `codecs`, so that it can be inserted into a store with other codecs.
+///
+/// `buf.len()` must exactly fit entry size
+fn read_raw_entry(buf: &[u8], codecs: &CodecPipeline) -> Result<RawEntry> {
+    let header = EntryHeader::read(buf)?;
+
+    let start = EntryHeader::serialized_len();
+    let end = start + (header.value_len + header.key_len) as usize;
+    let mut checksum = checksum(&buf[start..end]);
+    if checksum != header.checksum {
+        return Err(anyhow!("checksum mismatch, expected: {}, got: {}", header.checksum, checksum).into());
+    }
+
+    let value = &buf[start..start + header.value_len as usize];
+    let value = if header.codecs.is_empty() {
+        value.to_vec()
+    } else {
+        let mut value = codecs.decode(header.codecs, value)?.into_owned();
+        let len = value.len();
+        value.extend_from_slice(&buf[start + header.value_len as usize..end]);
+        checksum = self::checksum(&value);
+        value.truncate(len);
+        value
+    };
+
+    Ok(RawEntry {
+        value,
+        compression: header.compression,
+        uncompressed_len: header.uncompressed_value_len as usize,
+        checksum,
+    })
+}
+
+/// Encode and compress a value with `compression` into `writer`, returns the value back.
+pub(crate) fn encode_value<V, W>(value: V, compression: Compression, writer: &mut W) -> std::io::Result<V>
+where
+    V: Value,
+    W: std::io::Write,
+{
+    let mut cursor = value.into_cursor();
+    match compression {
+        Compression::None => {
+            std::io::copy(&mut cursor, writer)?;
+        }
+        Compression::Zstd => {
+            zstd::stream::copy_encode(&mut cursor, writer, 0)?;
+        }
+        Compression::Lz4 => {
+            let mut encoder = lz4::EncoderBuilder::new()
+                .checksum(lz4::ContentChecksum::NoChecksum)
+                .build(writer)?;
+            std::io::copy(&mut cursor, &mut encoder)?;
+            let (_w, res) = encoder.finish();
+            res?;
+        }
+    }
+    Ok(cursor.into_inner())
+}
+
+/// Decompress and decode a value written with `compression`.
+pub(crate) fn decode_value<V>(compressed: &[u8], compression: Compression, uncompressed_len: usize) -> Result<V>
+where
+    V: Value,
+{
+    let value = match compression {
+        Compression::None => V::read(compressed)?,
+        Compression::Zstd => {
+            let mut decompressed = Vec::with_capacity(uncompressed_len);
+            zstd::stream::copy_decode(compressed, &mut decompressed).map_err(CodingError::from)?;
+            V::read(&decompressed[..])?
+        }
+        Compression::Lz4 => {
+            let mut decompressed = Vec::with_capacity(uncompressed_len);
+            let mut decoder = lz4::Decoder::new(compressed).map_err(CodingError::from)?;
+            std::io::copy(&mut decoder, &mut decompressed).map_err(CodingError::from)?;
+            let (_r, res) = decoder.finish();
             res.map_err(CodingError::from)?;
             V::read(&decompressed[..])?
         }
     };
+    Ok(value)
+}
+
+/// | header | value (compressed, encoded) | key | <padding> |
+///
+/// # Safety
+///
+/// `buf.len()` must exactly fit entry size
+fn read_entry<K, V>(buf: &[u8], codecs: &CodecPipeline) -> Result<(K, V)>
+where
+    K: Key,
+    V: Value,
+{
+    // read entry header
+    let header = EntryHeader::read(buf)?;
+
+    // read value
+    let mut offset = EntryHeader::serialized_len();
+    let encoded = &buf[offset..offset + header.value_len as usize];
+    offset += header.value_len as usize;
+    let compressed = codecs.decode(header.codecs, encoded)?;
+    let value = decode_value(&compressed, header.compression, header.uncompressed_value_len as usize)?;
 
     // read key
     let key = K::read(&buf[offset..offset + header.key_len as usize])?;
@@ -840,12 +3296,30 @@ where
     Ok((key, value))
 }
 
+/// Returns `true` if the entry in `buf` has a valid header and a matching checksum.
+fn verify_entry(buf: &[u8]) -> bool {
+    let Ok(header) = EntryHeader::read(buf) else {
+        return false;
+    };
+    let start = EntryHeader::serialized_len();
+    let end = start + (header.value_len + header.key_len) as usize;
+    end <= buf.len() && checksum(&buf[start..end]) == header.checksum
+}
+
 pub fn checksum(buf: &[u8]) -> u64 {
     let mut hasher = XxHash64::with_seed(0);
     hasher.write(buf);
     hasher.finish()
 }
 
+/// Serialize the value uncompressed, to compare values byte by byte.
+fn value_bytes<V: Value>(value: &V) -> Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(value.serialized_len());
+    let mut cursor = value.clone().into_cursor();
+    std::io::copy(&mut cursor, &mut buf).map_err(CodingError::from)?;
+    Ok(buf)
+}
+
 pub struct RegionEntryIter<K, V, D>
 where
     K: Key,
@@ -856,6 +3330,19 @@ where
 
     cursor: usize,
 
+    created_at: u64,
+
+    padding: EntryPadding,
+
+    /// Entry directory from the footer if the region is sealed, entries are scanned otherwise.
+    directory: Option<VecDeque<DirectoryRecord>>,
+
+    /// Footer of the sealed region, without the records moved to `directory`.
+    footer: Option<RegionFooter>,
+
+    logical_bytes: usize,
+    physical_bytes: usize,
+
     _marker: PhantomData<(K, V)>,
 }
 
@@ -868,40 +3355,122 @@ where
     pub async fn open(region: Region<D>) -> Result<Option<Self>> {
         let align = region.device().align();
 
-        let slice = match region.load_range(..align).await? {
-            Some(slice) => slice,
-            None => return Ok(None),
+        let Some(header) = region.header().await? else {
+            return Ok(None);
         };
-
-        let Ok(_) = RegionHeader::read(slice.as_ref()) else {
+        if header.version != Version::latest() {
+            tracing::warn!(
+                "skip region {} with outdated format version {:?}",
+                region.id(),
+                header.version
+            );
             return Ok(None);
+        }
+
+        // A footer left by a former use of the region is ignored.
+        let (directory, footer) = match region.footer().await? {
+            Some(mut footer) if footer.created_at == header.created_at => {
+                (Some(std::mem::take(&mut footer.records).into()), Some(footer))
+            }
+            _ => (None, None),
         };
 
         Ok(Some(Self {
             region,
             cursor: align,
+            created_at: header.created_at,
+            padding: header.padding,
+            directory,
+            footer,
+            logical_bytes: 0,
+            physical_bytes: 0,
             _marker: PhantomData,
         }))
     }
 
+    /// Creation time of the region, in milliseconds since unix epoch.
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Returns `true` if the entries are iterated from the footer of a sealed region.
+    pub fn is_sealed(&self) -> bool {
+        self.directory.is_some()
+    }
+
+    /// Max sequence of the entries not iterated yet of a sealed region, or `None` if the region is not sealed.
+    pub fn max_sequence(&self) -> Option<Sequence> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.iter().map(|record| record.sequence).max().unwrap_or(0))
+    }
+
+    /// Returns `Some(true)` if the data of the sealed region matches the checksum in its footer, or `None` if the
+    /// region is not sealed.
+    pub async fn verify_data(&self) -> Result<Option<bool>> {
+        match &self.footer {
+            Some(footer) => self.region.verify_data(footer).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Uncompressed key and value bytes of the iterated entries.
+    pub fn logical_bytes(&self) -> usize {
+        self.logical_bytes
+    }
+
+    /// Aligned on-disk bytes of the iterated entries.
+    pub fn physical_bytes(&self) -> usize {
+        self.physical_bytes
+    }
+
     pub async fn next(&mut self) -> Result<Option<(K, Item<K, V>)>> {
+        if let Some(directory) = &mut self.directory {
+            let Some(record) = directory.pop_front() else {
+                return Ok(None);
+            };
+            let Ok(key) = K::read(&record.key) else {
+                return Ok(None);
+            };
+            let item = Item::new(
+                record.sequence,
+                Index::Region {
+                    view: self.region.view(record.offset, record.len),
+                },
+                record.inserted_at,
+            );
+            self.logical_bytes += record.logical as usize;
+            self.physical_bytes += record.len as usize;
+            return Ok(Some((key, item)));
+        }
+
         let region_size = self.region.device().region_size();
         let align = self.region.device().align();
 
-        if self.cursor + align >= region_size {
-            return Ok(None);
-        }
+        let (header, slice, start, end) = loop {
+            if self.cursor + align >= region_size {
+                return Ok(None);
+            }
 
-        let Some(slice) = self.region.load_range(self.cursor..self.cursor + align).await? else {
-            return Ok(None);
-        };
+            // A packed entry may start anywhere in a block and its header may cross blocks.
+            let start = bits::align_down(align, self.cursor);
+            let end = bits::align_up(align, self.cursor + EntryHeader::serialized_len());
+            let Some(slice) = self.region.load_range(start..end).await? else {
+                return Ok(None);
+            };
 
-        let Ok(header) = EntryHeader::read(slice.as_ref()) else {
-            return Ok(None);
+            match EntryHeader::read(&slice.as_ref()[self.cursor - start..]) {
+                Ok(header) => break (header, slice, start, end),
+                // The rest of the block is padded at flush, entries continue from the next block.
+                Err(_) if self.padding == EntryPadding::Packed && !bits::is_aligned(align, self.cursor) => {
+                    self.cursor = bits::align_up(align, self.cursor);
+                }
+                Err(_) => return Ok(None),
+            }
         };
 
         let entry_len = bits::align_up(
-            align,
+            self.padding.granularity(align),
             (header.value_len + header.key_len) as usize + EntryHeader::serialized_len(),
         );
 
@@ -916,12 +3485,9 @@ where
         let align_start = bits::align_down(align, abs_start);
         let align_end = bits::align_up(align, abs_end);
 
-        let key = if align_start == self.cursor - align && align_end == self.cursor {
-            // header and key are in the same block, read directly from slice
-            let rel_start = EntryHeader::serialized_len() + header.value_len as usize;
-            let rel_end = rel_start + header.key_len as usize;
-
-            let Ok(key) = K::read(&slice.as_ref()[rel_start..rel_end]) else {
+        let key = if align_start >= start && align_end <= end {
+            // header and key are in the same blocks, read directly from slice
+            let Ok(key) = K::read(&slice.as_ref()[abs_start - start..abs_end - start]) else {
                 return Ok(None);
             };
             drop(slice);
@@ -946,34 +3512,39 @@ where
             Index::Region {
                 view: self.region.view(self.cursor as u32, entry_len as u32),
             },
+            header.inserted_at,
         );
 
         self.cursor += entry_len;
+        self.logical_bytes += (header.key_len + header.uncompressed_value_len) as usize;
+        self.physical_bytes += entry_len;
 
         Ok(Some((key, info)))
     }
 
-    pub async fn next_kv(&mut self) -> Result<Option<(K, V)>> {
+    /// Returns the next entry with its insertion time in milliseconds since unix epoch.
+    ///
+    /// Values are decoded with `codecs`.
+    pub async fn next_kv(&mut self, codecs: &CodecPipeline) -> Result<Option<(K, V, u64)>> {
         let (_, item) = match self.next().await {
             Ok(Some(res)) => res,
             Ok(None) => return Ok(None),
             Err(e) => return Err(e),
         };
 
-        let Index::Region { view } = item.index() else {
+        let inserted_at = item.inserted_at();
+        let (_, Index::Region { view }) = item.consume() else {
             unreachable!("kv loaded from region must have index of region")
         };
 
         // TODO(MrCroxx): Optimize if all key, value and footer are in the same read block.
-        let start = *view.offset() as usize;
-        let end = start + *view.len() as usize;
-        let Some(slice) = self.region.load_range(start..end).await? else {
+        let Some(slice) = self.region.load(view).await? else {
             return Ok(None);
         };
-        let kv = read_entry::<K, V>(slice.as_ref()).ok();
+        let kv = read_entry::<K, V>(slice.as_ref(), codecs).ok();
         drop(slice);
 
-        Ok(kv)
+        Ok(kv.map(|(key, value)| (key, value, inserted_at)))
     }
 }
 
@@ -1015,6 +3586,10 @@ where
     fn set_compression(&mut self, compression: Compression) {
         self.set_compression(compression)
     }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        self.set_trace_id(trace_id)
+    }
 }
 
 impl<K, V, D, EP, EL> Storage for GenericStore<K, V, D, EP, EL>
@@ -1058,6 +3633,10 @@ where
         self.remove(key)
     }
 
+    fn remove_batch(&self, keys: &[Self::Key]) -> Result<usize> {
+        self.remove_batch(keys)
+    }
+
     fn clear(&self) -> Result<()> {
         self.clear()
     }
@@ -1065,21 +3644,58 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
 
+    use foyer_common::clock::MockClock;
     use foyer_intrusive::eviction::fifo::{Fifo, FifoConfig, FifoLink};
+    use futures::future::join_all;
+    use proptest::prelude::*;
 
     use super::*;
     use crate::{
-        device::fs::{FsDevice, FsDeviceConfig},
-        storage::StorageExt,
+        admission::rated_ticket::RatedTicketAdmissionPolicy,
+        codec::tests::{FramingCodec, XorCodec},
+        device::{
+            error::DeviceErrorKind,
+            fs::{FsDevice, FsDeviceConfig, FsReadMode},
+            mmap::MmapAdvice,
+            ReadIoPolicy,
+        },
+        quota::{SoftQuotaEvent, SoftQuotaListener},
+        stats::{OpStats, STATS_HISTORY_MINUTES},
         test_utils::JudgeRecorder,
+        testing::{arb_bytes, arb_compression, arb_entries},
     };
 
     type TestStore = GenericStore<u64, Vec<u8>, FsDevice, Fifo<RegionEpItemAdapter<FifoLink>>, FifoLink>;
 
     type TestStoreConfig = GenericStoreConfig<u64, Vec<u8>, FsDevice, Fifo<RegionEpItemAdapter<FifoLink>>>;
 
+    /// Device of 4 regions of 4 MiB on `dir`.
+    fn test_device_config(dir: &Path) -> FsDeviceConfig {
+        FsDeviceConfig {
+            dir: PathBuf::from(dir),
+            capacity: 16 * 1024 * 1024,
+            file_capacity: 4 * 1024 * 1024,
+            align: 4 * 1024,
+            io_size: 4 * 1024,
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        }
+    }
+
+    /// Store on [`test_device_config`] with a flusher, a reclaimer and the optional features disabled. Tests override
+    /// the fields they exercise.
+    fn test_config(name: &str, dir: &Path) -> TestStoreConfig {
+        TestStoreConfig {
+            catalog_bits: 1,
+            recover_concurrency: 2,
+            invariant_policy: InvariantPolicy::Panic,
+            ..TestStoreConfig::new(name, FifoConfig, test_device_config(dir))
+        }
+    }
+
     #[tokio::test]
     #[expect(clippy::identity_op)]
     async fn test_recovery() {
@@ -1093,23 +3709,9 @@ mod tests {
         let reinsertions: Vec<Arc<dyn ReinsertionPolicy<Key = u64, Value = Vec<u8>>>> = vec![recorder.clone()];
 
         let config = TestStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
-                dir: PathBuf::from(tempdir.path()),
-                capacity: 16 * MB,
-                file_capacity: 4 * MB,
-                align: 4 * KB,
-                io_size: 4 * KB,
-            },
-            catalog_bits: 1,
             admissions,
             reinsertions,
-            flushers: 1,
-            reclaimers: 1,
-            recover_concurrency: 2,
-            clean_region_threshold: 1,
-            compression: Compression::None,
+            ..test_config("", tempdir.path())
         };
 
         let store = TestStore::open(config).await.unwrap();
@@ -1139,23 +3741,13 @@ mod tests {
         drop(store);
 
         let config = TestStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
             device_config: FsDeviceConfig {
-                dir: PathBuf::from(tempdir.path()),
-                capacity: 16 * MB,
-                file_capacity: 4 * MB,
                 align: 4096,
                 io_size: 4096 * KB,
+                ..test_device_config(tempdir.path())
             },
-            catalog_bits: 1,
-            admissions: vec![],
-            reinsertions: vec![],
-            flushers: 1,
             reclaimers: 0,
-            recover_concurrency: 2,
-            clean_region_threshold: 1,
-            compression: Compression::None,
+            ..test_config("", tempdir.path())
         };
         let store = TestStore::open(config).await.unwrap();
 
@@ -1171,4 +3763,2579 @@ mod tests {
 
         drop(store);
     }
+
+    #[tokio::test]
+    async fn test_deduplicate_inflight() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                io_size: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            deduplicate_inflight: true,
+            ..test_config("test_deduplicate_inflight", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        // Small entries stay in the flush buffer, so they are still inflight.
+        assert!(store.insert(1, vec![1; KB]).await.unwrap());
+        assert!(store.insert(1, vec![1; KB]).await.unwrap());
+        assert_eq!(
+            store.inner.metrics.op_duration_insert_deduplicated.get_sample_count(),
+            1
+        );
+
+        // Different value of the same key is not deduplicated.
+        assert!(store.insert(1, vec![2; KB]).await.unwrap());
+        assert_eq!(
+            store.inner.metrics.op_duration_insert_deduplicated.get_sample_count(),
+            1
+        );
+
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![2; KB]);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_coalesce_inflight() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                io_size: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            reclaimers: 0,
+            coalesce_inflight: true,
+            ..test_config("test_coalesce_inflight", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        for i in 0..64u8 {
+            assert!(store.insert(1, vec![i; KB]).await.unwrap());
+        }
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![63; KB]);
+        store.close().await.unwrap();
+
+        // Whether coalesced or not, only the latest value is indexed after recovery.
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![63; KB]);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lookup_raw() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            compression: Compression::Zstd,
+            ..test_config("test_lookup_raw", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        let raw = store.lookup_raw(&1).await.unwrap().unwrap();
+        assert_eq!(raw.compression, Compression::Zstd);
+        assert!(raw.value.len() < 16 * KB);
+        let decompressed = zstd::stream::decode_all(&raw.value[..]).unwrap();
+        assert_eq!(<Vec<u8> as Value>::read(&decompressed).unwrap(), vec![1; 16 * KB]);
+
+        assert!(store.lookup_raw(&2).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_single_flight() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_lookup_single_flight", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        // The first lookup reads from the device, the others wait for its result.
+        let values = join_all((0..16).map(|_| store.lookup(&1))).await;
+        for value in values {
+            assert_eq!(value.unwrap().unwrap(), vec![1; 16 * KB]);
+        }
+        assert_eq!(store.inner.metrics.op_count_lookup_shared.get(), 15);
+        assert!(store.inner.lookup_waiters.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_lookup_single_flight_overwritten() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_lookup_single_flight_overwritten", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        while !matches!(store.inner.catalog.lookup(&1).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        // Start reading the old entry from the device.
+        let mut old = std::pin::pin!(store.lookup(&1));
+        assert!(futures::poll!(old.as_mut()).is_pending());
+        assert_eq!(store.inner.lookup_waiters.lock().len(), 1);
+
+        // A lookup after the key is overwritten reads the new entry, instead of waiting for the old read.
+        assert!(store.insert(1, vec![2; 16 * KB]).await.unwrap());
+        while !matches!(store.inner.catalog.lookup(&1).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let (old, new) = futures::join!(old, store.lookup(&1));
+        assert_eq!(old.unwrap().unwrap(), vec![1; 16 * KB]);
+        assert_eq!(new.unwrap().unwrap(), vec![2; 16 * KB]);
+        assert_eq!(store.inner.metrics.op_count_lookup_shared.get(), 0);
+        assert!(store.inner.lookup_waiters.lock().is_empty());
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_hot_keys() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_hot_keys", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..8 {
+            assert!(store.insert(i, vec![i as u8; 16 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Recovered regions are evictable.
+        let store = TestStore::open(config).await.unwrap();
+        for _ in 0..3 {
+            store.lookup(&3).await.unwrap().unwrap();
+        }
+        store.lookup(&5).await.unwrap().unwrap();
+
+        assert_eq!(store.hot_keys(2), vec![3, 5]);
+        assert_eq!(store.hot_keys(16).len(), 8);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_insert_raw() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            compression: Compression::Zstd,
+            max_value_len: Some(16 * KB),
+            ..test_config("test_insert_raw", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        let value = zstd::stream::encode_all(&vec![1; 16 * KB][..], 0).unwrap();
+        let mut buf = value.clone();
+        buf.put_u64(1);
+        let raw = RawEntry {
+            value,
+            compression: Compression::Zstd,
+            uncompressed_len: 16 * KB,
+            checksum: checksum(&buf),
+        };
+
+        assert!(store
+            .insert_raw(
+                2,
+                RawEntry {
+                    checksum: 0,
+                    ..raw.clone()
+                }
+            )
+            .await
+            .is_err());
+
+        // The value length limit applies to the uncompressed value.
+        let large = zstd::stream::encode_all(&vec![3; 32 * KB][..], 0).unwrap();
+        let mut buf = large.clone();
+        buf.put_u64(3);
+        let e = store
+            .insert_raw(
+                3,
+                RawEntry {
+                    value: large,
+                    compression: Compression::Zstd,
+                    uncompressed_len: 32 * KB,
+                    checksum: checksum(&buf),
+                },
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Validation(_)));
+
+        assert!(store.insert_raw(1, raw.clone()).await.unwrap());
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; 16 * KB]);
+        store.close().await.unwrap();
+
+        assert_eq!(store.lookup_raw(&1).await.unwrap().unwrap(), raw);
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; 16 * KB]);
+    }
+
+    #[derive(Debug)]
+    struct EvenKeyValidator;
+
+    impl KeyValidator for EvenKeyValidator {
+        type Key = u64;
+
+        fn validate(&self, key: &u64) -> anyhow::Result<()> {
+            if key % 2 == 0 {
+                Ok(())
+            } else {
+                Err(anyhow!("odd key"))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validation() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            max_key_len: Some(8),
+            max_value_len: Some(KB),
+            key_validators: vec![Arc::new(EvenKeyValidator)],
+            ..test_config("", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(2, vec![0; KB]).await.unwrap());
+
+        // The value is checked itself, not by the weight given to the writer.
+        let writer = store.writer(4, 8 + KB);
+        assert!(writer.finish(vec![0; 2 * KB]).await.is_err());
+        assert!(!store.exists(&4).unwrap());
+        assert!(store.insert(4, vec![0; 2 * KB]).await.is_err());
+        assert!(!store.exists(&4).unwrap());
+
+        // An invalid key is rejected when the writer is created.
+        assert!(store.try_writer(3, 8 + KB).is_err());
+        let mut writer = store.writer(3, 8 + KB);
+        assert!(!writer.judge());
+        assert!(writer.finish(vec![0; KB]).await.is_err());
+        assert!(store.insert(3, vec![0; KB]).await.is_err());
+        assert!(!store.exists(&3).unwrap());
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_quarantine() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let quarantine_dir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            quarantine_path: Some(quarantine_dir.path().join("quarantine")),
+            ..test_config("test_quarantine", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        assert!(store.insert(2, vec![2; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        let (_, index) = store.inner.catalog.lookup(&1).unwrap().consume();
+        let Index::Region { view } = index else {
+            panic!("entry is not flushed");
+        };
+        let region = *view.id();
+
+        // Corrupt the entry header.
+        let mut buf = store.inner.device.io_buffer(4 * KB, 4 * KB);
+        (&mut buf[..]).put_slice(&[0xff; 4 * KB]);
+        let (res, _) = store.inner.device.write(buf, .., region, *view.offset() as usize).await;
+        res.unwrap();
+        drop(view);
+
+        assert!(store.lookup(&1).await.is_err());
+        assert!(store.is_quarantined(region));
+        assert!(!store.exists(&2).unwrap());
+        assert_eq!(store.inner.metrics.quarantined_bytes.get(), 4 * MB as u64);
+
+        drop(store);
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.is_quarantined(region));
+        assert!(!store.exists(&1).unwrap());
+        assert!(!store.exists(&2).unwrap());
+        assert_eq!(store.inner.metrics.quarantined_bytes.get(), 4 * MB as u64);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invariant_policy_reclaimer() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let quarantine_dir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            quarantine_path: Some(quarantine_dir.path().join("quarantine")),
+            invariant_policy: InvariantPolicy::DropRegion,
+            ..test_config("test_invariant_policy_reclaimer", tempdir.path())
+        };
+
+        let store = TestStore::open(TestStoreConfig {
+            reclaimers: 0,
+            ..config.clone()
+        })
+        .await
+        .unwrap();
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        let (_, index) = store.inner.catalog.lookup(&1).unwrap().consume();
+        let Index::Region { view } = index else {
+            panic!("entry is not flushed");
+        };
+        let region = *view.id();
+
+        // Corrupt the entry header to quarantine the region.
+        let mut buf = store.inner.device.io_buffer(4 * KB, 4 * KB);
+        buf.fill(0xff);
+        let (res, _) = store.inner.device.write(buf, .., region, *view.offset() as usize).await;
+        res.unwrap();
+        drop(view);
+        assert!(store.lookup(&1).await.is_err());
+        assert!(store.is_quarantined(region));
+        drop(store);
+
+        // Fail to release the region from quarantine after it is zeroed.
+        std::fs::create_dir(quarantine_dir.path().join("quarantine.tmp")).unwrap();
+
+        // The quarantined region is reclaimed once the clean region threshold is raised.
+        let reclaim = |store: TestStore| async move {
+            let regions = store.inner.device.regions();
+            store.tunables().set_clean_region_threshold(regions);
+            while store.inner.metrics.op_count_invariant_violated.get() == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            store.tunables().set_clean_region_threshold(1);
+        };
+
+        // The reclaimer keeps running.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        reclaim(store.clone()).await;
+        assert!(store.health().is_healthy());
+        assert!(!store.is_degraded());
+        store.close().await.unwrap();
+        drop(store);
+
+        let config = TestStoreConfig {
+            invariant_policy: InvariantPolicy::Degrade,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        reclaim(store.clone()).await;
+        assert!(store.health().is_healthy());
+        assert!(store.is_degraded());
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_recovery_verify() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_recovery_verify", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        assert!(store.insert(2, vec![2; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        let (_, index) = store.inner.catalog.lookup(&1).unwrap().consume();
+        let Index::Region { view } = index else {
+            panic!("entry is not flushed");
+        };
+
+        // Corrupt the entry value, keep the header valid.
+        let mut buf = store.inner.device.io_buffer(4 * KB, 4 * KB);
+        (&mut buf[..]).put_slice(&[0xff; 4 * KB]);
+        let (res, _) = store
+            .inner
+            .device
+            .write(buf, .., *view.id(), *view.offset() as usize + 4 * KB)
+            .await;
+        res.unwrap();
+        drop(view);
+        drop(store);
+
+        // Corrupted entry is indexed without verification.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.exists(&1).unwrap());
+        assert!(store.exists(&2).unwrap());
+        store.close().await.unwrap();
+        drop(store);
+
+        let config = TestStoreConfig {
+            recovery_mode: RecoveryMode::Verify,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        assert!(!store.exists(&1).unwrap());
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; 16 * KB]);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_entry_inserted_at() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_entry_inserted_at", tempdir.path())
+        };
+
+        let before = SystemClock.now_millis();
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        let mut writer = store.writer(2, 16 * KB);
+        writer.set_inserted_at(42);
+        assert!(writer.finish(vec![2; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+        let after = SystemClock.now_millis();
+
+        let inserted_at = store.inner.catalog.lookup(&1).unwrap().inserted_at();
+        assert!((before..=after).contains(&inserted_at));
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; 16 * KB]);
+        assert_eq!(store.inner.metrics.entry_age_hit.get_sample_count(), 1);
+        drop(store);
+
+        // The insertion time is recovered from the entry header.
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(store.inner.catalog.lookup(&1).unwrap().inserted_at(), inserted_at);
+        assert_eq!(store.inner.catalog.lookup(&2).unwrap().inserted_at(), 42);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_miss_cache() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            miss_cache_config: Some(MissCacheConfig {
+                window: Duration::from_secs(3600),
+                capacity: 16,
+            }),
+            ..test_config("test_miss_cache", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.lookup(&1).await.unwrap().is_none());
+        assert!(store.lookup(&1).await.unwrap().is_none());
+        assert_eq!(store.inner.metrics.op_duration_lookup_miss_cached.get_sample_count(), 1);
+
+        // Insertion invalidates the recorded miss.
+        assert!(store.insert(1, vec![1; KB]).await.unwrap());
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; KB]);
+
+        store.close().await.unwrap();
+    }
+
+    #[derive(Debug)]
+    struct HundredPartitioner;
+
+    impl FlushPartitioner for HundredPartitioner {
+        type Key = u64;
+
+        fn partition(&self, key: &u64) -> u64 {
+            key / 100
+        }
+    }
+
+    #[tokio::test]
+    async fn test_flush_routing_partitioner() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                file_capacity: MB,
+                ..test_device_config(tempdir.path())
+            },
+            flushers: 4,
+            flush_routing: FlushRouting::Partitioner(Arc::new(HundredPartitioner)),
+            ..test_config("test_flush_routing_partitioner", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        for i in 0..8 {
+            for partition in 0..4 {
+                let key = partition * 100 + i;
+                assert!(store.insert(key, vec![key as u8; KB]).await.unwrap());
+            }
+        }
+        store.close().await.unwrap();
+
+        // Entries of a partition are written to the region of its flusher.
+        let regions = (0..4)
+            .map(|partition| {
+                (0..8)
+                    .map(|i| store.locate(&(partition * 100 + i)).unwrap().region().unwrap())
+                    .dedup()
+                    .collect_vec()
+            })
+            .collect_vec();
+        for region in regions.iter() {
+            assert_eq!(region.len(), 1);
+        }
+        assert_eq!(regions.iter().map(|region| region[0]).unique().count(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_locate_read() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            ..test_config("test_locate_read", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..8 {
+            assert!(store.insert(i, vec![i as u8; KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config).await.unwrap();
+        for i in 8..10 {
+            assert!(store.insert(i, vec![i as u8; KB]).await.unwrap());
+        }
+
+        assert!(store.locate(&42).is_none());
+
+        let mut locations = (0..10).rev().map(|i| store.locate(&i).unwrap()).collect_vec();
+        assert!(locations.iter().filter(|location| location.region().is_none()).count() >= 1);
+        // Flushed entries are sorted by their positions, and inflight entries go last.
+        locations.sort_by_key(|location| (location.region().is_none(), location.region(), location.offset()));
+        let flushed = locations
+            .iter()
+            .filter(|location| location.region().is_some())
+            .collect_vec();
+        assert!(flushed
+            .windows(2)
+            .all(|w| (w[0].region(), w[0].offset()) < (w[1].region(), w[1].offset())));
+
+        for location in locations {
+            let key = *location.key();
+            assert_eq!(store.read(location).await.unwrap().unwrap(), vec![key as u8; KB]);
+        }
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_iter_since() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            ..test_config("test_iter_since", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        async fn collect(store: &TestStore, sequence: Sequence) -> Vec<(u64, Vec<u8>, Sequence)> {
+            let mut iter = store.iter_since(sequence);
+            let mut entries = vec![];
+            while let Some(entry) = iter.next().await.unwrap() {
+                entries.push(entry);
+            }
+            entries
+        }
+
+        for i in 0..8 {
+            assert!(store.insert(i, vec![i as u8; KB]).await.unwrap());
+        }
+        // Flush some of the entries to the device.
+        store.close().await.unwrap();
+        drop(store);
+        let store = TestStore::open(config).await.unwrap();
+        for i in 8..12 {
+            assert!(store.insert(i, vec![i as u8; KB]).await.unwrap());
+        }
+
+        let entries = collect(&store, 0).await;
+        assert_eq!(
+            entries.iter().map(|(key, _, _)| *key).collect_vec(),
+            (0..12).collect_vec()
+        );
+        for (key, value, _) in entries.iter() {
+            assert_eq!(value, &vec![*key as u8; KB]);
+        }
+        assert!(entries.windows(2).all(|w| w[0].2 < w[1].2));
+
+        let checkpoint = entries[5].2;
+        assert!(store.remove(&7).unwrap());
+        assert!(store.insert(2, vec![42; KB]).await.unwrap());
+        let entries = collect(&store, checkpoint).await;
+        assert_eq!(
+            entries.into_iter().map(|(key, value, _)| (key, value[0])).collect_vec(),
+            vec![(6, 6), (8, 8), (9, 9), (10, 10), (11, 11), (2, 42)]
+        );
+
+        let last = collect(&store, 0).await.last().unwrap().2;
+        assert!(collect(&store, last).await.is_empty());
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_catalog_bytes() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            catalog_entry_overhead: Some(100),
+            ..test_config("test_catalog_bytes", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        for i in 0..16 {
+            assert!(store.insert(i, vec![i as u8; KB]).await.unwrap());
+        }
+        assert_eq!(store.stats().catalog_bytes, 16 * (100 + 2 * 8));
+
+        assert!(store.remove(&0).unwrap());
+        assert_eq!(store.stats().catalog_bytes, 15 * (100 + 2 * 8));
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reduce_memory() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                read_mode: FsReadMode::Mmap {
+                    advice: MmapAdvice::Random,
+                },
+                ..test_device_config(tempdir.path())
+            },
+            miss_cache_config: Some(MissCacheConfig {
+                window: Duration::from_secs(3600),
+                capacity: 16,
+            }),
+            ..test_config("test_reduce_memory", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.lookup(&1).await.unwrap().is_none());
+        assert!(store.insert(2, vec![2; 16 * KB]).await.unwrap());
+        while !matches!(store.inner.catalog.lookup(&2).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        // The miss cache alone satisfies a small target.
+        let released = store.reduce_memory(1);
+        assert!(released > 0);
+        assert_eq!(store.lookup(&1).await.unwrap(), None);
+        assert_eq!(store.inner.metrics.op_duration_lookup_miss_cached.get_sample_count(), 0);
+
+        // The cached header of the flushed region and the mapped pages are released, and read from device again on
+        // demand.
+        assert!(store.reduce_memory(usize::MAX) > 0);
+        assert_eq!(store.reduce_memory(usize::MAX), 0);
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; 16 * KB]);
+        store.reduce_memory(usize::MAX);
+        store.tunables().set_inflight_budget(None);
+
+        // With nothing cached left, the inflight budget is lowered by the remaining target.
+        store.inner.metrics.inflight_bytes.set(64 * KB as u64);
+        assert_eq!(store.reduce_memory(16 * KB), 16 * KB);
+        assert_eq!(store.tunables().inflight_budget(), Some(48 * KB));
+        assert!(!store.insert(3, vec![3; KB]).await.unwrap());
+
+        // A later call never raises the budget.
+        assert_eq!(store.reduce_memory(8 * KB), 16 * KB);
+        assert_eq!(store.tunables().inflight_budget(), Some(48 * KB));
+
+        store.inner.metrics.inflight_bytes.set(0);
+        assert!(store.insert(3, vec![3; KB]).await.unwrap());
+        store.tunables().set_inflight_budget(None);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_entry_padding_packed() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            recovery_mode: RecoveryMode::Verify,
+            entry_padding: EntryPadding::Packed,
+            ..test_config("test_entry_padding_packed", tempdir.path())
+        };
+
+        const ENTRIES: u64 = 256;
+
+        // Entries of various sizes, whose headers and keys may cross blocks.
+        let value = |i: u64| vec![i as u8; 64 + i as usize % 200];
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..ENTRIES {
+            assert!(store.insert(i, value(i)).await.unwrap());
+        }
+        store.close().await.unwrap();
+
+        // Far fewer bytes than one aligned block per entry.
+        let stats = store.stats();
+        assert!(stats.physical_bytes < ENTRIES as usize * KB, "{:?}", stats);
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), value(i));
+        }
+        drop(store);
+
+        // Entries are recovered across padded block tails.
+        let store = TestStore::open(config).await.unwrap();
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), value(i));
+        }
+        store.close().await.unwrap();
+    }
+
+    #[derive(Debug, Default)]
+    struct SoftQuotaRecorder(Mutex<Vec<SoftQuotaEvent>>);
+
+    impl SoftQuotaListener for SoftQuotaRecorder {
+        fn on_cross(&self, event: SoftQuotaEvent) {
+            self.0.lock().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_soft_quota() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(SoftQuotaRecorder::default());
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            soft_quota: Some(SoftQuotaConfig {
+                levels: vec![0.5],
+                listener: recorder.clone(),
+            }),
+            ..test_config("test_soft_quota", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        // Use more than half of the 16 regions.
+        for i in 0..150u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        for _ in 0..500 {
+            if !recorder.0.lock().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let events = recorder.0.lock().drain(..).collect_vec();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].level, events[0].rising), (0.5, true));
+        assert!(events[0].occupancy >= 0.5);
+        assert!(events[0].stats.physical_bytes > 0);
+        store.close().await.unwrap();
+        drop(store);
+
+        // The level reached by the recovered regions is notified on open.
+        let store = TestStore::open(config).await.unwrap();
+        let events = recorder.0.lock().drain(..).collect_vec();
+        assert_eq!(events.len(), 1);
+        assert!(events[0].rising);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reinsert_dropped_hot() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            reinsertions: vec![Arc::new(JudgeRecorder::default())],
+            ..test_config("test_reinsert_dropped_hot", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        store.tunables().set_hot_drop_report(2);
+
+        for i in 0..16u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        // Look up some entries once they are flushed to region 0.
+        for i in 0..4u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+
+        // Reclaim region 0, the reinsertion policy drops all entries.
+        for i in 16..400u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        let metric = &store.inner.metrics.op_count_reinsert_dropped_hot;
+        for _ in 0..500 {
+            if metric.get() >= 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(metric.get(), 4);
+
+        store.close().await.unwrap();
+    }
+
+    #[test]
+    fn test_clean_region_watermarks_counts() {
+        let watermarks = CleanRegionWatermarks { low: 0.25, high: 0.5 };
+        assert_eq!(watermarks.counts(16), (4, 8));
+        assert_eq!(watermarks.counts(10), (3, 5));
+        assert_eq!(CleanRegionWatermarks { low: 0.0, high: 0.0 }.counts(16), (1, 1));
+        assert_eq!(CleanRegionWatermarks { low: 0.5, high: 0.25 }.counts(16), (8, 8));
+        assert_eq!(CleanRegionWatermarks { low: 2.0, high: 2.0 }.counts(16), (16, 16));
+    }
+
+    #[tokio::test]
+    async fn test_clean_region_watermarks() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            reclaimers: 0,
+            clean_region_watermarks: Some(CleanRegionWatermarks { low: 0.25, high: 0.5 }),
+            ..test_config("test_clean_region_watermarks", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        let clean_regions = store.inner.region_manager.clean_regions(0);
+        let stats = store.stats();
+        assert_eq!(stats.clean_regions, 16);
+        assert!(!stats.reclaiming);
+
+        let mut taken = (0..12).map(|_| clean_regions.try_acquire().unwrap()).collect_vec();
+        assert!(!store.should_reclaim(0));
+
+        // Reclamation starts below the low watermark, and continues until the high watermark is reached.
+        taken.push(clean_regions.try_acquire().unwrap());
+        assert!(store.should_reclaim(0));
+        for _ in 0..3 {
+            clean_regions.release(taken.pop().unwrap());
+        }
+        assert!(store.should_reclaim(0));
+        assert!(store.stats().reclaiming);
+        for _ in 0..2 {
+            clean_regions.release(taken.pop().unwrap());
+        }
+        assert!(!store.should_reclaim(0));
+        assert_eq!(store.stats().clean_regions, 8);
+        assert!(!store.stats().reclaiming);
+
+        // It does not restart until clean regions drop below the low watermark again.
+        taken.extend((0..4).map(|_| clean_regions.try_acquire().unwrap()));
+        assert!(!store.should_reclaim(0));
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_region_data_checksum() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            recovery_mode: RecoveryMode::Verify,
+            ..test_config("test_region_data_checksum", tempdir.path())
+        };
+
+        const ENTRIES: u64 = 40;
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..ENTRIES {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // The data of a sealed region is verified as a whole.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let Index::Region { view } = store.inner.catalog.lookup(&0).unwrap().index().clone() else {
+            unreachable!()
+        };
+        let first = *view.id();
+        let region = store.inner.region_manager.region(&first).clone();
+        let iter = RegionEntryIter::<u64, Vec<u8>, FsDevice>::open(region)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(iter.verify_data().await.unwrap(), Some(true));
+        let (corrupted, _) = store
+            .inner
+            .catalog
+            .region_items(&first)
+            .into_iter()
+            .find(|(_, item)| matches!(item.index(), Index::Region { view } if *view.offset() == 4 * KB as u32))
+            .unwrap();
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Corrupt the value of the first entry in the first region.
+        let device = FsDevice::open(config.device_config.clone()).await.unwrap();
+        let buf = device.io_buffer(4 * KB, 4 * KB);
+        let (res, mut buf) = device.read(buf, .., first, 4 * KB).await;
+        res.unwrap();
+        buf[2 * KB] ^= 1;
+        let (res, _) = device.write(buf, .., first, 4 * KB).await;
+        res.unwrap();
+
+        // A corrupted region falls back to verifying entries one by one, only the corrupted entry is dropped.
+        let store = TestStore::open(config).await.unwrap();
+        let region = store.inner.region_manager.region(&first).clone();
+        let iter = RegionEntryIter::<u64, Vec<u8>, FsDevice>::open(region)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(iter.verify_data().await.unwrap(), Some(false));
+        for i in 0..ENTRIES {
+            if i == corrupted {
+                assert!(store.lookup(&i).await.unwrap().is_none());
+            } else {
+                assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+            }
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bulk_load() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            ..test_config("test_bulk_load", tempdir.path())
+        };
+
+        const ENTRIES: u64 = 40;
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let entries = (0..ENTRIES).map(|i| (i, vec![i as u8; 3 * KB]));
+        assert_eq!(
+            store.bulk_load(futures::stream::iter(entries)).await.unwrap(),
+            ENTRIES as usize
+        );
+        // Entries are written to the device directly, bypassing the flushers.
+        for i in 0..ENTRIES {
+            assert!(matches!(
+                store.inner.catalog.lookup(&i).unwrap().index(),
+                Index::Region { .. }
+            ));
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+        let Index::Region { view } = store.inner.catalog.lookup(&0).unwrap().index().clone() else {
+            unreachable!()
+        };
+        let first = *view.id();
+
+        // Keys must be in ascending order.
+        let entries = [(100, vec![0; KB]), (99, vec![0; KB])];
+        let e = store.bulk_load(futures::stream::iter(entries)).await.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Validation(_)));
+        assert!(store.lookup(&100).await.unwrap().is_some());
+        assert!(store.lookup(&99).await.unwrap().is_none());
+
+        // An entry larger than a whole region is rejected, the entries before it are kept.
+        let entries = [(200, vec![0; KB]), (201, vec![1; 128 * KB]), (202, vec![2; KB])];
+        let e = store.bulk_load(futures::stream::iter(entries)).await.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::Validation(_)));
+        assert_eq!(store.lookup(&200).await.unwrap().unwrap(), vec![0; KB]);
+        assert!(store.lookup(&201).await.unwrap().is_none());
+        assert!(store.lookup(&202).await.unwrap().is_none());
+        store.close().await.unwrap();
+        drop(store);
+
+        // The loaded regions are sealed and recovered from the footer.
+        let store = TestStore::open(config).await.unwrap();
+        let region = store.inner.region_manager.region(&first).clone();
+        let iter = RegionEntryIter::<u64, Vec<u8>, FsDevice>::open(region)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(iter.is_sealed());
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_region_footer_recovery() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: MB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            write_barrier: true,
+            ..test_config("test_region_footer_recovery", tempdir.path())
+        };
+
+        const ENTRIES: u64 = 40;
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..ENTRIES {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Filled regions are synced and sealed, and recovered from the footer.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let Index::Region { view } = store.inner.catalog.lookup(&0).unwrap().index().clone() else {
+            unreachable!()
+        };
+        let first = *view.id();
+        let region = store.inner.region_manager.region(&first).clone();
+        let iter = RegionEntryIter::<u64, Vec<u8>, FsDevice>::open(region)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(iter.is_sealed());
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+        let stats = store.stats();
+        store.close().await.unwrap();
+        drop(store);
+
+        // Recovery falls back to scanning entries without a valid footer.
+        let device = FsDevice::open(config.device_config.clone()).await.unwrap();
+        let mut buf = device.io_buffer(4 * KB, 4 * KB);
+        (&mut buf[..]).put_slice(&[0; 4 * KB]);
+        let (res, _) = device.write(buf, .., first, 60 * KB).await;
+        res.unwrap();
+
+        let store = TestStore::open(config).await.unwrap();
+        let region = store.inner.region_manager.region(&first).clone();
+        let iter = RegionEntryIter::<u64, Vec<u8>, FsDevice>::open(region)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!iter.is_sealed());
+        for i in 0..ENTRIES {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 3 * KB]);
+        }
+        assert_eq!(store.stats(), stats);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unknown_region_version() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = test_config("test_unknown_region_version", tempdir.path());
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.insert(1, vec![1; 3 * KB]).await.unwrap());
+        store.close().await.unwrap();
+        let Index::Region { view } = store.inner.catalog.lookup(&1).unwrap().index().clone() else {
+            panic!("entry is not flushed");
+        };
+        drop(store);
+
+        // Rewrite the region header as if written by a newer release.
+        let device = FsDevice::open(config.device_config.clone()).await.unwrap();
+        let buf = device.io_buffer(4 * KB, 4 * KB);
+        let (res, mut buf) = device.read(buf, .., *view.id(), 0).await;
+        res.unwrap();
+        (&mut buf[8..16]).put_u64(Version::latest().to_u64() + 1);
+        let (res, _) = device.write(buf, .., *view.id(), 0).await;
+        res.unwrap();
+
+        // The region is neither taken as clean nor recovered.
+        assert!(TestStore::open(config).await.is_err());
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(16))]
+
+        #[test]
+        fn test_entry_round_trip(
+            entries in arb_entries(any::<u64>(), arb_bytes(16 * 1024), 64),
+            compression in arb_compression(),
+            recovery_mode in prop_oneof![Just(RecoveryMode::Trust), Just(RecoveryMode::Verify)],
+            entry_padding in prop_oneof![Just(EntryPadding::Aligned), Just(EntryPadding::Packed)],
+        ) {
+
+            let runtime = tokio::runtime::Runtime::new().unwrap();
+            runtime.block_on(async move {
+                let tempdir = tempfile::tempdir().unwrap();
+
+                let config = TestStoreConfig { reclaimers: 0, compression, recovery_mode, entry_padding, ..test_config("test_entry_round_trip", tempdir.path()) };
+
+                let store = TestStore::open(config.clone()).await.unwrap();
+                for (key, value) in entries.iter() {
+                    prop_assert!(store.insert(*key, value.clone()).await.unwrap());
+                }
+                store.close().await.unwrap();
+                drop(store);
+
+                // Entries are decoded from the device after recovery.
+                let store = TestStore::open(config).await.unwrap();
+                prop_assert_eq!(
+                    store.stats().logical_bytes,
+                    entries.iter().map(|(_, value)| 8 + value.len()).sum::<usize>()
+                );
+                for (key, value) in entries.iter() {
+                    prop_assert_eq!(store.lookup(key).await.unwrap(), Some(value.clone()));
+                }
+                store.close().await.unwrap();
+                Ok(())
+            })?;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reopen_compatibility() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let mut config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: 64 * MB,
+                file_capacity: MB,
+                ..test_device_config(tempdir.path())
+            },
+            reclaimers: 0,
+            ..test_config("test_reopen_compatibility", tempdir.path())
+        };
+
+        // Runtime parameters may change across restarts, and all recoverable data is kept.
+        //
+        // Every reopen writes to new regions, the device is large enough to not evict any of them.
+        #[expect(clippy::type_complexity)]
+        let compatible: Vec<(&str, Box<dyn Fn(&mut TestStoreConfig)>)> = vec![
+            ("none", Box::new(|_| {})),
+            ("flushers", Box::new(|config| config.flushers = 2)),
+            ("reclaimers", Box::new(|config| config.reclaimers = 1)),
+            ("compression", Box::new(|config| config.compression = Compression::Zstd)),
+            (
+                "entry_padding",
+                Box::new(|config| config.entry_padding = EntryPadding::Packed),
+            ),
+            ("catalog_bits", Box::new(|config| config.catalog_bits = 4)),
+            (
+                "admissions",
+                Box::new(|config| config.admissions = vec![Arc::new(JudgeRecorder::default())]),
+            ),
+            (
+                "recovery_mode",
+                Box::new(|config| config.recovery_mode = RecoveryMode::Verify),
+            ),
+            (
+                "flush_routing",
+                Box::new(|config| config.flush_routing = FlushRouting::KeyHash),
+            ),
+            ("hash_seed", Box::new(|config| config.hash_seed = Some(42))),
+            ("max_value_len", Box::new(|config| config.max_value_len = Some(16 * KB))),
+            (
+                "device.capacity",
+                Box::new(|config| config.device_config.capacity = 128 * MB),
+            ),
+            (
+                "device.io_size",
+                Box::new(|config| config.device_config.io_size = 16 * KB),
+            ),
+            (
+                "device.read_io_policy",
+                Box::new(|config| config.device_config.read_io_policy = ReadIoPolicy::Adaptive { max: MB }),
+            ),
+        ];
+
+        let mut entries = vec![];
+        for (field, change) in compatible {
+            change(&mut config);
+            let store = TestStore::open(config.clone()).await.unwrap();
+            for (key, value) in entries.iter() {
+                assert_eq!(
+                    store.lookup(key).await.unwrap().as_ref(),
+                    Some(value),
+                    "change: {}",
+                    field
+                );
+            }
+            for _ in 0..4 {
+                let key = entries.len() as u64;
+                let value = vec![key as u8; 4 * KB + key as usize * 100];
+                assert!(store.insert(key, value.clone()).await.unwrap());
+                entries.push((key, value));
+            }
+            store.close().await.unwrap();
+        }
+
+        // The region geometry is pinned by the device, changing it requires a format migration.
+        #[expect(clippy::type_complexity)]
+        let incompatible: Vec<(&str, Box<dyn Fn(&mut TestStoreConfig)>)> = vec![
+            (
+                "device.file_capacity",
+                Box::new(|config| config.device_config.file_capacity = 2 * MB),
+            ),
+            ("device.align", Box::new(|config| config.device_config.align = 8 * KB)),
+        ];
+        for (field, change) in incompatible {
+            let mut config = config.clone();
+            change(&mut config);
+            let e = TestStore::open(config).await.unwrap_err();
+            assert!(
+                matches!(e.kind(), ErrorKind::Device(e) if matches!(e.kind(), DeviceErrorKind::Incompatible(_))),
+                "change: {}, error: {}",
+                field,
+                e
+            );
+        }
+
+        let store = TestStore::open(config).await.unwrap();
+        for (key, value) in entries.iter() {
+            assert_eq!(store.lookup(key).await.unwrap().as_ref(), Some(value));
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_codecs() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            compression: Compression::Zstd,
+            codecs: vec![Arc::new(XorCodec(0x5a)), Arc::new(FramingCodec)],
+            ..test_config("test_codecs", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..4 {
+            assert!(store.insert(i, vec![i as u8; 64 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..4 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 64 * KB]);
+        }
+
+        // The value is compressed, then encoded with the codecs in order.
+        let item = store.inner.catalog.lookup(&0).unwrap();
+        let Index::Region { view } = item.index() else {
+            panic!("entry must be on the device")
+        };
+        let region = store.inner.region_manager.region(view.id());
+        let buf = region.load(view.clone()).await.unwrap().unwrap();
+        let header = EntryHeader::read(&buf).unwrap();
+        assert_eq!(header.codecs.iter().collect_vec(), vec![1, 2]);
+        let start = EntryHeader::serialized_len();
+        let value = &buf[start..start + header.value_len as usize];
+        assert_eq!(&value[..4], &(header.value_len - 4).to_be_bytes());
+        drop(buf);
+        drop(item);
+
+        // The raw value is decoded, so it can be inserted into a store with other codecs.
+        let raw = store.lookup_raw(&0).await.unwrap().unwrap();
+        assert_eq!(raw.compression, Compression::Zstd);
+        assert_eq!(
+            decode_value::<Vec<u8>>(&raw.value, raw.compression, raw.uncompressed_len).unwrap(),
+            vec![0; 64 * KB]
+        );
+        store.close().await.unwrap();
+        drop(store);
+
+        // Entries are decoded with the codecs recorded in their headers, regardless of the pipeline order.
+        let mut reordered = config.clone();
+        reordered.codecs = vec![Arc::new(FramingCodec), Arc::new(XorCodec(0x5a))];
+        let store = TestStore::open(reordered).await.unwrap();
+        for i in 0..4 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 64 * KB]);
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Entries with unregistered codecs fail to read.
+        let mut plain = config;
+        plain.codecs = vec![];
+        let store = TestStore::open(plain).await.unwrap();
+        assert!(store.lookup(&1).await.is_err());
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_compression_stats() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            compression: Compression::Zstd,
+            ..test_config("test_compression_stats", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let stats = store.stats();
+        assert_eq!(
+            (stats.logical_bytes, stats.physical_bytes, stats.catalog_bytes),
+            (0, 0, 0)
+        );
+
+        for i in 0..4 {
+            assert!(store.insert(i, vec![i as u8; 64 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+
+        let stats = store.stats();
+        assert_eq!(stats.logical_bytes, 4 * (8 + 64 * KB));
+        // Each highly compressible entry fits in a single aligned block.
+        assert_eq!(stats.physical_bytes, 4 * 4 * KB);
+        assert_eq!(
+            store.inner.metrics.op_bytes_flush_logical.get(),
+            stats.logical_bytes as u64
+        );
+
+        drop(store);
+
+        // Entries on device are recovered, the operation counters start over.
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(
+            store.stats(),
+            StoreStats {
+                ops: OpStats::default(),
+                ..stats
+            }
+        );
+        for i in 0..4 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 64 * KB]);
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_overwrite_in_place() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            recovery_mode: RecoveryMode::Verify,
+            overwrite_in_place: true,
+            ..test_config("test_overwrite_in_place", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        for i in 0..4u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        for i in 0..4u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        let stats = store.stats();
+
+        // Values of the same size are overwritten in place.
+        for i in 0..4u64 {
+            assert!(store.insert(i, vec![i as u8 + 10; 3 * KB]).await.unwrap());
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8 + 10; 3 * KB]);
+        }
+        assert_eq!(store.inner.metrics.op_count_insert_overwritten.get(), 4);
+        assert_eq!(
+            store.stats(),
+            StoreStats {
+                ops: OpStats {
+                    lookups: 4,
+                    hits: 4,
+                    inserts: 8,
+                    admitted: 8,
+                },
+                ..stats
+            }
+        );
+
+        // A value of another size is appended.
+        assert!(store.insert(0, vec![20; 6 * KB]).await.unwrap());
+        assert_eq!(store.inner.metrics.op_count_insert_overwritten.get(), 4);
+        while !matches!(store.inner.catalog.lookup(&0).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(store.stats().physical_bytes > stats.physical_bytes);
+
+        // An entry of the key indexed after an overwrite is reserved supersedes the overwrite.
+        let sequence = *store.inner.catalog.lookup(&1).unwrap().sequence();
+        let reserved = store
+            .inner
+            .catalog
+            .reserve_overwrite(&1, sequence, || store.inner.sequence.fetch_add(1, Ordering::Relaxed))
+            .unwrap();
+        assert!(store.insert(1, vec![21; 6 * KB]).await.unwrap());
+        assert!(*store.inner.catalog.lookup(&1).unwrap().sequence() > reserved);
+        assert!(!store.inner.catalog.overwrite(&1, sequence, reserved, 0, None));
+        assert_eq!(
+            store.inner.catalog.reserve_overwrite(&1, sequence, || unreachable!()),
+            None
+        );
+        while !matches!(store.inner.catalog.lookup(&1).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        store.close().await.unwrap();
+        drop(store);
+
+        // The overwritten entries are verified one by one and recovered with their latest values.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.lookup(&0).await.unwrap().unwrap(), vec![20; 6 * KB]);
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![21; 6 * KB]);
+        for i in 2..4u64 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8 + 10; 3 * KB]);
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // So are they with the former sequences from the footer directory, the appended entry is still the newest.
+        let config = TestStoreConfig {
+            recovery_mode: RecoveryMode::Trust,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(store.lookup(&0).await.unwrap().unwrap(), vec![20; 6 * KB]);
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![21; 6 * KB]);
+        for i in 2..4u64 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8 + 10; 3 * KB]);
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_mmap_reads() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: 8 * MB,
+                file_capacity: MB,
+                read_mode: FsReadMode::Mmap {
+                    advice: MmapAdvice::Random,
+                },
+                ..test_device_config(tempdir.path())
+            },
+            reclaimers: 0,
+            ..test_config("test_mmap_reads", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        // Fill more than 2 regions.
+        for i in 0..40u64 {
+            assert!(store.insert(i, vec![i as u8; 60 * KB]).await.unwrap());
+        }
+        for i in 0..40u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+
+        let is_mapped = |store: &TestStore, key: u64| {
+            let item = store.inner.catalog.lookup(&key).unwrap();
+            let Index::Region { view } = item.index() else {
+                panic!("entry {key} is not in a region");
+            };
+            let region = store.inner.region_manager.region(view.id()).clone();
+            let view = view.clone();
+            async move { region.load(view).await.unwrap().unwrap().is_mapped() }
+        };
+
+        // Entries of the sealed region are read from the mapping, the others are read from the device.
+        assert!(is_mapped(&store, 0).await);
+        assert!(!is_mapped(&store, 39).await);
+        for i in 0..40u64 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 60 * KB]);
+        }
+
+        store.close().await.unwrap();
+        drop(store);
+
+        // Recovered regions are sealed.
+        let store = TestStore::open(config).await.unwrap();
+        assert!(is_mapped(&store, 39).await);
+        for i in 0..40u64 {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 60 * KB]);
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_remove_batch() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            catalog_bits: 2,
+            reclaimers: 0,
+            ..test_config("test_remove_batch", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        for i in 0..16u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        for i in 0..16u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+
+        let keys = (0..16u64).step_by(2).chain([100]).collect_vec();
+        assert_eq!(store.remove_batch(&keys).unwrap(), 8);
+        for i in 0..16u64 {
+            assert_eq!(store.lookup(&i).await.unwrap().is_some(), i % 2 == 1);
+        }
+
+        // Removed keys are dropped from the region indices, the reclaimer will not take them.
+        let mut remaining = (0..store.inner.device.regions() as RegionId)
+            .flat_map(|region| store.inner.catalog.region_items(&region))
+            .map(|(key, _)| key)
+            .collect_vec();
+        remaining.sort();
+        assert_eq!(remaining, (1..16u64).step_by(2).collect_vec());
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_lookup_range() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            catalog_bits: 2,
+            reclaimers: 0,
+            ..test_config("test_lookup_range", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        for i in (0..16u64).rev() {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        for i in 0..16u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+        assert!(store.remove(&5).unwrap());
+
+        assert_eq!(
+            store.lookup_range(4..8).await.unwrap(),
+            [4u64, 6, 7].map(|i| (i, vec![i as u8; 3 * KB])).to_vec()
+        );
+        assert_eq!(store.range_keys(14..), vec![14, 15]);
+        assert!(store.lookup_range(16..).await.unwrap().is_empty());
+        store.close().await.unwrap();
+        drop(store);
+
+        // Recovered entries are ordered as well.
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(store.range_keys(8..12), vec![8, 9, 10, 11]);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stats_history() {
+        const KB: usize = 1024;
+        const MINUTE: u64 = 60 * 1000;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let now = SystemClock.now_millis();
+        let start = now - now % MINUTE;
+        let clock = MockClock::new(start);
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: 256 * KB,
+                file_capacity: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            clock: Some(Arc::new(clock.clone())),
+            ..test_config("test_stats_history", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(0, vec![0; 3 * KB]).await.unwrap());
+        assert!(store.lookup(&0).await.unwrap().is_some());
+        assert!(store.lookup(&42).await.unwrap().is_none());
+
+        // Write more than the capacity two minutes later, so that regions are reclaimed.
+        clock.advance(Duration::from_secs(120));
+        for i in 1..=100u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        while store.stats_history().last().unwrap().reclaimed_bytes == 0 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        let history = store.stats_history();
+        assert_eq!(history.len(), 2, "{:?}", history);
+        assert_eq!(
+            history[0],
+            StatsRollup {
+                minute: start,
+                hits: 1,
+                misses: 1,
+                inserted_bytes: 8 + 3 * KB as u64,
+                reclaimed_bytes: 0,
+            }
+        );
+        assert_eq!(history[1].minute, start + 2 * MINUTE);
+        assert_eq!(history[1].inserted_bytes, 100 * (8 + 3 * KB as u64));
+        assert_eq!(history[1].reclaimed_bytes % (64 * KB as u64), 0);
+
+        // Rollups older than the retained minutes are dropped.
+        clock.advance(Duration::from_secs(STATS_HISTORY_MINUTES as u64 * 60));
+        let later = start + (2 + STATS_HISTORY_MINUTES as u64) * MINUTE;
+        assert!(store.stats_history().iter().all(|rollup| rollup.minute == later));
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_soft_remove() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let clock = MockClock::new(SystemClock.now_millis());
+
+        let config = TestStoreConfig {
+            flushers: 2,
+            clock: Some(Arc::new(clock.clone())),
+            soft_remove_grace: Some(Duration::from_secs(10)),
+            ..test_config("test_soft_remove", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        assert!(store.job(TaskKind::SoftRemovePurger).is_some());
+
+        for i in 0..4u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        // Soft remove entries both inflight and flushed.
+        assert!(store.remove_soft(&0).unwrap());
+        for i in 1..4u64 {
+            while !matches!(store.inner.catalog.lookup(&i).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+        assert!(store.remove_soft(&1).unwrap());
+        assert!(!store.remove_soft(&1).unwrap());
+        assert!(!store.remove_soft(&42).unwrap());
+
+        for i in 0..2u64 {
+            assert!(!store.exists(&i).unwrap());
+            assert_eq!(store.lookup(&i).await.unwrap(), None);
+        }
+        assert_eq!(store.range_keys(..), vec![2, 3]);
+
+        // Entries of the same flusher are flushed in order, so the soft removed inflight entry is flushed by now, and
+        // is resurrected from the device.
+        assert!(store.resurrect(&0).unwrap());
+        assert!(store.resurrect(&1).unwrap());
+        assert!(!store.resurrect(&1).unwrap());
+        for i in 0..2u64 {
+            assert_eq!(store.lookup(&i).await.unwrap(), Some(vec![i as u8; 3 * KB]));
+        }
+
+        // Inserting the key again drops the soft removed entry.
+        assert!(store.remove_soft(&2).unwrap());
+        assert!(store.insert(2, vec![42; 3 * KB]).await.unwrap());
+        assert!(!store.resurrect(&2).unwrap());
+        assert_eq!(store.lookup(&2).await.unwrap(), Some(vec![42; 3 * KB]));
+
+        // Entries can not be resurrected after the grace period.
+        assert!(store.remove_soft(&3).unwrap());
+        clock.advance(Duration::from_secs(11));
+        assert!(!store.resurrect(&3).unwrap());
+        assert_eq!(store.lookup(&3).await.unwrap(), None);
+        assert_eq!(store.inner.catalog.purge_soft_removed(clock.now_millis() - 10_000), 1);
+        assert_eq!(store.inner.catalog.soft_removed(), 0);
+
+        // Hard removal drops soft removed entries as well.
+        assert!(store.remove_soft(&0).unwrap());
+        assert!(store.remove(&0).unwrap());
+        assert!(!store.resurrect(&0).unwrap());
+
+        store.close().await.unwrap();
+    }
+
+    /// Admits while `admit` is set, and judges ahead of time while `peekable` is set.
+    #[derive(Debug, Default)]
+    struct ToggleAdmission {
+        admit: AtomicBool,
+        peekable: AtomicBool,
+    }
+
+    impl AdmissionPolicy for ToggleAdmission {
+        type Key = u64;
+        type Value = Vec<u8>;
+
+        fn judge(&self, _key: &u64, _weight: usize) -> bool {
+            self.admit.load(Ordering::Relaxed)
+        }
+
+        fn peek(&self, key: &u64, weight: usize) -> Option<bool> {
+            self.peekable.load(Ordering::Relaxed).then(|| self.judge(key, weight))
+        }
+
+        fn on_insert(&self, _key: &u64, _weight: usize, _judge: bool) {}
+
+        fn on_drop(&self, _key: &u64, _weight: usize, _judge: bool) {}
+    }
+
+    #[tokio::test]
+    async fn test_would_admit() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let recorder = Arc::new(JudgeRecorder::default());
+        let toggle = Arc::new(ToggleAdmission::default());
+
+        let config = TestStoreConfig {
+            admissions: vec![recorder.clone(), toggle.clone()],
+            flushers: 2,
+            max_value_len: Some(4 * KB),
+            ..test_config("test_would_admit", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        toggle.peekable.store(true, Ordering::Relaxed);
+        toggle.admit.store(true, Ordering::Relaxed);
+        assert_eq!(store.would_admit(&1, 8 + KB), AdmissionVerdict::Admit);
+        toggle.admit.store(false, Ordering::Relaxed);
+        assert_eq!(
+            store.would_admit(&1, 8 + KB),
+            AdmissionVerdict::Reject { policies: vec![1] }
+        );
+        toggle.peekable.store(false, Ordering::Relaxed);
+        assert_eq!(
+            store.would_admit(&1, 8 + KB),
+            AdmissionVerdict::Unknown { policies: vec![1] }
+        );
+        assert!(store.would_admit(&1, 8 + KB).is_admit());
+        assert!(matches!(
+            store.would_admit(&1, 8 + 8 * KB),
+            AdmissionVerdict::Invalid(_)
+        ));
+
+        // Dry runs do not judge, and nothing is inserted.
+        assert!(recorder.dump().is_empty());
+        assert!(!store.exists(&1).unwrap());
+
+        toggle.admit.store(true, Ordering::Relaxed);
+        assert!(store.insert(1, vec![1; KB]).await.unwrap());
+        assert_eq!(recorder.dump().len(), 1);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_clear_inflight() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            clear_active_regions: true,
+            ..test_config("test_clear_inflight", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        async fn flushed_region(store: &TestStore, key: u64) -> RegionId {
+            loop {
+                if let Index::Region { view } = store.inner.catalog.lookup(&key).unwrap().index() {
+                    return *view.id();
+                }
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+
+        assert!(store.insert(0, vec![0; 3 * KB]).await.unwrap());
+        let active = flushed_region(&store, 0).await;
+
+        // Entries still queued or buffered when the store is cleared are never indexed.
+        for i in 1..64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        store.clear().unwrap();
+        assert!(store.insert(100, vec![100; 3 * KB]).await.unwrap());
+
+        // The active region is retired, the entries inserted after the clear are written to a new region.
+        assert_ne!(flushed_region(&store, 100).await, active);
+        for i in 0..64 {
+            assert!(store.lookup(&i).await.unwrap().is_none());
+        }
+        assert_eq!(store.inner.catalog.entries(), 1);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_bump_generation() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let generation_dir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            generation_path: Some(generation_dir.path().join("generation")),
+            ..test_config("test_bump_generation", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.generation(), 0);
+        for i in 0..8 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Entries of the previous generation are invisible at once, both flushed and inflight ones.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.insert(8, vec![8; 3 * KB]).await.unwrap());
+        assert_eq!(store.bump_generation().unwrap(), 1);
+        for i in 0..9 {
+            assert!(store.lookup(&i).await.unwrap().is_none());
+        }
+        assert!(store.insert(100, vec![100; 3 * KB]).await.unwrap());
+        assert_eq!(store.lookup(&100).await.unwrap(), Some(vec![100; 3 * KB]));
+        store.close().await.unwrap();
+        drop(store);
+
+        // The generation is persisted, entries of the previous generation are not recovered.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.generation(), 1);
+        assert_eq!(store.range_keys(..), vec![100]);
+        assert_eq!(store.bump_generation().unwrap(), 2);
+        store.close().await.unwrap();
+        drop(store);
+
+        // Entries written after a restart without any entry of the current generation are visible.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.generation(), 2);
+        assert!(store.range_keys(..).is_empty());
+        assert!(store.insert(200, vec![200; 3 * KB]).await.unwrap());
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config).await.unwrap();
+        assert_eq!(store.range_keys(..), vec![200]);
+        assert_eq!(store.lookup(&200).await.unwrap(), Some(vec![200; 3 * KB]));
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_recovery() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            ..test_config("test_interleaved_recovery", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for i in 0..12 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        let config = TestStoreConfig {
+            interleaved_recovery: true,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        // Lookups are served during the recovery, either a hit or a miss.
+        for i in 0..12 {
+            if let Some(value) = store.lookup(&i).await.unwrap() {
+                assert_eq!(value, vec![i as u8; 3 * KB]);
+            }
+        }
+        // A removed entry would come back when its region is recovered.
+        if store.is_recovering() {
+            assert!(matches!(store.remove(&0).unwrap_err().kind(), ErrorKind::Recovering));
+        }
+        store.wait_recovered().await.unwrap();
+        assert!(!store.is_recovering());
+        assert!(store.recovered_regions().iter().all(|recovered| *recovered));
+        for i in 0..12 {
+            assert_eq!(store.lookup(&i).await.unwrap(), Some(vec![i as u8; 3 * KB]));
+        }
+        assert!(store.insert(100, vec![100; 3 * KB]).await.unwrap());
+        assert_eq!(store.lookup(&100).await.unwrap(), Some(vec![100; 3 * KB]));
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_recovery_overwritten() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                capacity: 64 * MB,
+                ..test_device_config(tempdir.path())
+            },
+            ..test_config("test_interleaved_recovery_overwritten", tempdir.path())
+        };
+
+        // Each round fills more than a region, the newer copies are in other regions than the older ones.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        for round in 0..2u8 {
+            for i in 0..40 {
+                assert!(store.insert(i, vec![round; 128 * KB]).await.unwrap());
+            }
+        }
+        store.close().await.unwrap();
+        drop(store);
+
+        // Regions are recovered one by one in order, the regions of the newer copies are recovered later.
+        let config = TestStoreConfig {
+            interleaved_recovery: true,
+            recover_concurrency: 1,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        while store.is_recovering() {
+            for i in 0..40 {
+                if let Some(value) = store.lookup(&i).await.unwrap() {
+                    assert_eq!(value, vec![1; 128 * KB]);
+                }
+                if let Some(raw) = store.lookup_raw(&i).await.unwrap() {
+                    assert_eq!(raw.value, vec![1; 128 * KB]);
+                }
+                if let Some(location) = store.locate(&i)
+                    && let Some(value) = store.read(location).await.unwrap()
+                {
+                    assert_eq!(value, vec![1; 128 * KB]);
+                }
+            }
+            let mut iter = store.iter_since(0);
+            loop {
+                match iter.next().await {
+                    Ok(Some((_, value, _))) => assert_eq!(value, vec![1; 128 * KB]),
+                    Ok(None) => break,
+                    Err(e) => {
+                        assert!(matches!(e.kind(), ErrorKind::Recovering));
+                        break;
+                    }
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+        store.wait_recovered().await.unwrap();
+        for i in 0..40 {
+            assert_eq!(store.lookup(&i).await.unwrap(), Some(vec![1; 128 * KB]));
+        }
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invariant_policy() {
+        const KB: usize = 1024;
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            invariant_policy: InvariantPolicy::DropRegion,
+            ..test_config("test_invariant_policy", tempdir.path())
+        };
+
+        // An entry larger than a region never fits a clean region.
+        let wait_dropped = |store: TestStore, key: u64| async move {
+            while store.lookup(&key).await.unwrap().is_some() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.insert(0, vec![0; 3 * KB]).await.unwrap());
+        assert!(store.insert(100, vec![100; 5 * MB]).await.unwrap());
+        wait_dropped(store.clone(), 100).await;
+        // The flusher keeps running.
+        assert!(store.insert(1, vec![1; 3 * KB]).await.unwrap());
+        assert!(store.health().is_healthy());
+        assert!(!store.is_degraded());
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.range_keys(..), vec![0, 1]);
+        store.close().await.unwrap();
+        drop(store);
+
+        // So is a pre-encoded entry.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let value = vec![101; 5 * MB];
+        let mut buf = value.clone();
+        buf.put_u64(101);
+        let raw = RawEntry {
+            checksum: checksum(&buf),
+            value,
+            compression: Compression::None,
+            uncompressed_len: 5 * MB,
+        };
+        assert!(store.insert_raw(101, raw).await.unwrap());
+        wait_dropped(store.clone(), 101).await;
+        assert!(store.inner.catalog.lookup(&101).is_none());
+        store.close().await.unwrap();
+        drop(store);
+
+        let config = TestStoreConfig {
+            invariant_policy: InvariantPolicy::Degrade,
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        assert!(store.insert(100, vec![100; 5 * MB]).await.unwrap());
+        wait_dropped(store.clone(), 100).await;
+        // Writes are not admitted by a degraded store, lookups are still served.
+        assert!(store.is_degraded());
+        assert!(!store.insert(2, vec![2; 3 * KB]).await.unwrap());
+        assert_eq!(store.lookup(&0).await.unwrap(), Some(vec![0; 3 * KB]));
+        store.close().await.unwrap();
+    }
+
+    #[cfg(feature = "profile")]
+    #[derive(Debug, Default)]
+    struct CountingHooks {
+        lookups: AtomicU64,
+        hits: AtomicU64,
+        flush_bytes: AtomicU64,
+    }
+
+    #[cfg(feature = "profile")]
+    impl crate::profile::ProfileHooks for Arc<CountingHooks> {
+        fn lookup_end(&self, _token: u64, hit: bool) {
+            self.lookups.fetch_add(1, Ordering::Relaxed);
+            if hit {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn flush_write_end(&self, _token: u64, _region: RegionId, bytes: usize) {
+            self.flush_bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(feature = "profile")]
+    #[tokio::test]
+    async fn test_profile_hooks() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = test_config("test_profile_hooks", tempdir.path());
+
+        // The hooks are shared by the stores of the tests running at the same time.
+        let hooks = Arc::new(CountingHooks::default());
+        assert!(crate::profile::install(hooks.clone()));
+        assert!(!crate::profile::install(hooks.clone()));
+
+        let store = TestStore::open(config).await.unwrap();
+
+        assert!(store.insert(1, vec![1; 3 * KB]).await.unwrap());
+        while !matches!(store.inner.catalog.lookup(&1).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(hooks.flush_bytes.load(Ordering::Relaxed) >= 4 * KB as u64);
+
+        assert!(store.lookup(&1).await.unwrap().is_some());
+        assert!(store.lookup(&2).await.unwrap().is_none());
+        assert!(hooks.lookups.load(Ordering::Relaxed) >= 2);
+        assert!(hooks.hits.load(Ordering::Relaxed) >= 1);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_write_once() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            flushers: 2,
+            write_once: true,
+            ..test_config("test_write_once", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        let exists = |res: Result<bool>| match res {
+            Err(e) => match e.kind() {
+                ErrorKind::Exists { conflict } => Some(*conflict),
+                _ => None,
+            },
+            Ok(_) => None,
+        };
+
+        assert!(store.insert(1, vec![1; 3 * KB]).await.unwrap());
+        assert_eq!(exists(store.insert(1, vec![1; 3 * KB]).await), Some(false));
+        assert_eq!(exists(store.insert(1, vec![2; 3 * KB]).await), Some(true));
+
+        // The existing value is read from the device once flushed.
+        while !matches!(store.inner.catalog.lookup(&1).unwrap().index(), Index::Region { .. }) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert_eq!(exists(store.insert(1, vec![1; 3 * KB]).await), Some(false));
+        assert_eq!(exists(store.insert(1, vec![2; 2 * KB]).await), Some(true));
+        assert_eq!(store.inner.metrics.op_count_insert_exists.get(), 4);
+
+        // The checks are not counted as lookups.
+        assert_eq!(store.inner.catalog.lookup(&1).unwrap().accesses(), 0);
+        assert_eq!(store.inner.metrics.op_duration_lookup_hit.get_sample_count(), 0);
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; 3 * KB]);
+
+        // A writer of the key getting in between the check and the enqueue wins, the checked writer fails.
+        let mut writer = store.writer(2, 8 + 3 * KB);
+        store.check_write_once(&writer, || unreachable!()).await.unwrap();
+        assert!(store.insert(2, vec![2; 3 * KB]).await.unwrap());
+        assert!(writer.judge());
+        let res = store
+            .enqueue_write_once(writer, Payload::Value(vec![5; 3 * KB]), Instant::now())
+            .await;
+        assert_eq!(exists(res), Some(true));
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; 3 * KB]);
+
+        let mut writer = store.writer(1, 8 + 3 * KB);
+        writer.set_overwrite();
+        assert!(writer.finish(vec![3; 3 * KB]).await.unwrap());
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![3; 3 * KB]);
+
+        // A removed key can be written again.
+        assert!(store.remove(&1).unwrap());
+        assert!(store.insert(1, vec![4; 3 * KB]).await.unwrap());
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![4; 3 * KB]);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_trace_id() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            flushers: 2,
+            ..test_config("test_trace_id", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        let mut writer = store.writer(1, 8 + 3 * KB);
+        writer.set_trace_id(42);
+        assert!(writer.finish(vec![1; 3 * KB]).await.unwrap());
+        assert!(store.insert(2, vec![2; 3 * KB]).await.unwrap());
+        assert_eq!(store.inner.catalog.lookup(&1).unwrap().trace_id(), Some(42));
+
+        // The trace id is kept by the index of the flushed entry, to be logged on eviction.
+        for key in [1, 2] {
+            while !matches!(store.inner.catalog.lookup(&key).unwrap().index(), Index::Region { .. }) {
+                tokio::time::sleep(Duration::from_millis(1)).await;
+            }
+        }
+        assert_eq!(store.inner.catalog.lookup(&1).unwrap().trace_id(), Some(42));
+        assert_eq!(store.inner.catalog.lookup(&2).unwrap().trace_id(), None);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            flushers: 2,
+            ..test_config("test_health", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        let health = store.health();
+        assert!(health.is_healthy());
+        assert_eq!(
+            health.tasks.iter().map(|task| (task.kind, task.index)).collect_vec(),
+            vec![(TaskKind::Flusher, 0), (TaskKind::Flusher, 1), (TaskKind::Reclaimer, 0)]
+        );
+
+        // Flushers report an iteration for each flushed entry.
+        for i in 0..16u64 {
+            assert!(store.insert(i, vec![i as u8; 3 * KB]).await.unwrap());
+        }
+        while store.health().tasks[..2]
+            .iter()
+            .any(|task| task.last_success_at.is_none())
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        // Tasks stopped by closing are no longer alive, without errors.
+        store.close().await.unwrap();
+        let health = store.health();
+        assert!(!health.is_healthy());
+        assert!(health.tasks.iter().all(|task| !task.alive && task.last_error.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_jobs() {
+        const MB: usize = 1024 * 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let policy = Arc::new(RatedTicketAdmissionPolicy::new(2 * MB));
+
+        let config = TestStoreConfig {
+            admissions: vec![policy.clone()],
+            flushers: 2,
+            admission_rate_controller: Some(AdmissionRateControllerConfig {
+                policy: policy.clone(),
+                target_hit_ratio: 0.5,
+                min_rate: MB,
+                max_rate: 4 * MB,
+                step: 0.25,
+                interval: Duration::from_millis(1),
+                min_lookups: 0,
+            }),
+            ..test_config("test_scheduled_jobs", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+        let controller = store.job(TaskKind::AdmissionRateController).unwrap();
+        assert!(store.job(TaskKind::Flusher).is_none());
+        while controller.runs() < 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        assert!(store.health().is_healthy());
+
+        controller.pause();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let runs = controller.runs();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(controller.runs(), runs);
+        controller.resume();
+        while controller.runs() < runs + 2 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        store.close().await.unwrap();
+        let health = store.health();
+        let task = health
+            .tasks
+            .iter()
+            .find(|task| task.kind == TaskKind::AdmissionRateController)
+            .unwrap();
+        assert!(!task.alive && task.last_error.is_none() && task.last_success_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tunables() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                io_size: 64 * KB,
+                ..test_device_config(tempdir.path())
+            },
+            ..test_config("test_tunables", tempdir.path())
+        };
+
+        let store = TestStore::open(config).await.unwrap();
+
+        store.tunables().set_compression(Compression::Lz4);
+        assert_eq!(store.writer(1, 8 + KB).compression(), Compression::Lz4);
+
+        // Small entries stay in the flush buffer until the flush interval is set.
+        assert!(store.insert(1, vec![1; KB]).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(matches!(
+            store.inner.catalog.lookup(&1).unwrap().index(),
+            Index::Inflight { .. }
+        ));
+
+        store.tunables().set_flush_interval(Some(Duration::from_millis(10)));
+        assert!(store.insert(2, vec![2; KB]).await.unwrap());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(matches!(
+            store.inner.catalog.lookup(&1).unwrap().index(),
+            Index::Region { .. }
+        ));
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; KB]);
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; KB]);
+
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_data_age() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let clock = MockClock::new(SystemClock.now_millis());
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            max_data_age: Some(Duration::from_millis(500)),
+            clock: Some(Arc::new(clock.clone())),
+            ..test_config("test_max_data_age", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert!(store.insert(1, vec![1; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+        assert_eq!(store.lookup(&1).await.unwrap().unwrap(), vec![1; 16 * KB]);
+
+        clock.advance(Duration::from_millis(600));
+        assert!(store.lookup(&1).await.unwrap().is_none());
+        assert!(!store.exists(&1).unwrap());
+        store.close().await.unwrap();
+        drop(store);
+
+        let store = TestStore::open(config).await.unwrap();
+        assert!(!store.exists(&1).unwrap());
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_max_data_age_reinserted() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            max_data_age: Some(Duration::from_secs(10)),
+            ..test_config("test_max_data_age_reinserted", tempdir.path())
+        };
+
+        // Entries reinserted with their original insert time, as the reclaimer does.
+        let store = TestStore::open(config.clone()).await.unwrap();
+        let expired = SystemClock.now_millis() - 20_000;
+        let fresh = SystemClock.now_millis() - 1_000;
+        let mut writer = store.writer(1, 16 * KB);
+        writer.set_inserted_at(expired);
+        assert!(writer.finish(vec![1; 16 * KB]).await.unwrap());
+        let mut writer = store.writer(2, 16 * KB);
+        writer.set_inserted_at(fresh);
+        assert!(writer.finish(vec![2; 16 * KB]).await.unwrap());
+        store.close().await.unwrap();
+
+        // The region is fresh, but the entry keeps expiring by its own age.
+        assert!(store.is_entry_expired(expired));
+        assert!(!store.is_entry_expired(fresh));
+        assert!(store.lookup_raw(&1).await.unwrap().is_none());
+        assert!(store.lookup(&1).await.unwrap().is_none());
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; 16 * KB]);
+        drop(store);
+
+        let store = TestStore::open(config).await.unwrap();
+        assert!(!store.exists(&1).unwrap());
+        assert_eq!(store.inner.catalog.lookup(&2).unwrap().inserted_at(), fresh);
+        assert_eq!(store.lookup(&2).await.unwrap().unwrap(), vec![2; 16 * KB]);
+        store.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_to() {
+        const KB: usize = 1024;
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let snapshot = tempfile::tempdir().unwrap();
+
+        let config = TestStoreConfig {
+            reclaimers: 0,
+            ..test_config("test_snapshot_to", tempdir.path())
+        };
+
+        let store = TestStore::open(config.clone()).await.unwrap();
+
+        // Fill the first region and write a few entries to the second.
+        for i in 0..300 {
+            store.insert(i, vec![i as u8; 16 * KB]).await.unwrap();
+        }
+        while store
+            .inner
+            .catalog
+            .lookup(&299)
+            .is_some_and(|item| matches!(item.index(), Index::Inflight { .. }))
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        store.snapshot_to(snapshot.path()).await.unwrap();
+        store.close().await.unwrap();
+        drop(store);
+
+        // Only the sealed region is in the snapshot.
+        let catalog = CatalogSnapshot::<u64>::read(snapshot.path()).unwrap();
+        assert!(!catalog.entries.is_empty());
+        assert!(catalog.entries.iter().all(|entry| entry.region == 0));
+        let keys = catalog.entries.iter().map(|entry| entry.key).collect_vec();
+
+        let config = TestStoreConfig {
+            device_config: FsDeviceConfig {
+                dir: PathBuf::from(snapshot.path()),
+                ..config.device_config.clone()
+            },
+            ..config
+        };
+        let store = TestStore::open(config).await.unwrap();
+        for i in 0..300 {
+            match keys.contains(&i) {
+                true => assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 16 * KB]),
+                false => assert!(store.lookup(&i).await.unwrap().is_none()),
+            }
+        }
+        store.close().await.unwrap();
+    }
 }
@@ -0,0 +1,223 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use foyer_common::clock::Clock;
+use futures::Future;
+use parking_lot::Mutex;
+
+use crate::error::Result;
+
+/// Kind of a background task of a store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskKind {
+    Flusher,
+    Reclaimer,
+    AdmissionRateController,
+    SoftRemovePurger,
+    CatalogCompactor,
+}
+
+impl TaskKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TaskKind::Flusher => "flusher",
+            TaskKind::Reclaimer => "reclaimer",
+            TaskKind::AdmissionRateController => "admission_rate_controller",
+            TaskKind::SoftRemovePurger => "soft_remove_purger",
+            TaskKind::CatalogCompactor => "catalog_compactor",
+        }
+    }
+}
+
+/// Health of a background task of a store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskHealth {
+    pub kind: TaskKind,
+    /// Index among the tasks of the same kind.
+    pub index: usize,
+    /// Whether the task is running. A task stops when the store is closed, or when it fails.
+    pub alive: bool,
+    /// When the task last finished an iteration, in milliseconds since unix epoch. `None` if it never has.
+    ///
+    /// An idle task only finishes an iteration when woken up, e.g. a reclaimer when clean regions are taken.
+    pub last_success_at: Option<u64>,
+    /// The error the task failed with, or for a periodic job, the error of its last run.
+    pub last_error: Option<String>,
+}
+
+/// Health of the background tasks of a store.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StoreHealth {
+    pub tasks: Vec<TaskHealth>,
+}
+
+impl StoreHealth {
+    /// Returns `true` if all background tasks are running without errors.
+    pub fn is_healthy(&self) -> bool {
+        self.tasks.iter().all(|task| task.alive && task.last_error.is_none())
+    }
+
+    /// Returns the tasks that stopped or failed.
+    pub fn unhealthy(&self) -> impl Iterator<Item = &TaskHealth> {
+        self.tasks
+            .iter()
+            .filter(|task| !task.alive || task.last_error.is_some())
+    }
+}
+
+/// Shared between a background task and its store, the task reports its progress and failure with it.
+#[derive(Debug)]
+pub struct HealthReporter {
+    kind: TaskKind,
+    index: usize,
+    clock: Arc<dyn Clock>,
+
+    alive: AtomicBool,
+    /// 0 means never.
+    last_success_at: AtomicU64,
+    last_error: Mutex<Option<String>>,
+}
+
+impl HealthReporter {
+    pub fn new(kind: TaskKind, index: usize, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            kind,
+            index,
+            clock,
+            alive: AtomicBool::new(true),
+            last_success_at: AtomicU64::new(0),
+            last_error: Mutex::new(None),
+        }
+    }
+
+    /// Record a finished iteration of the task.
+    pub fn success(&self) {
+        self.last_success_at.store(self.clock.now_millis(), Ordering::Relaxed);
+    }
+
+    pub fn error(&self, e: impl Display) {
+        *self.last_error.lock() = Some(e.to_string());
+    }
+
+    /// Clear the reported error, e.g. after a failed run of a periodic job is followed by a successful one.
+    pub fn clear_error(&self) {
+        *self.last_error.lock() = None;
+    }
+
+    pub fn health(&self) -> TaskHealth {
+        let last_success_at = match self.last_success_at.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(at),
+        };
+        TaskHealth {
+            kind: self.kind,
+            index: self.index,
+            alive: self.alive.load(Ordering::Acquire),
+            last_success_at,
+            last_error: self.last_error.lock().clone(),
+        }
+    }
+}
+
+/// Marks the task stopped when dropped, on return or when the task panics.
+struct ExitGuard(Arc<HealthReporter>);
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.error("panicked");
+        }
+        self.0.alive.store(false, Ordering::Release);
+    }
+}
+
+/// Run the background task `f`, reporting its error and its exit to `reporter`.
+pub async fn run_reported<F>(reporter: Arc<HealthReporter>, f: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    let guard = ExitGuard(reporter);
+    let res = f.await;
+    if let Err(e) = &res {
+        guard.0.error(e);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use foyer_common::clock::MockClock;
+
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[tokio::test]
+    async fn test_health_reporter() {
+        let clock = MockClock::new(1000);
+        let reporter = Arc::new(HealthReporter::new(TaskKind::Reclaimer, 1, Arc::new(clock.clone())));
+        assert_eq!(
+            reporter.health(),
+            TaskHealth {
+                kind: TaskKind::Reclaimer,
+                index: 1,
+                alive: true,
+                last_success_at: None,
+                last_error: None,
+            }
+        );
+
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = tokio::spawn(run_reported(reporter.clone(), {
+            let reporter = reporter.clone();
+            async move {
+                reporter.success();
+                rx.await.unwrap();
+                Err(ErrorKind::Validation("broken".to_string()).into())
+            }
+        }));
+        while reporter.health().last_success_at.is_none() {
+            tokio::task::yield_now().await;
+        }
+        let health = StoreHealth {
+            tasks: vec![reporter.health()],
+        };
+        assert!(health.is_healthy());
+        assert_eq!(health.tasks[0].last_success_at, Some(1000));
+
+        tx.send(()).unwrap();
+        assert!(handle.await.unwrap().is_err());
+        let health = StoreHealth {
+            tasks: vec![reporter.health()],
+        };
+        assert!(!health.is_healthy());
+        assert_eq!(health.unhealthy().count(), 1);
+        assert!(!health.tasks[0].alive);
+        assert!(health.tasks[0].last_error.as_ref().unwrap().contains("broken"));
+
+        // A panicked task is reported as well.
+        let reporter = Arc::new(HealthReporter::new(TaskKind::Flusher, 0, Arc::new(clock)));
+        let res = tokio::spawn(run_reported(reporter.clone(), async { panic!("boom") })).await;
+        assert!(res.is_err());
+        assert!(!reporter.health().alive);
+        assert_eq!(reporter.health().last_error.as_deref(), Some("panicked"));
+    }
+}
@@ -0,0 +1,130 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use crate::{metrics::Metrics, region::RegionId};
+
+/// How the background workers of a store handle a violated invariant, e.g. an entry that does not fit a clean region,
+/// or a worker that exits with an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantPolicy {
+    /// Panic the worker to fail fast.
+    Panic,
+    /// Log the violation, and drop the region involved together with its entries. The worker keeps running.
+    DropRegion,
+    /// Log the violation, and degrade the store, which stops admitting writes but still serves lookups.
+    Degrade,
+}
+
+impl Default for InvariantPolicy {
+    /// [`InvariantPolicy::Panic`] with debug assertions, [`InvariantPolicy::DropRegion`] otherwise.
+    fn default() -> Self {
+        if cfg!(debug_assertions) {
+            Self::Panic
+        } else {
+            Self::DropRegion
+        }
+    }
+}
+
+/// Handles the invariant violations of the background workers of a store with the [`InvariantPolicy`].
+#[derive(Debug)]
+pub struct Invariants {
+    policy: InvariantPolicy,
+    degraded: AtomicBool,
+    metrics: Arc<Metrics>,
+}
+
+impl Invariants {
+    pub fn new(policy: InvariantPolicy, metrics: Arc<Metrics>) -> Self {
+        Self {
+            policy,
+            degraded: AtomicBool::new(false),
+            metrics,
+        }
+    }
+
+    pub fn policy(&self) -> InvariantPolicy {
+        self.policy
+    }
+
+    /// Handle an invariant violated by `worker`, involving `region` if any.
+    ///
+    /// Panics with [`InvariantPolicy::Panic`], otherwise returns whether the worker should drop the region.
+    pub fn violated(&self, worker: &str, region: Option<RegionId>, reason: impl Display) -> bool {
+        let drop_region = match self.policy {
+            InvariantPolicy::Panic => {
+                panic!(
+                    "[{}] invariant violated, region: {:?}, reason: {}",
+                    worker, region, reason
+                )
+            }
+            InvariantPolicy::DropRegion => region.is_some(),
+            InvariantPolicy::Degrade => {
+                self.degraded.store(true, Ordering::Release);
+                false
+            }
+        };
+        tracing::error!(
+            "[{}] invariant violated, region: {:?}, reason: {}, policy: {:?}",
+            worker,
+            region,
+            reason,
+            self.policy
+        );
+        self.metrics.op_count_invariant_violated.inc();
+        drop_region
+    }
+
+    /// Whether the store is degraded by a violation, see [`InvariantPolicy::Degrade`].
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::METRICS;
+
+    #[test]
+    fn test_invariant_policy() {
+        let metrics = Arc::new(METRICS.foyer("test_invariant_policy"));
+
+        let invariants = Invariants::new(InvariantPolicy::DropRegion, metrics.clone());
+        assert!(invariants.violated("flusher", Some(1), "broken"));
+        assert!(!invariants.violated("flusher", None, "broken"));
+        assert!(!invariants.is_degraded());
+
+        let invariants = Invariants::new(InvariantPolicy::Degrade, metrics.clone());
+        assert!(!invariants.violated("flusher", Some(1), "broken"));
+        assert!(invariants.is_degraded());
+        assert_eq!(metrics.op_count_invariant_violated.get(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "invariant violated")]
+    fn test_invariant_policy_panic() {
+        let metrics = Arc::new(METRICS.foyer("test_invariant_policy_panic"));
+        let invariants = Invariants::new(InvariantPolicy::Panic, metrics);
+        invariants.violated("flusher", Some(1), "broken");
+    }
+}
@@ -0,0 +1,127 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use foyer_common::kv::AsyncKv;
+
+use crate::{
+    error::{Error, Result},
+    storage::{Storage, StorageExt},
+};
+
+/// Adapter that exposes a [`Storage`] as an [`AsyncKv`].
+#[derive(Debug, Clone)]
+pub struct StorageKv<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> StorageKv<S>
+where
+    S: Storage,
+{
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+}
+
+impl<S> AsyncKv for StorageKv<S>
+where
+    S: Storage,
+{
+    type Key = S::Key;
+    type Value = S::Value;
+    type Error = Error;
+
+    async fn get(&self, key: &Self::Key) -> Result<Option<Self::Value>> {
+        self.storage.lookup(key).await
+    }
+
+    async fn put(&self, key: Self::Key, value: Self::Value) -> Result<bool> {
+        self.storage.insert(key, value).await
+    }
+
+    async fn delete(&self, key: &Self::Key) -> Result<bool> {
+        self.storage.remove(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use foyer_intrusive::eviction::fifo::FifoConfig;
+
+    use super::*;
+    use crate::{
+        device::{
+            fs::{FsDeviceConfig, FsReadMode},
+            ReadIoPolicy,
+        },
+        store::{FifoFsStore, FifoFsStoreConfig},
+    };
+
+    const KB: usize = 1024;
+    const MB: usize = 1024 * 1024;
+
+    async fn roundtrip<KV>(kv: &KV)
+    where
+        KV: AsyncKv<Key = u64, Value = Vec<u8>>,
+    {
+        assert_eq!(kv.get(&1).await.unwrap(), None);
+        assert!(kv.put(1, vec![b'x'; KB]).await.unwrap());
+        assert_eq!(kv.get(&1).await.unwrap(), Some(vec![b'x'; KB]));
+        assert!(kv.delete(&1).await.unwrap());
+        assert!(!kv.delete(&1).await.unwrap());
+        assert_eq!(kv.get(&1).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_storage_kv() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = FifoFsStoreConfig {
+            catalog_bits: 1,
+            recover_concurrency: 2,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 4 * MB,
+                    file_capacity: MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
+        };
+        let kv = StorageKv::new(FifoFsStore::open(config).await.unwrap());
+
+        roundtrip(&kv).await;
+
+        kv.into_inner().close().await.unwrap();
+    }
+}
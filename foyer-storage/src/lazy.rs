@@ -92,6 +92,13 @@ where
             LazyStorageWriter::None { writer } => writer.set_compression(compression),
         }
     }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        match self {
+            LazyStorageWriter::Store { writer } => writer.set_trace_id(trace_id),
+            LazyStorageWriter::None { writer } => writer.set_trace_id(trace_id),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -212,6 +219,13 @@ where
         }
     }
 
+    fn remove_batch(&self, keys: &[Self::Key]) -> Result<usize> {
+        match self.once.get() {
+            Some(store) => store.remove_batch(keys),
+            None => self.none.remove_batch(keys),
+        }
+    }
+
     fn clear(&self) -> Result<()> {
         match self.once.get() {
             Some(store) => store.clear(),
@@ -231,7 +245,10 @@ mod tests {
 
     use super::*;
     use crate::{
-        device::fs::FsDeviceConfig,
+        device::{
+            fs::{FsDeviceConfig, FsReadMode},
+            ReadIoPolicy,
+        },
         storage::StorageExt,
         store::{FifoFsStoreConfig, Store},
     };
@@ -244,23 +261,22 @@ mod tests {
         let tempdir = tempfile::tempdir().unwrap();
 
         let config = FifoFsStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
-                dir: PathBuf::from(tempdir.path()),
-                capacity: 16 * MB,
-                file_capacity: 4 * MB,
-                align: 4096,
-                io_size: 4096 * KB,
-            },
             catalog_bits: 1,
-            admissions: vec![],
-            reinsertions: vec![],
-            flushers: 1,
-            reclaimers: 1,
             recover_concurrency: 2,
-            clean_region_threshold: 1,
-            compression: crate::compress::Compression::None,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 16 * MB,
+                    file_capacity: 4 * MB,
+                    align: 4096,
+                    io_size: 4096 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
         };
 
         let (store, handle) = LazyStorage::<_, _, Store<_, _>>::with_handle(config.into());
@@ -276,23 +292,22 @@ mod tests {
         drop(store);
 
         let config = FifoFsStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
-                dir: PathBuf::from(tempdir.path()),
-                capacity: 16 * MB,
-                file_capacity: 4 * MB,
-                align: 4096,
-                io_size: 4096 * KB,
-            },
             catalog_bits: 1,
-            admissions: vec![],
-            reinsertions: vec![],
-            flushers: 1,
-            reclaimers: 1,
             recover_concurrency: 2,
-            clean_region_threshold: 1,
-            compression: crate::compress::Compression::None,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 16 * MB,
+                    file_capacity: 4 * MB,
+                    align: 4096,
+                    io_size: 4096 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
         };
 
         let (store, handle) = LazyStorage::<_, _, Store<_, _>>::with_handle(config.into());
@@ -12,7 +12,6 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-#![feature(allocator_api)]
 #![feature(strict_provenance)]
 #![feature(trait_alias)]
 #![feature(get_mut_unchecked)]
@@ -28,20 +27,38 @@
 pub mod admission;
 pub mod buffer;
 pub mod catalog;
+pub mod codec;
 pub mod compress;
 pub mod device;
 pub mod error;
 pub mod flusher;
+pub mod generation;
 pub mod generic;
+pub mod health;
+pub mod invariant;
 pub mod judge;
+pub mod kv;
 pub mod lazy;
 pub mod metrics;
+pub mod miss_cache;
+pub mod partitioner;
+pub mod preset;
+pub mod profile;
+pub mod quarantine;
+pub mod quota;
 pub mod reclaimer;
 pub mod region;
 pub mod region_manager;
 pub mod reinsertion;
 pub mod runtime;
+pub mod scheduler;
+pub mod snapshot;
+pub mod stats;
 pub mod storage;
 pub mod store;
+pub mod tunables;
+pub mod validator;
 
 pub mod test_utils;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -12,10 +12,14 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-use std::sync::{LazyLock, OnceLock};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock, OnceLock},
+};
 
+use parking_lot::Mutex;
 use prometheus::{
-    core::{AtomicU64, GenericGauge, GenericGaugeVec},
+    core::{AtomicU64, Collector, GenericGauge, GenericGaugeVec, MetricVec, MetricVecBuilder},
     exponential_buckets, opts, register_histogram_vec_with_registry, register_int_counter_vec_with_registry,
     register_int_gauge_vec_with_registry, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, Registry,
 };
@@ -62,12 +66,22 @@ pub struct GlobalMetrics {
     op_duration: HistogramVec,
     slow_op_duration: HistogramVec,
     op_bytes: IntCounterVec,
+    op_count: IntCounterVec,
     total_bytes: UintGaugeVec,
+    quarantined_bytes: UintGaugeVec,
+    inflight_bytes: UintGaugeVec,
+    catalog_shard_entries: UintGaugeVec,
 
     entry_bytes: HistogramVec,
+    entry_age: HistogramVec,
 
     inner_op_duration: HistogramVec,
     _inner_bytes: IntGaugeVec,
+
+    /// Names of the opened foyer instances.
+    names: Arc<Mutex<HashSet<String>>>,
+    /// Id generator of the opened foyer instances.
+    instances: std::sync::atomic::AtomicU64,
 }
 
 impl Default for GlobalMetrics {
@@ -81,7 +95,7 @@ impl GlobalMetrics {
         let op_duration = register_histogram_vec_with_registry!(
             "foyer_storage_op_duration",
             "foyer storage op duration",
-            &["foyer", "op", "extra"],
+            &["foyer", "instance", "op", "extra"],
             vec![0.0001, 0.001, 0.005, 0.01, 0.02, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0],
             registry,
         )
@@ -90,7 +104,7 @@ impl GlobalMetrics {
         let slow_op_duration = register_histogram_vec_with_registry!(
             "foyer_storage_slow_op_duration",
             "foyer storage slow op duration",
-            &["foyer", "op", "extra"],
+            &["foyer", "instance", "op", "extra"],
             vec![0.01, 0.1, 0.5, 0.77, 1.0, 2.5, 5.0, 7.5, 10.0],
             registry,
         )
@@ -99,7 +113,15 @@ impl GlobalMetrics {
         let op_bytes = register_int_counter_vec_with_registry!(
             "foyer_storage_op_bytes",
             "foyer storage op bytes",
-            &["foyer", "op", "extra"],
+            &["foyer", "instance", "op", "extra"],
+            registry,
+        )
+        .unwrap();
+
+        let op_count = register_int_counter_vec_with_registry!(
+            "foyer_storage_op_count",
+            "foyer storage op count",
+            &["foyer", "instance", "op", "extra"],
             registry,
         )
         .unwrap();
@@ -107,7 +129,31 @@ impl GlobalMetrics {
         let total_bytes = register_uint_gauge_vec_with_registry!(
             "foyer_storage_total_bytes",
             "foyer storage total bytes",
-            &["foyer"],
+            &["foyer", "instance"],
+            registry,
+        )
+        .unwrap();
+
+        let quarantined_bytes = register_uint_gauge_vec_with_registry!(
+            "foyer_storage_quarantined_bytes",
+            "foyer storage quarantined bytes",
+            &["foyer", "instance"],
+            registry,
+        )
+        .unwrap();
+
+        let inflight_bytes = register_uint_gauge_vec_with_registry!(
+            "foyer_storage_inflight_bytes",
+            "foyer storage inflight bytes",
+            &["foyer", "instance"],
+            registry,
+        )
+        .unwrap();
+
+        let catalog_shard_entries = register_uint_gauge_vec_with_registry!(
+            "foyer_storage_catalog_shard_entries",
+            "foyer storage catalog shard entries",
+            &["foyer", "instance", "extra"],
             registry,
         )
         .unwrap();
@@ -115,16 +161,25 @@ impl GlobalMetrics {
         let entry_bytes = register_histogram_vec_with_registry!(
             "foyer_storage_entry_bytes",
             "foyer storage entry bytes",
-            &["foyer", "op", "extra"],
+            &["foyer", "instance", "op", "extra"],
             exponential_buckets(1.0, 2.0, 32).unwrap(),
             registry,
         )
         .unwrap();
 
+        let entry_age = register_histogram_vec_with_registry!(
+            "foyer_storage_entry_age",
+            "foyer storage entry age in seconds",
+            &["foyer", "instance", "op", "extra"],
+            exponential_buckets(1.0, 2.0, 24).unwrap(),
+            registry,
+        )
+        .unwrap();
+
         let inner_op_duration = register_histogram_vec_with_registry!(
             "foyer_storage_inner_op_duration",
             "foyer storage inner op duration",
-            &["foyer", "op", "extra"],
+            &["foyer", "instance", "op", "extra"],
             vec![0.0001, 0.01, 0.02, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 10.0],
             registry,
         )
@@ -133,7 +188,7 @@ impl GlobalMetrics {
         let inner_bytes = register_int_gauge_vec_with_registry!(
             "foyer_storage_inner_bytes",
             "foyer storage inner bytes",
-            &["foyer", "component", "extra"],
+            &["foyer", "instance", "component", "extra"],
             registry,
         )
         .unwrap();
@@ -142,17 +197,120 @@ impl GlobalMetrics {
             op_duration,
             slow_op_duration,
             op_bytes,
+            op_count,
             total_bytes,
+            quarantined_bytes,
+            inflight_bytes,
+            catalog_shard_entries,
 
             entry_bytes,
+            entry_age,
 
             inner_op_duration,
             _inner_bytes: inner_bytes,
+
+            names: Arc::new(Mutex::new(HashSet::new())),
+            instances: std::sync::atomic::AtomicU64::new(0),
         }
     }
 
+    /// Create metrics for a foyer instance.
+    ///
+    /// If the `name` is already used by another opened instance, a suffix is appended to keep their metrics apart.
     pub fn foyer(&self, name: &str) -> Metrics {
-        Metrics::new(self, name)
+        let name = {
+            let mut names = self.names.lock();
+            let mut unique = name.to_string();
+            let mut suffix = 0;
+            while names.contains(&unique) {
+                suffix += 1;
+                unique = format!("{}-{}", name, suffix);
+            }
+            if suffix > 0 {
+                tracing::warn!(
+                    "foyer name {:?} is already in use, rename the metrics label to {:?}",
+                    name,
+                    unique
+                );
+            }
+            names.insert(unique.clone());
+            unique
+        };
+        let instance = self.instances.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let name = NameGuard {
+            name,
+            instance: instance.to_string(),
+            names: self.names.clone(),
+            vecs: vec![
+                InstanceVec::Histogram(self.op_duration.clone()),
+                InstanceVec::Histogram(self.slow_op_duration.clone()),
+                InstanceVec::Counter(self.op_bytes.clone()),
+                InstanceVec::Counter(self.op_count.clone()),
+                InstanceVec::UintGauge(self.total_bytes.clone()),
+                InstanceVec::UintGauge(self.quarantined_bytes.clone()),
+                InstanceVec::UintGauge(self.inflight_bytes.clone()),
+                InstanceVec::UintGauge(self.catalog_shard_entries.clone()),
+                InstanceVec::Histogram(self.entry_bytes.clone()),
+                InstanceVec::Histogram(self.entry_age.clone()),
+                InstanceVec::Histogram(self.inner_op_duration.clone()),
+                InstanceVec::Gauge(self._inner_bytes.clone()),
+            ],
+        };
+        Metrics::new(self, name, instance)
+    }
+}
+
+/// A global metric vec labeled with `foyer` and `instance`.
+#[derive(Debug)]
+enum InstanceVec {
+    Histogram(HistogramVec),
+    Counter(IntCounterVec),
+    Gauge(IntGaugeVec),
+    UintGauge(UintGaugeVec),
+}
+
+impl InstanceVec {
+    fn remove(&self, foyer: &str, instance: &str) {
+        match self {
+            Self::Histogram(vec) => remove_instance_label_values(vec, foyer, instance),
+            Self::Counter(vec) => remove_instance_label_values(vec, foyer, instance),
+            Self::Gauge(vec) => remove_instance_label_values(vec, foyer, instance),
+            Self::UintGauge(vec) => remove_instance_label_values(vec, foyer, instance),
+        }
+    }
+}
+
+/// Remove all label values of the foyer instance from `vec`, including the dynamic ones, e.g. [`Metrics::job`].
+fn remove_instance_label_values<T: MetricVecBuilder>(vec: &MetricVec<T>, foyer: &str, instance: &str) {
+    for family in vec.collect() {
+        for metric in family.get_metric() {
+            let labels: HashMap<&str, &str> = metric
+                .get_label()
+                .iter()
+                .map(|label| (label.get_name(), label.get_value()))
+                .collect();
+            if labels.get("foyer") == Some(&foyer) && labels.get("instance") == Some(&instance) {
+                let _ = vec.remove(&labels);
+            }
+        }
+    }
+}
+
+/// Holds a foyer name in use, removes the label values of the instance and releases the name on drop.
+#[derive(Debug)]
+pub struct NameGuard {
+    name: String,
+    instance: String,
+    names: Arc<Mutex<HashSet<String>>>,
+    vecs: Vec<InstanceVec>,
+}
+
+impl Drop for NameGuard {
+    fn drop(&mut self) {
+        for vec in &self.vecs {
+            vec.remove(&self.name, &self.instance);
+        }
+        self.names.lock().remove(&self.name);
     }
 }
 
@@ -161,96 +319,308 @@ pub struct Metrics {
     pub op_duration_insert_inserted: Histogram,
     pub op_duration_insert_filtered: Histogram,
     pub op_duration_insert_dropped: Histogram,
+    pub op_duration_insert_deduplicated: Histogram,
     pub op_duration_lookup_hit: Histogram,
     pub op_duration_lookup_miss: Histogram,
+    pub op_duration_lookup_miss_cached: Histogram,
     pub op_duration_remove: Histogram,
     pub slow_op_duration_reclaim: Histogram,
 
     pub op_bytes_insert: IntCounter,
     pub op_bytes_lookup: IntCounter,
     pub op_bytes_flush: IntCounter,
+    pub op_bytes_flush_logical: IntCounter,
     pub op_bytes_reclaim: IntCounter,
     pub op_bytes_reinsert: IntCounter,
+    pub op_bytes_insert_shed: IntCounter,
+
+    pub op_count_insert_shed: IntCounter,
+    pub op_count_insert_coalesced: IntCounter,
+    pub op_count_insert_overwritten: IntCounter,
+    pub op_count_insert_exists: IntCounter,
+    pub op_count_lookup_timeout: IntCounter,
+    pub op_count_lookup_shared: IntCounter,
+    pub op_count_flush_timeout: IntCounter,
+    pub op_count_reinsert_dropped_hot: IntCounter,
+    pub op_count_catalog_compact: IntCounter,
+    pub op_count_invariant_violated: IntCounter,
 
     pub total_bytes: UintGauge,
+    pub quarantined_bytes: UintGauge,
+    pub inflight_bytes: UintGauge,
+    pub catalog_shard_entries_min: UintGauge,
+    pub catalog_shard_entries_max: UintGauge,
 
     pub insert_entry_bytes: Histogram,
 
+    pub entry_age_hit: Histogram,
+    pub entry_age_evict: Histogram,
+
     pub inner_op_duration_acquire_clean_region: Histogram,
     pub inner_op_duration_acquire_clean_buffer: Histogram,
     pub inner_op_duration_wait_ring_buffer: Histogram,
     pub inner_op_duration_update_catalog: Histogram,
     pub inner_op_duration_entry_flush: Histogram,
     pub inner_op_duration_flusher_handle: Histogram,
+
+    /// Labeled per job on [`Metrics::job`].
+    op_duration_job: HistogramVec,
+    op_count_job_error: IntCounterVec,
+
+    name: NameGuard,
+    instance: u64,
+}
+
+/// Metrics of a periodic background job of a store.
+#[derive(Debug, Clone)]
+pub struct JobMetrics {
+    pub op_duration_run: Histogram,
+    pub op_count_error: IntCounter,
 }
 
 impl Metrics {
-    pub fn new(global: &GlobalMetrics, foyer: &str) -> Self {
-        let op_duration_insert_inserted = global.op_duration.with_label_values(&[foyer, "insert", "inserted"]);
-        let op_duration_insert_filtered = global.op_duration.with_label_values(&[foyer, "insert", "filtered"]);
-        let op_duration_insert_dropped = global.op_duration.with_label_values(&[foyer, "insert", "dropped"]);
-        let op_duration_lookup_hit = global.op_duration.with_label_values(&[foyer, "lookup", "hit"]);
-        let op_duration_lookup_miss = global.op_duration.with_label_values(&[foyer, "lookup", "miss"]);
-        let op_duration_remove = global.op_duration.with_label_values(&[foyer, "remove", ""]);
-        let slow_op_duration_reclaim = global.slow_op_duration.with_label_values(&[foyer, "reclaim", ""]);
+    pub fn new(global: &GlobalMetrics, name: NameGuard, instance_id: u64) -> Self {
+        let foyer = name.name.as_str();
+        let instance_label = instance_id.to_string();
+        let instance = instance_label.as_str();
+
+        let op_duration_insert_inserted = global
+            .op_duration
+            .with_label_values(&[foyer, instance, "insert", "inserted"]);
+        let op_duration_insert_filtered = global
+            .op_duration
+            .with_label_values(&[foyer, instance, "insert", "filtered"]);
+        let op_duration_insert_dropped = global
+            .op_duration
+            .with_label_values(&[foyer, instance, "insert", "dropped"]);
+        let op_duration_insert_deduplicated =
+            global
+                .op_duration
+                .with_label_values(&[foyer, instance, "insert", "deduplicated"]);
+        let op_duration_lookup_hit = global
+            .op_duration
+            .with_label_values(&[foyer, instance, "lookup", "hit"]);
+        let op_duration_lookup_miss = global
+            .op_duration
+            .with_label_values(&[foyer, instance, "lookup", "miss"]);
+        let op_duration_lookup_miss_cached =
+            global
+                .op_duration
+                .with_label_values(&[foyer, instance, "lookup", "miss_cached"]);
+        let op_duration_remove = global.op_duration.with_label_values(&[foyer, instance, "remove", ""]);
+        let slow_op_duration_reclaim = global
+            .slow_op_duration
+            .with_label_values(&[foyer, instance, "reclaim", ""]);
+
+        let op_bytes_insert = global.op_bytes.with_label_values(&[foyer, instance, "insert", ""]);
+        let op_bytes_lookup = global.op_bytes.with_label_values(&[foyer, instance, "lookup", ""]);
+        let op_bytes_flush = global.op_bytes.with_label_values(&[foyer, instance, "flush", ""]);
+        let op_bytes_flush_logical = global
+            .op_bytes
+            .with_label_values(&[foyer, instance, "flush", "logical"]);
+        let op_bytes_reclaim = global.op_bytes.with_label_values(&[foyer, instance, "reclaim", ""]);
+        let op_bytes_reinsert = global.op_bytes.with_label_values(&[foyer, instance, "reinsert", ""]);
+        let op_bytes_insert_shed = global.op_bytes.with_label_values(&[foyer, instance, "insert", "shed"]);
+
+        let op_count_insert_shed = global.op_count.with_label_values(&[foyer, instance, "insert", "shed"]);
+        let op_count_insert_coalesced = global
+            .op_count
+            .with_label_values(&[foyer, instance, "insert", "coalesced"]);
+        let op_count_insert_overwritten =
+            global
+                .op_count
+                .with_label_values(&[foyer, instance, "insert", "overwritten"]);
+        let op_count_insert_exists = global
+            .op_count
+            .with_label_values(&[foyer, instance, "insert", "exists"]);
+        let op_count_lookup_timeout = global
+            .op_count
+            .with_label_values(&[foyer, instance, "lookup", "timeout"]);
+        let op_count_lookup_shared = global
+            .op_count
+            .with_label_values(&[foyer, instance, "lookup", "shared"]);
+        let op_count_flush_timeout = global
+            .op_count
+            .with_label_values(&[foyer, instance, "flush", "timeout"]);
+        let op_count_reinsert_dropped_hot =
+            global
+                .op_count
+                .with_label_values(&[foyer, instance, "reinsert", "dropped_hot"]);
+        let op_count_catalog_compact = global
+            .op_count
+            .with_label_values(&[foyer, instance, "catalog_compact", ""]);
+        let op_count_invariant_violated =
+            global
+                .op_count
+                .with_label_values(&[foyer, instance, "invariant", "violated"]);
 
-        let op_bytes_insert = global.op_bytes.with_label_values(&[foyer, "insert", ""]);
-        let op_bytes_lookup = global.op_bytes.with_label_values(&[foyer, "lookup", ""]);
-        let op_bytes_flush = global.op_bytes.with_label_values(&[foyer, "flush", ""]);
-        let op_bytes_reclaim = global.op_bytes.with_label_values(&[foyer, "reclaim", ""]);
-        let op_bytes_reinsert = global.op_bytes.with_label_values(&[foyer, "reinsert", ""]);
+        let total_bytes = global.total_bytes.with_label_values(&[foyer, instance]);
+        let quarantined_bytes = global.quarantined_bytes.with_label_values(&[foyer, instance]);
+        let inflight_bytes = global.inflight_bytes.with_label_values(&[foyer, instance]);
+        let catalog_shard_entries_min = global
+            .catalog_shard_entries
+            .with_label_values(&[foyer, instance, "min"]);
+        let catalog_shard_entries_max = global
+            .catalog_shard_entries
+            .with_label_values(&[foyer, instance, "max"]);
 
-        let total_bytes = global.total_bytes.with_label_values(&[foyer]);
+        let insert_entry_bytes = global.entry_bytes.with_label_values(&[foyer, instance, "insert", ""]);
 
-        let insert_entry_bytes = global.entry_bytes.with_label_values(&[foyer, "insert", ""]);
+        let entry_age_hit = global.entry_age.with_label_values(&[foyer, instance, "lookup", "hit"]);
+        let entry_age_evict = global.entry_age.with_label_values(&[foyer, instance, "evict", ""]);
 
         let inner_op_duration_acquire_clean_region =
             global
                 .inner_op_duration
-                .with_label_values(&[foyer, "acquire_clean_region", ""]);
+                .with_label_values(&[foyer, instance, "acquire_clean_region", ""]);
         let inner_op_duration_acquire_clean_buffer =
             global
                 .inner_op_duration
-                .with_label_values(&[foyer, "acquire_clean_buffer", ""]);
+                .with_label_values(&[foyer, instance, "acquire_clean_buffer", ""]);
         let inner_op_duration_wait_ring_buffer =
             global
                 .inner_op_duration
-                .with_label_values(&[foyer, "wait_ring_buffer", ""]);
+                .with_label_values(&[foyer, instance, "wait_ring_buffer", ""]);
         let inner_op_duration_update_catalog =
             global
                 .inner_op_duration
-                .with_label_values(&[foyer, "update_catalog", ""]);
-        let inner_op_duration_entry_flush = global.inner_op_duration.with_label_values(&[foyer, "entry_flush", ""]);
+                .with_label_values(&[foyer, instance, "update_catalog", ""]);
+        let inner_op_duration_entry_flush =
+            global
+                .inner_op_duration
+                .with_label_values(&[foyer, instance, "entry_flush", ""]);
         let inner_op_duration_flusher_handle =
             global
                 .inner_op_duration
-                .with_label_values(&[foyer, "flusher_handle", ""]);
+                .with_label_values(&[foyer, instance, "flusher_handle", ""]);
 
         Self {
             op_duration_insert_inserted,
             op_duration_insert_filtered,
             op_duration_insert_dropped,
+            op_duration_insert_deduplicated,
             op_duration_lookup_hit,
             op_duration_lookup_miss,
+            op_duration_lookup_miss_cached,
             op_duration_remove,
             slow_op_duration_reclaim,
 
             op_bytes_insert,
             op_bytes_lookup,
             op_bytes_flush,
+            op_bytes_flush_logical,
             op_bytes_reclaim,
             op_bytes_reinsert,
+            op_bytes_insert_shed,
+
+            op_count_insert_shed,
+            op_count_insert_coalesced,
+            op_count_insert_overwritten,
+            op_count_insert_exists,
+            op_count_lookup_timeout,
+            op_count_lookup_shared,
+            op_count_flush_timeout,
+            op_count_reinsert_dropped_hot,
+            op_count_catalog_compact,
+            op_count_invariant_violated,
 
             total_bytes,
+            quarantined_bytes,
+            inflight_bytes,
+            catalog_shard_entries_min,
+            catalog_shard_entries_max,
 
             insert_entry_bytes,
 
+            entry_age_hit,
+            entry_age_evict,
+
             inner_op_duration_acquire_clean_region,
             inner_op_duration_acquire_clean_buffer,
             inner_op_duration_wait_ring_buffer,
             inner_op_duration_update_catalog,
             inner_op_duration_entry_flush,
             inner_op_duration_flusher_handle,
+
+            op_duration_job: global.op_duration.clone(),
+            op_count_job_error: global.op_count.clone(),
+
+            name,
+            instance: instance_id,
+        }
+    }
+
+    /// Unique name of the foyer instance, used as the `foyer` label.
+    pub fn name(&self) -> &str {
+        &self.name.name
+    }
+
+    /// Id of the foyer instance, used as the `instance` label.
+    pub fn instance(&self) -> u64 {
+        self.instance
+    }
+
+    /// Metrics of the background job `job`, used as the `extra` label.
+    pub fn job(&self, job: &str) -> JobMetrics {
+        let instance = self.instance.to_string();
+        let labels = |op| [self.name(), instance.as_str(), op, job];
+        JobMetrics {
+            op_duration_run: self.op_duration_job.with_label_values(&labels("job")),
+            op_count_error: self.op_count_job_error.with_label_values(&labels("job_error")),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_name_isolation() {
+        let global = GlobalMetrics::new(&Registry::new());
+
+        let m1 = global.foyer("foyer");
+        let m2 = global.foyer("foyer");
+        assert_eq!(m1.name(), "foyer");
+        assert_eq!(m2.name(), "foyer-1");
+        assert_ne!(m1.instance(), m2.instance());
+
+        drop(m1);
+        let m3 = global.foyer("foyer");
+        assert_eq!(m3.name(), "foyer");
+    }
+
+    #[test]
+    fn test_metrics_label_values_removed_on_drop() {
+        let registry = Registry::new();
+        let global = GlobalMetrics::new(&registry);
+        let count = |name: &str| {
+            registry
+                .gather()
+                .iter()
+                .flat_map(|family| family.get_metric())
+                .filter(|metric| {
+                    metric
+                        .get_label()
+                        .iter()
+                        .any(|label| label.get_name() == "foyer" && label.get_value() == name)
+                })
+                .count()
+        };
+
+        let m1 = global.foyer("foyer");
+        let m2 = global.foyer("foyer");
+        m1.op_count_insert_shed.inc();
+        m1.job("purger").op_count_error.inc();
+        m2.total_bytes.set(1);
+        assert!(count("foyer") > 0);
+        assert!(count("foyer-1") > 0);
+
+        drop(m1);
+        assert_eq!(count("foyer"), 0);
+        assert!(count("foyer-1") > 0);
+
+        drop(m2);
+        assert_eq!(count("foyer-1"), 0);
+    }
+}
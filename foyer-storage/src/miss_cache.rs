@@ -0,0 +1,161 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    sync::Arc,
+    time::Duration,
+};
+
+use foyer_common::clock::Clock;
+use parking_lot::Mutex;
+use twox_hash::XxHash64;
+
+#[derive(Debug, Clone)]
+pub struct MissCacheConfig {
+    /// How long a recorded miss is trusted.
+    ///
+    /// An insert that races with a missed lookup may be invisible to lookups within the window.
+    pub window: Duration,
+
+    /// Max count of recorded misses.
+    pub capacity: usize,
+}
+
+/// A tiny in-memory set of recently missed keys (by hash).
+///
+/// It absorbs bursts of lookups on the same absent key without touching the catalog.
+#[derive(Debug)]
+pub struct MissCache {
+    window: Duration,
+    capacity: usize,
+    seed: u64,
+    clock: Arc<dyn Clock>,
+
+    inner: Mutex<MissCacheInner>,
+}
+
+#[derive(Debug, Default)]
+struct MissCacheInner {
+    /// Record time in clock millis.
+    misses: HashMap<u64, u64>,
+    /// Record order for eviction, may contain outdated records.
+    queue: VecDeque<(u64, u64)>,
+}
+
+impl MissCache {
+    /// `seed` seeds the key hasher, `clock` times the records.
+    pub fn new(config: MissCacheConfig, seed: u64, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            window: config.window,
+            capacity: config.capacity,
+            seed,
+            clock,
+            inner: Mutex::new(MissCacheInner::default()),
+        }
+    }
+
+    /// Returns `true` if the key is recorded missed within the window.
+    pub fn contains<K: Hash>(&self, key: &K) -> bool {
+        let hash = self.hash(key);
+        let now = self.clock.now_millis();
+        let inner = self.inner.lock();
+        matches!(inner.misses.get(&hash), Some(&time) if (now.saturating_sub(time) as u128) < self.window.as_millis())
+    }
+
+    pub fn record<K: Hash>(&self, key: &K) {
+        let hash = self.hash(key);
+        let now = self.clock.now_millis();
+        let mut inner = self.inner.lock();
+        inner.misses.insert(hash, now);
+        inner.queue.push_back((hash, now));
+        while inner.queue.len() > self.capacity {
+            let (hash, time) = inner.queue.pop_front().unwrap();
+            if inner.misses.get(&hash) == Some(&time) {
+                inner.misses.remove(&hash);
+            }
+        }
+    }
+
+    pub fn invalidate<K: Hash>(&self, key: &K) {
+        let hash = self.hash(key);
+        self.inner.lock().misses.remove(&hash);
+    }
+
+    /// Forget all recorded misses and release their memory, returns the estimated released bytes.
+    pub fn clear(&self) -> usize {
+        let inner = std::mem::take(&mut *self.inner.lock());
+        (inner.misses.capacity() + inner.queue.capacity()) * std::mem::size_of::<(u64, u64)>()
+    }
+
+    fn hash<K: Hash>(&self, key: &K) -> u64 {
+        let mut hasher = XxHash64::with_seed(self.seed);
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use foyer_common::clock::MockClock;
+
+    use super::*;
+
+    #[test]
+    fn test_miss_cache() {
+        let clock = MockClock::new(1000);
+        let cache = MissCache::new(
+            MissCacheConfig {
+                window: Duration::from_secs(3600),
+                capacity: 2,
+            },
+            0,
+            Arc::new(clock.clone()),
+        );
+
+        cache.record(&1u64);
+        cache.record(&2u64);
+        assert!(cache.contains(&1u64));
+        assert!(cache.contains(&2u64));
+        assert!(!cache.contains(&3u64));
+
+        // Evict the oldest record.
+        cache.record(&3u64);
+        assert!(!cache.contains(&1u64));
+        assert!(cache.contains(&2u64));
+        assert!(cache.contains(&3u64));
+
+        cache.invalidate(&2u64);
+        assert!(!cache.contains(&2u64));
+
+        assert!(cache.clear() > 0);
+        assert!(!cache.contains(&3u64));
+        assert_eq!(cache.clear(), 0);
+
+        let cache = MissCache::new(
+            MissCacheConfig {
+                window: Duration::from_secs(10),
+                capacity: 2,
+            },
+            0,
+            Arc::new(clock.clone()),
+        );
+        cache.record(&1u64);
+        clock.advance(Duration::from_millis(9999));
+        assert!(cache.contains(&1u64));
+        clock.advance(Duration::from_millis(1));
+        assert!(!cache.contains(&1u64));
+    }
+}
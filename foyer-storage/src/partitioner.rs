@@ -0,0 +1,25 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::fmt::Debug;
+
+use foyer_common::code::Key;
+
+/// Partitioner to route related entries to the same flusher, so they are written to the same regions.
+pub trait FlushPartitioner: Send + Sync + 'static + Debug {
+    type Key: Key;
+
+    /// Return the partition of the key, entries of a partition are routed to flusher `partition % flushers`.
+    fn partition(&self, key: &Self::Key) -> u64;
+}
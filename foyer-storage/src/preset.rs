@@ -0,0 +1,152 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use foyer_common::code::{Key, Value};
+use foyer_intrusive::eviction::EvictionPolicy;
+
+use crate::{
+    device::fs::{FsDevice, FsDeviceConfig},
+    generic::GenericStoreConfig,
+};
+
+const KB: usize = 1024;
+const MB: usize = 1024 * 1024;
+
+/// Store config values for a class of devices.
+///
+/// A preset fills the region size, io alignment, io size, flusher and reclaimer count and clean region threshold.
+/// The device dir and capacity and all other fields are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    /// Local NVMe SSD, optimized for lookup latency with small ios and more background workers.
+    NvmeLowLatency,
+    /// SATA SSD or HDD, optimized for throughput with large sequential ios.
+    SataThroughput,
+    /// Network attached block storage (e.g. AWS EBS), whose iops are billed and throttled by io count, with ios
+    /// large enough to reach the throughput limit and enough clean regions to absorb latency spikes.
+    CloudEbs,
+}
+
+impl Preset {
+    /// Region size.
+    pub fn file_capacity(&self) -> usize {
+        match self {
+            Preset::NvmeLowLatency => 16 * MB,
+            Preset::SataThroughput => 64 * MB,
+            Preset::CloudEbs => 64 * MB,
+        }
+    }
+
+    pub fn align(&self) -> usize {
+        4 * KB
+    }
+
+    pub fn io_size(&self) -> usize {
+        match self {
+            Preset::NvmeLowLatency => 16 * KB,
+            Preset::SataThroughput => MB,
+            Preset::CloudEbs => 256 * KB,
+        }
+    }
+
+    pub fn flushers(&self) -> usize {
+        match self {
+            Preset::NvmeLowLatency => 4,
+            Preset::SataThroughput => 2,
+            Preset::CloudEbs => 4,
+        }
+    }
+
+    pub fn reclaimers(&self) -> usize {
+        match self {
+            Preset::NvmeLowLatency => 4,
+            Preset::SataThroughput => 2,
+            Preset::CloudEbs => 2,
+        }
+    }
+
+    pub fn clean_region_threshold(&self) -> usize {
+        match self {
+            Preset::NvmeLowLatency => 4,
+            Preset::SataThroughput => 2,
+            Preset::CloudEbs => 8,
+        }
+    }
+
+    /// Fill the device config, the capacity is rounded down to a multiple of the region size.
+    pub fn apply_device_config(&self, config: FsDeviceConfig) -> FsDeviceConfig {
+        let file_capacity = self.file_capacity();
+        assert!(
+            config.capacity >= file_capacity,
+            "capacity ({}) must not be smaller than the region size ({}) of preset {:?}",
+            config.capacity,
+            file_capacity,
+            self
+        );
+        FsDeviceConfig {
+            capacity: config.capacity / file_capacity * file_capacity,
+            file_capacity,
+            align: self.align(),
+            io_size: self.io_size(),
+            ..config
+        }
+    }
+
+    /// Fill the store config.
+    pub fn apply<K, V, EP>(
+        &self,
+        config: GenericStoreConfig<K, V, FsDevice, EP>,
+    ) -> GenericStoreConfig<K, V, FsDevice, EP>
+    where
+        K: Key,
+        V: Value,
+        EP: EvictionPolicy,
+    {
+        GenericStoreConfig {
+            device_config: self.apply_device_config(config.device_config),
+            flushers: self.flushers(),
+            reclaimers: self.reclaimers(),
+            clean_region_threshold: self.clean_region_threshold(),
+            ..config
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::device::{fs::FsReadMode, ReadIoPolicy};
+
+    #[test]
+    fn test_preset_device_config() {
+        for preset in [Preset::NvmeLowLatency, Preset::SataThroughput, Preset::CloudEbs] {
+            let config = preset.apply_device_config(FsDeviceConfig {
+                dir: PathBuf::from("/tmp/foyer"),
+                capacity: 1024 * MB + 1,
+                file_capacity: 0,
+                align: 0,
+                io_size: 0,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            });
+            config.verify();
+            assert_eq!(config.capacity, 1024 * MB);
+            assert_eq!(config.file_capacity, preset.file_capacity());
+            assert_eq!(config.io_size % config.align, 0);
+        }
+    }
+}
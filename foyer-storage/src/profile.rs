@@ -0,0 +1,109 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! Hooks on the hot paths of the stores to attach external samplers, e.g. rdtsc-based profilers.
+//!
+//! The hooks are only invoked with the `profile` feature, and compile to nothing otherwise. They are shared by all
+//! stores of the process and run inline on the hot paths, so they must be cheap and must not block.
+
+#[cfg(feature = "profile")]
+use std::sync::OnceLock;
+
+use crate::region::RegionId;
+
+/// Hooks invoked on the hot paths of the stores, see [`install`].
+///
+/// A `*_start` hook returns a token passed to the matching `*_end` hook, e.g. a timestamp counter. The end hook is
+/// not invoked if the operation fails or is cancelled.
+#[cfg(feature = "profile")]
+pub trait ProfileHooks: Send + Sync + 'static {
+    fn lookup_start(&self) -> u64 {
+        0
+    }
+
+    fn lookup_end(&self, _token: u64, _hit: bool) {}
+
+    /// The flush buffer of `bytes` bytes is written to `region`.
+    fn flush_write_start(&self, _region: RegionId, _bytes: usize) -> u64 {
+        0
+    }
+
+    fn flush_write_end(&self, _token: u64, _region: RegionId, _bytes: usize) {}
+
+    fn reclaim_start(&self, _region: RegionId) -> u64 {
+        0
+    }
+
+    fn reclaim_end(&self, _token: u64, _region: RegionId) {}
+}
+
+#[cfg(feature = "profile")]
+static HOOKS: OnceLock<Box<dyn ProfileHooks>> = OnceLock::new();
+
+/// Install the hooks of the process. Returns `false` if hooks are already installed, they cannot be replaced.
+#[cfg(feature = "profile")]
+pub fn install(hooks: impl ProfileHooks) -> bool {
+    HOOKS.set(Box::new(hooks)).is_ok()
+}
+
+#[inline(always)]
+pub(crate) fn lookup_start() -> u64 {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        return hooks.lookup_start();
+    }
+    0
+}
+
+#[inline(always)]
+pub(crate) fn lookup_end(_token: u64, _hit: bool) {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        hooks.lookup_end(_token, _hit);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn flush_write_start(_region: RegionId, _bytes: usize) -> u64 {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        return hooks.flush_write_start(_region, _bytes);
+    }
+    0
+}
+
+#[inline(always)]
+pub(crate) fn flush_write_end(_token: u64, _region: RegionId, _bytes: usize) {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        hooks.flush_write_end(_token, _region, _bytes);
+    }
+}
+
+#[inline(always)]
+pub(crate) fn reclaim_start(_region: RegionId) -> u64 {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        return hooks.reclaim_start(_region);
+    }
+    0
+}
+
+#[inline(always)]
+pub(crate) fn reclaim_end(_token: u64, _region: RegionId) {
+    #[cfg(feature = "profile")]
+    if let Some(hooks) = HOOKS.get() {
+        hooks.reclaim_end(_token, _region);
+    }
+}
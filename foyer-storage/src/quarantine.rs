@@ -0,0 +1,189 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use bytes::{Buf, BufMut};
+use parking_lot::Mutex;
+
+use crate::{error::Result, region::RegionId};
+
+const QUARANTINE_MAGIC: u32 = 0x19_97_03_27;
+
+/// Set of regions with corrupted data.
+///
+/// Quarantined regions are skipped by recovery and fully zeroed by reclamation. The set is persisted to `path` (if
+/// given) on every change, so a corrupted region will not be indexed again after restart. The in-memory set is not
+/// changed if it fails to persist.
+#[derive(Debug)]
+pub struct Quarantine {
+    path: Option<PathBuf>,
+    regions: Mutex<BTreeSet<RegionId>>,
+}
+
+impl Quarantine {
+    /// Open quarantine set, load persisted regions from `path` if exists.
+    pub fn open(path: Option<PathBuf>) -> Result<Self> {
+        let regions = match &path {
+            Some(path) if path.exists() => {
+                let buf = std::fs::read(path).map_err(anyhow::Error::from)?;
+                Self::decode(&buf)?
+            }
+            _ => BTreeSet::new(),
+        };
+        Ok(Self {
+            path,
+            regions: Mutex::new(regions),
+        })
+    }
+
+    pub fn contains(&self, region: RegionId) -> bool {
+        self.regions.lock().contains(&region)
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if the region is newly quarantined.
+    pub fn insert(&self, region: RegionId) -> Result<bool> {
+        let mut regions = self.regions.lock();
+        if regions.contains(&region) {
+            return Ok(false);
+        }
+        let mut next = regions.clone();
+        next.insert(region);
+        self.persist(&next)?;
+        *regions = next;
+        Ok(true)
+    }
+
+    /// Returns `true` if the region was quarantined.
+    pub fn remove(&self, region: RegionId) -> Result<bool> {
+        let mut regions = self.regions.lock();
+        if !regions.contains(&region) {
+            return Ok(false);
+        }
+        let mut next = regions.clone();
+        next.remove(&region);
+        self.persist(&next)?;
+        *regions = next;
+        Ok(true)
+    }
+
+    fn persist(&self, regions: &BTreeSet<RegionId>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+
+        // Write to a temporary file and rename it to make the update atomic, sync both the file before the rename and
+        // the directory after it to make the update durable.
+        let tmp = path.with_extension("tmp");
+        let mut file = File::create(&tmp).map_err(anyhow::Error::from)?;
+        file.write_all(&Self::encode(regions)).map_err(anyhow::Error::from)?;
+        file.sync_all().map_err(anyhow::Error::from)?;
+        drop(file);
+        std::fs::rename(&tmp, path).map_err(anyhow::Error::from)?;
+        if let Some(dir) = path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(anyhow::Error::from)?;
+        }
+        Ok(())
+    }
+
+    /// | magic (4B) | count (4B) | region id (4B) * count |
+    fn encode(regions: &BTreeSet<RegionId>) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8 + regions.len() * 4);
+        buf.put_u32(QUARANTINE_MAGIC);
+        buf.put_u32(regions.len() as u32);
+        for region in regions {
+            buf.put_u32(*region);
+        }
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> Result<BTreeSet<RegionId>> {
+        if buf.remaining() < 8 || buf.get_u32() != QUARANTINE_MAGIC {
+            return Err(anyhow!("invalid quarantine file").into());
+        }
+        let count = buf.get_u32() as usize;
+        if buf.remaining() != count * 4 {
+            return Err(anyhow!(
+                "quarantine file size mismatch, count: {}, remaining: {}",
+                count,
+                buf.remaining()
+            )
+            .into());
+        }
+        Ok((0..count).map(|_| buf.get_u32()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarantine_persist() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("quarantine");
+
+        let quarantine = Quarantine::open(Some(path.clone())).unwrap();
+        assert!(quarantine.is_empty());
+        assert!(quarantine.insert(1).unwrap());
+        assert!(quarantine.insert(3).unwrap());
+        assert!(!quarantine.insert(3).unwrap());
+        drop(quarantine);
+
+        let quarantine = Quarantine::open(Some(path.clone())).unwrap();
+        assert_eq!(quarantine.len(), 2);
+        assert!(quarantine.contains(1));
+        assert!(quarantine.contains(3));
+        assert!(quarantine.remove(1).unwrap());
+        assert!(!quarantine.remove(1).unwrap());
+        drop(quarantine);
+
+        let quarantine = Quarantine::open(Some(path)).unwrap();
+        assert_eq!(quarantine.len(), 1);
+        assert!(quarantine.contains(3));
+    }
+
+    #[test]
+    fn test_quarantine_persist_failure() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path().join("missing").join("quarantine");
+
+        // Nothing is changed in memory if the set fails to persist.
+        let quarantine = Quarantine::open(Some(path)).unwrap();
+        assert!(quarantine.insert(1).is_err());
+        assert!(!quarantine.contains(1));
+        assert!(quarantine.is_empty());
+    }
+}
@@ -0,0 +1,133 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{fmt::Debug, sync::Arc};
+
+use parking_lot::Mutex;
+
+use crate::stats::StoreStats;
+
+/// Notification of the device occupancy crossing a soft quota level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoftQuotaEvent {
+    /// The crossed level, as a ratio of the device capacity.
+    pub level: f64,
+    /// `true` if the occupancy rises above the level, `false` if it falls below.
+    pub rising: bool,
+    /// Ratio of the regions in use, including the regions being written and reclaimed.
+    pub occupancy: f64,
+    pub stats: StoreStats,
+}
+
+/// Listener of soft quota crossings.
+pub trait SoftQuotaListener: Send + Sync + 'static + Debug {
+    /// Called on a background worker of the store, must not block.
+    fn on_cross(&self, event: SoftQuotaEvent);
+}
+
+#[derive(Debug, Clone)]
+pub struct SoftQuotaConfig {
+    /// Occupancy levels to notify when crossed, as ratios of the device capacity, e.g. `[0.7, 0.85, 0.95]`.
+    pub levels: Vec<f64>,
+    pub listener: Arc<dyn SoftQuotaListener>,
+}
+
+/// Tracks the soft quota levels reached by the occupancy.
+#[derive(Debug)]
+pub struct SoftQuota {
+    /// Sorted ascending.
+    levels: Vec<f64>,
+    listener: Arc<dyn SoftQuotaListener>,
+    /// Count of levels reached by the last occupancy.
+    reached: Mutex<usize>,
+}
+
+impl SoftQuota {
+    pub fn new(config: SoftQuotaConfig) -> Self {
+        let mut levels = config.levels;
+        levels.sort_by(|a, b| a.total_cmp(b));
+        Self {
+            levels,
+            listener: config.listener,
+            reached: Mutex::new(0),
+        }
+    }
+
+    /// Notify the listener of the levels crossed since the last update, from the nearest.
+    pub fn update(&self, occupancy: f64, stats: impl FnOnce() -> StoreStats) {
+        let mut reached = self.reached.lock();
+        let current = self.levels.iter().take_while(|level| occupancy >= **level).count();
+        if current == *reached {
+            return;
+        }
+
+        let stats = stats();
+        let event = |index: usize, rising: bool| SoftQuotaEvent {
+            level: self.levels[index],
+            rising,
+            occupancy,
+            stats,
+        };
+        if current > *reached {
+            for index in *reached..current {
+                self.listener.on_cross(event(index, true));
+            }
+        } else {
+            for index in (current..*reached).rev() {
+                self.listener.on_cross(event(index, false));
+            }
+        }
+        *reached = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Recorder(Mutex<Vec<(f64, bool)>>);
+
+    impl SoftQuotaListener for Recorder {
+        fn on_cross(&self, event: SoftQuotaEvent) {
+            self.0.lock().push((event.level, event.rising));
+        }
+    }
+
+    #[test]
+    fn test_soft_quota() {
+        let recorder = Arc::new(Recorder::default());
+        let quota = SoftQuota::new(SoftQuotaConfig {
+            levels: vec![0.95, 0.7, 0.85],
+            listener: recorder.clone(),
+        });
+
+        quota.update(0.5, StoreStats::default);
+        assert!(recorder.0.lock().is_empty());
+
+        quota.update(0.9, StoreStats::default);
+        quota.update(0.88, || unreachable!());
+        assert_eq!(
+            recorder.0.lock().drain(..).collect::<Vec<_>>(),
+            vec![(0.7, true), (0.85, true)]
+        );
+
+        quota.update(0.95, StoreStats::default);
+        quota.update(0.1, StoreStats::default);
+        assert_eq!(
+            recorder.0.lock().drain(..).collect::<Vec<_>>(),
+            vec![(0.95, true), (0.95, false), (0.85, false), (0.7, false)]
+        );
+    }
+}
@@ -13,23 +13,29 @@
 //  limitations under the License.
 
 use std::{
+    collections::BTreeMap,
     sync::{atomic::Ordering, Arc},
     time::Duration,
 };
 
-use bytes::BufMut;
-use foyer_common::code::{Key, Value};
+use foyer_common::{
+    cancel::CancellationToken,
+    code::{Key, Value},
+};
 use foyer_intrusive::{core::adapter::Link, eviction::EvictionPolicy};
-use tokio::sync::broadcast;
 
 use crate::{
     device::Device,
     error::Result,
-    generic::{GenericStore, RegionEntryIter},
+    generic::{GenericStore, RegionEntryIter, YIELD_BUDGET},
+    health::HealthReporter,
+    invariant::Invariants,
     judge::Judges,
     metrics::Metrics,
-    region_manager::{RegionEpItemAdapter, RegionManager},
+    profile,
+    region_manager::{DomainId, RegionEpItemAdapter, RegionManager},
     storage::Storage,
+    tunables::Tunables,
 };
 
 #[derive(Debug)]
@@ -41,7 +47,10 @@ where
     EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
     EL: Link,
 {
-    threshold: usize,
+    /// The eviction domain to reclaim regions from.
+    domain: DomainId,
+
+    tunables: Arc<Tunables>,
 
     store: GenericStore<K, V, D, EP, EL>,
 
@@ -49,7 +58,11 @@ where
 
     metrics: Arc<Metrics>,
 
-    stop_rx: broadcast::Receiver<()>,
+    invariants: Arc<Invariants>,
+
+    health: Arc<HealthReporter>,
+
+    cancel: CancellationToken,
 }
 
 impl<K, V, D, EP, EL> Reclaimer<K, V, D, EP, EL>
@@ -60,57 +73,90 @@ where
     EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
     EL: Link,
 {
+    #[expect(clippy::too_many_arguments)]
     pub fn new(
-        threshold: usize,
+        domain: DomainId,
+        tunables: Arc<Tunables>,
         store: GenericStore<K, V, D, EP, EL>,
         region_manager: Arc<RegionManager<D, EP, EL>>,
         metrics: Arc<Metrics>,
-        stop_rx: broadcast::Receiver<()>,
+        invariants: Arc<Invariants>,
+        health: Arc<HealthReporter>,
+        cancel: CancellationToken,
     ) -> Self {
         Self {
-            threshold,
+            domain,
+            tunables,
             store,
             region_manager,
             metrics,
-            stop_rx,
+            invariants,
+            health,
+            cancel,
         }
     }
 
-    pub async fn run(mut self) -> Result<()> {
-        let mut watch = self.region_manager.clean_regions().watch();
+    pub async fn run(self) -> Result<()> {
+        let mut watch = self.region_manager.clean_regions(self.domain).watch();
         loop {
             tokio::select! {
                 biased;
                 Ok(()) = watch.changed() => {
+                    self.store.update_soft_quota();
                     self.handle().await?;
                 }
-                _ = self.stop_rx.recv() => {
+                _ = self.tunables.changed() => {
+                    self.handle().await?;
+                }
+                _ = self.cancel.cancelled() => {
                     tracing::info!("[reclaimer] exit");
                     return Ok(())
                 }
             }
+            self.health.success();
         }
     }
 
     async fn handle(&self) -> Result<()> {
-        if self.region_manager.clean_regions().len() >= self.threshold {
+        if !self.store.should_reclaim(self.domain) {
             return Ok(());
         }
 
         // TODO(MrCroxx): subscribe evictable region changes.
         let region_id = loop {
-            match self.region_manager.eviction_pop() {
+            // Regions older than the max data age are reclaimed first regardless of the eviction policy.
+            if self.store.max_data_age().is_some()
+                && let Some(id) = self
+                    .region_manager
+                    .eviction_pop_if(self.domain, |id| self.store.is_expired(self.region_manager.region(&id)))
+            {
+                break id;
+            }
+            match self.region_manager.eviction_pop(self.domain) {
                 Some(id) => break id,
                 None => tokio::time::sleep(Duration::from_millis(100)).await,
             }
         };
 
         let _timer = self.metrics.slow_op_duration_reclaim.start_timer();
+        let token = profile::reclaim_start(region_id);
 
         let region = self.region_manager.region(&region_id);
 
         // step 1: drop indices
         let indices = self.store.catalog().take_region(&region_id);
+        let now = self.store.clock().now_millis();
+        for (_, item) in indices.iter() {
+            self.metrics.entry_age_evict.observe(item.age(now).as_secs_f64());
+            if let Some(trace_id) = item.trace_id() {
+                tracing::info!(
+                    "[reclaimer] entry evicted, trace id: {}, sequence: {}, region: {}",
+                    trace_id,
+                    item.sequence(),
+                    region_id
+                );
+            }
+        }
 
         // Must guarantee there is no following reads on the region to be reclaim.
         // Which means there is no unfinished reader or reader who holds index and prepare to read.
@@ -128,6 +174,19 @@ where
             let region = region.clone();
             let metrics = self.metrics.clone();
             let reinsertions = self.store.reinsertions().clone();
+            let codecs = self.store.codecs().clone();
+            // Entries looked up since indexed, dropping them hints the reinsertion budget is too tight.
+            let accessed: BTreeMap<_, _> = indices
+                .iter()
+                .filter(|(_, item)| item.accesses() > 0)
+                .map(|(key, item)| (key.clone(), item.accesses()))
+                .collect();
+            // Reinserted entries keep their trace ids, and are logged again when flushed.
+            let traced: BTreeMap<_, _> = indices
+                .iter()
+                .filter_map(|(key, item)| item.trace_id().map(|trace_id| (key.clone(), trace_id)))
+                .collect();
+            let report = self.tunables.hot_drop_report();
 
             tracing::info!("[reclaimer] begin reinsertion, region: {}", region_id);
 
@@ -138,7 +197,19 @@ where
                     Err(e) => return Err(e),
                 };
 
-                while let Some((key, value)) = iter.next_kv().await? {
+                let mut processed = 0;
+                let mut dropped_hot = vec![];
+                while let Some((key, value, inserted_at)) = iter.next_kv(&codecs).await? {
+                    processed += 1;
+                    if processed % YIELD_BUDGET == 0 {
+                        tokio::task::yield_now().await;
+                    }
+
+                    // Expired entries are dropped instead of being refreshed by reinsertion.
+                    if self.store.is_entry_expired(inserted_at) {
+                        continue;
+                    }
+
                     let weight = key.serialized_len() + value.serialized_len();
 
                     let mut judges = Judges::new(reinsertions.len());
@@ -151,11 +222,23 @@ where
                             let judge = judges.get(index);
                             reinsertion.on_drop(&key, weight, judge);
                         }
+                        if let Some(accesses) = accessed.get(&key) {
+                            metrics.op_count_reinsert_dropped_hot.inc();
+                            if report > 0 {
+                                dropped_hot.push((*accesses, self.store.catalog().hash(&key)));
+                            }
+                        }
                         continue;
                     }
 
                     let mut writer = self.store.writer(key.clone(), weight);
                     writer.set_skippable();
+                    // The entry is still indexed by the reclaimed region.
+                    writer.set_overwrite();
+                    writer.set_inserted_at(inserted_at);
+                    if let Some(trace_id) = traced.get(&key) {
+                        writer.set_trace_id(*trace_id);
+                    }
 
                     if !writer.judge() {
                         continue;
@@ -180,13 +263,28 @@ where
                     metrics.op_bytes_reinsert.inc_by(weight as u64);
                 }
 
+                if !dropped_hot.is_empty() {
+                    let count = dropped_hot.len();
+                    dropped_hot.sort_by(|a, b| b.cmp(a));
+                    dropped_hot.truncate(report);
+                    tracing::warn!(
+                        "[reclaimer] reinsertion dropped {} accessed entries, region: {}, hottest (accesses, key hash): {:?}",
+                        count,
+                        region_id,
+                        dropped_hot
+                    );
+                }
+
                 tracing::info!("[reclaimer] finish reinsertion, region: {}", region_id);
 
                 Ok(true)
             }
         };
 
-        if !self.store.reinsertions().is_empty() {
+        // Entries on a quarantined region are corrupted and must not be reinserted.
+        let quarantined = self.store.is_quarantined(region_id);
+
+        if !self.store.reinsertions().is_empty() && !quarantined {
             match reinsert().await {
                 Ok(true) => {
                     tracing::info!("[reclaimer] reinsertion finish, region: {}", region_id)
@@ -198,23 +296,53 @@ where
             }
         }
 
-        // step 3: wipe region header
+        // step 3: wipe region header and footer, or zero the whole region if it is quarantined
         let align = region.device().align();
-        let mut buf = region.device().io_buffer(align, align);
-        (&mut buf[..]).put_slice(&vec![0; align]);
-        let (res, _buf) = region.device().write(buf, .., region_id, 0).await;
-        res?;
+        let region_size = region.device().region_size();
+        let (len, offsets) = if quarantined {
+            let io_size = region.device().io_size();
+            (io_size, (0..region_size).step_by(io_size).collect())
+        } else {
+            (align, vec![0, region_size - align])
+        };
+        for offset in offsets {
+            let mut buf = region.device().io_buffer(len, len);
+            buf.fill(0);
+            let (res, _buf) = region.device().write(buf, .., region_id, offset).await;
+            if let Err(e) = res
+                && self.invariants.violated("reclaimer", Some(region_id), e)
+            {
+                // The region is not released, its entries would be recovered otherwise. It is pushed back to be
+                // reclaimed again, which retries the wipe.
+                self.region_manager.eviction_push(region_id);
+                return Ok(());
+            }
+        }
+        region.invalidate_header();
+        if quarantined
+            && let Err(e) = self.store.release_quarantine(region_id)
+            && self.invariants.violated("reclaimer", Some(region_id), e)
+        {
+            return Ok(());
+        }
+        region.stats().reset();
 
         // step 4: send clean region
-        self.region_manager.clean_regions().release(region_id);
+        self.region_manager.release_clean_region(region_id);
+        profile::reclaim_end(token, region_id);
 
         tracing::info!("[reclaimer] finish reclaim task, region: {}", region_id);
 
         self.metrics
             .op_bytes_reclaim
             .inc_by(region.device().region_size() as u64);
+        self.store.record_reclaimed(region.device().region_size());
         self.metrics.total_bytes.sub(region.device().region_size() as u64);
 
+        if let Some(throttle) = self.tunables.reclaim_throttle() {
+            tokio::time::sleep(throttle).await;
+        }
+
         Ok(())
     }
 }
@@ -15,49 +15,60 @@
 use std::{
     collections::btree_map::{BTreeMap, Entry},
     fmt::Debug,
-    ops::RangeBounds,
+    ops::{Deref, RangeBounds},
     sync::{
-        atomic::{AtomicUsize, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
+    time::Duration,
 };
 
 use bytes::{Buf, BufMut};
-use foyer_common::range::RangeBoundsExt;
+use foyer_common::{
+    bits::{align_down, align_up, is_aligned},
+    buf::AlignedBuffer,
+    range::RangeBoundsExt,
+};
 use parking_lot::Mutex;
-use tokio::sync::oneshot;
+use tokio::sync::{oneshot, OwnedRwLockReadGuard, RwLock, RwLockWriteGuard};
 
 use crate::{
-    device::{BufferAllocator, Device, DeviceExt},
+    device::{mmap::MappedSlice, Device, DeviceExt},
     error::Result,
+    generic::{checksum, EntryPadding},
 };
 
 pub type RegionId = u32;
 
 pub const REGION_MAGIC: u64 = 0x19970327;
 
-#[derive(Debug)]
+pub const FOOTER_MAGIC: u64 = 0x20240327;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Version {
     V1,
+    /// Region header records the creation time and the entry padding. Entry header records the compressed and
+    /// uncompressed value length, the insertion time and the codecs of the value. Sealed regions end with a footer of
+    /// the entry directory and the checksum of the region data.
+    V2,
 }
 
 impl Version {
     pub fn latest() -> Self {
-        Self::V1
+        Self::V2
     }
 
     pub fn to_u64(&self) -> u64 {
         match self {
             Version::V1 => 1,
+            Version::V2 => 2,
         }
     }
 }
 
 impl From<Version> for u64 {
     fn from(value: Version) -> Self {
-        match value {
-            Version::V1 => 1,
-        }
+        value.to_u64()
     }
 }
 
@@ -67,46 +78,285 @@ impl TryFrom<u64> for Version {
     fn try_from(value: u64) -> std::result::Result<Self, Self::Error> {
         match value {
             1 => Ok(Self::V1),
-            v => Err(anyhow::anyhow!("invalid region format version: {}", v)),
+            2 => Ok(Self::V2),
+            v => Err(anyhow::anyhow!(
+                "unknown region format version: {}, the region may be written by a newer release",
+                v
+            )),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct RegionHeader {
     /// magic number to decide a valid region
     pub magic: u64,
     /// format version
     pub version: Version,
+    /// creation time in milliseconds since unix epoch
+    pub created_at: u64,
+    /// how entries are padded in the region
+    pub padding: EntryPadding,
 }
 
 impl RegionHeader {
     pub fn write(&self, mut buf: &mut [u8]) {
         buf.put_u64(self.magic);
         buf.put_u64(self.version.to_u64());
+        buf.put_u64(self.created_at);
+        buf.put_u64(self.padding.to_u64());
     }
 
-    pub fn read(mut buf: &[u8]) -> std::result::Result<Self, anyhow::Error> {
+    /// Returns `None` if the magic mismatches, e.g. the region is clean, or an error if the header is written in an
+    /// unknown format.
+    pub fn read(mut buf: &[u8]) -> std::result::Result<Option<Self>, anyhow::Error> {
         let magic = buf.get_u64();
         if magic != REGION_MAGIC {
+            return Ok(None);
+        }
+        let version = buf.get_u64().try_into()?;
+        let created_at = buf.get_u64();
+        let padding = buf.get_u64().try_into()?;
+        Ok(Some(Self {
+            magic,
+            version,
+            created_at,
+            padding,
+        }))
+    }
+}
+
+/// Location and identity of an entry in a sealed region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryRecord {
+    pub offset: u32,
+    /// Padded on-disk bytes of the entry.
+    pub len: u32,
+    pub sequence: u64,
+    /// When the entry is inserted, in milliseconds since unix epoch.
+    pub inserted_at: u64,
+    /// Uncompressed key and value bytes.
+    pub logical: u32,
+    /// Serialized key.
+    pub key: Vec<u8>,
+}
+
+impl DirectoryRecord {
+    pub fn serialized_len(&self) -> usize {
+        4 + 4 + 8 + 8 + 4 + 4 + self.key.len()
+    }
+
+    pub fn write(&self, buf: &mut Vec<u8>) {
+        buf.put_u32(self.offset);
+        buf.put_u32(self.len);
+        buf.put_u64(self.sequence);
+        buf.put_u64(self.inserted_at);
+        buf.put_u32(self.logical);
+        buf.put_u32(self.key.len() as u32);
+        buf.put_slice(&self.key);
+    }
+
+    pub fn read(buf: &mut &[u8]) -> std::result::Result<Self, anyhow::Error> {
+        if buf.len() < 32 {
+            return Err(anyhow::anyhow!("directory record truncated"));
+        }
+        let offset = buf.get_u32();
+        let len = buf.get_u32();
+        let sequence = buf.get_u64();
+        let inserted_at = buf.get_u64();
+        let logical = buf.get_u32();
+        let key_len = buf.get_u32() as usize;
+        if buf.len() < key_len {
+            return Err(anyhow::anyhow!("directory record key truncated"));
+        }
+        let key = buf[..key_len].to_vec();
+        buf.advance(key_len);
+        Ok(Self {
+            offset,
+            len,
+            sequence,
+            inserted_at,
+            logical,
+            key,
+        })
+    }
+}
+
+/// Directory of the entries in a region, written at the end of the region when it is sealed.
+///
+/// # Format
+///
+/// | <padding> | records | magic | created at | count | records len | data len | data checksum | checksum |
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionFooter {
+    /// Creation time of the region it belongs to, a footer left by a former use of the region is ignored.
+    pub created_at: u64,
+    /// Bytes of the region data from the region start, including the region header.
+    pub data_len: u64,
+    /// Checksum of the region data, computed while flushing.
+    pub data_checksum: u64,
+    pub records: Vec<DirectoryRecord>,
+}
+
+impl RegionFooter {
+    pub const TRAILER_LEN: usize = 8 + 8 + 4 + 4 + 8 + 8 + 8;
+
+    /// Aligned on-disk bytes of the footer.
+    pub fn len(records_len: usize, align: usize) -> usize {
+        align_up(align, records_len + Self::TRAILER_LEN)
+    }
+
+    /// Write the footer to the end of `buf`.
+    pub fn write(&self, buf: &mut [u8]) {
+        let mut records = Vec::with_capacity(self.records.iter().map(|r| r.serialized_len()).sum());
+        for record in self.records.iter() {
+            record.write(&mut records);
+        }
+        let start = buf.len() - Self::TRAILER_LEN - records.len();
+        buf[start..start + records.len()].copy_from_slice(&records);
+
+        let len = buf.len();
+        let mut trailer = &mut buf[len - Self::TRAILER_LEN..];
+        trailer.put_u64(FOOTER_MAGIC);
+        trailer.put_u64(self.created_at);
+        trailer.put_u32(self.records.len() as u32);
+        trailer.put_u32(records.len() as u32);
+        trailer.put_u64(self.data_len);
+        trailer.put_u64(self.data_checksum);
+        trailer.put_u64(checksum(&records));
+    }
+
+    /// Returns the length of the records if the trailer at the end of `buf` is valid.
+    pub fn records_len(buf: &[u8]) -> Option<usize> {
+        let mut trailer = &buf[buf.len() - Self::TRAILER_LEN..];
+        if trailer.get_u64() != FOOTER_MAGIC {
+            return None;
+        }
+        trailer.advance(8 + 4);
+        Some(trailer.get_u32() as usize)
+    }
+
+    /// Read the footer from the end of `buf`.
+    pub fn read(buf: &[u8]) -> std::result::Result<Self, anyhow::Error> {
+        let mut trailer = &buf[buf.len() - Self::TRAILER_LEN..];
+        let magic = trailer.get_u64();
+        if magic != FOOTER_MAGIC {
             return Err(anyhow::anyhow!(
-                "region magic mismatch, magic: {}, expected: {}",
+                "footer magic mismatch, magic: {}, expected: {}",
                 magic,
-                REGION_MAGIC
+                FOOTER_MAGIC
             ));
         }
-        let version = buf.get_u64().try_into()?;
-        Ok(Self { magic, version })
+        let created_at = trailer.get_u64();
+        let count = trailer.get_u32() as usize;
+        let records_len = trailer.get_u32() as usize;
+        let data_len = trailer.get_u64();
+        let data_checksum = trailer.get_u64();
+        let expected = trailer.get_u64();
+
+        let start = buf
+            .len()
+            .checked_sub(records_len + Self::TRAILER_LEN)
+            .ok_or_else(|| anyhow::anyhow!("footer records len {} out of range", records_len))?;
+        let mut records = &buf[start..start + records_len];
+        let checksum = checksum(records);
+        if checksum != expected {
+            return Err(anyhow::anyhow!(
+                "footer checksum mismatch, expected: {}, got: {}",
+                expected,
+                checksum
+            ));
+        }
+
+        let mut res = Vec::with_capacity(count);
+        for _ in 0..count {
+            res.push(DirectoryRecord::read(&mut records)?);
+        }
+        Ok(Self {
+            created_at,
+            data_len,
+            data_checksum,
+            records: res,
+        })
+    }
+}
+
+/// Bytes of entries written to a region, reset when the region is reclaimed.
+#[derive(Debug, Default)]
+pub struct RegionStats {
+    /// Uncompressed key and value bytes.
+    logical_bytes: AtomicUsize,
+    /// Aligned on-disk bytes, including header and padding.
+    physical_bytes: AtomicUsize,
+}
+
+impl RegionStats {
+    pub fn add(&self, logical: usize, physical: usize) {
+        self.logical_bytes.fetch_add(logical, Ordering::Relaxed);
+        self.physical_bytes.fetch_add(physical, Ordering::Relaxed);
+    }
+
+    pub fn sub(&self, logical: usize, physical: usize) {
+        self.logical_bytes.fetch_sub(logical, Ordering::Relaxed);
+        self.physical_bytes.fetch_sub(physical, Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.logical_bytes.store(0, Ordering::Relaxed);
+        self.physical_bytes.store(0, Ordering::Relaxed);
+    }
+
+    pub fn logical_bytes(&self) -> usize {
+        self.logical_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn physical_bytes(&self) -> usize {
+        self.physical_bytes.load(Ordering::Relaxed)
     }
 }
 
+/// Region data loaded by view, read into a buffer or backed by the device mapping.
 #[derive(Debug)]
-pub struct RegionInner<A>
-where
-    A: BufferAllocator,
-{
+pub struct RegionSlice(RegionSliceInner);
+
+#[derive(Debug)]
+enum RegionSliceInner {
+    Buffer(Arc<AlignedBuffer>),
+    Mapped {
+        slice: MappedSlice,
+        _view: RegionView,
+        _guard: OwnedRwLockReadGuard<()>,
+    },
+}
+
+impl RegionSlice {
+    /// Returns `true` if the data is backed by the device mapping.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.0, RegionSliceInner::Mapped { .. })
+    }
+}
+
+impl Deref for RegionSlice {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        match &self.0 {
+            RegionSliceInner::Buffer(buf) => buf,
+            RegionSliceInner::Mapped { slice, .. } => slice,
+        }
+    }
+}
+
+impl AsRef<[u8]> for RegionSlice {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+#[derive(Debug)]
+pub struct RegionInner {
     #[expect(clippy::type_complexity)]
-    waits: BTreeMap<(usize, usize), Vec<oneshot::Sender<Result<Arc<Vec<u8, A>>>>>>,
+    waits: BTreeMap<(usize, usize), Vec<oneshot::Sender<Result<Arc<AlignedBuffer>>>>>,
 }
 
 #[derive(Debug, Clone)]
@@ -116,11 +366,28 @@ where
 {
     id: RegionId,
 
-    inner: Arc<Mutex<RegionInner<D::IoBufferAllocator>>>,
+    inner: Arc<Mutex<RegionInner>>,
 
     device: D,
 
     refs: Arc<AtomicUsize>,
+
+    stats: Arc<RegionStats>,
+
+    /// Creation time of the data in the region, in milliseconds since unix epoch.
+    created_at: Arc<AtomicU64>,
+
+    /// Parsed region header, with the epoch it is cached in.
+    header: Arc<Mutex<Option<(u64, RegionHeader)>>>,
+
+    /// Advanced every time the region is reclaimed, a header cached in an older epoch is invalid.
+    epoch: Arc<AtomicU64>,
+
+    /// Held shared by device reads and exclusively by in-place overwrites, so no read observes a half-written entry.
+    overwrite: Arc<RwLock<()>>,
+
+    /// Whether the region is in the eviction policy, so no more entries are appended to it until reclaimed.
+    sealed: Arc<AtomicBool>,
 }
 
 impl<D> Region<D>
@@ -134,6 +401,12 @@ where
             inner: Arc::new(Mutex::new(inner)),
             device,
             refs: Arc::new(AtomicUsize::default()),
+            stats: Arc::new(RegionStats::default()),
+            created_at: Arc::new(AtomicU64::new(0)),
+            header: Arc::new(Mutex::new(None)),
+            epoch: Arc::new(AtomicU64::new(0)),
+            overwrite: Arc::new(RwLock::new(())),
+            sealed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -151,25 +424,174 @@ where
         &self.refs
     }
 
+    pub fn stats(&self) -> &Arc<RegionStats> {
+        &self.stats
+    }
+
+    pub fn created_at(&self) -> u64 {
+        self.created_at.load(Ordering::Relaxed)
+    }
+
+    pub fn set_created_at(&self, created_at: u64) {
+        self.created_at.store(created_at, Ordering::Relaxed);
+    }
+
+    /// Age of the data in the region at `now`, in milliseconds since unix epoch.
+    pub fn age(&self, now: u64) -> Duration {
+        Duration::from_millis(now.saturating_sub(self.created_at()))
+    }
+
+    pub fn is_sealed(&self) -> bool {
+        self.sealed.load(Ordering::Acquire)
+    }
+
+    pub fn set_sealed(&self, sealed: bool) {
+        self.sealed.store(sealed, Ordering::Release);
+    }
+
+    /// Block reads of the region until the returned guard is dropped, for overwriting entries in place.
+    pub async fn exclusive(&self) -> RwLockWriteGuard<'_, ()> {
+        self.overwrite.write().await
+    }
+
+    /// Returns the region header, or `None` if the region has no header, e.g. it is clean.
+    ///
+    /// Returns an error if the header is written in an unknown format, instead of taking the region as clean and
+    /// overwriting the data of a newer release.
+    ///
+    /// A valid header is read from device only once until the region is reclaimed.
+    pub async fn header(&self) -> Result<Option<RegionHeader>> {
+        let epoch = self.epoch.load(Ordering::Acquire);
+        if let Some((e, header)) = &*self.header.lock()
+            && *e == epoch
+        {
+            return Ok(Some(header.clone()));
+        }
+
+        let Some(slice) = self.load_range(..self.device.align()).await? else {
+            return Ok(None);
+        };
+        let Some(header) = RegionHeader::read(slice.as_ref())? else {
+            return Ok(None);
+        };
+
+        // A header read in an outdated epoch will never be returned.
+        *self.header.lock() = Some((epoch, header.clone()));
+        Ok(Some(header))
+    }
+
+    /// Cache the header written to the region.
+    pub fn set_header(&self, header: RegionHeader) {
+        let epoch = self.epoch.load(Ordering::Acquire);
+        *self.header.lock() = Some((epoch, header));
+    }
+
+    /// Invalidate the cached header, must be called after the header on device is wiped.
+    pub fn invalidate_header(&self) {
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+        *self.header.lock() = None;
+    }
+
+    /// Drop the cached header, it will be read from device again on demand.
+    ///
+    /// Returns the released bytes.
+    pub fn drop_header(&self) -> usize {
+        match self.header.lock().take() {
+            Some(_) => std::mem::size_of::<(u64, RegionHeader)>(),
+            None => 0,
+        }
+    }
+
+    /// Returns the footer of the region, or `None` if the region is not sealed or the footer is corrupted.
+    ///
+    /// The caller must check the footer belongs to the current use of the region by its creation time.
+    pub async fn footer(&self) -> Result<Option<RegionFooter>> {
+        let align = self.device.align();
+        let region_size = self.device.region_size();
+
+        let Some(block) = self.load_range(region_size - align..region_size).await? else {
+            return Ok(None);
+        };
+        let Some(records_len) = RegionFooter::records_len(block.as_ref()) else {
+            return Ok(None);
+        };
+        let len = RegionFooter::len(records_len, align);
+        if len > region_size - align {
+            return Ok(None);
+        }
+
+        let buf = if len == align {
+            block
+        } else {
+            drop(block);
+            let Some(buf) = self.load_range(region_size - len..region_size).await? else {
+                return Ok(None);
+            };
+            buf
+        };
+        match RegionFooter::read(buf.as_ref()) {
+            Ok(footer) => Ok(Some(footer)),
+            Err(e) => {
+                tracing::warn!("skip corrupted footer of region {}: {}", self.id, e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Returns `true` if the region data matches the checksum recorded in `footer`, with one sequential read.
+    pub async fn verify_data(&self, footer: &RegionFooter) -> Result<bool> {
+        let len = footer.data_len as usize;
+        if len == 0 || len > self.device.region_size() || !is_aligned(self.device.align(), len) {
+            return Ok(false);
+        }
+        let Some(buf) = self.load_range(0..len).await? else {
+            return Ok(false);
+        };
+        Ok(checksum(buf.as_ref()) == footer.data_checksum)
+    }
+
     /// Load region data by view from device.
-    #[expect(clippy::type_complexity)]
+    ///
+    /// The data of a sealed region is backed by the device mapping if the device maps its data.
     #[tracing::instrument(skip(self, view))]
-    pub async fn load(&self, view: RegionView) -> Result<Option<Arc<Vec<u8, D::IoBufferAllocator>>>> {
-        let res = self
-            .load_range(view.offset as usize..view.offset as usize + view.len as usize)
-            .await;
+    pub async fn load(&self, view: RegionView) -> Result<Option<RegionSlice>> {
+        let align = self.device.align();
+        let (start, end) = (view.offset as usize, view.offset as usize + view.len as usize);
+
+        if self.is_sealed()
+            && let Some(slice) = self.device.map(self.id, start..end)
+        {
+            // The slice holds the view and the guard, so the data is neither reclaimed nor overwritten until dropped.
+            let guard = self.overwrite.clone().read_owned().await;
+            return Ok(Some(RegionSlice(RegionSliceInner::Mapped {
+                slice,
+                _view: view,
+                _guard: guard,
+            })));
+        }
+
+        let res = if is_aligned(align, start) && is_aligned(align, end) {
+            self.load_range(start..end).await
+        } else {
+            // A packed entry shares blocks with its neighbours, load the blocks and copy the entry out.
+            let (align_start, align_end) = (align_down(align, start), align_up(align, end));
+            match self.load_range(align_start..align_end).await {
+                Ok(Some(blocks)) => {
+                    let mut buf = self.device.io_buffer(0, align_up(align, end - start));
+                    buf.extend_from_slice(&blocks[start - align_start..end - align_start]);
+                    Ok(Some(Arc::new(buf)))
+                }
+                res => res,
+            }
+        };
         // drop view after load finish
         drop(view);
-        res
+        res.map(|buf| buf.map(|buf| RegionSlice(RegionSliceInner::Buffer(buf))))
     }
 
     /// Load region data with given `range` from device.
-    #[expect(clippy::type_complexity)]
     #[tracing::instrument(skip(self, range), fields(start, end))]
-    pub async fn load_range(
-        &self,
-        range: impl RangeBounds<usize>,
-    ) -> Result<Option<Arc<Vec<u8, D::IoBufferAllocator>>>> {
+    pub async fn load_range(&self, range: impl RangeBounds<usize>) -> Result<Option<Arc<AlignedBuffer>>> {
         let range = range.bounds(0..self.device.region_size());
 
         let rx = {
@@ -201,7 +623,10 @@ where
         // otherwise, read from device
         let region = self.id;
 
-        let buf = match self.device.load(region, range.start..range.end).await {
+        let guard = self.overwrite.read().await;
+        let res = self.device.load(region, range.start..range.end).await;
+        drop(guard);
+        let buf = match res {
             Err(e) => {
                 self.cleanup(range.start, range.end)?;
                 return Err(e.into());
@@ -215,8 +640,9 @@ where
         let buf = Arc::new(buf);
 
         if let Some(txs) = self.inner.lock().waits.remove(&(range.start, range.end)) {
+            // A waiter may have given up, e.g. on the read timeout.
             for tx in txs {
-                tx.send(Ok(buf.clone())).unwrap()
+                let _ = tx.send(Ok(buf.clone()));
             }
         }
 
@@ -235,8 +661,7 @@ where
     fn cleanup(&self, start: usize, end: usize) -> Result<()> {
         if let Some(txs) = self.inner.lock().waits.remove(&(start, end)) {
             for tx in txs {
-                tx.send(Err(anyhow::anyhow!("cancelled by previous error").into()))
-                    .unwrap()
+                let _ = tx.send(Err(anyhow::anyhow!("cancelled by previous error").into()));
             }
         }
         Ok(())
@@ -286,3 +711,115 @@ impl RegionView {
         &self.refs
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BufMut;
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::device::{
+        fs::{FsDevice, FsDeviceConfig, FsReadMode},
+        ReadIoPolicy,
+    };
+
+    async fn write_block(device: &FsDevice, region: RegionId, header: Option<&RegionHeader>) {
+        let align = device.align();
+        let mut buf = device.io_buffer(align, align);
+        (&mut buf[..]).put_slice(&vec![0; align]);
+        if let Some(header) = header {
+            header.write(&mut buf[..]);
+        }
+        let (res, _buf) = device.write(buf, .., region, 0).await;
+        res.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_region_header_cache() {
+        let tempdir = tempdir().unwrap();
+
+        let device = FsDevice::open(FsDeviceConfig {
+            dir: tempdir.path().into(),
+            capacity: 256 * 1024,     // 256 KiB
+            file_capacity: 64 * 1024, // 64 KiB
+            align: 4 * 1024,          // 4 KiB
+            io_size: 16 * 1024,       // 16 KiB
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        })
+        .await
+        .unwrap();
+
+        let region = Region::new(0, device.clone());
+        assert_eq!(region.header().await.unwrap(), None);
+
+        let header = RegionHeader {
+            magic: REGION_MAGIC,
+            version: Version::latest(),
+            created_at: 42,
+            padding: EntryPadding::Packed,
+        };
+        write_block(&device, 0, Some(&header)).await;
+        assert_eq!(region.header().await.unwrap(), Some(header.clone()));
+
+        // The cached header is returned without reading the device.
+        write_block(&device, 0, None).await;
+        assert_eq!(region.header().await.unwrap(), Some(header.clone()));
+
+        region.invalidate_header();
+        assert_eq!(region.header().await.unwrap(), None);
+
+        region.set_header(header.clone());
+        assert_eq!(region.header().await.unwrap(), Some(header));
+
+        // A header of an unknown format version is an error.
+        let region = Region::new(1, device.clone());
+        let align = device.align();
+        let mut buf = device.io_buffer(align, align);
+        (&mut buf[..]).put_slice(&vec![0; align]);
+        let mut slice = &mut buf[..];
+        slice.put_u64(REGION_MAGIC);
+        slice.put_u64(Version::latest().to_u64() + 1);
+        let (res, _buf) = device.write(buf, .., 1, 0).await;
+        res.unwrap();
+        assert!(region.header().await.is_err());
+    }
+
+    #[test]
+    fn test_region_footer() {
+        const ALIGN: usize = 4096;
+
+        let footer = RegionFooter {
+            created_at: 42,
+            data_len: 8 * ALIGN as u64,
+            data_checksum: 42,
+            records: (0..200u64)
+                .map(|i| DirectoryRecord {
+                    offset: i as u32 * 64,
+                    len: 64,
+                    sequence: i,
+                    inserted_at: 42 + i,
+                    logical: 32,
+                    key: i.to_be_bytes().to_vec(),
+                })
+                .collect(),
+        };
+        let records_len = footer.records.iter().map(|r| r.serialized_len()).sum();
+
+        // The footer spans multiple blocks.
+        let len = RegionFooter::len(records_len, ALIGN);
+        assert_eq!(len, 2 * ALIGN);
+        let mut buf = vec![0; len];
+        footer.write(&mut buf);
+
+        assert_eq!(RegionFooter::records_len(&buf[ALIGN..]), Some(records_len));
+        assert_eq!(RegionFooter::read(&buf).unwrap(), footer);
+
+        // A corrupted record is detected by the checksum.
+        buf[len - RegionFooter::TRAILER_LEN - 1] ^= 1;
+        assert!(RegionFooter::read(&buf).is_err());
+
+        assert_eq!(RegionFooter::records_len(&[0; ALIGN]), None);
+    }
+}
@@ -12,7 +12,13 @@
 //  See the License for the specific language governing permissions and
 //  limitations under the License.
 
-use std::sync::Arc;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread::available_parallelism,
+};
 
 use foyer_common::async_queue::AsyncQueue;
 use foyer_intrusive::{
@@ -20,11 +26,12 @@ use foyer_intrusive::{
     eviction::{EvictionPolicy, EvictionPolicyExt},
     intrusive_adapter, key_adapter,
 };
-use parking_lot::RwLock;
+use itertools::Itertools;
+use parking_lot::{Mutex, RwLock};
 
 use crate::{
     device::Device,
-    region::{Region, RegionId},
+    region::{Region, RegionId, RegionView},
 };
 
 #[derive(Debug)]
@@ -39,6 +46,27 @@ where
 intrusive_adapter! { pub RegionEpItemAdapter<L> = Arc<RegionEpItem<L>>: RegionEpItem<L> { link: L } where L: Link }
 key_adapter! { RegionEpItemAdapter<L> = RegionEpItem<L> { id: RegionId } where L: Link }
 
+/// Identifier of an eviction domain in [`RegionManager`].
+pub type DomainId = usize;
+
+/// The only domain of a [`RegionManager`] created with [`RegionManager::new`].
+pub const DEFAULT_DOMAIN: DomainId = 0;
+
+/// A partition of regions with its own eviction policy instance and clean regions.
+#[derive(Debug)]
+struct EvictionDomain<EP> {
+    /// Empty regions.
+    clean_regions: AsyncQueue<RegionId>,
+    /// Count of regions in the domain.
+    regions: usize,
+
+    /// Eviction policy.
+    eviction: RwLock<EP>,
+
+    /// Sharded buffers of region accesses not applied to the eviction policy yet.
+    access_buffers: Vec<Mutex<Vec<RegionId>>>,
+}
+
 #[derive(Debug)]
 pub struct RegionManager<D, EP, EL>
 where
@@ -46,14 +74,23 @@ where
     EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
     EL: Link,
 {
-    /// Empty regions.
-    clean_regions: AsyncQueue<RegionId>,
-
     regions: Vec<Region<D>>,
     items: Vec<Arc<RegionEpItem<EL>>>,
 
-    /// Eviction policy.
-    eviction: RwLock<EP>,
+    domains: Vec<EvictionDomain<EP>>,
+    /// Domain of each region.
+    region_domains: Vec<DomainId>,
+
+    access_record_batch: usize,
+}
+
+/// Index of the access buffer shard used by the current thread.
+fn access_buffer_shard() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SHARD: usize = NEXT.fetch_add(1, Ordering::Relaxed);
+    }
+    SHARD.with(|shard| *shard)
 }
 
 impl<D, EP, EL> RegionManager<D, EP, EL>
@@ -62,9 +99,23 @@ where
     EP: EvictionPolicy<Adapter = RegionEpItemAdapter<EL>>,
     EL: Link,
 {
-    pub fn new(region_count: usize, eviction_config: EP::Config, device: D) -> Self {
-        let eviction = EP::new(eviction_config);
-        let clean_regions = AsyncQueue::new();
+    /// Create a region manager with all regions in [`DEFAULT_DOMAIN`].
+    pub fn new(region_count: usize, eviction_config: EP::Config, access_record_batch: usize, device: D) -> Self {
+        Self::with_domains(&[region_count], eviction_config, access_record_batch, device)
+    }
+
+    /// Create a region manager with an eviction domain for each of `domain_regions`, which is the region count of
+    /// the domain.
+    ///
+    /// Regions are assigned to domains in order of region id.
+    pub fn with_domains(
+        domain_regions: &[usize],
+        eviction_config: EP::Config,
+        access_record_batch: usize,
+        device: D,
+    ) -> Self {
+        assert!(!domain_regions.is_empty());
+        let region_count = domain_regions.iter().sum::<usize>();
 
         let mut regions = Vec::with_capacity(region_count);
         let mut items = Vec::with_capacity(region_count);
@@ -80,11 +131,35 @@ where
             items.push(item);
         }
 
+        let region_domains = domain_regions
+            .iter()
+            .enumerate()
+            .flat_map(|(domain, &count)| std::iter::repeat(domain).take(count))
+            .collect();
+
+        let shards = if access_record_batch == 0 {
+            0
+        } else {
+            available_parallelism().map(|n| n.get()).unwrap_or(1)
+        };
+        let domains = domain_regions
+            .iter()
+            .map(|&regions| EvictionDomain {
+                clean_regions: AsyncQueue::new(),
+                regions,
+                eviction: RwLock::new(EP::new(eviction_config.clone())),
+                access_buffers: (0..shards)
+                    .map(|_| Mutex::new(Vec::with_capacity(access_record_batch)))
+                    .collect(),
+            })
+            .collect();
+
         Self {
-            clean_regions,
             regions,
             items,
-            eviction: RwLock::new(eviction),
+            domains,
+            region_domains,
+            access_record_batch,
         }
     }
 
@@ -92,24 +167,240 @@ where
         &self.regions[*id as usize]
     }
 
+    /// Count of the eviction domains.
+    pub fn domains(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Count of regions in the domain.
+    pub fn domain_regions(&self, domain: DomainId) -> usize {
+        self.domains[domain].regions
+    }
+
+    /// Domain the region belongs to.
+    pub fn domain_of(&self, id: &RegionId) -> DomainId {
+        self.region_domains[*id as usize]
+    }
+
     #[tracing::instrument(skip(self))]
     pub fn record_access(&self, id: &RegionId) {
-        let mut eviction = self.eviction.write();
+        let domain = &self.domains[self.domain_of(id)];
         let item = &self.items[*id as usize];
-        if item.link.is_linked() {
-            eviction.access(&self.items[*id as usize]);
+        if domain.eviction.read().access_shared(item) {
+            return;
+        }
+
+        if domain.access_buffers.is_empty() {
+            let mut eviction = domain.eviction.write();
+            if item.link.is_linked() {
+                eviction.access(item);
+            }
+            return;
+        }
+
+        let batch = {
+            let mut buffer = domain.access_buffers[access_buffer_shard() % domain.access_buffers.len()].lock();
+            buffer.push(*id);
+            if buffer.len() < self.access_record_batch {
+                return;
+            }
+            std::mem::replace(&mut *buffer, Vec::with_capacity(self.access_record_batch))
+        };
+
+        let mut eviction = domain.eviction.write();
+        self.apply_accesses(&mut eviction, batch);
+    }
+
+    /// Apply all buffered accesses of the domain to its eviction policy.
+    fn flush_accesses(&self, domain: &EvictionDomain<EP>, eviction: &mut EP) {
+        for buffer in domain.access_buffers.iter() {
+            let batch = std::mem::take(&mut *buffer.lock());
+            self.apply_accesses(eviction, batch);
         }
     }
 
-    pub fn clean_regions(&self) -> &AsyncQueue<RegionId> {
-        &self.clean_regions
+    fn apply_accesses(&self, eviction: &mut EP, batch: Vec<RegionId>) {
+        for id in batch {
+            let item = &self.items[id as usize];
+            if item.link.is_linked() {
+                eviction.access(item);
+            }
+        }
     }
 
+    /// Clean regions of the domain.
+    pub fn clean_regions(&self, domain: DomainId) -> &AsyncQueue<RegionId> {
+        &self.domains[domain].clean_regions
+    }
+
+    /// Release the region to the clean regions of its domain.
+    pub fn release_clean_region(&self, region_id: RegionId) {
+        self.clean_regions(self.domain_of(&region_id)).release(region_id);
+    }
+
+    /// Push the region to the eviction policy of its domain, the region is sealed until popped.
     pub fn eviction_push(&self, region_id: RegionId) {
-        self.eviction.write().push(self.items[region_id as usize].clone());
+        let domain = &self.domains[self.domain_of(&region_id)];
+        self.region(&region_id).set_sealed(true);
+        domain.eviction.write().push(self.items[region_id as usize].clone());
+    }
+
+    pub fn eviction_pop(&self, domain: DomainId) -> Option<RegionId> {
+        let domain = &self.domains[domain];
+        let mut eviction = domain.eviction.write();
+        self.flush_accesses(domain, &mut eviction);
+        let id = eviction.pop().map(|item| item.id)?;
+        self.region(&id).set_sealed(false);
+        Some(id)
+    }
+
+    /// Returns the regions in the eviction policy of the domain, in the order of the policy's iteration.
+    ///
+    /// For policies that keep an order, the first region is the next to evict.
+    pub fn eviction_order(&self, domain: DomainId) -> Vec<RegionId> {
+        let domain = &self.domains[domain];
+        let mut eviction = domain.eviction.write();
+        self.flush_accesses(domain, &mut eviction);
+        eviction.iter().map(|item| item.id).collect()
+    }
+
+    /// Returns views of the whole regions in the eviction policies of all domains.
+    ///
+    /// These regions are sealed, and the views hold them from being reclaimed until dropped.
+    pub fn pin_evictable_regions(&self) -> Vec<RegionView> {
+        self.domains
+            .iter()
+            .flat_map(|domain| {
+                let eviction = domain.eviction.read();
+                eviction
+                    .iter()
+                    .map(|item| {
+                        let region = self.region(&item.id);
+                        region.view(0, region.device().region_size() as u32)
+                    })
+                    .collect_vec()
+            })
+            .collect()
     }
 
-    pub fn eviction_pop(&self) -> Option<RegionId> {
-        self.eviction.write().pop().map(|item| item.id)
+    /// Pop the first region of the domain in eviction order that satisfies `f`.
+    pub fn eviction_pop_if(&self, domain: DomainId, f: impl Fn(RegionId) -> bool) -> Option<RegionId> {
+        let domain = &self.domains[domain];
+        let mut eviction = domain.eviction.write();
+        self.flush_accesses(domain, &mut eviction);
+        let item = eviction.iter().find(|item| f(item.id)).cloned()?;
+        let id = eviction.remove(&item).id;
+        self.region(&id).set_sealed(false);
+        Some(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use foyer_intrusive::eviction::lru::{Lru, LruConfig, LruLink};
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::device::{
+        fs::{FsDevice, FsDeviceConfig, FsReadMode},
+        ReadIoPolicy,
+    };
+
+    #[tokio::test]
+    async fn test_access_record_batch() {
+        let tempdir = tempdir().unwrap();
+
+        let device = FsDevice::open(FsDeviceConfig {
+            dir: tempdir.path().into(),
+            capacity: 256 * 1024,     // 256 KiB
+            file_capacity: 64 * 1024, // 64 KiB
+            align: 4 * 1024,          // 4 KiB
+            io_size: 16 * 1024,       // 16 KiB
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        })
+        .await
+        .unwrap();
+
+        let region_manager = RegionManager::<_, Lru<RegionEpItemAdapter<LruLink>>, LruLink>::new(
+            device.regions(),
+            LruConfig {
+                lru_insertion_point_fraction: 0.0,
+            },
+            16,
+            device,
+        );
+
+        for id in 0..3 {
+            region_manager.eviction_push(id);
+        }
+
+        // Buffered accesses are applied before picking the victim.
+        region_manager.record_access(&0);
+        region_manager.record_access(&1);
+        assert_eq!(region_manager.eviction_pop(DEFAULT_DOMAIN), Some(2));
+        assert!(region_manager.domains[DEFAULT_DOMAIN]
+            .access_buffers
+            .iter()
+            .all(|buffer| buffer.lock().is_empty()));
+
+        // Accesses to unlinked regions are ignored.
+        region_manager.record_access(&2);
+        assert_eq!(region_manager.eviction_pop(DEFAULT_DOMAIN), Some(0));
+        assert_eq!(region_manager.eviction_pop(DEFAULT_DOMAIN), Some(1));
+        assert_eq!(region_manager.eviction_pop(DEFAULT_DOMAIN), None);
+    }
+
+    #[tokio::test]
+    async fn test_eviction_domains() {
+        let tempdir = tempdir().unwrap();
+
+        let device = FsDevice::open(FsDeviceConfig {
+            dir: tempdir.path().into(),
+            capacity: 256 * 1024,     // 256 KiB
+            file_capacity: 64 * 1024, // 64 KiB
+            align: 4 * 1024,          // 4 KiB
+            io_size: 16 * 1024,       // 16 KiB
+            hugepage_buffers: false,
+            read_io_policy: ReadIoPolicy::Fixed,
+            read_mode: FsReadMode::Pread,
+        })
+        .await
+        .unwrap();
+
+        let region_manager = RegionManager::<_, Lru<RegionEpItemAdapter<LruLink>>, LruLink>::with_domains(
+            &[1, 3],
+            LruConfig {
+                lru_insertion_point_fraction: 0.0,
+            },
+            0,
+            device,
+        );
+
+        assert_eq!(region_manager.domains(), 2);
+        assert_eq!(
+            (0..4).map(|id| region_manager.domain_of(&id)).collect_vec(),
+            vec![0, 1, 1, 1]
+        );
+
+        for id in 0..4 {
+            region_manager.release_clean_region(id);
+        }
+        assert_eq!(region_manager.clean_regions(0).len(), 1);
+        assert_eq!(region_manager.clean_regions(1).len(), 3);
+
+        for id in [3, 0, 1, 2] {
+            region_manager.eviction_push(id);
+        }
+        region_manager.record_access(&3);
+
+        assert_eq!(region_manager.pin_evictable_regions().len(), 4);
+        assert_eq!(region_manager.eviction_pop_if(1, |id| id != 1), Some(2));
+        assert_eq!(region_manager.eviction_pop(1), Some(1));
+        assert_eq!(region_manager.eviction_pop(1), Some(3));
+        assert_eq!(region_manager.eviction_pop(1), None);
+        assert_eq!(region_manager.eviction_pop(0), Some(0));
+        assert_eq!(region_manager.eviction_pop(0), None);
     }
 }
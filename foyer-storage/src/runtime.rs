@@ -108,6 +108,10 @@ where
     fn set_compression(&mut self, compression: Compression) {
         self.writer.set_compression(compression)
     }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        self.writer.set_trace_id(trace_id)
+    }
 }
 
 #[derive(Debug)]
@@ -198,6 +202,10 @@ where
         self.store.remove(key)
     }
 
+    fn remove_batch(&self, keys: &[Self::Key]) -> crate::error::Result<usize> {
+        self.store.remove_batch(keys)
+    }
+
     fn clear(&self) -> crate::error::Result<()> {
         self.store.clear()
     }
@@ -0,0 +1,406 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use foyer_common::{cancel::CancellationToken, clock::Clock};
+use futures::Future;
+use itertools::Itertools;
+use parking_lot::Mutex;
+use rand::Rng;
+use tokio::{sync::Notify, task::JoinHandle};
+
+use crate::{
+    error::Result,
+    health::{run_reported, HealthReporter, TaskKind},
+    invariant::Invariants,
+    metrics::{JobMetrics, Metrics},
+};
+
+/// A periodic background job of a store, run by the [`Scheduler`].
+pub trait Job: Send + 'static {
+    /// Run the job once.
+    ///
+    /// An error is reported to the health of the job, and the job runs again on schedule.
+    fn run(&mut self) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// When a [`Job`] runs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JobSchedule {
+    /// Interval between runs.
+    pub interval: Duration,
+    /// Ratio of the interval by which each run is randomly shifted either way, in `[0, 1]`.
+    ///
+    /// Spreads the runs of the jobs of many stores that are opened at the same time.
+    pub jitter: f64,
+}
+
+impl JobSchedule {
+    /// Run every `interval`, without jitter.
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, jitter: 0.0 }
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before the next run.
+    pub fn delay(&self) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return self.interval;
+        }
+        let factor = 1.0 + rand::thread_rng().gen_range(-jitter..=jitter);
+        self.interval.mul_f64(factor)
+    }
+}
+
+/// Controls a job spawned by the [`Scheduler`].
+#[derive(Debug)]
+pub struct JobHandle {
+    kind: TaskKind,
+    paused: AtomicBool,
+    resumed: Notify,
+    runs: AtomicU64,
+}
+
+impl JobHandle {
+    fn new(kind: TaskKind) -> Self {
+        Self {
+            kind,
+            paused: AtomicBool::new(false),
+            resumed: Notify::new(),
+            runs: AtomicU64::new(0),
+        }
+    }
+
+    pub fn kind(&self) -> TaskKind {
+        self.kind
+    }
+
+    /// Skip the runs of the job until resumed. A run in progress is not interrupted.
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Release);
+    }
+
+    /// Resume the job, it runs on its next scheduled time.
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Release);
+        self.resumed.notify_waiters();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Acquire)
+    }
+
+    /// Count of finished runs, successful or not.
+    pub fn runs(&self) -> u64 {
+        self.runs.load(Ordering::Relaxed)
+    }
+
+    async fn resumed(&self) {
+        loop {
+            let notified = self.resumed.notified();
+            if !self.is_paused() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Runs the periodic background jobs of a store, e.g. the admission rate controller.
+///
+/// Each job runs on its own task, reports its health and metrics, and stops when the store is closed.
+#[derive(Debug)]
+pub struct Scheduler {
+    clock: Arc<dyn Clock>,
+    metrics: Arc<Metrics>,
+    invariants: Arc<Invariants>,
+    /// Cancelled to stop all jobs.
+    cancel: CancellationToken,
+    jobs: Mutex<Vec<(Arc<JobHandle>, JoinHandle<()>)>>,
+}
+
+impl Scheduler {
+    pub fn new(
+        clock: Arc<dyn Clock>,
+        metrics: Arc<Metrics>,
+        invariants: Arc<Invariants>,
+        cancel: CancellationToken,
+    ) -> Self {
+        Self {
+            clock,
+            metrics,
+            invariants,
+            cancel,
+            jobs: Mutex::new(vec![]),
+        }
+    }
+
+    /// Spawn `job` to run on `schedule`, returns the health reporter of the job.
+    pub fn spawn<J: Job>(&self, kind: TaskKind, schedule: JobSchedule, job: J) -> Arc<HealthReporter> {
+        let mut jobs = self.jobs.lock();
+        let index = jobs.iter().filter(|(handle, _)| handle.kind == kind).count();
+        let reporter = Arc::new(HealthReporter::new(kind, index, self.clock.clone()));
+        let handle = Arc::new(JobHandle::new(kind));
+        let metrics = self.metrics.job(kind.name());
+        let cancel = self.cancel.child();
+        let invariants = self.invariants.clone();
+
+        let join = tokio::spawn({
+            let reporter = reporter.clone();
+            let handle = handle.clone();
+            async move {
+                // The error is reported to the health of the job.
+                if let Err(e) =
+                    run_reported(reporter.clone(), run(job, schedule, handle, metrics, reporter, cancel)).await
+                {
+                    invariants.violated(kind.name(), None, e);
+                }
+            }
+        });
+        jobs.push((handle, join));
+        reporter
+    }
+
+    /// Handle of the first job of `kind`.
+    pub fn job(&self, kind: TaskKind) -> Option<Arc<JobHandle>> {
+        self.jobs
+            .lock()
+            .iter()
+            .find(|(handle, _)| handle.kind == kind)
+            .map(|(handle, _)| handle.clone())
+    }
+
+    /// Stop all jobs and wait for them. A run in progress is finished first.
+    pub async fn shutdown(&self) {
+        self.cancel.cancel();
+        let joins = self.jobs.lock().drain(..).map(|(_, join)| join).collect_vec();
+        for join in joins {
+            // The job panicked.
+            if let Err(e) = join.await {
+                self.invariants.violated("scheduler", None, e);
+            }
+        }
+    }
+}
+
+async fn run<J: Job>(
+    mut job: J,
+    schedule: JobSchedule,
+    handle: Arc<JobHandle>,
+    metrics: JobMetrics,
+    reporter: Arc<HealthReporter>,
+    cancel: CancellationToken,
+) -> Result<()> {
+    let name = handle.kind.name();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(schedule.delay()) => {}
+            _ = cancel.cancelled() => break,
+        }
+        tokio::select! {
+            _ = handle.resumed() => {}
+            _ = cancel.cancelled() => break,
+        }
+
+        let now = Instant::now();
+        let res = job.run().await;
+        metrics.op_duration_run.observe(now.elapsed().as_secs_f64());
+        handle.runs.fetch_add(1, Ordering::Relaxed);
+
+        match res {
+            Ok(()) => {
+                reporter.clear_error();
+                reporter.success();
+            }
+            Err(e) => {
+                tracing::warn!("[scheduler] job {} failed: {}", name, e);
+                metrics.op_count_error.inc();
+                reporter.error(e);
+            }
+        }
+    }
+    tracing::info!("[scheduler] job {} exit", name);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use foyer_common::clock::SystemClock;
+
+    use super::*;
+    use crate::{error::ErrorKind, invariant::InvariantPolicy, metrics::METRICS};
+
+    #[derive(Debug)]
+    struct FlakyJob {
+        runs: Arc<AtomicU64>,
+        fail: Arc<AtomicBool>,
+    }
+
+    impl Job for FlakyJob {
+        async fn run(&mut self) -> Result<()> {
+            self.runs.fetch_add(1, Ordering::Relaxed);
+            if self.fail.load(Ordering::Relaxed) {
+                return Err(ErrorKind::Validation("flaky".to_string()).into());
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Debug)]
+    struct PanicJob;
+
+    impl Job for PanicJob {
+        async fn run(&mut self) -> Result<()> {
+            panic!("job panicked");
+        }
+    }
+
+    async fn spawn_panic_job(name: &str, policy: InvariantPolicy) -> (Scheduler, Arc<Invariants>, Arc<Metrics>) {
+        let metrics = Arc::new(METRICS.foyer(name));
+        let invariants = Arc::new(Invariants::new(policy, metrics.clone()));
+        let scheduler = Scheduler::new(
+            Arc::new(SystemClock),
+            metrics.clone(),
+            invariants.clone(),
+            CancellationToken::new(),
+        );
+        let reporter = scheduler.spawn(
+            TaskKind::SoftRemovePurger,
+            JobSchedule::new(Duration::from_millis(1)),
+            PanicJob,
+        );
+        while reporter.health().alive {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        (scheduler, invariants, metrics)
+    }
+
+    async fn wait_runs(handle: &JobHandle, runs: u64) {
+        while handle.runs() < runs {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_schedule() {
+        let interval = Duration::from_millis(100);
+        assert_eq!(JobSchedule::new(interval).delay(), interval);
+
+        let schedule = JobSchedule::new(interval).with_jitter(0.2);
+        for _ in 0..100 {
+            let delay = schedule.delay();
+            assert!(delay >= Duration::from_millis(80) && delay <= Duration::from_millis(120));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scheduler() {
+        let metrics = Arc::new(METRICS.foyer("test_scheduler"));
+        let invariants = Arc::new(Invariants::new(InvariantPolicy::Panic, metrics.clone()));
+        let scheduler = Scheduler::new(
+            Arc::new(SystemClock),
+            metrics.clone(),
+            invariants,
+            CancellationToken::new(),
+        );
+
+        let runs = Arc::new(AtomicU64::new(0));
+        let fail = Arc::new(AtomicBool::new(false));
+        let reporter = scheduler.spawn(
+            TaskKind::AdmissionRateController,
+            JobSchedule::new(Duration::from_millis(1)).with_jitter(0.5),
+            FlakyJob {
+                runs: runs.clone(),
+                fail: fail.clone(),
+            },
+        );
+        let handle = scheduler.job(TaskKind::AdmissionRateController).unwrap();
+        assert!(scheduler.job(TaskKind::Flusher).is_none());
+
+        wait_runs(&handle, 3).await;
+        assert!(reporter.health().last_success_at.is_some());
+        assert!(reporter.health().last_error.is_none());
+
+        // Failed runs are reported, but do not stop the job.
+        let job_metrics = metrics.job(TaskKind::AdmissionRateController.name());
+        fail.store(true, Ordering::Relaxed);
+        let failed = handle.runs() + 1;
+        wait_runs(&handle, failed + 1).await;
+        assert!(reporter.health().alive);
+        assert!(reporter.health().last_error.as_ref().unwrap().contains("flaky"));
+        assert!(job_metrics.op_count_error.get() >= 1);
+
+        // A successful run clears the error.
+        fail.store(false, Ordering::Relaxed);
+        let recovered = handle.runs() + 1;
+        wait_runs(&handle, recovered + 1).await;
+        assert!(reporter.health().last_error.is_none());
+
+        handle.pause();
+        assert!(handle.is_paused());
+        // Let a run in progress finish.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let paused = runs.load(Ordering::Relaxed);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::Relaxed), paused);
+
+        handle.resume();
+        wait_runs(&handle, paused + 2).await;
+        assert!(job_metrics.op_duration_run.get_sample_count() >= paused + 2);
+
+        // A paused job stops on shutdown as well.
+        handle.pause();
+        scheduler.shutdown().await;
+        assert!(!reporter.health().alive);
+        assert!(reporter.health().last_error.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_invariant_policy() {
+        // A panicked job is reported on shutdown.
+        let (scheduler, invariants, metrics) = spawn_panic_job(
+            "test_scheduler_invariant_policy_drop_region",
+            InvariantPolicy::DropRegion,
+        )
+        .await;
+        scheduler.shutdown().await;
+        assert_eq!(metrics.op_count_invariant_violated.get(), 1);
+        assert!(!invariants.is_degraded());
+
+        let (scheduler, invariants, metrics) =
+            spawn_panic_job("test_scheduler_invariant_policy_degrade", InvariantPolicy::Degrade).await;
+        scheduler.shutdown().await;
+        assert_eq!(metrics.op_count_invariant_violated.get(), 1);
+        assert!(invariants.is_degraded());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "invariant violated")]
+    async fn test_scheduler_invariant_policy_panic() {
+        let (scheduler, _, _) = spawn_panic_job("test_scheduler_invariant_policy_panic", InvariantPolicy::Panic).await;
+        scheduler.shutdown().await;
+    }
+}
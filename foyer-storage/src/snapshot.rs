@@ -0,0 +1,153 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{fs::File, io::Write, path::Path};
+
+use anyhow::anyhow;
+use bytes::{Buf, BufMut};
+use foyer_common::code::Key;
+
+use crate::{catalog::Sequence, error::Result, region::RegionId};
+
+const CATALOG_SNAPSHOT_MAGIC: u32 = 0x20_24_05_08;
+
+/// File name of the catalog snapshot in a store snapshot directory.
+pub const CATALOG_SNAPSHOT_FILENAME: &str = "foyer-catalog";
+
+/// Position of an entry on device, recorded in the catalog snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogSnapshotEntry<K>
+where
+    K: Key,
+{
+    pub key: K,
+    pub sequence: Sequence,
+    pub region: RegionId,
+    pub offset: u32,
+    pub len: u32,
+}
+
+/// Indices of the entries in the regions of a store snapshot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogSnapshot<K>
+where
+    K: Key,
+{
+    pub entries: Vec<CatalogSnapshotEntry<K>>,
+}
+
+impl<K> CatalogSnapshot<K>
+where
+    K: Key,
+{
+    /// Read the catalog snapshot in the store snapshot directory `dir`.
+    pub fn read(dir: impl AsRef<Path>) -> Result<Self> {
+        let buf = std::fs::read(dir.as_ref().join(CATALOG_SNAPSHOT_FILENAME)).map_err(anyhow::Error::from)?;
+        Self::decode(&buf)
+    }
+
+    /// Write the snapshot and sync it to stable storage.
+    pub(crate) fn write(&self, dir: impl AsRef<Path>) -> Result<()> {
+        let mut file = File::create(dir.as_ref().join(CATALOG_SNAPSHOT_FILENAME)).map_err(anyhow::Error::from)?;
+        file.write_all(&self.encode()).map_err(anyhow::Error::from)?;
+        file.sync_all().map_err(anyhow::Error::from)?;
+        Ok(())
+    }
+
+    /// | magic (4B) | count (8B) | entry * count |
+    ///
+    /// entry: | sequence (8B) | region (4B) | offset (4B) | len (4B) | key len (4B) | key |
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        buf.put_u32(CATALOG_SNAPSHOT_MAGIC);
+        buf.put_u64(self.entries.len() as u64);
+        for entry in self.entries.iter() {
+            buf.put_u64(entry.sequence);
+            buf.put_u32(entry.region);
+            buf.put_u32(entry.offset);
+            buf.put_u32(entry.len);
+            buf.put_u32(entry.key.serialized_len() as u32);
+            std::io::copy(&mut entry.key.clone().into_cursor(), &mut buf).unwrap();
+        }
+        buf
+    }
+
+    fn decode(mut buf: &[u8]) -> Result<Self> {
+        if buf.remaining() < 12 || buf.get_u32() != CATALOG_SNAPSHOT_MAGIC {
+            return Err(anyhow!("invalid catalog snapshot file").into());
+        }
+        let count = buf.get_u64() as usize;
+        // Each entry takes at least 24 bytes, a corrupted count must not make a huge allocation.
+        if count > buf.remaining() / 24 {
+            return Err(anyhow!("catalog snapshot file truncated").into());
+        }
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            if buf.remaining() < 24 {
+                return Err(anyhow!("catalog snapshot file truncated").into());
+            }
+            let sequence = buf.get_u64();
+            let region = buf.get_u32();
+            let offset = buf.get_u32();
+            let len = buf.get_u32();
+            let key_len = buf.get_u32() as usize;
+            if buf.remaining() < key_len {
+                return Err(anyhow!("catalog snapshot file truncated").into());
+            }
+            let key = K::read(&buf[..key_len])?;
+            buf.advance(key_len);
+            entries.push(CatalogSnapshotEntry {
+                key,
+                sequence,
+                region,
+                offset,
+                len,
+            });
+        }
+        Ok(Self { entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_snapshot_codec() {
+        let snapshot = CatalogSnapshot {
+            entries: (0..10u64)
+                .map(|i| CatalogSnapshotEntry {
+                    key: vec![i as u8; i as usize],
+                    sequence: i,
+                    region: i as RegionId,
+                    offset: i as u32 * 4096,
+                    len: 4096,
+                })
+                .collect(),
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        snapshot.write(dir.path()).unwrap();
+        assert_eq!(CatalogSnapshot::read(dir.path()).unwrap(), snapshot);
+
+        std::fs::write(dir.path().join(CATALOG_SNAPSHOT_FILENAME), [0u8; 8]).unwrap();
+        assert!(CatalogSnapshot::<Vec<u8>>::read(dir.path()).is_err());
+
+        let mut buf = vec![];
+        buf.put_u32(CATALOG_SNAPSHOT_MAGIC);
+        buf.put_u64(u64::MAX);
+        std::fs::write(dir.path().join(CATALOG_SNAPSHOT_FILENAME), buf).unwrap();
+        assert!(CatalogSnapshot::<Vec<u8>>::read(dir.path()).is_err());
+    }
+}
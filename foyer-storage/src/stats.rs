@@ -0,0 +1,284 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use parking_lot::Mutex;
+
+/// Statistics of a store.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreStats {
+    /// Uncompressed key and value bytes of entries on device.
+    pub logical_bytes: usize,
+    /// Aligned on-disk bytes of entries on device, including headers and paddings.
+    pub physical_bytes: usize,
+    /// Estimated memory of the catalog indexing the entries, including inflight ones.
+    pub catalog_bytes: usize,
+    /// Clean regions ready for writing.
+    pub clean_regions: usize,
+    /// Whether reclamation is triggered, by clean regions below the threshold or the low watermark, until they reach
+    /// the threshold or the high watermark.
+    pub reclaiming: bool,
+    /// Counters of the operations since the store is opened.
+    pub ops: OpStats,
+}
+
+/// Counters of the operations of a store.
+///
+/// The counters of an operation are updated at once, so the counters of a snapshot are consistent with each other,
+/// e.g. `hits <= lookups`, and ratios can be computed from them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub lookups: u64,
+    /// Lookups that found the entry.
+    pub hits: u64,
+    /// Entries written, including reinserted ones.
+    pub inserts: u64,
+    /// Written entries admitted by the admission policies.
+    pub admitted: u64,
+}
+
+impl OpStats {
+    /// Ratio of lookups that found the entry, `0` without lookups.
+    pub fn hit_ratio(&self) -> f64 {
+        if self.lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.lookups as f64
+    }
+}
+
+const OP_COUNTER_SHARDS: usize = 16;
+
+/// Sharded [`OpStats`] of a store.
+///
+/// An operation updates its counters in the shard of its thread under the shard lock, and a snapshot sums the shards
+/// one by one. Since an operation only touches one shard, a snapshot contains either all or none of its updates.
+#[derive(Debug)]
+pub struct OpCounters {
+    shards: Vec<Mutex<OpStats>>,
+}
+
+impl Default for OpCounters {
+    fn default() -> Self {
+        Self {
+            shards: (0..OP_COUNTER_SHARDS).map(|_| Mutex::new(OpStats::default())).collect(),
+        }
+    }
+}
+
+impl OpCounters {
+    /// Update the counters of an operation.
+    pub fn record(&self, f: impl FnOnce(&mut OpStats)) {
+        f(&mut self.shards[shard()].lock());
+    }
+
+    pub fn snapshot(&self) -> OpStats {
+        let mut stats = OpStats::default();
+        for shard in self.shards.iter() {
+            let shard = shard.lock();
+            stats.lookups += shard.lookups;
+            stats.hits += shard.hits;
+            stats.inserts += shard.inserts;
+            stats.admitted += shard.admitted;
+        }
+        stats
+    }
+}
+
+/// Minutes of rollups retained by [`StatsHistory`].
+pub const STATS_HISTORY_MINUTES: usize = 30;
+
+const MINUTE_MILLIS: u64 = 60 * 1000;
+
+/// Rollup of the operations of a store in one minute.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatsRollup {
+    /// Start of the minute, in milliseconds since unix epoch.
+    pub minute: u64,
+    /// Lookups that found the entry.
+    pub hits: u64,
+    /// Lookups that found nothing.
+    pub misses: u64,
+    /// Key and value bytes of admitted entries.
+    pub inserted_bytes: u64,
+    /// Bytes of reclaimed regions.
+    pub reclaimed_bytes: u64,
+}
+
+impl StatsRollup {
+    fn merge(&mut self, other: &StatsRollup) {
+        self.hits += other.hits;
+        self.misses += other.misses;
+        self.inserted_bytes += other.inserted_bytes;
+        self.reclaimed_bytes += other.reclaimed_bytes;
+    }
+}
+
+/// Per-minute rollups of the operations of a store in the last [`STATS_HISTORY_MINUTES`] minutes, kept in memory.
+///
+/// Sharded the same way as [`OpCounters`]. Minutes without operations have no rollup.
+#[derive(Debug)]
+pub struct StatsHistory {
+    shards: Vec<Mutex<VecDeque<StatsRollup>>>,
+}
+
+impl Default for StatsHistory {
+    fn default() -> Self {
+        Self {
+            shards: (0..OP_COUNTER_SHARDS).map(|_| Mutex::new(VecDeque::new())).collect(),
+        }
+    }
+}
+
+impl StatsHistory {
+    /// Update the rollup of the minute of `now`, in milliseconds since unix epoch.
+    pub fn record(&self, now: u64, f: impl FnOnce(&mut StatsRollup)) {
+        let minute = now - now % MINUTE_MILLIS;
+        let mut rollups = self.shards[shard()].lock();
+        // A clock moving backwards updates the latest rollup.
+        if rollups.back().map_or(true, |rollup| rollup.minute < minute) {
+            rollups.push_back(StatsRollup {
+                minute,
+                ..Default::default()
+            });
+        }
+        while rollups
+            .front()
+            .is_some_and(|rollup| rollup.minute + STATS_HISTORY_MINUTES as u64 * MINUTE_MILLIS <= minute)
+        {
+            rollups.pop_front();
+        }
+        f(rollups.back_mut().unwrap());
+    }
+
+    /// Rollups of the minutes retained at `now`, oldest first.
+    pub fn snapshot(&self, now: u64) -> Vec<StatsRollup> {
+        let since = (now - now % MINUTE_MILLIS).saturating_sub((STATS_HISTORY_MINUTES as u64 - 1) * MINUTE_MILLIS);
+        let mut merged: Vec<StatsRollup> = vec![];
+        for shard in self.shards.iter() {
+            for rollup in shard.lock().iter().filter(|rollup| rollup.minute >= since) {
+                match merged.iter_mut().find(|merged| merged.minute == rollup.minute) {
+                    Some(merged) => merged.merge(rollup),
+                    None => merged.push(*rollup),
+                }
+            }
+        }
+        merged.sort_by_key(|rollup| rollup.minute);
+        merged
+    }
+}
+
+/// Shard of the current thread, threads are assigned to shards in turn.
+fn shard() -> usize {
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    thread_local! {
+        static SHARD: usize = NEXT.fetch_add(1, Ordering::Relaxed) % OP_COUNTER_SHARDS;
+    }
+    SHARD.with(|shard| *shard)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_op_counters_snapshot() {
+        let counters = Arc::new(OpCounters::default());
+
+        let writers = (0..4)
+            .map(|_| {
+                let counters = counters.clone();
+                std::thread::spawn(move || {
+                    for i in 0..10000 {
+                        counters.record(|ops| {
+                            ops.lookups += 1;
+                            if i % 2 == 0 {
+                                ops.hits += 1;
+                            }
+                        });
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // Every snapshot is consistent, even while the counters are updated.
+        let mut last = OpStats::default();
+        while writers.iter().any(|writer| !writer.is_finished()) {
+            let stats = counters.snapshot();
+            assert!(stats.hits <= stats.lookups);
+            assert!(stats.lookups >= last.lookups && stats.hits >= last.hits);
+            last = stats;
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let stats = counters.snapshot();
+        assert_eq!((stats.lookups, stats.hits), (40000, 20000));
+        assert_eq!(stats.hit_ratio(), 0.5);
+    }
+
+    #[test]
+    fn test_stats_history() {
+        const MINUTE: u64 = 60 * 1000;
+
+        let history = StatsHistory::default();
+        let start = 100 * MINUTE;
+
+        history.record(start, |rollup| rollup.hits += 1);
+        history.record(start + MINUTE - 1, |rollup| rollup.misses += 1);
+        history.record(start + 2 * MINUTE, |rollup| rollup.inserted_bytes += 10);
+        std::thread::scope(|scope| {
+            scope.spawn(|| history.record(start + 2 * MINUTE + 1, |rollup| rollup.reclaimed_bytes += 20));
+        });
+
+        // Rollups of all shards are merged by minute, minutes without operations are omitted.
+        assert_eq!(
+            history.snapshot(start + 2 * MINUTE),
+            vec![
+                StatsRollup {
+                    minute: start,
+                    hits: 1,
+                    misses: 1,
+                    ..Default::default()
+                },
+                StatsRollup {
+                    minute: start + 2 * MINUTE,
+                    inserted_bytes: 10,
+                    reclaimed_bytes: 20,
+                    ..Default::default()
+                },
+            ]
+        );
+
+        // Old rollups are dropped.
+        let later = start + STATS_HISTORY_MINUTES as u64 * MINUTE;
+        assert_eq!(history.snapshot(later).len(), 1);
+        history.record(later, |rollup| rollup.hits += 1);
+        assert_eq!(
+            history
+                .snapshot(later)
+                .iter()
+                .map(|rollup| rollup.minute)
+                .collect::<Vec<_>>(),
+            vec![start + 2 * MINUTE, later]
+        );
+    }
+}
@@ -15,13 +15,15 @@
 use std::fmt::Debug;
 
 use foyer_common::code::{Key, Value};
-use futures::Future;
+use futures::{future::BoxFuture, Future, FutureExt};
 
 use crate::{compress::Compression, error::Result};
 
 pub trait FetchValueFuture<V> = Future<Output = anyhow::Result<V>> + Send + 'static;
 
-pub trait StorageWriter: Send + Sync + Debug {
+/// A writer holds its own handle of the store instead of borrowing it, so it can be moved into a spawned task, e.g.
+/// to fetch the value and finish the insertion there after it is judged.
+pub trait StorageWriter: Send + Sync + Debug + 'static {
     type Key: Key;
     type Value: Value;
 
@@ -37,6 +39,9 @@ pub trait StorageWriter: Send + Sync + Debug {
 
     fn set_compression(&mut self, compression: Compression);
 
+    /// Attach an opaque trace id to the entry, logged when the entry is flushed to the device and when it is evicted.
+    fn set_trace_id(&mut self, trace_id: u64);
+
     fn finish(self, value: Self::Value) -> impl Future<Output = Result<bool>> + Send;
 }
 
@@ -63,10 +68,26 @@ pub trait Storage: Send + Sync + Debug + Clone + 'static {
 
     fn remove(&self, key: &Self::Key) -> Result<bool>;
 
+    /// Remove `keys`, returns the number of removed keys.
+    fn remove_batch(&self, keys: &[Self::Key]) -> Result<usize> {
+        let mut removed = 0;
+        for key in keys {
+            if self.remove(key)? {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
     fn clear(&self) -> Result<()>;
 }
 
 pub trait StorageExt: Storage {
+    /// Box the store as a [`DynStorage`] trait object.
+    fn into_dyn(self) -> Box<dyn DynStorage<Self::Key, Self::Value>> {
+        Box::new(DynStorageAdapter(self))
+    }
+
     #[must_use]
     #[tracing::instrument(skip(self, value))]
     fn insert(&self, key: Self::Key, value: Self::Value) -> impl Future<Output = Result<bool>> + Send {
@@ -312,6 +333,152 @@ pub trait ForceStorageExt: Storage {
 
 impl<S> ForceStorageExt for S where S: Storage {}
 
+/// Object-safe variant of [`StorageWriter`], see [`DynStorage`].
+pub trait DynStorageWriter<K, V>: Send + Sync + Debug + 'static
+where
+    K: Key,
+    V: Value,
+{
+    fn key(&self) -> &K;
+
+    fn weight(&self) -> usize;
+
+    fn judge(&mut self) -> bool;
+
+    fn force(&mut self);
+
+    fn compression(&self) -> Compression;
+
+    fn set_compression(&mut self, compression: Compression);
+
+    fn set_trace_id(&mut self, trace_id: u64);
+
+    fn finish(self: Box<Self>, value: V) -> BoxFuture<'static, Result<bool>>;
+}
+
+/// Object-safe subset of [`Storage`] and [`StorageExt`] with boxed futures.
+///
+/// Applications can hold a store as `Box<dyn DynStorage<K, V>>` and choose the backend at runtime, without the
+/// generic parameters of the store. Any [`Storage`] is adapted with [`StorageExt::into_dyn`]. Opening a store is not
+/// covered, stores are opened with their own configs.
+pub trait DynStorage<K, V>: Send + Sync + Debug + 'static
+where
+    K: Key,
+    V: Value,
+{
+    fn is_ready(&self) -> bool;
+
+    fn close(&self) -> BoxFuture<'_, Result<()>>;
+
+    fn writer(&self, key: K, weight: usize) -> Box<dyn DynStorageWriter<K, V>>;
+
+    fn exists(&self, key: &K) -> Result<bool>;
+
+    fn lookup<'a>(&'a self, key: &'a K) -> BoxFuture<'a, Result<Option<V>>>;
+
+    fn remove(&self, key: &K) -> Result<bool>;
+
+    fn remove_batch(&self, keys: &[K]) -> Result<usize>;
+
+    fn clear(&self) -> Result<()>;
+
+    fn insert(&self, key: K, value: V) -> BoxFuture<'_, Result<bool>>;
+
+    fn insert_if_not_exists(&self, key: K, value: V) -> BoxFuture<'_, Result<bool>>;
+}
+
+/// Adapts a [`StorageWriter`] to [`DynStorageWriter`].
+#[derive(Debug)]
+struct DynStorageWriterAdapter<W>(W);
+
+impl<W> DynStorageWriter<W::Key, W::Value> for DynStorageWriterAdapter<W>
+where
+    W: StorageWriter,
+{
+    fn key(&self) -> &W::Key {
+        self.0.key()
+    }
+
+    fn weight(&self) -> usize {
+        self.0.weight()
+    }
+
+    fn judge(&mut self) -> bool {
+        self.0.judge()
+    }
+
+    fn force(&mut self) {
+        self.0.force()
+    }
+
+    fn compression(&self) -> Compression {
+        self.0.compression()
+    }
+
+    fn set_compression(&mut self, compression: Compression) {
+        self.0.set_compression(compression)
+    }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        self.0.set_trace_id(trace_id)
+    }
+
+    fn finish(self: Box<Self>, value: W::Value) -> BoxFuture<'static, Result<bool>> {
+        self.0.finish(value).boxed()
+    }
+}
+
+/// Adapts a [`Storage`] to [`DynStorage`].
+///
+/// A wrapper instead of a blanket implementation, so that the methods of both traits do not clash on stores.
+#[derive(Debug, Clone)]
+pub struct DynStorageAdapter<S>(pub S);
+
+impl<S> DynStorage<S::Key, S::Value> for DynStorageAdapter<S>
+where
+    S: Storage,
+{
+    fn is_ready(&self) -> bool {
+        self.0.is_ready()
+    }
+
+    fn close(&self) -> BoxFuture<'_, Result<()>> {
+        self.0.close().boxed()
+    }
+
+    fn writer(&self, key: S::Key, weight: usize) -> Box<dyn DynStorageWriter<S::Key, S::Value>> {
+        Box::new(DynStorageWriterAdapter(self.0.writer(key, weight)))
+    }
+
+    fn exists(&self, key: &S::Key) -> Result<bool> {
+        self.0.exists(key)
+    }
+
+    fn lookup<'a>(&'a self, key: &'a S::Key) -> BoxFuture<'a, Result<Option<S::Value>>> {
+        self.0.lookup(key).boxed()
+    }
+
+    fn remove(&self, key: &S::Key) -> Result<bool> {
+        self.0.remove(key)
+    }
+
+    fn remove_batch(&self, keys: &[S::Key]) -> Result<usize> {
+        self.0.remove_batch(keys)
+    }
+
+    fn clear(&self) -> Result<()> {
+        self.0.clear()
+    }
+
+    fn insert(&self, key: S::Key, value: S::Value) -> BoxFuture<'_, Result<bool>> {
+        self.0.insert(key, value).boxed()
+    }
+
+    fn insert_if_not_exists(&self, key: S::Key, value: S::Value) -> BoxFuture<'_, Result<bool>> {
+        self.0.insert_if_not_exists(key, value).boxed()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     //! storage interface test
@@ -323,8 +490,11 @@ mod tests {
 
     use super::*;
     use crate::{
-        device::fs::FsDeviceConfig,
-        store::{FifoFsStore, FifoFsStoreConfig},
+        device::{
+            fs::{FsDeviceConfig, FsReadMode},
+            ReadIoPolicy,
+        },
+        store::{FifoFsStore, FifoFsStoreConfig, NoneStore},
     };
 
     const KB: usize = 1024;
@@ -332,23 +502,22 @@ mod tests {
 
     fn config_for_test(dir: impl AsRef<Path>) -> FifoFsStoreConfig<u64, Vec<u8>> {
         FifoFsStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
-                dir: dir.as_ref().into(),
-                capacity: 4 * MB,
-                file_capacity: MB,
-                align: 4 * KB,
-                io_size: 4 * KB,
-            },
             catalog_bits: 1,
-            admissions: vec![],
-            reinsertions: vec![],
-            flushers: 1,
-            reclaimers: 1,
-            clean_region_threshold: 1,
             recover_concurrency: 2,
-            compression: Compression::None,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: dir.as_ref().into(),
+                    capacity: 4 * MB,
+                    file_capacity: MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
         }
     }
 
@@ -427,6 +596,64 @@ mod tests {
         assert!(storage.exists(&6).unwrap());
     }
 
+    #[tokio::test]
+    async fn test_writer_spawn() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = config_for_test(tempdir.path());
+
+        let storage = FifoFsStore::open(config).await.unwrap();
+
+        let mut writer = storage.writer(1, KB);
+        assert!(writer.judge());
+        let handle = tokio::spawn(async move {
+            let value = tokio::spawn(async move { vec![b'x'; KB] }).await.unwrap();
+            writer.finish(value).await
+        });
+        assert!(handle.await.unwrap().unwrap());
+        assert_eq!(storage.lookup(&1).await.unwrap().unwrap(), vec![b'x'; KB]);
+
+        storage.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dyn_storage() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        for enabled in [true, false] {
+            let storage: Box<dyn DynStorage<u64, Vec<u8>>> = if enabled {
+                FifoFsStore::open(config_for_test(tempdir.path()))
+                    .await
+                    .unwrap()
+                    .into_dyn()
+            } else {
+                NoneStore::open(()).await.unwrap().into_dyn()
+            };
+            assert!(storage.is_ready());
+
+            assert_eq!(storage.insert(1, vec![b'x'; KB]).await.unwrap(), enabled);
+            assert!(!storage.insert_if_not_exists(1, vec![b'y'; KB]).await.unwrap());
+            assert_eq!(storage.exists(&1).unwrap(), enabled);
+
+            let mut writer = storage.writer(2, KB);
+            assert_eq!(writer.key(), &2);
+            assert_eq!(writer.weight(), KB);
+            writer.set_compression(Compression::Lz4);
+            if writer.judge() {
+                assert_eq!(writer.compression(), Compression::Lz4);
+                let handle = tokio::spawn(writer.finish(vec![b'z'; KB]));
+                assert!(handle.await.unwrap().unwrap());
+            }
+
+            assert_eq!(storage.lookup(&1).await.unwrap(), enabled.then(|| vec![b'x'; KB]));
+            assert_eq!(storage.lookup(&2).await.unwrap(), enabled.then(|| vec![b'z'; KB]));
+            assert_eq!(storage.remove_batch(&[1, 2, 3]).unwrap(), if enabled { 2 } else { 0 });
+            assert!(!storage.remove(&1).unwrap());
+
+            storage.clear().unwrap();
+            storage.close().await.unwrap();
+        }
+    }
+
     async fn exists_with_retry(storage: &impl Storage<Key = u64, Value = Vec<u8>>, key: &u64) -> bool {
         tokio::time::sleep(Duration::from_millis(1)).await;
         for _ in 0..10 {
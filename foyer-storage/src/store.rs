@@ -19,6 +19,7 @@ use foyer_intrusive::eviction::{
     fifo::{Fifo, FifoLink},
     lfu::{Lfu, LfuLink},
     lru::{Lru, LruLink},
+    sampling::{Sampling, SamplingLink},
 };
 
 use crate::{
@@ -48,6 +49,14 @@ pub type FifoFsStoreConfig<K, V> = GenericStoreConfig<K, V, FsDevice, Fifo<Regio
 
 pub type FifoFsStoreWriter<K, V> = GenericStoreWriter<K, V, FsDevice, Fifo<RegionEpItemAdapter<FifoLink>>, FifoLink>;
 
+pub type SamplingFsStore<K, V> =
+    GenericStore<K, V, FsDevice, Sampling<RegionEpItemAdapter<SamplingLink>>, SamplingLink>;
+
+pub type SamplingFsStoreConfig<K, V> = GenericStoreConfig<K, V, FsDevice, Sampling<RegionEpItemAdapter<SamplingLink>>>;
+
+pub type SamplingFsStoreWriter<K, V> =
+    GenericStoreWriter<K, V, FsDevice, Sampling<RegionEpItemAdapter<SamplingLink>>, SamplingLink>;
+
 #[derive(Debug)]
 pub struct NoneStoreWriter<K: Key, V: Value> {
     key: K,
@@ -92,6 +101,8 @@ impl<K: Key, V: Value> StorageWriter for NoneStoreWriter<K, V> {
     }
 
     fn set_compression(&mut self, _: Compression) {}
+
+    fn set_trace_id(&mut self, _: u64) {}
 }
 
 #[derive(Debug)]
@@ -354,6 +365,15 @@ where
             StoreWriter::NoneStoreWriter { writer } => writer.set_compression(compression),
         }
     }
+
+    fn set_trace_id(&mut self, trace_id: u64) {
+        match self {
+            StoreWriter::LruFsStorWriter { writer } => writer.set_trace_id(trace_id),
+            StoreWriter::LfuFsStorWriter { writer } => writer.set_trace_id(trace_id),
+            StoreWriter::FifoFsStoreWriter { writer } => writer.set_trace_id(trace_id),
+            StoreWriter::NoneStoreWriter { writer } => writer.set_trace_id(trace_id),
+        }
+    }
 }
 
 impl<K, V> Storage for Store<K, V>
@@ -441,6 +461,15 @@ where
         }
     }
 
+    fn remove_batch(&self, keys: &[Self::Key]) -> Result<usize> {
+        match self {
+            Store::LruFsStore { store } => store.remove_batch(keys),
+            Store::LfuFsStore { store } => store.remove_batch(keys),
+            Store::FifoFsStore { store } => store.remove_batch(keys),
+            Store::NoneStore { store } => store.remove_batch(keys),
+        }
+    }
+
     fn clear(&self) -> Result<()> {
         match self {
             Store::LruFsStore { store } => store.clear(),
@@ -88,6 +88,10 @@ where
         true
     }
 
+    fn peek(&self, _key: &K, _weight: usize) -> Option<bool> {
+        Some(true)
+    }
+
     fn on_insert(&self, _key: &K, _weight: usize, _judge: bool) {}
 
     fn on_drop(&self, _key: &K, _weight: usize, _judge: bool) {}
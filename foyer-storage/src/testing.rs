@@ -0,0 +1,117 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+//! Generators and round-trip checks for property-based tests, enabled by the `testing` feature.
+//!
+//! Implementors of [`Key`] and [`Value`] can validate their codecs against the store with them, e.g.:
+//!
+//! ```ignore
+//! proptest! {
+//!     #[test]
+//!     fn test_value_round_trip(value in any::<MyValue>(), compression in arb_compression()) {
+//!         check_value_round_trip(value, compression)?;
+//!     }
+//! }
+//! ```
+
+use foyer_common::code::{Key, Value};
+use proptest::prelude::*;
+
+use crate::{
+    compress::Compression,
+    generic::{decode_value, encode_value},
+};
+
+/// Generates all compression algorithms.
+pub fn arb_compression() -> impl Strategy<Value = Compression> {
+    prop_oneof![Just(Compression::None), Just(Compression::Zstd), Just(Compression::Lz4)]
+}
+
+/// Generates bytes of up to `max_len`, either random or repeated to be compressible.
+pub fn arb_bytes(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        prop::collection::vec(any::<u8>(), 0..=max_len),
+        (any::<u8>(), 0..=max_len).prop_map(|(byte, len)| vec![byte; len]),
+    ]
+}
+
+/// Generates up to `max_entries` entries with distinct keys.
+pub fn arb_entries<K, V>(
+    key: impl Strategy<Value = K>,
+    value: impl Strategy<Value = V>,
+    max_entries: usize,
+) -> impl Strategy<Value = Vec<(K, V)>>
+where
+    K: Key,
+    V: Value,
+{
+    prop::collection::btree_map(key, value, 0..=max_entries).prop_map(|entries| entries.into_iter().collect())
+}
+
+/// Checks that `key` is encoded in [`Key::serialized_len`] bytes and decoded back by [`Key::read`].
+pub fn check_key_round_trip<K>(key: K) -> Result<(), TestCaseError>
+where
+    K: Key,
+{
+    let len = key.serialized_len();
+    let mut buf = vec![];
+    std::io::copy(&mut key.clone().into_cursor(), &mut buf).map_err(fail)?;
+    prop_assert_eq!(buf.len(), len, "encoded length mismatches `serialized_len`");
+
+    let decoded = K::read(&buf).map_err(fail)?;
+    prop_assert_eq!(decoded, key);
+    Ok(())
+}
+
+/// Checks that `value` is encoded in [`Value::serialized_len`] bytes, and decoded back by [`Value::read`] after it is
+/// compressed and decompressed with `compression` as the store does.
+pub fn check_value_round_trip<V>(value: V, compression: Compression) -> Result<(), TestCaseError>
+where
+    V: Value + PartialEq,
+{
+    let len = value.serialized_len();
+    let mut buf = vec![];
+    encode_value(value.clone(), Compression::None, &mut buf).map_err(fail)?;
+    prop_assert_eq!(buf.len(), len, "encoded length mismatches `serialized_len`");
+
+    buf.clear();
+    encode_value(value.clone(), compression, &mut buf).map_err(fail)?;
+    let decoded: V = decode_value(&buf, compression, len).map_err(fail)?;
+    prop_assert_eq!(decoded, value);
+    Ok(())
+}
+
+fn fail(e: impl std::fmt::Display) -> TestCaseError {
+    TestCaseError::fail(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_key_round_trip(a in any::<u64>(), b in any::<i32>(), c in arb_bytes(256)) {
+            check_key_round_trip(a)?;
+            check_key_round_trip(b)?;
+            check_key_round_trip(c)?;
+        }
+
+        #[test]
+        fn test_value_round_trip(a in any::<u64>(), b in arb_bytes(64 * 1024), compression in arb_compression()) {
+            check_value_round_trip(a, compression)?;
+            check_value_round_trip(b, compression)?;
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::{
+    sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use tokio::sync::{futures::Notified, Notify};
+
+use crate::compress::Compression;
+
+/// Parameters that can be adjusted while the store is running.
+///
+/// Background workers pick up the changes on their next round.
+#[derive(Debug)]
+pub struct Tunables {
+    clean_region_threshold: AtomicUsize,
+    /// Nanoseconds, `0` means disabled.
+    reclaim_throttle: AtomicU64,
+    compression: AtomicU8,
+    /// Nanoseconds, `0` means disabled.
+    flush_interval: AtomicU64,
+    hot_drop_report: AtomicUsize,
+    /// `usize::MAX` means unlimited.
+    inflight_budget: AtomicUsize,
+
+    changed: Notify,
+}
+
+impl Tunables {
+    pub fn new(clean_region_threshold: usize, compression: Compression) -> Self {
+        Self {
+            clean_region_threshold: AtomicUsize::new(clean_region_threshold),
+            reclaim_throttle: AtomicU64::new(0),
+            compression: AtomicU8::new(compression.to_u8()),
+            flush_interval: AtomicU64::new(0),
+            hot_drop_report: AtomicUsize::new(0),
+            inflight_budget: AtomicUsize::new(usize::MAX),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Reclaimers keep at least this many clean regions.
+    pub fn clean_region_threshold(&self) -> usize {
+        self.clean_region_threshold.load(Ordering::Relaxed)
+    }
+
+    pub fn set_clean_region_threshold(&self, threshold: usize) {
+        self.clean_region_threshold.store(threshold, Ordering::Relaxed);
+        self.changed.notify_waiters();
+    }
+
+    /// Pause of a reclaimer after reclaiming a region, `None` means no pause.
+    pub fn reclaim_throttle(&self) -> Option<Duration> {
+        Self::load_duration(&self.reclaim_throttle)
+    }
+
+    pub fn set_reclaim_throttle(&self, throttle: Option<Duration>) {
+        Self::store_duration(&self.reclaim_throttle, throttle);
+        self.changed.notify_waiters();
+    }
+
+    /// Compression algorithm of new writers.
+    pub fn compression(&self) -> Compression {
+        Compression::try_from(self.compression.load(Ordering::Relaxed)).unwrap()
+    }
+
+    pub fn set_compression(&self, compression: Compression) {
+        self.compression.store(compression.to_u8(), Ordering::Relaxed);
+        self.changed.notify_waiters();
+    }
+
+    /// Max duration that entries wait in a partially filled flush buffer, `None` means waiting until the buffer is
+    /// full.
+    pub fn flush_interval(&self) -> Option<Duration> {
+        Self::load_duration(&self.flush_interval)
+    }
+
+    pub fn set_flush_interval(&self, interval: Option<Duration>) {
+        Self::store_duration(&self.flush_interval, interval);
+        self.changed.notify_waiters();
+    }
+
+    /// Count of the hottest key hashes logged when the reinsertion policies drop accessed entries of a reclaimed
+    /// region, `0` means no report.
+    pub fn hot_drop_report(&self) -> usize {
+        self.hot_drop_report.load(Ordering::Relaxed)
+    }
+
+    pub fn set_hot_drop_report(&self, count: usize) {
+        self.hot_drop_report.store(count, Ordering::Relaxed);
+        self.changed.notify_waiters();
+    }
+
+    /// Max serialized bytes of entries waiting for flushers, inserts are rejected beyond it. `None` means unlimited.
+    pub fn inflight_budget(&self) -> Option<usize> {
+        match self.inflight_budget.load(Ordering::Relaxed) {
+            usize::MAX => None,
+            budget => Some(budget),
+        }
+    }
+
+    pub fn set_inflight_budget(&self, budget: Option<usize>) {
+        self.inflight_budget
+            .store(budget.unwrap_or(usize::MAX), Ordering::Relaxed);
+        self.changed.notify_waiters();
+    }
+
+    /// Resolves when any of the tunables is changed.
+    pub(crate) fn changed(&self) -> Notified<'_> {
+        self.changed.notified()
+    }
+
+    fn load_duration(v: &AtomicU64) -> Option<Duration> {
+        match v.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn store_duration(v: &AtomicU64, duration: Option<Duration>) {
+        let nanos = duration.map(|d| d.as_nanos() as u64).unwrap_or_default();
+        v.store(nanos, Ordering::Relaxed);
+    }
+}
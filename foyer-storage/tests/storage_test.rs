@@ -17,14 +17,22 @@
 
 use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use foyer_intrusive::eviction::fifo::FifoConfig;
+use foyer_intrusive::eviction::{
+    fifo::FifoConfig,
+    lfu::{Lfu, LfuConfig},
+    lru::{Lru, LruConfig},
+    sampling::{Sampling, SamplingConfig, SamplingScore},
+};
 use foyer_storage::{
     compress::Compression,
-    device::fs::FsDeviceConfig,
+    device::{
+        fs::{FsDeviceConfig, FsReadMode},
+        ReadIoPolicy,
+    },
     lazy::LazyStore,
     runtime::{RuntimeConfig, RuntimeLazyStore, RuntimeStorageConfig, RuntimeStore},
     storage::{Storage, StorageExt},
-    store::{FifoFsStoreConfig, Store},
+    store::{FifoFsStore, FifoFsStoreConfig, LfuFsStore, LruFsStore, SamplingFsStore, SamplingFsStoreConfig, Store},
     test_utils::JudgeRecorder,
 };
 
@@ -65,40 +73,48 @@ where
     drop(store);
 
     for _ in 0..LOOPS {
-        let store = S::open(config.clone()).await.unwrap();
-        while !store.is_ready() {
-            tokio::time::sleep(Duration::from_millis(10)).await;
-        }
+        reopen_and_insert::<S>(config.clone(), &recorder, &mut index).await;
+    }
+}
 
-        let remains = recorder.remains();
+/// Reopen the store, verify the recovered entries, then insert more entries and verify again.
+async fn reopen_and_insert<S>(config: S::Config, recorder: &JudgeRecorder<u64, Vec<u8>>, index: &mut u64)
+where
+    S: Storage<Key = u64, Value = Vec<u8>>,
+{
+    let store = S::open(config).await.unwrap();
+    while !store.is_ready() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
 
-        for i in 0..INSERTS as u64 * (LOOPS + 1) as u64 {
-            if remains.contains(&i) {
-                assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 1 * KB],);
-            } else {
-                assert!(store.lookup(&i).await.unwrap().is_none());
-            }
-        }
+    let remains = recorder.remains();
 
-        for _ in 0..INSERTS as u64 {
-            index += 1;
-            store.insert(index, vec![index as u8; 1 * KB]).await.unwrap();
+    for i in 0..INSERTS as u64 * (LOOPS + 1) as u64 {
+        if remains.contains(&i) {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 1 * KB],);
+        } else {
+            assert!(store.lookup(&i).await.unwrap().is_none());
         }
+    }
 
-        store.close().await.unwrap();
+    for _ in 0..INSERTS as u64 {
+        *index += 1;
+        store.insert(*index, vec![*index as u8; 1 * KB]).await.unwrap();
+    }
 
-        let remains = recorder.remains();
+    store.close().await.unwrap();
 
-        for i in 0..INSERTS as u64 * (LOOPS + 1) as u64 {
-            if remains.contains(&i) {
-                assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 1 * KB],);
-            } else {
-                assert!(store.lookup(&i).await.unwrap().is_none());
-            }
-        }
+    let remains = recorder.remains();
 
-        drop(store);
+    for i in 0..INSERTS as u64 * (LOOPS + 1) as u64 {
+        if remains.contains(&i) {
+            assert_eq!(store.lookup(&i).await.unwrap().unwrap(), vec![i as u8; 1 * KB],);
+        } else {
+            assert!(store.lookup(&i).await.unwrap().is_none());
+        }
     }
+
+    drop(store);
 }
 
 #[tokio::test]
@@ -106,23 +122,24 @@ async fn test_store() {
     let tempdir = tempfile::tempdir().unwrap();
     let recorder = Arc::new(JudgeRecorder::default());
     let config = FifoFsStoreConfig {
-        name: "".to_string(),
-        eviction_config: FifoConfig,
-        device_config: FsDeviceConfig {
-            dir: PathBuf::from(tempdir.path()),
-            capacity: 4 * MB,
-            file_capacity: 1 * MB,
-            align: 4 * KB,
-            io_size: 4 * KB,
-        },
         catalog_bits: 1,
         admissions: vec![recorder.clone()],
         reinsertions: vec![recorder.clone()],
-        flushers: 1,
-        reclaimers: 1,
-        clean_region_threshold: 1,
         recover_concurrency: 2,
-        compression: Compression::None,
+        ..FifoFsStoreConfig::new(
+            "",
+            FifoConfig,
+            FsDeviceConfig {
+                dir: PathBuf::from(tempdir.path()),
+                capacity: 4 * MB,
+                file_capacity: 1 * MB,
+                align: 4 * KB,
+                io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
     };
 
     test_storage::<Store<_, _>>(config.into(), recorder).await;
@@ -133,23 +150,25 @@ async fn test_store_zstd() {
     let tempdir = tempfile::tempdir().unwrap();
     let recorder = Arc::new(JudgeRecorder::default());
     let config = FifoFsStoreConfig {
-        name: "".to_string(),
-        eviction_config: FifoConfig,
-        device_config: FsDeviceConfig {
-            dir: PathBuf::from(tempdir.path()),
-            capacity: 4 * MB,
-            file_capacity: 1 * MB,
-            align: 4 * KB,
-            io_size: 4 * KB,
-        },
         catalog_bits: 1,
         admissions: vec![recorder.clone()],
         reinsertions: vec![recorder.clone()],
-        flushers: 1,
-        reclaimers: 1,
-        clean_region_threshold: 1,
         recover_concurrency: 2,
         compression: Compression::Zstd,
+        ..FifoFsStoreConfig::new(
+            "",
+            FifoConfig,
+            FsDeviceConfig {
+                dir: PathBuf::from(tempdir.path()),
+                capacity: 4 * MB,
+                file_capacity: 1 * MB,
+                align: 4 * KB,
+                io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
     };
 
     test_storage::<Store<_, _>>(config.into(), recorder).await;
@@ -160,78 +179,169 @@ async fn test_store_lz4() {
     let tempdir = tempfile::tempdir().unwrap();
     let recorder = Arc::new(JudgeRecorder::default());
     let config = FifoFsStoreConfig {
-        name: "".to_string(),
-        eviction_config: FifoConfig,
-        device_config: FsDeviceConfig {
-            dir: PathBuf::from(tempdir.path()),
-            capacity: 4 * MB,
-            file_capacity: 1 * MB,
-            align: 4 * KB,
-            io_size: 4 * KB,
-        },
         catalog_bits: 1,
         admissions: vec![recorder.clone()],
         reinsertions: vec![recorder.clone()],
-        flushers: 1,
-        reclaimers: 1,
-        clean_region_threshold: 1,
         recover_concurrency: 2,
         compression: Compression::Lz4,
+        ..FifoFsStoreConfig::new(
+            "",
+            FifoConfig,
+            FsDeviceConfig {
+                dir: PathBuf::from(tempdir.path()),
+                capacity: 4 * MB,
+                file_capacity: 1 * MB,
+                align: 4 * KB,
+                io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
     };
 
     test_storage::<Store<_, _>>(config.into(), recorder).await;
 }
 
 #[tokio::test]
-async fn test_lazy_store() {
+async fn test_sampling_store() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let recorder = Arc::new(JudgeRecorder::default());
+    let config = SamplingFsStoreConfig {
+        catalog_bits: 1,
+        admissions: vec![recorder.clone()],
+        reinsertions: vec![recorder.clone()],
+        recover_concurrency: 2,
+        ..SamplingFsStoreConfig::new(
+            "",
+            SamplingConfig {
+                samples: 4,
+                score: SamplingScore::AccessCount,
+            },
+            FsDeviceConfig {
+                dir: PathBuf::from(tempdir.path()),
+                capacity: 4 * MB,
+                file_capacity: 1 * MB,
+                align: 4 * KB,
+                io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
+    };
+
+    test_storage::<SamplingFsStore<_, _>>(config, recorder).await;
+}
+
+#[tokio::test]
+async fn test_store_switch_eviction_policy() {
     let tempdir = tempfile::tempdir().unwrap();
     let recorder = Arc::new(JudgeRecorder::default());
     let config = FifoFsStoreConfig {
-        name: "".to_string(),
-        eviction_config: FifoConfig,
-        device_config: FsDeviceConfig {
-            dir: PathBuf::from(tempdir.path()),
-            capacity: 4 * MB,
-            file_capacity: 1 * MB,
-            align: 4 * KB,
-            io_size: 4 * KB,
-        },
         catalog_bits: 1,
         admissions: vec![recorder.clone()],
         reinsertions: vec![recorder.clone()],
-        flushers: 1,
-        reclaimers: 1,
-        clean_region_threshold: 1,
         recover_concurrency: 2,
-        compression: Compression::None,
+        ..FifoFsStoreConfig::new(
+            "",
+            FifoConfig,
+            FsDeviceConfig {
+                dir: PathBuf::from(tempdir.path()),
+                capacity: 4 * MB,
+                file_capacity: 1 * MB,
+                align: 4 * KB,
+                io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
+            },
+        )
     };
 
-    test_storage::<LazyStore<_, _>>(config.into(), recorder).await;
+    // Regions written under one eviction policy must be recovered under any other.
+    let mut index = 0;
+    for round in 0..LOOPS {
+        let config = config.clone();
+        match round % 4 {
+            0 => reopen_and_insert::<FifoFsStore<_, _>>(config, &recorder, &mut index).await,
+            1 => {
+                let config = config.with_eviction_config::<Lru<_>>(LruConfig {
+                    lru_insertion_point_fraction: 0.0,
+                });
+                reopen_and_insert::<LruFsStore<_, _>>(config, &recorder, &mut index).await
+            }
+            2 => {
+                let config = config.with_eviction_config::<Lfu<_>>(LfuConfig {
+                    window_to_cache_size_ratio: 1,
+                    tiny_lru_capacity_ratio: 0.01,
+                });
+                reopen_and_insert::<LfuFsStore<_, _>>(config, &recorder, &mut index).await
+            }
+            3 => {
+                let config = config.with_eviction_config::<Sampling<_>>(SamplingConfig {
+                    samples: 4,
+                    score: SamplingScore::AccessCount,
+                });
+                reopen_and_insert::<SamplingFsStore<_, _>>(config, &recorder, &mut index).await
+            }
+            _ => unreachable!(),
+        }
+    }
 }
 
 #[tokio::test]
-async fn test_runtime_store() {
+async fn test_lazy_store() {
     let tempdir = tempfile::tempdir().unwrap();
     let recorder = Arc::new(JudgeRecorder::default());
-    let config = RuntimeStorageConfig {
-        store: FifoFsStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
+    let config = FifoFsStoreConfig {
+        catalog_bits: 1,
+        admissions: vec![recorder.clone()],
+        reinsertions: vec![recorder.clone()],
+        recover_concurrency: 2,
+        ..FifoFsStoreConfig::new(
+            "",
+            FifoConfig,
+            FsDeviceConfig {
                 dir: PathBuf::from(tempdir.path()),
                 capacity: 4 * MB,
                 file_capacity: 1 * MB,
                 align: 4 * KB,
                 io_size: 4 * KB,
+                hugepage_buffers: false,
+                read_io_policy: ReadIoPolicy::Fixed,
+                read_mode: FsReadMode::Pread,
             },
+        )
+    };
+
+    test_storage::<LazyStore<_, _>>(config.into(), recorder).await;
+}
+
+#[tokio::test]
+async fn test_runtime_store() {
+    let tempdir = tempfile::tempdir().unwrap();
+    let recorder = Arc::new(JudgeRecorder::default());
+    let config = RuntimeStorageConfig {
+        store: FifoFsStoreConfig {
             catalog_bits: 1,
             admissions: vec![recorder.clone()],
             reinsertions: vec![recorder.clone()],
-            flushers: 1,
-            reclaimers: 1,
-            clean_region_threshold: 1,
             recover_concurrency: 2,
-            compression: Compression::None,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 4 * MB,
+                    file_capacity: 1 * MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
         }
         .into(),
         runtime: RuntimeConfig {
@@ -249,23 +359,24 @@ async fn test_runtime_lazy_store() {
     let recorder = Arc::new(JudgeRecorder::default());
     let config = RuntimeStorageConfig {
         store: FifoFsStoreConfig {
-            name: "".to_string(),
-            eviction_config: FifoConfig,
-            device_config: FsDeviceConfig {
-                dir: PathBuf::from(tempdir.path()),
-                capacity: 4 * MB,
-                file_capacity: 1 * MB,
-                align: 4 * KB,
-                io_size: 4 * KB,
-            },
             catalog_bits: 1,
             admissions: vec![recorder.clone()],
             reinsertions: vec![recorder.clone()],
-            flushers: 1,
-            reclaimers: 1,
-            clean_region_threshold: 1,
             recover_concurrency: 2,
-            compression: Compression::None,
+            ..FifoFsStoreConfig::new(
+                "",
+                FifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 4 * MB,
+                    file_capacity: 1 * MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
         }
         .into(),
         runtime: RuntimeConfig {
@@ -0,0 +1,217 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use foyer_common::code::{Key, Value};
+use foyer_storage::storage::{Storage, StorageWriter};
+use tokio::{runtime::Handle, sync::mpsc, task::JoinHandle};
+
+/// Hints an upstream memory cache passes along with an evicted entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvictionHint {
+    /// Weight of the entry in the upstream cache.
+    ///
+    /// Only informational, the queue of [`StorageEvictionBridge`] is bounded by the serialized size of the entries.
+    pub weight: usize,
+    /// Hits of the entry while in the upstream cache.
+    pub accesses: usize,
+}
+
+/// Receives entries evicted by an in-memory cache, e.g. from the eviction listener of moka, quick-cache or a custom
+/// LRU, and offers them to a disk tier.
+///
+/// [`EvictionBridge::offer`] is called on the eviction path of the upstream cache, so it must never block.
+pub trait EvictionBridge<K, V>: Send + Sync + 'static
+where
+    K: Key,
+    V: Value,
+{
+    /// Offer an evicted entry, returns `false` if it is dropped at once.
+    fn offer(&self, key: K, value: V, hint: EvictionHint) -> bool;
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageEvictionBridgeConfig {
+    /// Max total serialized bytes of the queued entries, entries offered beyond it are dropped.
+    pub queue_capacity: usize,
+    /// Entries with fewer accesses are dropped.
+    pub min_accesses: usize,
+}
+
+/// An [`EvictionBridge`] that inserts offered entries into a disk store.
+///
+/// Offered entries are queued and inserted by a background task in order, each offered to the admission policies of
+/// the store first. The queue is bounded by the total serialized bytes of the queued and inserting entries, and
+/// offering never waits for the store.
+///
+/// The background task is spawned to the tokio runtime the bridge is created within, so entries can be offered from
+/// any thread. The task exits after the queued entries are drained when the bridge is dropped or closed.
+#[derive(Debug)]
+pub struct StorageEvictionBridge<S>
+where
+    S: Storage,
+{
+    tx: mpsc::UnboundedSender<(S::Key, S::Value, usize)>,
+    handle: JoinHandle<()>,
+
+    queued: Arc<AtomicUsize>,
+    dropped: AtomicU64,
+
+    config: StorageEvictionBridgeConfig,
+}
+
+impl<S> StorageEvictionBridge<S>
+where
+    S: Storage,
+{
+    pub fn new(store: S, config: StorageEvictionBridgeConfig) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let queued = Arc::new(AtomicUsize::new(0));
+        let handle = Handle::current().spawn(Self::run(store, rx, queued.clone()));
+        Self {
+            tx,
+            handle,
+            queued,
+            dropped: AtomicU64::new(0),
+            config,
+        }
+    }
+
+    /// Total serialized bytes of the queued and inserting entries.
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::Relaxed)
+    }
+
+    /// Count of the entries dropped for too few accesses or a full queue.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop accepting entries and wait for the queued ones to be inserted.
+    pub async fn close(self) {
+        drop(self.tx);
+        self.handle.await.unwrap();
+    }
+
+    async fn run(store: S, mut rx: mpsc::UnboundedReceiver<(S::Key, S::Value, usize)>, queued: Arc<AtomicUsize>) {
+        while let Some((key, value, weight)) = rx.recv().await {
+            let mut writer = store.writer(key, weight);
+            if writer.judge() {
+                if let Err(e) = writer.finish(value).await {
+                    tracing::warn!("[eviction bridge] insert evicted entry error: {:?}", e);
+                }
+            }
+            queued.fetch_sub(weight, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<S> EvictionBridge<S::Key, S::Value> for StorageEvictionBridge<S>
+where
+    S: Storage,
+{
+    fn offer(&self, key: S::Key, value: S::Value, hint: EvictionHint) -> bool {
+        if hint.accesses < self.config.min_accesses {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+
+        let weight = key.serialized_len() + value.serialized_len();
+        let reserved = self
+            .queued
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |queued| {
+                let queued = queued + weight;
+                (queued <= self.config.queue_capacity).then_some(queued)
+            })
+            .is_ok();
+        if !reserved || self.tx.send((key, value, weight)).is_err() {
+            if reserved {
+                self.queued.fetch_sub(weight, Ordering::Relaxed);
+            }
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{FifoFsStore, FifoFsStoreConfig, FsDeviceConfig, FsReadMode, FsStoreFifoConfig, ReadIoPolicy};
+
+    const KB: usize = 1024;
+    const MB: usize = 1024 * 1024;
+
+    #[tokio::test]
+    async fn test_storage_eviction_bridge() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = FifoFsStoreConfig {
+            catalog_bits: 1,
+            recover_concurrency: 2,
+            ..FifoFsStoreConfig::new(
+                "test_storage_eviction_bridge",
+                FsStoreFifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 4 * MB,
+                    file_capacity: MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
+        };
+        let store = FifoFsStore::open(config).await.unwrap();
+
+        let bridge = StorageEvictionBridge::new(
+            store.clone(),
+            StorageEvictionBridgeConfig {
+                queue_capacity: 4 * KB,
+                min_accesses: 1,
+            },
+        );
+
+        let hint = |accesses| EvictionHint { weight: KB, accesses };
+        assert!(bridge.offer(1, vec![1; KB], hint(1)));
+        // Never accessed while in memory.
+        assert!(!bridge.offer(2, vec![2; KB], hint(0)));
+        // Larger than the whole queue, regardless of the weight hint.
+        assert!(!bridge.offer(3, vec![3; 8 * KB], EvictionHint { weight: 0, accesses: 1 }));
+        // Offered from a thread outside of the runtime.
+        assert!(std::thread::scope(|s| s
+            .spawn(|| bridge.offer(4, vec![4; KB], hint(2)))
+            .join()
+            .unwrap()));
+        assert_eq!(bridge.dropped(), 2);
+
+        bridge.close().await;
+
+        assert_eq!(store.lookup(&1).await.unwrap(), Some(vec![1; KB]));
+        assert!(!store.exists(&2).unwrap());
+        assert!(!store.exists(&3).unwrap());
+        assert_eq!(store.lookup(&4).await.unwrap(), Some(vec![4; KB]));
+
+        store.close().await.unwrap();
+    }
+}
@@ -0,0 +1,143 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+use std::sync::Arc;
+
+use foyer_memory::{CacheContext, CacheEventListener};
+use foyer_storage::storage::Storage;
+
+use crate::bridge::{EvictionBridge, EvictionHint, StorageEvictionBridge};
+
+/// A memory cache event listener that demotes entries evicted from the memory cache to the disk store.
+///
+/// Every evicted entry is offered to a [`StorageEvictionBridge`] with its charges and hits while in memory as the hint,
+/// so the demotion is filtered, bounded and inserted the same way as entries from any other upstream cache. Entries
+/// removed, replaced or cleared from the memory cache are not demoted.
+#[derive(Debug)]
+pub struct DemotionListener<S>
+where
+    S: Storage,
+{
+    bridge: Arc<StorageEvictionBridge<S>>,
+}
+
+impl<S> DemotionListener<S>
+where
+    S: Storage,
+{
+    pub fn new(bridge: Arc<StorageEvictionBridge<S>>) -> Self {
+        Self { bridge }
+    }
+
+    pub fn bridge(&self) -> &Arc<StorageEvictionBridge<S>> {
+        &self.bridge
+    }
+}
+
+impl<S> CacheEventListener<S::Key, S::Value> for DemotionListener<S>
+where
+    S: Storage,
+{
+    fn on_release(&self, _key: S::Key, _value: S::Value, _context: CacheContext, _charges: usize) {}
+
+    fn on_evict(&self, key: S::Key, value: S::Value, _context: CacheContext, charges: usize, accesses: usize) {
+        self.bridge.offer(
+            key,
+            value,
+            EvictionHint {
+                weight: charges,
+                accesses,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        bridge::StorageEvictionBridgeConfig, Cache, FifoCacheConfig, FifoConfig, FifoFsStore, FifoFsStoreConfig,
+        FsDeviceConfig, FsReadMode, FsStoreFifoConfig, ReadIoPolicy,
+    };
+
+    const KB: usize = 1024;
+    const MB: usize = 1024 * 1024;
+
+    #[tokio::test]
+    async fn test_demotion() {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        let config = FifoFsStoreConfig {
+            catalog_bits: 1,
+            recover_concurrency: 2,
+            ..FifoFsStoreConfig::new(
+                "test_demotion",
+                FsStoreFifoConfig,
+                FsDeviceConfig {
+                    dir: PathBuf::from(tempdir.path()),
+                    capacity: 4 * MB,
+                    file_capacity: MB,
+                    align: 4 * KB,
+                    io_size: 4 * KB,
+                    hugepage_buffers: false,
+                    read_io_policy: ReadIoPolicy::Fixed,
+                    read_mode: FsReadMode::Pread,
+                },
+            )
+        };
+        let store = FifoFsStore::open(config).await.unwrap();
+
+        let bridge = Arc::new(StorageEvictionBridge::new(
+            store.clone(),
+            StorageEvictionBridgeConfig {
+                queue_capacity: MB,
+                min_accesses: 1,
+            },
+        ));
+
+        let cache = Cache::fifo(FifoCacheConfig::<u64, Vec<u8>, _> {
+            capacity: 2,
+            shards: 1,
+            eviction_config: FifoConfig {},
+            object_pool_capacity: 1,
+            hash_builder: Default::default(),
+            event_listener: DemotionListener::new(bridge.clone()),
+        });
+
+        cache.insert(1, vec![1; KB], 1);
+        cache.insert(2, vec![2; KB], 1);
+        drop(cache.get(&1).unwrap());
+
+        // Removed entry is not demoted.
+        cache.remove(&2);
+
+        // Evict `1` (accessed) and `3` (never accessed).
+        cache.insert(3, vec![3; KB], 1);
+        cache.insert(4, vec![4; KB], 1);
+        cache.insert(5, vec![5; KB], 1);
+
+        // Wait for the demoted entry to be inserted.
+        while bridge.queued() > 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(bridge.dropped(), 1);
+        assert_eq!(store.lookup(&1).await.unwrap(), Some(vec![1; KB]));
+        assert!(!store.exists(&2).unwrap());
+        assert!(!store.exists(&3).unwrap());
+
+        store.close().await.unwrap();
+    }
+}
@@ -15,7 +15,18 @@
 #![feature(trait_alias)]
 #![feature(pattern)]
 
+//! A hybrid cache built from an in-memory cache and a disk store.
+//!
+//! The commonly used types are re-exported at the crate root, so depending on this crate alone is enough for most
+//! usages. The underlying crates are still re-exported as modules for the rest.
+
 pub use foyer_common as common;
 pub use foyer_intrusive as intrusive;
 pub use foyer_memory as memory;
 pub use foyer_storage as storage;
+
+pub mod bridge;
+pub mod hybrid;
+mod prelude;
+
+pub use prelude::*;
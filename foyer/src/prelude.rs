@@ -0,0 +1,63 @@
+//  Copyright 2024 Foyer Project Authors
+//
+//  Licensed under the Apache License, Version 2.0 (the "License");
+//  you may not use this file except in compliance with the License.
+//  You may obtain a copy of the License at
+//
+//  http://www.apache.org/licenses/LICENSE-2.0
+//
+//  Unless required by applicable law or agreed to in writing, software
+//  distributed under the License is distributed on an "AS IS" BASIS,
+//  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+//  See the License for the specific language governing permissions and
+//  limitations under the License.
+
+pub use foyer_common::{
+    clock::{Clock, MockClock, SystemClock},
+    code::{Key, Value},
+};
+pub use foyer_intrusive::eviction::{
+    fifo::FifoConfig as FsStoreFifoConfig, lfu::LfuConfig as FsStoreLfuConfig, lru::LruConfig as FsStoreLruConfig,
+    sampling::SamplingConfig as FsStoreSamplingConfig,
+};
+pub use foyer_memory::{
+    Cache, CacheContext, CacheEntry, CacheEventListener, DefaultCacheEventListener, Entry, EntryState, FifoCacheConfig,
+    FifoConfig, LfuCacheConfig, LfuConfig, LruCacheConfig, LruConfig, S3FifoCacheConfig, S3FifoConfig,
+};
+pub use foyer_storage::{
+    admission::{
+        controller::AdmissionRateControllerConfig, rated_ticket::RatedTicketAdmissionPolicy,
+        write_stall::WriteStallAdmissionPolicy, AdmissionPolicy, AdmissionVerdict,
+    },
+    codec::Codec,
+    compress::Compression,
+    device::{
+        fs::{FsDeviceConfig, FsReadMode},
+        mmap::MmapAdvice,
+        ReadIoPolicy,
+    },
+    error::{Error as StorageError, Result as StorageResult},
+    generic::{EntryPadding, FlushRouting, RecoveryMode},
+    health::{StoreHealth, TaskHealth, TaskKind},
+    invariant::InvariantPolicy,
+    lazy::{LazyStore, LazyStoreWriter},
+    metrics::set_metrics_registry,
+    partitioner::FlushPartitioner,
+    preset::Preset,
+    quota::{SoftQuotaConfig, SoftQuotaEvent, SoftQuotaListener},
+    reinsertion::{exist::ExistReinsertionPolicy, rated_ticket::RatedTicketReinsertionPolicy, ReinsertionPolicy},
+    runtime::{RuntimeConfig, RuntimeLazyStore, RuntimeLazyStoreConfig, RuntimeStore, RuntimeStoreConfig},
+    storage::{
+        AsyncStorageExt, DynStorage, DynStorageAdapter, DynStorageWriter, ForceStorageExt, Storage, StorageExt,
+        StorageWriter,
+    },
+    store::{
+        FifoFsStore, FifoFsStoreConfig, LfuFsStore, LfuFsStoreConfig, LruFsStore, LruFsStoreConfig, NoneStore,
+        SamplingFsStore, SamplingFsStoreConfig, Store, StoreConfig, StoreWriter,
+    },
+};
+
+pub use crate::{
+    bridge::{EvictionBridge, EvictionHint, StorageEvictionBridge, StorageEvictionBridgeConfig},
+    hybrid::DemotionListener,
+};